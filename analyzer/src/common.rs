@@ -0,0 +1,375 @@
+use std::{
+    iter::Sum,
+    ops::{Add, AddAssign},
+    sync::Mutex,
+};
+
+use bitflags::bitflags;
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use log::warn;
+use rustc_hash::FxHashSet;
+
+bitflags! {
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+    pub struct ValueFlags: u16{
+        const NONE = 0;
+        const CRITICAL = 1;
+        const FLANK = 1 << 1;
+        const KILL = 1 << 2;
+        const IMMUNE = 1 << 3;
+        const SHIELD_BREAK = 1 << 4;
+        const MISS = 1 << 5;
+        /// not from the log, set for values above the anomaly threshold of the settings
+        const ANOMALY = 1 << 6;
+        const DODGE = 1 << 7;
+        /// not from the log, set for shield values with a positive first and a non-zero second value,
+        /// shield damage is always logged with negative values, see [`crate::parser::Record::reclassify_ally_shield_heal`]
+        const POSITIVE_SHIELD = 1 << 8;
+    }
+}
+
+lazy_static! {
+    /// flags of the log, which are not known, so each of them is only logged once
+    static ref UNKNOWN_FLAGS: Mutex<FxHashSet<String>> = Default::default();
+}
+
+impl Default for ValueFlags {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShieldHullValues {
+    pub all: f64,
+    pub shield: f64,
+    pub hull: f64,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShieldHullOptionalValues {
+    pub all: Option<f64>,
+    pub shield: Option<f64>,
+    pub hull: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ShieldHullCounts {
+    pub all: u64,
+    pub shield: u64,
+    pub hull: u64,
+}
+
+impl ShieldHullValues {
+    pub fn per_seconds(total: &Self, duration: f64) -> Self {
+        // avoid absurd high numbers by having a combat duration of at least 1 sec
+        Self {
+            all: total.all / duration.max(1.0),
+            shield: total.shield / duration.max(1.0),
+            hull: total.hull / duration.max(1.0),
+        }
+    }
+}
+
+impl Sum for ShieldHullValues {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        let (shield, hull, all) = iter.fold((0.0, 0.0, 0.0), |(s, h, a), v| {
+            (v.shield + s, v.hull + h, v.all + a)
+        });
+        Self { all, shield, hull }
+    }
+}
+
+impl Add for ShieldHullValues {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            all: self.all + rhs.all,
+            shield: self.shield + rhs.shield,
+            hull: self.hull + rhs.hull,
+        }
+    }
+}
+
+impl AddAssign for ShieldHullValues {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        self.all += rhs.all;
+        self.shield += rhs.shield;
+        self.hull += rhs.hull;
+    }
+}
+
+impl ShieldHullOptionalValues {
+    pub fn average(
+        total: &ShieldHullValues,
+        shield_count: u64,
+        hull_count: u64,
+        all_count: u64,
+    ) -> Self {
+        Self {
+            all: if all_count == 0 {
+                None
+            } else {
+                Some(total.all / all_count as f64)
+            },
+            shield: if shield_count == 0 {
+                None
+            } else {
+                Some(total.shield / shield_count as f64)
+            },
+            hull: if hull_count == 0 {
+                None
+            } else {
+                Some(total.hull / hull_count as f64)
+            },
+        }
+    }
+
+    pub fn percentage(amount: &ShieldHullValues, total: &ShieldHullValues) -> Self {
+        Self {
+            all: percentage_f64(amount.all, total.all),
+            shield: percentage_f64(amount.shield, total.shield),
+            hull: percentage_f64(amount.hull, total.hull),
+        }
+    }
+}
+
+impl ShieldHullCounts {
+    pub fn to_values(&self) -> ShieldHullValues {
+        ShieldHullValues {
+            all: self.all as _,
+            shield: self.shield as _,
+            hull: self.hull as _,
+        }
+    }
+}
+
+impl Sum for ShieldHullCounts {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        let (shield, hull, all) = iter.fold((0, 0, 0), |(s, h, a), v| {
+            (v.shield + s, v.hull + h, v.all + a)
+        });
+        Self { all, shield, hull }
+    }
+}
+
+impl Add for ShieldHullCounts {
+    type Output = Self;
+
+    #[inline]
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            all: self.all + rhs.all,
+            shield: self.shield + rhs.shield,
+            hull: self.hull + rhs.hull,
+        }
+    }
+}
+
+impl AddAssign for ShieldHullCounts {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        self.all += rhs.all;
+        self.shield += rhs.shield;
+        self.hull += rhs.hull;
+    }
+}
+
+impl ValueFlags {
+    pub fn parse(input: &str) -> Self {
+        let mut flags = ValueFlags::NONE;
+        for flag in input.split('|') {
+            flags |= match flag {
+                "Critical" => ValueFlags::CRITICAL,
+                "Flank" => ValueFlags::FLANK,
+                "Kill" => ValueFlags::KILL,
+                "Immune" => ValueFlags::IMMUNE,
+                "ShieldBreak" => ValueFlags::SHIELD_BREAK,
+                "Miss" => ValueFlags::MISS,
+                "Dodge" => ValueFlags::DODGE,
+                "" => ValueFlags::NONE,
+                unknown => {
+                    Self::log_unknown(unknown);
+                    ValueFlags::NONE
+                }
+            };
+        }
+
+        flags
+    }
+
+    /// any of these marks a hit, which the target avoided
+    pub const AVOIDED: Self = Self::IMMUNE.union(Self::MISS).union(Self::DODGE);
+
+    #[cold]
+    fn log_unknown(flag: &str) {
+        let mut unknown_flags = UNKNOWN_FLAGS.lock().unwrap();
+        if !unknown_flags.contains(flag) {
+            warn!("unknown value flag in the log: {}", flag);
+            unknown_flags.insert(flag.to_string());
+        }
+    }
+}
+
+pub fn percentage_f64(amount: f64, total: f64) -> Option<f64> {
+    if total == 0.0 {
+        None
+    } else {
+        Some((amount / total) * 100.0)
+    }
+}
+
+pub fn percentage_u64(amount: u64, total_count: u64) -> Option<f64> {
+    if total_count == 0 {
+        return None;
+    }
+
+    Some((amount as f64 / total_count as f64) * 100.0)
+}
+
+/// Approximates the share of the combat, in which something was active, from the (sorted) times it occurred.
+/// Occurrences closer than `gap_millis` are merged into one active interval.
+pub fn uptime_percentage(
+    times_millis: impl Iterator<Item = u32>,
+    gap_millis: u32,
+    combat_duration: f64,
+) -> Option<f64> {
+    active_millis_percentage(active_millis(times_millis, gap_millis), combat_duration)
+}
+
+/// share of the combat of the `active_millis` of [`ActivityTimes`]
+pub fn active_millis_percentage(active_millis: Option<u64>, combat_duration: f64) -> Option<f64> {
+    let uptime = percentage_f64(active_millis? as f64 / 1000.0, combat_duration)?;
+    Some(uptime.min(100.0))
+}
+
+/// summed duration of the intervals, in which the occurrences are closer than `gap_millis`.
+/// `None` if nothing occurred.
+pub(crate) fn active_millis(
+    times_millis: impl Iterator<Item = u32>,
+    gap_millis: u32,
+) -> Option<u64> {
+    let mut times_millis = times_millis.peekable();
+    let first = *times_millis.peek()?;
+    let mut interval = first..first;
+    let mut active_millis = 0u64;
+    for time in times_millis {
+        if time.saturating_sub(interval.end) > gap_millis {
+            active_millis += (interval.end - interval.start) as u64;
+            interval = time..time;
+        } else {
+            interval.end = interval.end.max(time);
+        }
+    }
+    active_millis += (interval.end - interval.start) as u64;
+    Some(active_millis)
+}
+
+/// when something occurred during a combat, in milliseconds since the combat start
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ActivityTimes {
+    pub first_millis: u32,
+    pub last_millis: u32,
+    /// see [`uptime_percentage`]
+    pub active_millis: u64,
+    /// longest time without an occurrence between the first and the last one
+    pub max_gap_millis: u32,
+    /// `None` if it occurred only once
+    pub average_interval_millis: Option<f64>,
+}
+
+impl ActivityTimes {
+    /// from the (sorted) times something occurred, `None` if nothing occurred
+    pub fn new(times_millis: impl Iterator<Item = u32> + Clone, gap_millis: u32) -> Option<Self> {
+        let active_millis = active_millis(times_millis.clone(), gap_millis)?;
+        let intervals = intervals_millis(times_millis.clone());
+        Some(Self {
+            first_millis: times_millis.clone().min()?,
+            last_millis: times_millis.max()?,
+            active_millis,
+            max_gap_millis: intervals.iter().copied().max().unwrap_or(0),
+            average_interval_millis: (!intervals.is_empty())
+                .then(|| intervals.iter().map(|i| *i as f64).sum::<f64>() / intervals.len() as f64),
+        })
+    }
+}
+
+/// Intervals between the (sorted) times something occurred.
+/// Occurrences at the same time (e.g. the shield and hull part of one hit) count as one.
+/// Out of order times (e.g. due to client hitching) result in an interval of 0.
+pub fn intervals_millis(times_millis: impl Iterator<Item = u32>) -> Vec<u32> {
+    times_millis
+        .dedup()
+        .tuple_windows()
+        .map(|(previous, time)| time.saturating_sub(previous))
+        .collect()
+}
+
+/// Highest sum of the values within any window of `window_millis`, divided by the length of the window.
+/// Expects the values sorted by their time in milliseconds.
+pub fn peak_value_per_second(values: &[(u32, f64)], window_millis: u32) -> f64 {
+    let mut window_start = 0;
+    let mut window_sum = 0.0;
+    let mut peak_sum = 0.0_f64;
+    for &(time, value) in values.iter() {
+        window_sum += value;
+        while values[window_start].0.saturating_add(window_millis) <= time {
+            window_sum -= values[window_start].1;
+            window_start += 1;
+        }
+        peak_sum = peak_sum.max(window_sum);
+    }
+    peak_sum / (window_millis as f64 / 1e3)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intervals_millis_merges_simultaneous_occurrences() {
+        let times = [0, 0, 1200, 2400, 2400, 3700];
+        assert_eq!(intervals_millis(times.into_iter()), vec![1200, 1200, 1300]);
+        assert!(intervals_millis(std::iter::once(5)).is_empty());
+    }
+
+    #[test]
+    fn uptime_percentage_merges_close_occurrences() {
+        // ticks from 0s to 4s merged, the tick at 10s stands alone
+        let times = [0, 1000, 2000, 4000, 10000];
+        assert_eq!(uptime_percentage(times.into_iter(), 3000, 20.0), Some(20.0));
+        assert_eq!(uptime_percentage(std::iter::empty(), 3000, 20.0), None);
+    }
+
+    #[test]
+    fn peak_value_per_second_of_sliding_windows() {
+        let values = [
+            (0, 10.0),
+            (4000, 10.0),
+            (9000, 40.0),
+            (12000, 50.0),
+            (25000, 5.0),
+        ];
+        // 4s to 12s
+        assert_eq!(peak_value_per_second(&values, 10_000), 10.0);
+        // 0s to 25s
+        assert_eq!(peak_value_per_second(&values, 30_000), 115.0 / 30.0);
+        assert_eq!(peak_value_per_second(&[], 10_000), 0.0);
+    }
+
+    #[test]
+    fn activity_times_of_ticks() {
+        let times = [500, 1500, 2500, 9500, 9500, 10500];
+        let activity = ActivityTimes::new(times.into_iter(), 3000).unwrap();
+        assert_eq!(activity.first_millis, 500);
+        assert_eq!(activity.last_millis, 10500);
+        assert_eq!(activity.active_millis, 3000);
+        assert_eq!(activity.max_gap_millis, 7000);
+        assert_eq!(activity.average_interval_millis, Some(2500.0));
+        assert_eq!(ActivityTimes::new(std::iter::empty(), 3000), None);
+    }
+}
@@ -31,16 +31,28 @@ pub struct DamageMetrics {
     pub accuracy_percentage: Option<f64>,
     pub total_damage: ShieldHullValues,
     pub total_shield_drain: f64,
+    pub shield_drain_per_second: f64,
     pub total_damage_prevented_to_hull_by_shields: f64,
     pub total_base_damage: f64,
     pub base_dps: f64,
     pub dps: ShieldHullValues,
     pub average_hit: ShieldHullOptionalValues,
     pub critical_percentage: Option<f64>,
+    /// share of the hull hits, which flanked the target
     pub flanking: Option<f64>,
     pub damage_resistance_percentage: Option<f64>,
     pub crits: u64,
+    /// hull hits flagged as flanking, flanking has no effect on shields
     pub flanks: u64,
+    /// all hits flagged as flanking, including the ones on shields
+    pub flagged_flanks: u64,
+    pub immunes: u64,
+    pub dodges: u64,
+    /// hits, which were immune, missed or dodged
+    pub avoided: u64,
+    /// share of the hull hits, which were avoided, as each attack on shields also has a hull hit
+    pub avoided_percentage: Option<f64>,
+    pub kills: u64,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -53,23 +65,61 @@ pub struct DamageMetricsDelta {
     pub total_base_damage: f64,
     pub crits: u64,
     pub flanks: u64,
+    pub flagged_flanks: u64,
+    pub immunes: u64,
+    pub dodges: u64,
+    pub avoided: u64,
+    pub kills: u64,
+}
+
+/// highest DPS within any sliding window of 10, 30 and 60 seconds
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PeakDps {
+    pub ten_seconds: f64,
+    pub thirty_seconds: f64,
+    pub sixty_seconds: f64,
 }
 
 #[derive(Clone, Debug, Default)]
 pub struct MaxOneHit {
     pub name: NameHandle,
     pub damage: f64,
+    pub hit: Option<Hit>,
 }
 
 impl MaxOneHit {
-    pub fn update_from_hits(&mut self, name: NameHandle, hits: &[Hit]) {
-        hits.iter().for_each(|h| self.update(name, h.damage));
+    pub fn update_from_hits(&mut self, name: NameHandle, hits: &[Hit], exclude_anomalies: bool) {
+        hits.iter()
+            .filter(|h| !exclude_anomalies || !h.flags.contains(ValueFlags::ANOMALY))
+            .for_each(|h| self.update(name, h));
     }
 
-    pub fn update(&mut self, name: NameHandle, damage: f64) {
-        if self.damage < damage {
-            self.damage = damage;
+    pub fn update(&mut self, name: NameHandle, hit: &Hit) {
+        if self.damage < hit.damage {
+            self.damage = hit.damage;
             self.name = name;
+            self.hit = Some(*hit);
+        }
+    }
+
+    pub fn update_from_max(&mut self, max_one_hit: &MaxOneHit) {
+        if self.damage < max_one_hit.damage {
+            self.damage = max_one_hit.damage;
+            self.name = max_one_hit.name;
+            self.hit = max_one_hit.hit;
+        }
+    }
+}
+
+impl PeakDps {
+    /// from the hits in any order
+    pub fn new<'a>(hits: impl Iterator<Item = &'a Hit>) -> Self {
+        let mut hits: Vec<_> = hits.map(|h| (h.time_millis, h.damage)).collect();
+        hits.sort_unstable_by_key(|(time, _)| *time);
+        Self {
+            ten_seconds: peak_value_per_second(&hits, 10_000),
+            thirty_seconds: peak_value_per_second(&hits, 30_000),
+            sixty_seconds: peak_value_per_second(&hits, 60_000),
         }
     }
 }
@@ -103,6 +153,17 @@ impl BaseHit {
         }
     }
 
+    pub fn is_all_zero(&self) -> bool {
+        self.damage == 0.0
+            && match self.specific {
+                SpecificHit::Shield {
+                    damage_prevented_to_hull,
+                } => damage_prevented_to_hull == 0.0,
+                SpecificHit::ShieldDrain => true,
+                SpecificHit::Hull { base_damage } => base_damage == 0.0,
+            }
+    }
+
     pub fn to_hit(self, time_millis: u32) -> Hit {
         Hit {
             hit: self,
@@ -121,7 +182,20 @@ impl DamageMetrics {
                 SpecificHit::Hull { .. } => delta.hits.hull += 1,
             }
 
+            if hit.flags.contains(ValueFlags::KILL) {
+                delta.kills += 1;
+            }
+
+            if hit.flags.intersects(ValueFlags::AVOIDED) {
+                delta.avoided += 1;
+            }
+
+            if hit.flags.contains(ValueFlags::DODGE) {
+                delta.dodges += 1;
+            }
+
             if hit.flags.contains(ValueFlags::IMMUNE) {
+                delta.immunes += 1;
                 continue;
             }
 
@@ -147,7 +221,10 @@ impl DamageMetrics {
             }
 
             if hit.flags.contains(ValueFlags::FLANK) {
-                delta.flanks += 1;
+                delta.flagged_flanks += 1;
+                if let SpecificHit::Hull { .. } = hit.specific {
+                    delta.flanks += 1;
+                }
             }
 
             if hit.flags.contains(ValueFlags::MISS) {
@@ -171,12 +248,18 @@ impl DamageMetrics {
         self.total_shield_drain += delta.total_shield_drain;
         self.crits += delta.crits;
         self.flanks += delta.flanks;
+        self.flagged_flanks += delta.flagged_flanks;
+        self.immunes += delta.immunes;
+        self.dodges += delta.dodges;
+        self.avoided += delta.avoided;
+        self.kills += delta.kills;
         self.misses += delta.misses;
 
         self.critical_percentage = percentage_u64(self.crits, self.hits.hull);
 
         self.flanking = percentage_u64(self.flanks, self.hits.hull);
         self.accuracy_percentage = percentage_u64(self.misses, self.hits.hull).map(|m| 100.0 - m);
+        self.avoided_percentage = percentage_u64(self.avoided, self.hits.hull);
 
         self.damage_resistance_percentage = damage_resistance_percentage(
             &self.total_damage,
@@ -187,6 +270,7 @@ impl DamageMetrics {
 
     pub fn recalculate_time_based_metrics(&mut self, combat_duration: f64) {
         self.base_dps = self.total_base_damage / combat_duration.max(1.0);
+        self.shield_drain_per_second = self.total_shield_drain / combat_duration.max(1.0);
         self.hits_per_second =
             ShieldHullValues::per_seconds(&self.hits.to_values(), combat_duration);
 
@@ -200,6 +284,18 @@ impl DamageMetrics {
     }
 }
 
+impl Hit {
+    /// damage resistance of this single hit, only known for hull hits
+    pub fn damage_resistance_percentage(&self) -> Option<f64> {
+        match self.specific {
+            SpecificHit::Hull { base_damage } if base_damage != 0.0 => {
+                Some((1.0 - self.damage / base_damage) * 100.0)
+            }
+            _ => None,
+        }
+    }
+}
+
 pub fn damage_resistance_percentage(
     total_damage: &ShieldHullValues,
     total_base_damage: f64,
@@ -214,3 +310,28 @@ pub fn damage_resistance_percentage(
     let res = 1.0 - total_damage_without_drain / total_base_damage;
     Some(res * 100.0)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flanking_counts_only_hull_hits() {
+        let flank = ValueFlags::FLANK;
+        let hits = [
+            BaseHit::shield(100.0, flank, 50.0),
+            BaseHit::hull(50.0, flank, 60.0),
+            BaseHit::shield(100.0, flank, 50.0),
+            BaseHit::hull(50.0, ValueFlags::NONE, 60.0),
+            BaseHit::shield(100.0, ValueFlags::NONE, 50.0),
+            BaseHit::hull(50.0, flank | ValueFlags::CRITICAL, 60.0),
+        ]
+        .map(|h| h.to_hit(0));
+
+        let mut metrics = DamageMetrics::default();
+        metrics.calc_and_apply_delta(&hits);
+        assert_eq!(metrics.flanks, 2);
+        assert_eq!(metrics.flagged_flanks, 4);
+        assert_eq!(metrics.flanking, percentage_u64(2, 3));
+    }
+}
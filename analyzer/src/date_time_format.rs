@@ -0,0 +1,155 @@
+use std::ops::Range;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+#[cfg(feature = "serde")]
+use serde::*;
+
+/// How dates and times are presented, e.g. in the combat identifiers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct DateTimeFormat {
+    pub clock: ClockFormat,
+    pub date: DateFormat,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ClockFormat {
+    #[default]
+    Hours24,
+    Hours12,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DateFormat {
+    #[default]
+    Iso,
+    MonthDayYear,
+    DayMonthYear,
+}
+
+impl DateTimeFormat {
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        date.format(self.date.pattern()).to_string()
+    }
+
+    pub fn format_time(&self, time: NaiveTime) -> String {
+        time.format(self.clock.pattern()).to_string()
+    }
+
+    pub fn format_date_time(&self, date_time: NaiveDateTime) -> String {
+        format!(
+            "{} {}",
+            self.format_date(date_time.date()),
+            self.format_time(date_time.time())
+        )
+    }
+
+    /// e.g. `Infected Space | 2024-01-05 20:01:02 - 20:03:04`
+    pub fn combat_identifier(&self, name: &str, time: &Range<NaiveDateTime>) -> String {
+        format!(
+            "{} | {} - {}",
+            name,
+            self.format_date_time(time.start),
+            self.format_time(time.end.time())
+        )
+    }
+
+    /// like [`DateTimeFormat::combat_identifier`], but safe to be used as a file name
+    pub fn combat_file_identifier(&self, name: &str, time: &Range<NaiveDateTime>) -> String {
+        format!(
+            "{} {} {} - {}",
+            name,
+            time.start.date().format(self.date.file_name_pattern()),
+            time.start.time().format(self.clock.file_name_pattern()),
+            time.end.time().format(self.clock.file_name_pattern())
+        )
+    }
+}
+
+impl ClockFormat {
+    pub const fn display(&self) -> &'static str {
+        match self {
+            ClockFormat::Hours24 => "24 hours (20:01:02)",
+            ClockFormat::Hours12 => "12 hours (08:01:02 PM)",
+        }
+    }
+
+    const fn pattern(&self) -> &'static str {
+        match self {
+            ClockFormat::Hours24 => "%T",
+            ClockFormat::Hours12 => "%r",
+        }
+    }
+
+    const fn file_name_pattern(&self) -> &'static str {
+        match self {
+            ClockFormat::Hours24 => "%H-%M-%S",
+            ClockFormat::Hours12 => "%I-%M-%S%p",
+        }
+    }
+}
+
+impl DateFormat {
+    pub const fn display(&self) -> &'static str {
+        match self {
+            DateFormat::Iso => "ISO (2024-01-31)",
+            DateFormat::MonthDayYear => "Month/Day/Year (01/31/2024)",
+            DateFormat::DayMonthYear => "Day.Month.Year (31.01.2024)",
+        }
+    }
+
+    const fn pattern(&self) -> &'static str {
+        match self {
+            DateFormat::Iso => "%F",
+            DateFormat::MonthDayYear => "%m/%d/%Y",
+            DateFormat::DayMonthYear => "%d.%m.%Y",
+        }
+    }
+
+    const fn file_name_pattern(&self) -> &'static str {
+        match self {
+            DateFormat::Iso => "%F",
+            DateFormat::MonthDayYear => "%m-%d-%Y",
+            DateFormat::DayMonthYear => "%d-%m-%Y",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combat_identifiers() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 31)
+            .unwrap()
+            .and_hms_opt(20, 1, 2)
+            .unwrap();
+        let time = start..start + chrono::Duration::seconds(122);
+
+        let format = DateTimeFormat::default();
+        assert_eq!(
+            format.combat_identifier("Combat", &time),
+            "Combat | 2024-01-31 20:01:02 - 20:03:04"
+        );
+        assert_eq!(
+            format.combat_file_identifier("Combat", &time),
+            "Combat 2024-01-31 20-01-02 - 20-03-04"
+        );
+
+        let format = DateTimeFormat {
+            clock: ClockFormat::Hours12,
+            date: DateFormat::MonthDayYear,
+        };
+        assert_eq!(
+            format.combat_identifier("Combat", &time),
+            "Combat | 01/31/2024 08:01:02 PM - 08:03:04 PM"
+        );
+        assert_eq!(
+            format.combat_file_identifier("Combat", &time),
+            "Combat 01-31-2024 08-01-02PM - 08-03-04PM"
+        );
+    }
+}
@@ -0,0 +1,82 @@
+use std::{
+    collections::VecDeque,
+    hash::{Hash, Hasher},
+};
+
+use chrono::{Duration, NaiveDateTime};
+use rustc_hash::{FxHashMap, FxHasher};
+
+use super::parser::Record;
+
+/// duplicated parts of the log are expected to follow the original within this time
+const DUPLICATE_WINDOW_SECONDS: i64 = 10;
+
+/// Finds records of blocks, which the game wrote to the log again.
+/// Identical lines are legitimate, as long as they are written right after each other
+/// (e.g. the ticks of several stacks of a DoT), so they are tracked as runs of identical lines.
+/// A record is a duplicate, if a recent run of lines identical to it was at least as long as its own run.
+/// Identical lines share their time, so only the recent runs have to be remembered.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct DuplicateRecords {
+    /// time and hash of the recent runs, oldest first
+    recent: VecDeque<(NaiveDateTime, u64)>,
+    /// lengths of the recent runs of each line, oldest first
+    runs: FxHashMap<u64, VecDeque<u32>>,
+    /// the run the last record belongs to
+    current_run: Option<Run>,
+}
+
+#[derive(Clone, Debug)]
+struct Run {
+    time: NaiveDateTime,
+    hash: u64,
+    length: u32,
+}
+
+impl DuplicateRecords {
+    /// Returns true, if the `record` repeats a recent run of identical records.
+    pub(crate) fn is_duplicate(&mut self, record: &Record) -> bool {
+        self.forget_before(record.time - Duration::seconds(DUPLICATE_WINDOW_SECONDS));
+
+        let mut hasher = FxHasher::default();
+        record.raw.trim_end().hash(&mut hasher);
+        let hash = hasher.finish();
+        let length = match &mut self.current_run {
+            Some(run) if run.hash == hash => {
+                run.length += 1;
+                run.length
+            }
+            _ => {
+                if let Some(run) = self.current_run.take() {
+                    self.recent.push_back((run.time, run.hash));
+                    self.runs.entry(run.hash).or_default().push_back(run.length);
+                }
+                self.current_run = Some(Run {
+                    time: record.time,
+                    hash,
+                    length: 1,
+                });
+                1
+            }
+        };
+
+        self.runs
+            .get(&hash)
+            .is_some_and(|runs| runs.iter().any(|l| *l >= length))
+    }
+
+    fn forget_before(&mut self, time: NaiveDateTime) {
+        while let Some(&(recent_time, hash)) = self.recent.front() {
+            if recent_time >= time {
+                return;
+            }
+            self.recent.pop_front();
+            if let Some(runs) = self.runs.get_mut(&hash) {
+                runs.pop_front();
+                if runs.is_empty() {
+                    self.runs.remove(&hash);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use std::{fs::File, path::Path};
+
+/// Identifies a file independent of its path, to detect when the file behind a path got replaced
+/// (e.g. the log got rotated while it was still opened).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileIdentity {
+    volume: u64,
+    index: u64,
+}
+
+impl FileIdentity {
+    pub fn of_path(path: &Path) -> Option<Self> {
+        Self::of_file(&File::open(path).ok()?)
+    }
+
+    #[cfg(unix)]
+    pub fn of_file(file: &File) -> Option<Self> {
+        use std::os::unix::fs::MetadataExt;
+
+        let metadata = file.metadata().ok()?;
+        Some(Self {
+            volume: metadata.dev(),
+            index: metadata.ino(),
+        })
+    }
+
+    #[cfg(windows)]
+    pub fn of_file(file: &File) -> Option<Self> {
+        use std::os::windows::io::AsRawHandle;
+        use windows_sys::Win32::Storage::FileSystem::{
+            GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION,
+        };
+
+        let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+        // SAFETY: the handle is valid for the lifetime of `file` and `info` is a valid out pointer
+        let result = unsafe { GetFileInformationByHandle(file.as_raw_handle() as _, &mut info) };
+        if result == 0 {
+            return None;
+        }
+        Some(Self {
+            volume: info.dwVolumeSerialNumber as u64,
+            index: ((info.nFileIndexHigh as u64) << 32) | info.nFileIndexLow as u64,
+        })
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    pub fn of_file(_file: &File) -> Option<Self> {
+        None
+    }
+}
@@ -0,0 +1,764 @@
+use super::{
+    values_manager::{Values, ValuesManager},
+    *,
+};
+use std::fmt::Debug;
+
+pub trait AnalysisGroup: Clone + Debug {
+    type Value: Clone;
+
+    fn name(&self) -> NameHandle;
+    fn sub_groups(&self) -> &NameMap<Self>;
+    fn sub_groups_mut(&mut self) -> &mut NameMap<Self>;
+
+    fn values(&self) -> &Values<Self::Value>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum GroupPathSegment {
+    Group(NameHandle),
+    Value(NameHandle),
+}
+
+impl Default for GroupPathSegment {
+    #[inline]
+    fn default() -> Self {
+        Self::Group(Default::default())
+    }
+}
+
+pub(super) trait AnalysisGroupInternal: AnalysisGroup {
+    fn new_leaf(segment: GroupPathSegment) -> Self;
+    fn new_branch(segment: GroupPathSegment) -> Self;
+
+    fn segment(&self) -> GroupPathSegment;
+    fn values_mut(&mut self) -> &mut Values<Self::Value>;
+    /// marks that values got added to the group or its sub groups
+    fn set_changed(&mut self);
+
+    #[inline]
+    fn is_leaf(&self) -> bool {
+        self.values().is_leaf()
+    }
+    #[inline]
+    fn is_branch(&self) -> bool {
+        self.values().is_branch()
+    }
+
+    /// Adds the values of the group to the parent group being tracked by the `manager`.
+    /// The ranges of branches move along, so they stay within the range of the parent.
+    fn add_values_to(&mut self, manager: &mut ValuesManager<Self::Value>) {
+        let to = manager.len();
+        manager.add_group(self.values());
+        if let Values::Branch(range) = self.values() {
+            let from = range.start;
+            self.move_branches(from, to);
+        }
+    }
+
+    /// Moves the values of the group from the `stale` manager to the end of the `manager`,
+    /// leaving out the values of ranges, which are not used anymore.
+    fn move_values(
+        &mut self,
+        stale: &ValuesManager<Self::Value>,
+        manager: &mut ValuesManager<Self::Value>,
+    ) {
+        if let Values::Branch(range) = self.values() {
+            let from = range.start;
+            let to = manager.add_from(stale, range.clone());
+            self.move_branches(from, to);
+        }
+    }
+
+    fn move_branches(&mut self, from: usize, to: usize) {
+        self.values_mut().move_branch(from, to);
+        self.sub_groups_mut()
+            .values_mut()
+            .for_each(|s| s.move_branches(from, to));
+    }
+
+    fn get_sub_group(&self, sub_group: GroupPathSegment) -> Option<&Self> {
+        self.sub_groups().get(&sub_group.name())
+    }
+
+    fn get_sub_group_mut(&mut self, sub_group: GroupPathSegment) -> Option<&mut Self> {
+        self.sub_groups_mut().get_mut(&sub_group.name())
+    }
+
+    /// The sub group gets values added, so all groups on the way to it are marked as changed.
+    fn get_leaf_sub_group(&mut self, sub_group: GroupPathSegment) -> &mut Self {
+        self.set_changed();
+        let candidate = self.get_sub_group(sub_group);
+
+        match candidate {
+            Some(candidate) if candidate.is_leaf() && candidate.segment() == sub_group => {
+                self.get_sub_group_mut(sub_group).unwrap()
+            }
+            Some(_) => self
+                .get_sub_group_mut(sub_group)
+                .unwrap()
+                .get_leaf_sub_group(sub_group),
+            None => {
+                let leaf = Self::new_leaf(sub_group);
+                self.sub_groups_mut().insert(sub_group.name(), leaf);
+                self.get_sub_group_mut(sub_group).unwrap()
+            }
+        }
+    }
+    /// see [`Self::get_leaf_sub_group`]
+    fn get_branch_sub_group(&mut self, sub_group: GroupPathSegment) -> &mut Self {
+        self.set_changed();
+        let candidate = self.get_sub_group(sub_group);
+
+        match candidate {
+            Some(candidate) if candidate.is_branch() && candidate.segment() == sub_group => {
+                self.get_sub_group_mut(sub_group).unwrap()
+            }
+            Some(candidate)
+                if (candidate.is_branch() && candidate.segment().is_value()
+                    || candidate.is_leaf()) =>
+            {
+                let value_or_leaf = self.sub_groups_mut().remove(&sub_group.name()).unwrap();
+                let mut branch = Self::new_branch(sub_group);
+                branch
+                    .sub_groups_mut()
+                    .insert(value_or_leaf.name(), value_or_leaf);
+                self.sub_groups_mut().insert(sub_group.name(), branch);
+                self.get_sub_group_mut(sub_group).unwrap()
+            }
+            Some(_) => self
+                .get_sub_group_mut(sub_group)
+                .unwrap()
+                .get_branch_sub_group(sub_group),
+            None => {
+                let branch = Self::new_branch(sub_group);
+                self.sub_groups_mut().insert(sub_group.name(), branch);
+                self.get_sub_group_mut(sub_group).unwrap()
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Educe, Default)]
+#[educe(Deref, DerefMut)]
+pub struct DamageGroup {
+    pub segment: GroupPathSegment,
+    pub sub_groups: NameMap<Self>,
+
+    #[educe(Deref, DerefMut)]
+    pub damage_metrics: DamageMetrics,
+    pub max_one_hit: MaxOneHit,
+    /// share of the damage of the parent group
+    pub damage_percentage: ShieldHullOptionalValues,
+    /// share of the total damage of the combat
+    pub combat_damage_percentage: ShieldHullOptionalValues,
+    pub hits_percentage: ShieldHullOptionalValues,
+    pub hits: Hits,
+    pub damage_types: NameSet,
+
+    /// time share of the combat, in which the hits occurred, only known for leafs
+    pub uptime_percentage: Option<f64>,
+    /// only known for leafs
+    pub activity_times: Option<ActivityTimes>,
+
+    pub kills: NameMap<u32>,
+    /// killed target and the time of the kill in milliseconds since the combat start
+    pub kill_times: Vec<(NameHandle, u32)>,
+
+    /// whether hits got added since the last recalculation
+    pub(super) changed: bool,
+}
+
+impl AnalysisGroup for DamageGroup {
+    #[inline]
+    fn name(&self) -> NameHandle {
+        self.segment.name()
+    }
+
+    #[inline]
+    fn sub_groups(&self) -> &NameMap<Self> {
+        &self.sub_groups
+    }
+
+    #[inline]
+    fn sub_groups_mut(&mut self) -> &mut NameMap<Self> {
+        &mut self.sub_groups
+    }
+
+    type Value = Hit;
+
+    #[inline]
+    fn values(&self) -> &Values<Self::Value> {
+        &self.hits
+    }
+}
+
+impl AnalysisGroupInternal for DamageGroup {
+    fn new_leaf(segment: GroupPathSegment) -> Self {
+        Self {
+            segment,
+            hits: Values::empty_leaf(),
+            changed: true,
+            ..Default::default()
+        }
+    }
+
+    fn new_branch(segment: GroupPathSegment) -> Self {
+        Self {
+            segment,
+            hits: Values::empty_branch(),
+            changed: true,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    fn segment(&self) -> GroupPathSegment {
+        self.segment
+    }
+
+    #[inline]
+    fn values_mut(&mut self) -> &mut Values<Self::Value> {
+        &mut self.hits
+    }
+
+    #[inline]
+    fn set_changed(&mut self) {
+        self.changed = true;
+    }
+}
+
+#[derive(Clone, Debug, Educe, Default)]
+#[educe(Deref, DerefMut)]
+pub struct HealGroup {
+    pub segment: GroupPathSegment,
+    pub sub_groups: NameMap<Self>,
+
+    #[educe(Deref, DerefMut)]
+    pub heal_metrics: HealMetrics,
+
+    /// share of the heal of the parent group
+    pub heal_percentage: ShieldHullOptionalValues,
+    /// share of the total heal of the combat
+    pub combat_heal_percentage: ShieldHullOptionalValues,
+    pub ticks_percentage: ShieldHullOptionalValues,
+    /// for outgoing heal split by the target, for incoming heal by the source
+    pub target_split: HealTargetSplit,
+    /// time share of the combat, in which the ticks occurred, only known for leafs
+    pub uptime_percentage: Option<f64>,
+
+    pub ticks: HealTicks,
+
+    /// see [`active_millis`], only known for leafs
+    active_millis: Option<u64>,
+    /// whether ticks got added since the last recalculation
+    pub(super) changed: bool,
+}
+
+impl AnalysisGroup for HealGroup {
+    #[inline]
+    fn name(&self) -> NameHandle {
+        self.segment.name()
+    }
+
+    #[inline]
+    fn sub_groups(&self) -> &NameMap<Self> {
+        &self.sub_groups
+    }
+
+    #[inline]
+    fn sub_groups_mut(&mut self) -> &mut NameMap<Self> {
+        &mut self.sub_groups
+    }
+
+    type Value = HealTick;
+
+    #[inline]
+    fn values(&self) -> &Values<Self::Value> {
+        &self.ticks
+    }
+}
+
+impl AnalysisGroupInternal for HealGroup {
+    fn new_leaf(segment: GroupPathSegment) -> Self {
+        Self {
+            segment,
+            ticks: Values::empty_leaf(),
+            changed: true,
+            ..Default::default()
+        }
+    }
+
+    fn new_branch(segment: GroupPathSegment) -> Self {
+        Self {
+            segment,
+            ticks: Values::empty_branch(),
+            changed: true,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    fn segment(&self) -> GroupPathSegment {
+        self.segment
+    }
+
+    #[inline]
+    fn values_mut(&mut self) -> &mut Values<Self::Value> {
+        &mut self.ticks
+    }
+
+    #[inline]
+    fn set_changed(&mut self) {
+        self.changed = true;
+    }
+}
+
+impl DamageGroup {
+    /// Applies the hits added since the last call. Groups without new hits keep the values,
+    /// which don't depend on the combat duration, and their range in the `hits_manager`.
+    /// Branches with new hits are tracked again at the end of the `hits_manager`,
+    /// see [`Combat::drop_stale_values`] for the values they leave behind.
+    pub(super) fn recalculate_metrics(
+        &mut self,
+        combat_duration: f64,
+        uptime_gap_millis: u32,
+        exclude_anomalies: bool,
+        hits_manager: &mut HitsManager,
+        apply_delta: &mut dyn FnMut(&DamageMetricsDelta, &MaxOneHit),
+    ) {
+        if self.is_leaf() {
+            let hits = self.hits.get_leaf();
+            if self.changed {
+                self.activity_times =
+                    ActivityTimes::new(hits.iter().map(|h| h.time_millis), uptime_gap_millis);
+            }
+            self.uptime_percentage = active_millis_percentage(
+                self.activity_times.map(|a| a.active_millis),
+                combat_duration,
+            );
+            let delta_hits = &hits[self.damage_metrics.hits.all as usize..];
+            if delta_hits.len() > 0 {
+                self.max_one_hit
+                    .update_from_hits(self.name(), delta_hits, exclude_anomalies);
+                let delta = self.damage_metrics.calc_and_apply_delta(delta_hits);
+                apply_delta(&delta, &self.max_one_hit);
+            }
+        } else {
+            let changed = self.changed;
+            let hits = hits_manager.track_group(|hits_manager| {
+                for sub_group in self.sub_groups.values_mut() {
+                    // changed branches are tracked again within the range of this group
+                    let tracked_again = sub_group.changed && sub_group.is_branch();
+                    sub_group.recalculate_metrics(
+                        combat_duration,
+                        uptime_gap_millis,
+                        exclude_anomalies,
+                        hits_manager,
+                        &mut |d, m| {
+                            self.damage_metrics.apply_delta(d);
+                            self.max_one_hit.update_from_max(m);
+                            if self.segment.is_value() {
+                                self.max_one_hit.name = self.segment.name();
+                            }
+                            apply_delta(d, &self.max_one_hit);
+                        },
+                    );
+                    if changed && !tracked_again {
+                        sub_group.add_values_to(hits_manager);
+                    }
+                }
+            });
+            if changed {
+                self.hits = hits;
+            }
+
+            if self.changed {
+                self.kills.clear();
+                self.kill_times.clear();
+                for sub_group in self.sub_groups.values() {
+                    for damage_type in sub_group.damage_types.iter() {
+                        if !self.damage_types.contains(damage_type) {
+                            self.damage_types.insert(damage_type.clone());
+                        }
+                    }
+
+                    for (&name, &kills) in sub_group.kills.iter() {
+                        *self.kills.entry(name).or_default() += kills;
+                    }
+                    self.kill_times.extend_from_slice(&sub_group.kill_times);
+                }
+                self.kill_times.sort_unstable_by_key(|(_, time)| *time);
+            }
+        }
+        self.damage_metrics
+            .recalculate_time_based_metrics(combat_duration);
+    }
+
+    /// Only the groups, whose totals or the totals of their parents changed, get updated,
+    /// unless `force` is set, e.g. since the combat totals changed.
+    /// Finishes the recalculation started with [`Self::recalculate_metrics`].
+    pub(super) fn recalculate_percentages(
+        &mut self,
+        combat_total_damage: &ShieldHullValues,
+        parent_total_damage: &ShieldHullValues,
+        parent_hits: &ShieldHullCounts,
+        force: bool,
+    ) {
+        if !force && !self.changed {
+            return;
+        }
+
+        self.damage_percentage =
+            ShieldHullOptionalValues::percentage(&self.total_damage, parent_total_damage);
+        self.combat_damage_percentage =
+            ShieldHullOptionalValues::percentage(&self.total_damage, combat_total_damage);
+        self.hits_percentage = ShieldHullOptionalValues::percentage(
+            &self.damage_metrics.hits.to_values(),
+            &parent_hits.to_values(),
+        );
+        self.sub_groups.values_mut().for_each(|s| {
+            s.recalculate_percentages(
+                combat_total_damage,
+                &self.damage_metrics.total_damage,
+                &self.damage_metrics.hits,
+                force || self.changed,
+            )
+        });
+        self.changed = false;
+    }
+
+    /// `killed` is the target, if the damage killed it, as the target can be anywhere within the `path`
+    pub(super) fn add_damage(
+        &mut self,
+        path: &[GroupPathSegment],
+        hit: BaseHit,
+        killed: Option<NameHandle>,
+        damage_type: NameHandle,
+        combat_start_offset_millis: u32,
+        name_manager: &NameManager,
+    ) {
+        if path.len() == 1 {
+            let indirect_source = self.get_leaf_sub_group(path[0]);
+            indirect_source
+                .hits
+                .push(hit.to_hit(combat_start_offset_millis));
+            indirect_source.changed = true;
+            indirect_source.add_damage_type_non_pool(damage_type, name_manager);
+
+            if let Some(target) = killed {
+                *indirect_source.kills.entry(target).or_default() += 1;
+                indirect_source
+                    .kill_times
+                    .push((target, combat_start_offset_millis));
+            }
+
+            return;
+        }
+
+        let indirect_source = self.get_branch_sub_group(*path.last().unwrap());
+        indirect_source.add_damage(
+            &path[..path.len() - 1],
+            hit,
+            killed,
+            damage_type,
+            combat_start_offset_millis,
+            name_manager,
+        );
+    }
+
+    /// Adds the hits of `other`, the same group of another combat, as if they were recorded in this combat.
+    /// `name` maps the name handles of the other combat to the ones of this combat and `time_millis` moves
+    /// the time of a hit into this combat or returns `None` to leave the hit out. Sub groups without hits are left out.
+    pub(super) fn merge(
+        &mut self,
+        other: &Self,
+        name: &impl Fn(NameHandle) -> NameHandle,
+        time_millis: &impl Fn(u32) -> Option<u32>,
+        name_manager: &NameManager,
+    ) {
+        if let Values::Leaf(hits) = &other.hits {
+            for hit in hits.iter() {
+                if let Some(time_millis) = time_millis(hit.time_millis) {
+                    self.hits.push(Hit {
+                        time_millis,
+                        ..*hit
+                    });
+                }
+            }
+            self.changed = true;
+            for &damage_type in other.damage_types.iter() {
+                self.add_damage_type_non_pool(name(damage_type), name_manager);
+            }
+            for &(target, time) in other.kill_times.iter() {
+                if let Some(time) = time_millis(time) {
+                    *self.kills.entry(name(target)).or_default() += 1;
+                    self.kill_times.push((name(target), time));
+                }
+            }
+            return;
+        }
+
+        for sub_group in other.sub_groups.values() {
+            if !sub_group.has_hits(time_millis) {
+                continue;
+            }
+            let segment = sub_group.segment.with_name(name(sub_group.name()));
+            let merged = if sub_group.is_leaf() {
+                self.get_leaf_sub_group(segment)
+            } else {
+                self.get_branch_sub_group(segment)
+            };
+            merged.merge(sub_group, name, time_millis, name_manager);
+        }
+    }
+
+    /// whether any hit of the leafs is kept by `time_millis`, see [`Self::merge`]
+    pub(super) fn has_hits(&self, time_millis: &impl Fn(u32) -> Option<u32>) -> bool {
+        match &self.hits {
+            Values::Leaf(hits) => hits.iter().any(|h| time_millis(h.time_millis).is_some()),
+            Values::Branch(_) => self.sub_groups.values().any(|s| s.has_hits(time_millis)),
+        }
+    }
+
+    /// calls `f` for the hits of all leafs, which don't depend on the hits manager
+    pub(super) fn for_each_leaf_hit(&self, f: &mut impl FnMut(&Hit)) {
+        match &self.hits {
+            Values::Leaf(hits) => hits.iter().for_each(&mut *f),
+            Values::Branch(_) => self
+                .sub_groups
+                .values()
+                .for_each(|s| s.for_each_leaf_hit(f)),
+        }
+    }
+
+    pub(super) fn add_damage_type_non_pool(
+        &mut self,
+        damage_type: NameHandle,
+        name_manager: &NameManager,
+    ) {
+        let shield_handle = name_manager.get_handle("Shield");
+        if damage_type == NameHandle::UNKNOWN {
+            return;
+        }
+
+        if self.damage_types.contains(&damage_type) {
+            return;
+        }
+
+        if self.damage_types.contains(&damage_type) {
+            return;
+        }
+
+        if Some(damage_type) == shield_handle && !self.damage_types.is_empty() {
+            return;
+        }
+
+        if shield_handle
+            .map(|s| damage_type != s && self.damage_types.contains(&s))
+            .unwrap_or(false)
+        {
+            self.damage_types.remove(&shield_handle.unwrap());
+        }
+
+        self.damage_types.insert(damage_type);
+    }
+}
+
+impl HealGroup {
+    /// see [`DamageGroup::recalculate_metrics`]
+    pub(super) fn recalculate_metrics(
+        &mut self,
+        combat_duration: f64,
+        uptime_gap_millis: u32,
+        ticks_manager: &mut HealTicksManager,
+        apply_delta: &mut dyn FnMut(&HealMetricsDelta),
+    ) {
+        if self.is_leaf() {
+            let ticks = self.ticks.get_leaf();
+            if self.changed {
+                self.active_millis =
+                    active_millis(ticks.iter().map(|t| t.time_millis), uptime_gap_millis);
+            }
+            self.uptime_percentage = active_millis_percentage(self.active_millis, combat_duration);
+            let delta_ticks = &ticks[self.heal_metrics.ticks.all as usize..];
+            if delta_ticks.len() > 0 {
+                let delta = self.heal_metrics.calc_and_apply(delta_ticks);
+                apply_delta(&delta);
+            }
+        } else {
+            let changed = self.changed;
+            let ticks = ticks_manager.track_group(|ticks_manager| {
+                for sub_group in self.sub_groups.values_mut() {
+                    let tracked_again = sub_group.changed && sub_group.is_branch();
+                    sub_group.recalculate_metrics(
+                        combat_duration,
+                        uptime_gap_millis,
+                        ticks_manager,
+                        &mut |d| {
+                            self.heal_metrics.apply_delta(d);
+                            apply_delta(d);
+                        },
+                    );
+                    if changed && !tracked_again {
+                        sub_group.add_values_to(ticks_manager);
+                    }
+                }
+            });
+            if changed {
+                self.ticks = ticks;
+            }
+        }
+        self.heal_metrics
+            .recalculate_time_based_metrics(combat_duration);
+    }
+
+    /// see [`DamageGroup::recalculate_percentages`]
+    pub(super) fn recalculate_percentages(
+        &mut self,
+        combat_total_heal: &ShieldHullValues,
+        parent_total_heal: &ShieldHullValues,
+        parent_ticks: &ShieldHullCounts,
+        force: bool,
+    ) {
+        if !force && !self.changed {
+            return;
+        }
+
+        self.heal_percentage =
+            ShieldHullOptionalValues::percentage(&self.total_heal, parent_total_heal);
+        self.combat_heal_percentage =
+            ShieldHullOptionalValues::percentage(&self.total_heal, combat_total_heal);
+        self.ticks_percentage = ShieldHullOptionalValues::percentage(
+            &self.heal_metrics.ticks.to_values(),
+            &parent_ticks.to_values(),
+        );
+        self.sub_groups.values_mut().for_each(|s| {
+            s.recalculate_percentages(
+                combat_total_heal,
+                &self.heal_metrics.total_heal,
+                &self.heal_metrics.ticks,
+                force || self.changed,
+            )
+        });
+        self.changed = false;
+    }
+
+    pub(super) fn add_heal(
+        &mut self,
+        path: &[GroupPathSegment],
+        tick: BaseHealTick,
+        flags: ValueFlags,
+        combat_start_offset_millis: u32,
+        to_self: bool,
+    ) {
+        self.target_split.add(&tick, to_self);
+        if path.len() == 1 {
+            let indirect_source = self.get_leaf_sub_group(path[0]);
+            indirect_source.target_split.add(&tick, to_self);
+            indirect_source
+                .ticks
+                .push(tick.to_tick(combat_start_offset_millis));
+            indirect_source.changed = true;
+
+            return;
+        }
+
+        let indirect_source = self.get_branch_sub_group(*path.last().unwrap());
+        indirect_source.add_heal(
+            &path[..path.len() - 1],
+            tick,
+            flags,
+            combat_start_offset_millis,
+            to_self,
+        );
+    }
+}
+
+impl HealGroup {
+    /// See [`DamageGroup::merge`]. Returns the heal added to the [`HealGroup::target_split`].
+    pub(super) fn merge(
+        &mut self,
+        other: &Self,
+        name: &impl Fn(NameHandle) -> NameHandle,
+        time_millis: &impl Fn(u32) -> Option<u32>,
+    ) -> HealTargetSplit {
+        let mut target_split = HealTargetSplit::default();
+        if let Values::Leaf(ticks) = &other.ticks {
+            // the target or source is part of the path to the leaf, so the ticks of a leaf all went to the same one
+            let to_self = other.target_split.self_ticks > 0;
+            for tick in ticks.iter() {
+                if let Some(time_millis) = time_millis(tick.time_millis) {
+                    target_split.add(tick, to_self);
+                    self.ticks.push(HealTick {
+                        time_millis,
+                        ..*tick
+                    });
+                }
+            }
+            self.changed = true;
+        } else {
+            for sub_group in other.sub_groups.values() {
+                if !sub_group.has_ticks(time_millis) {
+                    continue;
+                }
+                let segment = sub_group.segment.with_name(name(sub_group.name()));
+                let merged = if sub_group.is_leaf() {
+                    self.get_leaf_sub_group(segment)
+                } else {
+                    self.get_branch_sub_group(segment)
+                };
+                target_split += merged.merge(sub_group, name, time_millis);
+            }
+        }
+        self.target_split += target_split;
+        target_split
+    }
+
+    /// see [`DamageGroup::has_hits`]
+    pub(super) fn has_ticks(&self, time_millis: &impl Fn(u32) -> Option<u32>) -> bool {
+        match &self.ticks {
+            Values::Leaf(ticks) => ticks.iter().any(|t| time_millis(t.time_millis).is_some()),
+            Values::Branch(_) => self.sub_groups.values().any(|s| s.has_ticks(time_millis)),
+        }
+    }
+
+    /// see [`DamageGroup::for_each_leaf_hit`]
+    pub(super) fn for_each_leaf_tick(&self, f: &mut impl FnMut(&HealTick)) {
+        match &self.ticks {
+            Values::Leaf(ticks) => ticks.iter().for_each(&mut *f),
+            Values::Branch(_) => self
+                .sub_groups
+                .values()
+                .for_each(|s| s.for_each_leaf_tick(f)),
+        }
+    }
+}
+
+impl GroupPathSegment {
+    /// the same kind of segment with another name
+    #[inline]
+    pub fn with_name(&self, name: NameHandle) -> Self {
+        match *self {
+            GroupPathSegment::Group(_) => GroupPathSegment::Group(name),
+            GroupPathSegment::Value(_) => GroupPathSegment::Value(name),
+        }
+    }
+
+    #[inline]
+    pub fn name(&self) -> NameHandle {
+        match *self {
+            GroupPathSegment::Group(n) => n,
+            GroupPathSegment::Value(n) => n,
+        }
+    }
+
+    #[inline]
+    pub fn is_value(&self) -> bool {
+        if let Self::Value(_) = self {
+            return true;
+        }
+
+        false
+    }
+}
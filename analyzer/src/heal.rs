@@ -1,3 +1,5 @@
+use std::ops::AddAssign;
+
 use educe::Educe;
 
 use super::*;
@@ -26,18 +28,25 @@ pub enum SpecificHealTick {
 #[derive(Clone, Default, Debug)]
 pub struct HealMetrics {
     pub ticks: ShieldHullCounts,
+    /// ticks, which healed nothing, e.g. shield heals on full facings. They are part of
+    /// [`Self::ticks`], but are left out of the average heal.
+    pub zero_ticks: ShieldHullCounts,
     pub ticks_per_second: ShieldHullValues,
     pub total_heal: ShieldHullValues,
     pub hps: ShieldHullValues,
     pub average_heal: ShieldHullOptionalValues,
     pub critical_percentage: Option<f64>,
+    pub average_crit_heal: Option<f64>,
+    pub total_crit_heal: f64,
     pub crits: u64,
 }
 
 #[derive(Clone, Default, Debug)]
 pub struct HealMetricsDelta {
     pub ticks: ShieldHullCounts,
+    pub zero_ticks: ShieldHullCounts,
     pub total_heal: ShieldHullValues,
+    pub total_crit_heal: f64,
     pub crits: u64,
 }
 
@@ -71,23 +80,28 @@ impl HealMetrics {
         let mut delta = HealMetricsDelta::default();
 
         for tick in delta_ticks.iter() {
+            let zero_tick = (tick.amount == 0.0) as u64;
             match tick.specific {
                 SpecificHealTick::Shield => {
                     delta.ticks.shield += 1;
+                    delta.zero_ticks.shield += zero_tick;
                     delta.total_heal.shield += tick.amount;
                 }
                 SpecificHealTick::Hull => {
                     delta.ticks.hull += 1;
+                    delta.zero_ticks.hull += zero_tick;
                     delta.total_heal.hull += tick.amount;
                 }
             }
 
             if tick.flags.contains(ValueFlags::CRITICAL) {
                 delta.crits += 1;
+                delta.total_crit_heal += tick.amount;
             }
         }
 
         delta.ticks.all = delta.ticks.shield + delta.ticks.hull;
+        delta.zero_ticks.all = delta.zero_ticks.shield + delta.zero_ticks.hull;
         delta.total_heal.all = delta.total_heal.shield + delta.total_heal.hull;
 
         self.apply_delta(&delta);
@@ -97,17 +111,24 @@ impl HealMetrics {
 
     pub fn apply_delta(&mut self, delta: &HealMetricsDelta) {
         self.ticks += delta.ticks;
+        self.zero_ticks += delta.zero_ticks;
         self.total_heal += delta.total_heal;
         self.crits += delta.crits;
+        self.total_crit_heal += delta.total_crit_heal;
 
         self.average_heal = ShieldHullOptionalValues::average(
             &self.total_heal,
-            self.ticks.shield,
-            self.ticks.hull,
-            self.ticks.all,
+            self.ticks.shield - self.zero_ticks.shield,
+            self.ticks.hull - self.zero_ticks.hull,
+            self.ticks.all - self.zero_ticks.all,
         );
 
         self.critical_percentage = percentage_u64(self.crits, self.ticks.hull);
+        self.average_crit_heal = if self.crits == 0 {
+            None
+        } else {
+            Some(self.total_crit_heal / self.crits as f64)
+        };
     }
 
     pub fn recalculate_time_based_metrics(&mut self, active_duration: f64) {
@@ -117,3 +138,48 @@ impl HealMetrics {
         self.hps = ShieldHullValues::per_seconds(&self.total_heal, active_duration);
     }
 }
+
+/// heal split by whether it went to the player themselves or to someone else
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HealTargetSplit {
+    pub to_self: ShieldHullValues,
+    pub to_others: ShieldHullValues,
+    pub self_ticks: u64,
+    pub others_ticks: u64,
+}
+
+impl HealTargetSplit {
+    pub(crate) fn add(&mut self, tick: &BaseHealTick, to_self: bool) {
+        let (heal, ticks) = if to_self {
+            (&mut self.to_self, &mut self.self_ticks)
+        } else {
+            (&mut self.to_others, &mut self.others_ticks)
+        };
+        match tick.specific {
+            SpecificHealTick::Shield => heal.shield += tick.amount,
+            SpecificHealTick::Hull => heal.hull += tick.amount,
+        }
+        heal.all += tick.amount;
+        *ticks += 1;
+    }
+
+    /// share of the heal, which went to the player themselves
+    pub fn self_percentage(&self) -> Option<f64> {
+        percentage_f64(self.to_self.all, self.to_self.all + self.to_others.all)
+    }
+
+    /// share of the heal, which went to someone else
+    pub fn others_percentage(&self) -> Option<f64> {
+        percentage_f64(self.to_others.all, self.to_self.all + self.to_others.all)
+    }
+}
+
+impl AddAssign for HealTargetSplit {
+    #[inline]
+    fn add_assign(&mut self, rhs: Self) {
+        self.to_self += rhs.to_self;
+        self.to_others += rhs.to_others;
+        self.self_ticks += rhs.self_ticks;
+        self.others_ticks += rhs.others_ticks;
+    }
+}
@@ -0,0 +1,2548 @@
+//! Parsing and analysis of Star Trek Online combat logs.
+//!
+//! Create an [`Analyzer`] with [`AnalysisSettings`](settings::AnalysisSettings) pointing to a combat log,
+//! call [`Analyzer::update`] whenever the log has grown and read the combats via [`Analyzer::result`].
+//! Serialization of the settings is available with the `serde` feature.
+
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, VecDeque},
+    fmt::Debug,
+    fs::File,
+    io::{self, BufReader, Read, Seek, SeekFrom},
+    ops::Range,
+    path::Path,
+};
+
+use chrono::{Duration, NaiveDateTime};
+use educe::Educe;
+use itertools::Itertools;
+use log::warn;
+use rustc_hash::FxHashMap;
+use smallvec::SmallVec;
+
+mod common;
+mod damage;
+mod date_time_format;
+mod duplicates;
+mod file_identity;
+mod groups;
+mod heal;
+mod name_manager;
+mod parser;
+pub mod settings;
+#[cfg(any(test, feature = "test-log"))]
+pub mod test_log;
+mod values_manager;
+pub use common::*;
+pub use damage::*;
+pub use date_time_format::*;
+use groups::*;
+pub use groups::{AnalysisGroup, DamageGroup, HealGroup};
+pub use heal::*;
+pub use name_manager::*;
+pub use parser::{Entity, Record, RecordValue};
+pub use values_manager::*;
+
+use self::{duplicates::DuplicateRecords, parser::*, settings::*};
+
+pub struct Analyzer {
+    parser: Parser,
+    processor: RecordProcessor,
+    combats: Vec<Combat>,
+    invalid_records: InvalidRecords,
+}
+
+/// lines of the log, which could not be parsed and were skipped
+#[derive(Clone, Debug, Default)]
+pub struct InvalidRecords {
+    pub count: u64,
+    /// the first [`InvalidRecords::LISTED`] of them
+    pub lines: Vec<String>,
+}
+
+/// assigns the records to combats and players according to the settings
+struct RecordProcessor {
+    combat_separation_time: Duration,
+    settings: AnalysisSettings,
+    player_merges: FxHashMap<String, String>,
+}
+
+/// below this combat time of a player no kills or deaths per minute are calculated
+const MIN_RATE_DURATION_SECONDS: i64 = 30;
+
+/// bytes at the end of a log read by [`Analyzer::is_log_end_valid`]
+const LOG_END_CHECK_BYTES: u64 = 1 << 16; // 64KB
+
+type Players = NameMap<Player>;
+type GroupingPath = SmallVec<[GroupPathSegment; 8]>;
+
+#[derive(Clone, Debug)]
+pub struct Combat {
+    pub combat_names: FxHashMap<String, CombatName>,
+    pub dps_denominator: DpsDenominator,
+    pub combat_time: Option<Range<NaiveDateTime>>,
+    pub active_time: Range<NaiveDateTime>,
+    pub total_damage_out: ShieldHullValues,
+    pub total_damage_in: ShieldHullValues,
+    pub total_heal_in: ShieldHullValues,
+    pub total_heal_out: ShieldHullValues,
+    pub players: Players,
+    pub log_pos: Option<Range<u64>>,
+    /// file name of the log the combat was read from, part of the [`Combat::identity`]
+    pub log_file_name: String,
+    pub total_deaths: u32,
+    pub total_kills: u32,
+    pub name_manager: NameManager,
+    pub hits_manger: HitsManager,
+    pub heal_ticks_manger: HealTicksManager,
+    /// records with implausible values, see [`AnalysisSettings::anomaly_threshold`]
+    pub anomalies: Vec<Anomaly>,
+    /// copied from [`AnalysisSettings::exclude_anomalies`]
+    pub exclude_anomalies: bool,
+    /// records with a time before the one of the previous record (e.g. due to client hitching)
+    pub out_of_order_records: u32,
+    /// see [`AnalysisSettings::drop_duplicate_records`]
+    pub dropped_duplicate_records: u32,
+    pub applied_rules: AppliedRules,
+    /// see [`AnalysisSettings::digest`]
+    pub settings_digest: String,
+    /// players with the same character name as another player, see [`NameManager::ambiguous_players`]
+    pub ambiguous_players: NameSet,
+    /// identities of the combats this one got merged from, see [`Analyzer::analyze_merged`]
+    pub merged_combats: Vec<String>,
+    /// name given to the combat by the user, replacing the one of the [`Combat::combat_names`]
+    pub name_override: Option<String>,
+    /// milliseconds since the start of the combat this one got sliced from, see [`Analyzer::analyze_time_slice`]
+    pub time_slice: Option<Range<u32>>,
+    /// first combat time start of any player, which reached the
+    /// [`AnalysisSettings::combat_start_damage_threshold`]
+    group_combat_start: Option<NaiveDateTime>,
+    duplicate_records: DuplicateRecords,
+    /// amount of the [`Combat::anomalies`] already logged, see [`Combat::log_anomalies`]
+    logged_anomalies: usize,
+}
+
+/// the rules of the [`AnalysisSettings`], which matched at least one record or name of a combat, by their index.
+/// They are copied, as the settings may have changed since the combat got analyzed.
+#[derive(Clone, Debug, Default)]
+pub struct AppliedRules {
+    pub exclusion_rules: BTreeMap<usize, MatchRule>,
+    /// names of the custom groups
+    pub grouping_rules: BTreeMap<usize, String>,
+    pub reversal_rules: BTreeMap<usize, MatchRule>,
+    /// names of the combat names
+    pub combat_name_rules: BTreeMap<usize, String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Anomaly {
+    pub time: NaiveDateTime,
+    pub value_name: String,
+    pub amount: f64,
+    pub raw: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct CombatName {
+    pub name: String,
+    pub additional_infos: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Player {
+    pub combat_time: Option<Range<NaiveDateTime>>,
+    pub active_time: Option<Range<NaiveDateTime>>,
+    pub damage_out: DamageGroup,
+    pub damage_in: DamageGroup,
+    pub heal_out: HealGroup,
+    pub heal_in: HealGroup,
+    pub heal_in_split: HealInSplit,
+    /// time of the first outgoing damage, if the combat time starts after it,
+    /// see [`AnalysisSettings::combat_start_damage_threshold`]
+    pub pre_combat_damage_start: Option<NaiveDateTime>,
+    /// first to last outgoing damage, regardless of the threshold
+    damage_time: Option<Range<NaiveDateTime>>,
+    /// damage within the window, while the threshold is not reached yet
+    pending_combat_damage: VecDeque<(NaiveDateTime, f64)>,
+    /// the combat time is the damage time, as the threshold is not reached yet
+    combat_time_is_fallback: bool,
+    /// of the outgoing damage
+    pub peak_dps: PeakDps,
+}
+
+/// incoming healing split into healing from the player themselves and from anyone else
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HealInSplit {
+    pub from_self: ShieldHullValues,
+    pub from_others: ShieldHullValues,
+}
+
+impl Analyzer {
+    /// Opens the combat log of the settings. Returns `None` if the file can not be opened.
+    /// Nothing is parsed until [`Analyzer::update`] is called.
+    pub fn new(settings: AnalysisSettings) -> Option<Self> {
+        Some(Self {
+            parser: Parser::new(settings.combatlog_file())?,
+            processor: RecordProcessor::new(settings),
+            combats: Default::default(),
+            invalid_records: Default::default(),
+        })
+    }
+
+    /// Parses all records appended to the log since the last update and recalculates the modified combats.
+    /// `on_recalculate` is called with the index of the combat about to be recalculated and the total combat count.
+    /// `is_cancelled` is polled regularly, once it returns true the update stops and the result is left incomplete.
+    /// Returns false if the update got cancelled and an error if reading the log failed.
+    /// In both cases the result is left incomplete.
+    pub fn update(
+        &mut self,
+        is_cancelled: impl Fn() -> bool,
+        on_recalculate: impl FnMut(usize, usize),
+    ) -> io::Result<bool> {
+        self.processor.analyze(
+            &mut self.parser,
+            &mut self.combats,
+            &mut self.invalid_records,
+            is_cancelled,
+            on_recalculate,
+        )
+    }
+
+    /// Analyzes only the records starting within the byte `range` of the log, e.g. the
+    /// [`Combat::log_pos`] of a combat, the same way as [`Analyzer::update`] analyzes the whole log.
+    /// The result is independent of the combats found by the updates.
+    /// Returns no combats, if the log can not be read.
+    pub fn analyze_range(&self, range: Range<u64>) -> Vec<Combat> {
+        let mut combats = Vec::new();
+        let mut parser = match Parser::new(self.settings().combatlog_file()) {
+            Some(p) => p,
+            None => return combats,
+        };
+        if let Err(err) = parser.set_range(range) {
+            warn!("failed to seek in the log: {}", err);
+            return combats;
+        }
+
+        if let Err(err) = self.processor.analyze(
+            &mut parser,
+            &mut combats,
+            &mut InvalidRecords::default(),
+            || false,
+            |_, _| (),
+        ) {
+            warn!("failed to read the log: {}", err);
+            combats.clear();
+        }
+        combats
+    }
+
+    /// Analyzes several combats as one, e.g. to summarize multiple runs of the same mission.
+    /// The values of the combats are merged in the given order, each combat moved in time to directly follow
+    /// the previous one, so the time between them neither counts towards the combat time nor splits the result.
+    /// The players and their groups are matched by name. Returns `None` if there are no combats.
+    pub fn analyze_merged<'a>(
+        settings: &AnalysisSettings,
+        combats: impl IntoIterator<Item = &'a Combat>,
+    ) -> Option<Combat> {
+        let mut merged: Option<Combat> = None;
+        for combat in combats {
+            merged
+                .get_or_insert_with(|| {
+                    Combat::empty(combat.active_time.start, combat.log_file_name.clone())
+                })
+                .merge(combat);
+        }
+
+        let mut merged = merged?;
+        merged.update(settings);
+        Some(merged)
+    }
+
+    /// Analyzes only the values of the `combat` within the `time_slice`, given in milliseconds since the start of the
+    /// combat, e.g. to get the values of a single phase of a long mission. Gaps within the slice do not split the result.
+    /// The sliced combat starts with the slice and keeps the [`Combat::identity`] of the `combat`.
+    /// Returns `None` if nothing happened in the slice.
+    pub fn analyze_time_slice(
+        settings: &AnalysisSettings,
+        combat: &Combat,
+        time_slice: Range<u32>,
+    ) -> Option<Combat> {
+        let mut sliced = combat.slice(time_slice)?;
+        sliced.update(settings);
+        Some(sliced)
+    }
+
+    /// true if the file at the path of the log is not the opened one anymore (e.g. it got rotated),
+    /// so the combats found so far are not in that file
+    pub fn is_log_replaced(&self) -> bool {
+        self.parser
+            .is_file_replaced(self.processor.settings.combatlog_file())
+    }
+
+    /// true if the log got shorter than what was parsed so far (e.g. it got cleared by another tool),
+    /// so the combats found so far are not in the log anymore
+    pub fn is_log_truncated(&mut self) -> bool {
+        let Some(pos) = self.parser.pos() else {
+            return false;
+        };
+        std::fs::metadata(self.settings().combatlog_file()).is_ok_and(|m| m.len() < pos)
+    }
+
+    /// All combats found so far, in the order they appear in the log.
+    pub fn result(&self) -> &Vec<Combat> {
+        &self.combats
+    }
+
+    /// All combats found so far, for when the log is not analyzed any further.
+    pub fn into_result(self) -> Vec<Combat> {
+        self.combats
+    }
+
+    /// lines skipped by the updates so far
+    pub fn invalid_records(&self) -> &InvalidRecords {
+        &self.invalid_records
+    }
+
+    /// true if the log is gzip compressed, so its combats have no [`Combat::log_pos`]
+    pub fn is_log_compressed(&self) -> bool {
+        self.parser.is_compressed()
+    }
+
+    pub fn settings(&self) -> &AnalysisSettings {
+        &self.processor.settings
+    }
+
+    /// Returns true if the last `lines` of the log at `file` can be parsed.
+    /// Logs with fewer lines are valid as long as the lines they have can be parsed.
+    pub fn is_log_end_valid(file: &Path, lines: usize) -> bool {
+        let mut file = match File::open(file) {
+            Ok(f) => f,
+            Err(_) => return false,
+        };
+        let len = match file.metadata() {
+            Ok(m) => m.len(),
+            Err(_) => return false,
+        };
+        let start = len.saturating_sub(LOG_END_CHECK_BYTES);
+        let mut data = Vec::new();
+        if file
+            .seek(SeekFrom::Start(start))
+            .and_then(|_| file.read_to_end(&mut data))
+            .is_err()
+        {
+            return false;
+        }
+        let data = String::from_utf8_lossy(&data);
+        let mut end = data.lines().collect::<Vec<_>>();
+        // the first line may be cut off
+        if start > 0 && !end.is_empty() {
+            end.remove(0);
+        }
+        let mut scratch_pad = String::new();
+        end.iter().rev().take(lines).all(|line| {
+            Parser::parse_from_line(line, &mut scratch_pad, None, false).is_some()
+                || Parser::parse_from_line(line, &mut scratch_pad, None, true).is_some()
+        })
+    }
+}
+
+impl InvalidRecords {
+    pub const LISTED: usize = 100;
+
+    fn add(&mut self, line: &str) {
+        warn!("failed to parse record: {}", line);
+        self.count += 1;
+        if self.lines.len() < Self::LISTED {
+            self.lines.push(line.trim_end().to_string());
+        }
+    }
+}
+
+/// amount of records processed between checks, whether the analysis got cancelled
+const CANCEL_CHECK_RECORDS: u32 = 4096;
+
+impl RecordProcessor {
+    fn new(settings: AnalysisSettings) -> Self {
+        Self {
+            combat_separation_time: Duration::seconds(settings.combat_separation_time_seconds as _),
+            player_merges: settings
+                .player_merges
+                .iter()
+                .filter(|m| !m.old_name.is_empty() && !m.new_name.is_empty())
+                .map(|m| (m.old_name.clone(), m.new_name.clone()))
+                .collect(),
+            settings,
+        }
+    }
+
+    fn log_file_name(&self) -> String {
+        self.settings
+            .combatlog_file()
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Parses the records from the current position of the `parser` until the end of the log or its range,
+    /// adds them to the `combats` and recalculates the modified ones, see [`Analyzer::update`].
+    fn analyze(
+        &self,
+        parser: &mut Parser,
+        combats: &mut Vec<Combat>,
+        invalid_records: &mut InvalidRecords,
+        is_cancelled: impl Fn() -> bool,
+        mut on_recalculate: impl FnMut(usize, usize),
+    ) -> io::Result<bool> {
+        let mut first_modified_combat = None;
+        // the initial load of the whole log is parsed in parallel
+        if parser.is_at_start()
+            && !parser.has_range()
+            && !self.process_records_in_bulk(
+                parser,
+                combats,
+                &mut first_modified_combat,
+                invalid_records,
+                &is_cancelled,
+            )
+        {
+            return Ok(false);
+        }
+        if !self.process_records(
+            parser,
+            combats,
+            &mut first_modified_combat,
+            invalid_records,
+            &is_cancelled,
+        )? {
+            return Ok(false);
+        }
+
+        if let Some(first_modified_combat) = first_modified_combat {
+            let combats_count = combats.len();
+            for (i, combat) in combats[first_modified_combat..].iter_mut().enumerate() {
+                if is_cancelled() {
+                    return Ok(false);
+                }
+                on_recalculate(first_modified_combat + i, combats_count);
+                combat.log_anomalies();
+                combat.update(&self.settings);
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Processes the records until the end of the log or range is reached.
+    /// Returns false if `is_cancelled` returned true in between.
+    fn process_records(
+        &self,
+        parser: &mut Parser,
+        combats: &mut Vec<Combat>,
+        first_modified_combat: &mut Option<usize>,
+        invalid_records: &mut InvalidRecords,
+        is_cancelled: impl Fn() -> bool,
+    ) -> io::Result<bool> {
+        let mut records_until_cancel_check = CANCEL_CHECK_RECORDS;
+        loop {
+            match self.process_next_record(parser, combats, first_modified_combat) {
+                Ok(_) => (),
+                Err(RecordError::EndReached) => return Ok(true),
+                Err(RecordError::InvalidRecord(invalid_record)) => {
+                    invalid_records.add(invalid_record)
+                }
+                Err(RecordError::Io(err)) => return Err(err),
+            }
+
+            records_until_cancel_check -= 1;
+            if records_until_cancel_check == 0 {
+                if is_cancelled() {
+                    return Ok(false);
+                }
+                records_until_cancel_check = CANCEL_CHECK_RECORDS;
+            }
+        }
+    }
+
+    /// see [`Parser::parse_bulk`], returns false if cancelled
+    fn process_records_in_bulk(
+        &self,
+        parser: &mut Parser,
+        combats: &mut Vec<Combat>,
+        first_modified_combat: &mut Option<usize>,
+        invalid_records: &mut InvalidRecords,
+        is_cancelled: impl Fn() -> bool,
+    ) -> bool {
+        let mut records_until_cancel_check = CANCEL_CHECK_RECORDS;
+        let result = parser.parse_bulk(|records| {
+            for record in records {
+                match record {
+                    Ok(record) => self.process_record(record, combats, first_modified_combat),
+                    Err(invalid_record) => invalid_records.add(invalid_record),
+                }
+
+                records_until_cancel_check -= 1;
+                if records_until_cancel_check == 0 {
+                    if is_cancelled() {
+                        return false;
+                    }
+                    records_until_cancel_check = CANCEL_CHECK_RECORDS;
+                }
+            }
+            !is_cancelled()
+        });
+
+        // the remaining records get parsed one by one
+        result.unwrap_or_else(|err| {
+            warn!("failed to read the log in bulk: {}", err);
+            true
+        })
+    }
+
+    fn process_next_record<'a>(
+        &'a self,
+        parser: &'a mut Parser,
+        combats: &mut Vec<Combat>,
+        first_modified_combat: &mut Option<usize>,
+    ) -> Result<(), RecordError<'a>> {
+        let record = parser.parse_next()?;
+        self.process_record(record, combats, first_modified_combat);
+        Ok(())
+    }
+
+    fn process_record<'a>(
+        &'a self,
+        mut record: Record<'a>,
+        combats: &mut Vec<Combat>,
+        first_modified_combat: &mut Option<usize>,
+    ) {
+        Self::merge_players(&self.player_merges, &mut record);
+        if self.settings.detect_ally_shield_heals {
+            record.reclassify_ally_shield_heal();
+        }
+        let is_anomaly = record.value.amount() > self.settings.anomaly_threshold;
+        if is_anomaly {
+            record.value_flags |= ValueFlags::ANOMALY;
+            record.value.add_flags(ValueFlags::ANOMALY);
+        }
+
+        match combats.last_mut() {
+            Some(combat)
+                if record.time.signed_duration_since(combat.active_time.end)
+                    > self.combat_separation_time =>
+            {
+                combats.push(Combat::new(&record, self.log_file_name()));
+            }
+            None => {
+                combats.push(Combat::new(&record, self.log_file_name()));
+            }
+            _ => (),
+        }
+        first_modified_combat.get_or_insert(combats.len() - 1);
+        let combat = combats.last_mut().unwrap();
+
+        if self.settings.drop_duplicate_records && combat.duplicate_records.is_duplicate(&record) {
+            combat.dropped_duplicate_records += 1;
+            return;
+        }
+        if record.time < combat.active_time.end {
+            combat.out_of_order_records += 1;
+        }
+        combat.update_meta_data(&record);
+        combat.update_names(&record);
+        if is_anomaly {
+            combat.anomalies.push(Anomaly {
+                time: record.time,
+                value_name: record.value_name.to_string(),
+                amount: record.value.amount(),
+                raw: record.raw.trim_end().to_string(),
+            });
+        }
+
+        // out of order records can lie before the start of the combat
+        let combat_start_offset_millis = record
+            .time
+            .signed_duration_since(combat.active_time.start)
+            .num_milliseconds()
+            .max(0) as u32;
+
+        if let Entity::Player { full_name, .. } = &record.source {
+            let player =
+                Combat::get_player(&mut combat.players, combat.name_manager.handle(full_name));
+            player.add_out_value(
+                &record,
+                combat_start_offset_millis,
+                &self.settings,
+                &mut combat.name_manager,
+                &mut combat.applied_rules,
+                &mut combat.group_combat_start,
+            );
+        }
+
+        if let Entity::Player { full_name, .. } = &record.target {
+            let player =
+                Combat::get_player(&mut combat.players, combat.name_manager.handle(full_name));
+            player.add_in_value(
+                &record,
+                combat_start_offset_millis,
+                &self.settings,
+                &mut combat.name_manager,
+                &mut combat.applied_rules,
+            );
+        }
+
+        if let (Entity::Player { full_name, .. }, Entity::NonPlayer { .. }) =
+            (&record.indirect_source, &record.source)
+        {
+            let player =
+                Combat::get_player(&mut combat.players, combat.name_manager.handle(full_name));
+            player.add_in_value(
+                &record,
+                combat_start_offset_millis,
+                &self.settings,
+                &mut combat.name_manager,
+                &mut combat.applied_rules,
+            );
+        }
+
+        if let (Entity::Player { full_name, .. }, Entity::None, Entity::None) =
+            (&record.source, &record.indirect_source, &record.target)
+        {
+            let player =
+                Combat::get_player(&mut combat.players, combat.name_manager.handle(full_name));
+            player.add_in_value(
+                &record,
+                combat_start_offset_millis,
+                &self.settings,
+                &mut combat.name_manager,
+                &mut combat.applied_rules,
+            );
+        }
+    }
+
+    fn merge_players<'a>(player_merges: &'a FxHashMap<String, String>, record: &mut Record<'a>) {
+        if player_merges.is_empty() {
+            return;
+        }
+
+        for entity in [
+            &mut record.source,
+            &mut record.target,
+            &mut record.indirect_source,
+        ] {
+            if let Entity::Player { full_name, .. } = entity {
+                if let Some(new_name) = player_merges.get(*full_name) {
+                    *full_name = new_name;
+                }
+            }
+        }
+    }
+}
+
+impl Combat {
+    fn new(start_record: &Record, log_file_name: String) -> Self {
+        let time = start_record.time..start_record.time;
+        Self {
+            combat_time: if start_record.is_player_out_damage() {
+                Some(time.clone())
+            } else {
+                None
+            },
+            log_pos: start_record.log_pos.clone(),
+            ..Self::empty(start_record.time, log_file_name)
+        }
+    }
+
+    /// a combat without any values, e.g. to [`Combat::merge`] others into
+    fn empty(start: NaiveDateTime, log_file_name: String) -> Self {
+        Self {
+            combat_time: None,
+            active_time: start..start,
+            combat_names: Default::default(),
+            dps_denominator: Default::default(),
+            players: Default::default(),
+            log_pos: None,
+            log_file_name,
+            total_damage_out: Default::default(),
+            total_damage_in: Default::default(),
+            total_heal_in: Default::default(),
+            total_heal_out: Default::default(),
+            total_kills: 0,
+            total_deaths: 0,
+            name_manager: Default::default(),
+            hits_manger: Default::default(),
+            heal_ticks_manger: Default::default(),
+            anomalies: Vec::new(),
+            exclude_anomalies: false,
+            out_of_order_records: 0,
+            dropped_duplicate_records: 0,
+            applied_rules: Default::default(),
+            settings_digest: String::new(),
+            ambiguous_players: Default::default(),
+            merged_combats: Vec::new(),
+            time_slice: None,
+            name_override: None,
+            group_combat_start: None,
+            duplicate_records: Default::default(),
+            logged_anomalies: 0,
+        }
+    }
+
+    /// Adds the values of the `other` combat, moved in time to directly follow the values added so far.
+    fn merge(&mut self, other: &Combat) {
+        let offset = self
+            .active_time
+            .end
+            .signed_duration_since(other.active_time.start);
+        let offset_millis = self
+            .active_time
+            .end
+            .signed_duration_since(self.active_time.start)
+            .num_milliseconds() as u32;
+        let names = self.name_manager.merge(&other.name_manager);
+        let name = |handle| names.get(&handle).copied().unwrap_or(NameHandle::UNKNOWN);
+        let time_millis = |millis: u32| Some(millis + offset_millis);
+        for (&handle, player) in other.players.iter() {
+            let merged = Self::get_player(&mut self.players, name(handle));
+            merged.merge_values(player, &name, &time_millis, &self.name_manager);
+            merged.merge_times(player, offset);
+        }
+
+        let shift = |t: &Range<NaiveDateTime>| t.start + offset..t.end + offset;
+        self.active_time.end = self.active_time.end.max(other.active_time.end + offset);
+        self.combat_time = union_times(
+            self.combat_time.take(),
+            other.combat_time.as_ref().map(shift),
+        );
+        self.anomalies
+            .extend(other.anomalies.iter().map(|a| Anomaly {
+                time: a.time + offset,
+                ..a.clone()
+            }));
+        self.out_of_order_records += other.out_of_order_records;
+        self.dropped_duplicate_records += other.dropped_duplicate_records;
+        self.applied_rules.merge(&other.applied_rules);
+        self.merged_combats.push(other.identity());
+    }
+
+    /// the values within the `time_slice`, moved to start with it, see [`Analyzer::analyze_time_slice`]
+    fn slice(&self, time_slice: Range<u32>) -> Option<Combat> {
+        let time_millis = |millis: u32| {
+            time_slice
+                .contains(&millis)
+                .then(|| millis - time_slice.start)
+        };
+        let start = self.active_time.start + Duration::milliseconds(time_slice.start as _);
+        let mut sliced = Self::empty(start, self.log_file_name.clone());
+        sliced.name_manager = self.name_manager.clone();
+        for (&handle, player) in self.players.iter() {
+            if !player.has_values(&time_millis) {
+                continue;
+            }
+            let sliced_player = Self::get_player(&mut sliced.players, handle);
+            sliced_player.merge_values(player, &|n| n, &time_millis, &sliced.name_manager);
+            sliced_player.slice_times(player, start);
+        }
+
+        let mut values_millis = None;
+        for player in sliced.players.values() {
+            player
+                .damage_out
+                .for_each_leaf_hit(&mut |h| extend_millis(&mut values_millis, h.time_millis));
+            player
+                .damage_in
+                .for_each_leaf_hit(&mut |h| extend_millis(&mut values_millis, h.time_millis));
+            player
+                .heal_out
+                .for_each_leaf_tick(&mut |t| extend_millis(&mut values_millis, t.time_millis));
+            player
+                .heal_in
+                .for_each_leaf_tick(&mut |t| extend_millis(&mut values_millis, t.time_millis));
+        }
+        sliced.active_time.end = start + Duration::milliseconds(values_millis?.end as _);
+        sliced.combat_time = sliced
+            .players
+            .values()
+            .fold(None, |t, p| union_times(t, p.damage_time.clone()));
+        sliced.anomalies = self
+            .anomalies
+            .iter()
+            .filter(|a| a.time >= start && a.time <= sliced.active_time.end)
+            .cloned()
+            .collect();
+        sliced.applied_rules = self.applied_rules.clone();
+        sliced.merged_combats = self.merged_combats.clone();
+        sliced.time_slice = Some(time_slice);
+        Some(sliced)
+    }
+
+    /// Groups with new values are tracked again at the end of the managers and leave their previous values behind.
+    /// Once those make up most of a manager, the values of all groups are moved to a new one.
+    fn drop_stale_values(&mut self) {
+        let hits: usize = self
+            .players
+            .values()
+            .map(|p| {
+                p.damage_out.hits.get(&self.hits_manger).len()
+                    + p.damage_in.hits.get(&self.hits_manger).len()
+            })
+            .sum();
+        if self.hits_manger.len() > 2 * hits {
+            let stale = std::mem::take(&mut self.hits_manger);
+            for player in self.players.values_mut() {
+                player.damage_out.move_values(&stale, &mut self.hits_manger);
+                player.damage_in.move_values(&stale, &mut self.hits_manger);
+            }
+        }
+
+        let ticks: usize = self
+            .players
+            .values()
+            .map(|p| {
+                p.heal_out.ticks.get(&self.heal_ticks_manger).len()
+                    + p.heal_in.ticks.get(&self.heal_ticks_manger).len()
+            })
+            .sum();
+        if self.heal_ticks_manger.len() > 2 * ticks {
+            let stale = std::mem::take(&mut self.heal_ticks_manger);
+            for player in self.players.values_mut() {
+                player
+                    .heal_out
+                    .move_values(&stale, &mut self.heal_ticks_manger);
+                player
+                    .heal_in
+                    .move_values(&stale, &mut self.heal_ticks_manger);
+            }
+        }
+    }
+
+    fn get_player(players: &mut NameMap<Player>, name: NameHandle) -> &mut Player {
+        if !players.contains_key(&name) {
+            let player = Player::new(name);
+            players.insert(player.damage_out.name(), player);
+        }
+        players.get_mut(&name).unwrap()
+    }
+
+    pub fn identifier(&self) -> String {
+        self.formatted_identifier(&DateTimeFormat::default())
+    }
+
+    /// logs a single warning for the anomalies added since the last call, instead of one per record
+    fn log_anomalies(&mut self) {
+        let new_anomalies = self.anomalies.len() - self.logged_anomalies;
+        if new_anomalies == 0 {
+            return;
+        }
+        warn!(
+            "{} records with implausible values in {}",
+            new_anomalies,
+            self.identifier()
+        );
+        self.logged_anomalies = self.anomalies.len();
+    }
+
+    pub fn formatted_identifier(&self, format: &DateTimeFormat) -> String {
+        format.combat_identifier(&self.name(), &self.active_time)
+    }
+
+    /// Identifies the combat across re-parses, unlike [`Combat::identifier`] it does not change while the combat grows.
+    /// The file name of the log and the start time are used, as the detected name changes with new records
+    /// and the position in the log changes when the log gets cleared.
+    /// Time slices have the identity of the combat they are part of.
+    pub fn identity(&self) -> String {
+        if !self.merged_combats.is_empty() {
+            return format!("Merged: {}", self.merged_combats.join(" + "));
+        }
+        let start = match &self.time_slice {
+            Some(time_slice) => {
+                self.active_time.start - Duration::milliseconds(time_slice.start as _)
+            }
+            None => self.active_time.start,
+        };
+        format!("{} | {}", self.log_file_name, start)
+    }
+
+    /// whether a hit or tick with these flags should be part of graphs
+    pub fn is_shown_in_graphs(&self, flags: ValueFlags) -> bool {
+        !self.exclude_anomalies || !flags.contains(ValueFlags::ANOMALY)
+    }
+
+    /// the [`Combat::name_override`] if there is one, otherwise the [`Combat::detected_name`]
+    pub fn name(&self) -> String {
+        match &self.name_override {
+            Some(name) => name.clone(),
+            None => self.detected_name(),
+        }
+    }
+
+    /// the name from the combat name rules of the settings
+    pub fn detected_name(&self) -> String {
+        if self.combat_names.len() == 0 {
+            return "Combat".to_string();
+        }
+
+        self.combat_names.values().map(|n| n.format()).join(", ")
+    }
+
+    pub fn file_identifier(&self) -> String {
+        self.formatted_file_identifier(&DateTimeFormat::default())
+    }
+
+    pub fn formatted_file_identifier(&self, format: &DateTimeFormat) -> String {
+        format.combat_file_identifier(&self.name(), &self.active_time)
+    }
+
+    fn update(&mut self, settings: &AnalysisSettings) {
+        self.update_combat_names(settings);
+        self.dps_denominator = settings.dps_denominator;
+        self.exclude_anomalies = settings.exclude_anomalies;
+        self.settings_digest = settings.digest();
+
+        self.players.values_mut().for_each(|p| {
+            p.recalculate_metrics(
+                settings.dps_denominator,
+                (settings.uptime_gap_seconds * 1000.0) as u32,
+                settings.exclude_anomalies,
+                &self.combat_time,
+                &mut self.hits_manger,
+                &mut self.heal_ticks_manger,
+            )
+        });
+        self.drop_stale_values();
+
+        self.ambiguous_players = self
+            .name_manager
+            .ambiguous_players(self.players.keys().copied());
+        let players = self.players.values();
+
+        self.total_damage_out = players.clone().map(|p| p.damage_out.total_damage).sum();
+        self.total_damage_in = players.clone().map(|p| p.damage_in.total_damage).sum();
+        self.total_heal_out = players.clone().map(|p| p.heal_out.total_heal).sum();
+        self.total_heal_in = players.clone().map(|p| p.heal_in.total_heal).sum();
+        self.total_kills = players
+            .clone()
+            .map(|p| p.damage_out.kills.values().copied().sum::<u32>())
+            .sum();
+        self.total_deaths = players
+            .clone()
+            .map(|p| p.damage_in.kills.values().copied().sum::<u32>())
+            .sum();
+        let total_hits_out: ShieldHullCounts = players
+            .clone()
+            .map(|p| p.damage_out.damage_metrics.hits)
+            .sum();
+        let total_hits_in: ShieldHullCounts = players
+            .clone()
+            .map(|p| p.damage_in.damage_metrics.hits)
+            .sum();
+        let total_heal_ticks_out = players.clone().map(|p| p.heal_out.heal_metrics.ticks).sum();
+        let total_heal_ticks_in = players.clone().map(|p| p.heal_in.heal_metrics.ticks).sum();
+        self.recalculate_damage_group_percentage(self.total_damage_out, total_hits_out, |p| {
+            &mut p.damage_out
+        });
+        self.recalculate_damage_group_percentage(self.total_damage_in, total_hits_in, |p| {
+            &mut p.damage_in
+        });
+        self.recalculate_heal_group_percentage(self.total_heal_out, total_heal_ticks_out, |p| {
+            &mut p.heal_out
+        });
+        self.recalculate_heal_group_percentage(self.total_heal_in, total_heal_ticks_in, |p| {
+            &mut p.heal_in
+        });
+    }
+
+    fn recalculate_damage_group_percentage(
+        &mut self,
+        total_damage: ShieldHullValues,
+        total_hits: ShieldHullCounts,
+        mut group: impl FnMut(&mut Player) -> &mut DamageGroup,
+    ) {
+        // the combat totals only change, if any of the groups changed
+        let force = self.players.values_mut().any(|p| group(p).changed);
+        self.players.values_mut().for_each(|p| {
+            group(p).recalculate_percentages(&total_damage, &total_damage, &total_hits, force)
+        });
+    }
+
+    fn recalculate_heal_group_percentage(
+        &mut self,
+        total_heal: ShieldHullValues,
+        parent_ticks: ShieldHullCounts,
+        mut group: impl FnMut(&mut Player) -> &mut HealGroup,
+    ) {
+        // the combat totals only change, if any of the groups changed
+        let force = self.players.values_mut().any(|p| group(p).changed);
+        self.players.values_mut().for_each(|p| {
+            group(p).recalculate_percentages(&total_heal, &total_heal, &parent_ticks, force)
+        });
+    }
+
+    fn update_meta_data(&mut self, record: &Record) {
+        self.update_time(record);
+        self.update_log_pos(record);
+    }
+
+    fn update_names(&mut self, record: &Record) {
+        self.name_manager.insert_some(
+            record.source.name(),
+            NameFlags::SOURCE.set_if(NameFlags::PLAYER, record.source.is_player()),
+        );
+        self.name_manager.insert_some(
+            record.source.unique_name(),
+            NameFlags::SOURCE_UNIQUE.set_if(NameFlags::PLAYER, record.source.is_player()),
+        );
+        self.name_manager.insert_some(
+            record.target.name(),
+            NameFlags::TARGET.set_if(NameFlags::PLAYER, record.target.is_player()),
+        );
+        self.name_manager.insert_some(
+            record.target.unique_name(),
+            NameFlags::TARGET_UNIQUE.set_if(NameFlags::PLAYER, record.target.is_player()),
+        );
+        self.name_manager.insert_some(
+            record.indirect_source.name(),
+            NameFlags::INDIRECT_SOURCE
+                .set_if(NameFlags::PLAYER, record.indirect_source.is_player()),
+        );
+        self.name_manager.insert_some(
+            record.indirect_source.unique_name(),
+            NameFlags::INDIRECT_SOURCE_UNIQUE
+                .set_if(NameFlags::PLAYER, record.indirect_source.is_player()),
+        );
+        self.name_manager
+            .insert(&record.value_name, NameFlags::VALUE);
+        self.name_manager.insert(record.value_type, NameFlags::NONE);
+    }
+
+    fn update_combat_names(&mut self, settings: &AnalysisSettings) {
+        self.combat_names.clear();
+        self.applied_rules.combat_name_rules.clear();
+
+        settings
+            .combat_name_rules
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| self.name_manager.matches(&r.name_rule))
+            .for_each(|(i, r)| {
+                self.applied_rules
+                    .combat_name_rules
+                    .insert(i, r.name_rule.name.clone());
+                self.combat_names.insert(
+                    r.name_rule.name.clone(),
+                    CombatName::new(r, &self.name_manager),
+                );
+            });
+    }
+
+    fn update_time(&mut self, record: &Record) {
+        if record.is_player_out_damage() && !record.is_immune_or_zero() {
+            let combat_time = self
+                .combat_time
+                .get_or_insert_with(|| record.time..record.time);
+            combat_time.end = combat_time.end.max(record.time);
+        }
+        self.active_time.end = self.active_time.end.max(record.time);
+    }
+
+    fn update_log_pos(&mut self, record: &Record) {
+        if let (Some(log_pos), Some(record_log_pos)) =
+            (self.log_pos.as_mut(), record.log_pos.as_ref())
+        {
+            log_pos.end = record_log_pos.end;
+        }
+    }
+}
+
+impl Player {
+    /// time from the `combat_start` to the first outgoing damage of the player,
+    /// regardless of the [`AnalysisSettings::combat_start_damage_threshold`]
+    pub fn start_delay(&self, combat_start: NaiveDateTime) -> Option<Duration> {
+        let first_damage = self.damage_time.as_ref()?.start;
+        Some(first_damage.signed_duration_since(combat_start))
+    }
+
+    /// kills per minute of the player's combat time, [`None`] if the combat time is too short
+    pub fn kills_per_minute(&self) -> Option<f64> {
+        self.per_minute(self.damage_out.kills.values().copied().sum())
+    }
+
+    /// deaths per minute of the player's combat time, [`None`] if the combat time is too short
+    pub fn deaths_per_minute(&self) -> Option<f64> {
+        self.per_minute(self.damage_in.kills.values().copied().sum())
+    }
+
+    fn per_minute(&self, count: u32) -> Option<f64> {
+        let combat_time = self.combat_time.as_ref()?;
+        let duration = combat_time.end.signed_duration_since(combat_time.start);
+        if duration < Duration::seconds(MIN_RATE_DURATION_SECONDS) {
+            return None;
+        }
+
+        Some(count as f64 / (duration.num_milliseconds() as f64 / 60e3))
+    }
+
+    fn new(full_name: NameHandle) -> Self {
+        Self {
+            combat_time: None,
+            active_time: None,
+            damage_out: DamageGroup::new_branch(GroupPathSegment::Group(full_name)),
+            damage_in: DamageGroup::new_branch(GroupPathSegment::Group(full_name)),
+            heal_out: HealGroup::new_branch(GroupPathSegment::Group(full_name)),
+            heal_in: HealGroup::new_branch(GroupPathSegment::Group(full_name)),
+            heal_in_split: Default::default(),
+            pre_combat_damage_start: None,
+            damage_time: None,
+            pending_combat_damage: VecDeque::new(),
+            combat_time_is_fallback: false,
+            peak_dps: Default::default(),
+        }
+    }
+
+    /// adds the values of the `other` player, see [`DamageGroup::merge`]
+    fn merge_values(
+        &mut self,
+        other: &Player,
+        name: &impl Fn(NameHandle) -> NameHandle,
+        time_millis: &impl Fn(u32) -> Option<u32>,
+        name_manager: &NameManager,
+    ) {
+        self.damage_out
+            .merge(&other.damage_out, name, time_millis, name_manager);
+        self.damage_in
+            .merge(&other.damage_in, name, time_millis, name_manager);
+        self.heal_out.merge(&other.heal_out, name, time_millis);
+        self.heal_in.merge(&other.heal_in, name, time_millis);
+    }
+
+    /// whether any value is kept by `time_millis`, see [`DamageGroup::merge`]
+    fn has_values(&self, time_millis: &impl Fn(u32) -> Option<u32>) -> bool {
+        self.damage_out.has_hits(time_millis)
+            || self.damage_in.has_hits(time_millis)
+            || self.heal_out.has_ticks(time_millis)
+            || self.heal_in.has_ticks(time_millis)
+    }
+
+    /// Sets the times from the values added with [`Player::merge_values`] to a slice starting at `start`.
+    /// The combat time is the one of the `parent` player, limited to the damage within the slice.
+    fn slice_times(&mut self, parent: &Player, start: NaiveDateTime) {
+        let mut active_millis = None;
+        let mut damage_millis = None;
+        self.damage_out.for_each_leaf_hit(&mut |h| {
+            extend_millis(&mut active_millis, h.time_millis);
+            if !h.is_all_zero() && !h.flags.contains(ValueFlags::IMMUNE) {
+                extend_millis(&mut damage_millis, h.time_millis);
+            }
+        });
+        self.damage_in
+            .for_each_leaf_hit(&mut |h| extend_millis(&mut active_millis, h.time_millis));
+        self.heal_out
+            .for_each_leaf_tick(&mut |t| extend_millis(&mut active_millis, t.time_millis));
+
+        let time = |millis: Range<u32>| {
+            start + Duration::milliseconds(millis.start as _)
+                ..start + Duration::milliseconds(millis.end as _)
+        };
+        self.active_time = active_millis.map(time);
+        self.damage_time = damage_millis.map(time);
+        self.combat_time = parent
+            .combat_time
+            .as_ref()
+            .zip(self.damage_time.as_ref())
+            .map(|(combat_time, damage_time)| {
+                combat_time.start.max(damage_time.start)..combat_time.end.min(damage_time.end)
+            })
+            .filter(|t| t.start <= t.end);
+    }
+
+    /// adds the times of the `other` player moved by the `offset`, as if the values of both were recorded in one combat
+    fn merge_times(&mut self, other: &Player, offset: Duration) {
+        let shift = |t: &Range<NaiveDateTime>| t.start + offset..t.end + offset;
+        self.active_time = union_times(
+            self.active_time.take(),
+            other.active_time.as_ref().map(shift),
+        );
+        self.damage_time = union_times(
+            self.damage_time.take(),
+            other.damage_time.as_ref().map(shift),
+        );
+        // once the threshold got reached, the combat time lasts until the last damage
+        let other_combat_start = other
+            .combat_time
+            .as_ref()
+            .filter(|_| !other.combat_time_is_fallback)
+            .map(|t| t.start + offset);
+        let combat_start = self
+            .combat_time
+            .as_ref()
+            .map(|t| t.start)
+            .or(other_combat_start);
+        self.combat_time = combat_start
+            .zip(self.damage_time.as_ref())
+            .map(|(start, damage_time)| start..damage_time.end);
+    }
+
+    fn add_out_value(
+        &mut self,
+        record: &Record,
+        combat_start_offset_millis: u32,
+        settings: &AnalysisSettings,
+        name_manager: &mut NameManager,
+        applied_rules: &mut AppliedRules,
+        group_combat_start: &mut Option<NaiveDateTime>,
+    ) {
+        if let Some(rule) = settings.exclusion_rule(record) {
+            applied_rules.add_exclusion_rule(rule, settings);
+            return;
+        }
+        self.update_active_time(record);
+        let mut path = Self::build_grouping_path(record, settings, name_manager, applied_rules);
+        let target_name = if record.is_self_directed() {
+            record.source.name()
+        } else {
+            record
+                .target
+                .name()
+                .or_else(|| record.indirect_source.name())
+        };
+        let target_name = target_name
+            .map(|n| name_manager.handle(n))
+            .unwrap_or_default();
+        match record.value {
+            RecordValue::Damage(damage) if !record.is_direct_self_damage() => {
+                match settings.damage_out_grouping {
+                    DamageOutGrouping::AbilityThenTarget => {
+                        path.insert(0, GroupPathSegment::Group(target_name))
+                    }
+                    DamageOutGrouping::TargetThenAbility => {
+                        path.push(GroupPathSegment::Group(target_name))
+                    }
+                }
+                self.damage_out.add_damage(
+                    &path,
+                    damage,
+                    record
+                        .value_flags
+                        .contains(ValueFlags::KILL)
+                        .then_some(target_name),
+                    name_manager.handle(record.value_type),
+                    combat_start_offset_millis,
+                    name_manager,
+                );
+
+                self.update_combat_time(record, settings, group_combat_start);
+            }
+            RecordValue::Heal(heal) => {
+                match settings.heal_out_grouping {
+                    HealOutGrouping::TargetThenAbility => {
+                        path.push(GroupPathSegment::Group(target_name))
+                    }
+                    HealOutGrouping::AbilityThenTarget => {
+                        path.insert(0, GroupPathSegment::Group(target_name))
+                    }
+                }
+                let to_self = target_name == self.heal_out.name();
+                self.heal_out.add_heal(
+                    &path,
+                    heal,
+                    record.value_flags,
+                    combat_start_offset_millis,
+                    to_self,
+                );
+            }
+            _ => (),
+        }
+    }
+
+    fn add_in_value(
+        &mut self,
+        record: &Record,
+        combat_start_offset_millis: u32,
+        settings: &AnalysisSettings,
+        name_manager: &mut NameManager,
+        applied_rules: &mut AppliedRules,
+    ) {
+        if let Some(rule) = settings
+            .exclusion_rule(record)
+            .filter(|_| settings.exclude_from_incoming)
+        {
+            applied_rules.add_exclusion_rule(rule, settings);
+            return;
+        }
+        let source_name = record
+            .source
+            .name()
+            .map(|n| name_manager.handle(n))
+            .unwrap_or_default();
+        let mut path = Self::build_grouping_path(record, settings, name_manager, applied_rules);
+        path.push(GroupPathSegment::Group(source_name));
+        match record.value {
+            RecordValue::Damage(damage) => {
+                // the player is the one killed
+                self.damage_in.add_damage(
+                    &path,
+                    damage,
+                    record
+                        .value_flags
+                        .contains(ValueFlags::KILL)
+                        .then_some(self.damage_in.name()),
+                    name_manager.handle(record.value_type),
+                    combat_start_offset_millis,
+                    name_manager,
+                );
+                self.update_active_time(record);
+            }
+            RecordValue::Heal(heal) => {
+                let from_self = source_name == self.heal_in.name();
+                self.heal_in.add_heal(
+                    &path,
+                    heal,
+                    record.value_flags,
+                    combat_start_offset_millis,
+                    from_self,
+                );
+            }
+        }
+    }
+
+    fn build_grouping_path(
+        record: &Record,
+        settings: &AnalysisSettings,
+        name_manager: &mut NameManager,
+        applied_rules: &mut AppliedRules,
+    ) -> GroupingPath {
+        let mut path = GroupingPath::new();
+
+        match (&record.indirect_source, &record.target) {
+            (Entity::None, _) | (_, Entity::None) => {
+                path.push(GroupPathSegment::Value(
+                    name_manager.handle(&record.value_name),
+                ));
+            }
+
+            (indirect_source, _) => {
+                let indirect_source = match indirect_source {
+                    Entity::NonPlayer { name, id, .. } if settings.separate_pet_instances => {
+                        name_manager.insert_instance(name, *id, NameFlags::INDIRECT_SOURCE)
+                    }
+                    _ => name_manager.handle(indirect_source.name().unwrap_or_default()),
+                };
+                if let Some(rule) = settings
+                    .indirect_source_grouping_revers_rules
+                    .iter()
+                    .position(|r| r.matches_record(record))
+                {
+                    applied_rules.reversal_rules.entry(rule).or_insert_with(|| {
+                        settings.indirect_source_grouping_revers_rules[rule].clone()
+                    });
+                    path.extend_from_slice(&[
+                        GroupPathSegment::Value(indirect_source),
+                        GroupPathSegment::Group(name_manager.handle(&record.value_name)),
+                    ]);
+                } else {
+                    path.extend_from_slice(&[
+                        GroupPathSegment::Value(name_manager.handle(&record.value_name)),
+                        GroupPathSegment::Group(indirect_source),
+                    ]);
+                }
+            }
+        }
+
+        if let Some((index, rule)) = settings
+            .custom_group_rules
+            .iter()
+            .find_position(|r| r.matches_record(record))
+        {
+            applied_rules
+                .grouping_rules
+                .entry(index)
+                .or_insert_with(|| rule.name.clone());
+            path.push(GroupPathSegment::Group(
+                name_manager.insert(rule.name.as_str(), NameFlags::NONE),
+            ));
+        }
+
+        path
+    }
+
+    fn update_combat_time(
+        &mut self,
+        record: &Record,
+        settings: &AnalysisSettings,
+        group_combat_start: &mut Option<NaiveDateTime>,
+    ) {
+        if record.is_immune_or_zero() {
+            return;
+        }
+        let damage_time = self.damage_time.get_or_insert(record.time..record.time);
+        damage_time.start = damage_time.start.min(record.time);
+        damage_time.end = damage_time.end.max(record.time);
+
+        if !self.combat_time_is_fallback {
+            if let Some(combat_time) = &mut self.combat_time {
+                combat_time.start = combat_time.start.min(record.time);
+                combat_time.end = combat_time.end.max(record.time);
+                return;
+            }
+        }
+        let start = if settings.combat_start_damage_threshold > 0.0 {
+            self.threshold_combat_start(record, settings, group_combat_start)
+        } else {
+            Some(record.time)
+        };
+        if let Some(start) = start {
+            self.combat_time = Some(start.min(record.time)..start.max(record.time));
+            self.combat_time_is_fallback = false;
+        } else if self.combat_time_is_fallback {
+            self.combat_time = self.damage_time.clone();
+        }
+    }
+
+    /// The combat time starts with the first damage within the window, once the damage in it reached
+    /// the threshold, or with the first damage after the combat time of another player started.
+    fn threshold_combat_start(
+        &mut self,
+        record: &Record,
+        settings: &AnalysisSettings,
+        group_combat_start: &mut Option<NaiveDateTime>,
+    ) -> Option<NaiveDateTime> {
+        if group_combat_start.is_some_and(|s| s <= record.time) {
+            self.pending_combat_damage.clear();
+            return Some(record.time);
+        }
+
+        let window =
+            Duration::milliseconds((settings.combat_start_window_seconds * 1e3).round() as _);
+        self.pending_combat_damage
+            .push_back((record.time, record.value.amount()));
+        while self
+            .pending_combat_damage
+            .front()
+            .is_some_and(|(t, _)| *t < record.time - window)
+        {
+            self.pending_combat_damage.pop_front();
+        }
+        let damage: f64 = self.pending_combat_damage.iter().map(|(_, d)| d).sum();
+        if damage < settings.combat_start_damage_threshold {
+            return None;
+        }
+
+        let start = self.pending_combat_damage.front()?.0;
+        self.pending_combat_damage.clear();
+        *group_combat_start = Some(group_combat_start.map_or(start, |s| s.min(start)));
+        Some(start)
+    }
+
+    /// falls back to the first damage, if the threshold was never reached
+    fn finish_combat_time(&mut self) {
+        if self.combat_time.is_none() && self.damage_time.is_some() {
+            self.combat_time = self.damage_time.clone();
+            self.combat_time_is_fallback = true;
+        }
+        self.pre_combat_damage_start = match (&self.damage_time, &self.combat_time) {
+            (Some(damage_time), Some(combat_time)) if damage_time.start < combat_time.start => {
+                Some(damage_time.start)
+            }
+            _ => None,
+        };
+    }
+
+    fn update_active_time(&mut self, record: &Record) {
+        let active_time = self
+            .active_time
+            .get_or_insert_with(|| record.time..record.time);
+        active_time.start = active_time.start.min(record.time);
+        active_time.end = active_time.end.max(record.time);
+    }
+
+    fn recalculate_metrics(
+        &mut self,
+        dps_denominator: DpsDenominator,
+        uptime_gap_millis: u32,
+        exclude_anomalies: bool,
+        combat_time: &Option<Range<NaiveDateTime>>,
+        hits_manager: &mut HitsManager,
+        heal_ticks_manager: &mut HealTicksManager,
+    ) {
+        self.finish_combat_time();
+        let active_duration = Self::metrics_duration(&self.active_time);
+        let dps_duration = match dps_denominator {
+            DpsDenominator::PlayerCombatTime => Self::metrics_duration(&self.combat_time),
+            DpsDenominator::PlayerActiveTime => active_duration,
+            DpsDenominator::CombatDuration => Self::metrics_duration(combat_time),
+        };
+        self.damage_out.recalculate_metrics(
+            dps_duration,
+            uptime_gap_millis,
+            exclude_anomalies,
+            hits_manager,
+            &mut |_, _| {},
+        );
+        self.damage_in.recalculate_metrics(
+            active_duration,
+            uptime_gap_millis,
+            exclude_anomalies,
+            hits_manager,
+            &mut |_, _| {},
+        );
+        self.heal_out.recalculate_metrics(
+            active_duration,
+            uptime_gap_millis,
+            heal_ticks_manager,
+            &mut |_| {},
+        );
+        self.heal_in.recalculate_metrics(
+            active_duration,
+            uptime_gap_millis,
+            heal_ticks_manager,
+            &mut |_| {},
+        );
+        self.heal_in_split = HealInSplit::new(&self.heal_in);
+        if self.damage_out.changed {
+            self.peak_dps = PeakDps::new(
+                self.damage_out
+                    .hits
+                    .get(hits_manager)
+                    .iter()
+                    .filter(|h| !exclude_anomalies || !h.flags.contains(ValueFlags::ANOMALY)),
+            );
+        }
+    }
+
+    fn metrics_duration(time: &Option<Range<NaiveDateTime>>) -> f64 {
+        let duration = time
+            .as_ref()
+            .map(|t| t.end.signed_duration_since(t.start))
+            .unwrap_or(Duration::max_value());
+        duration
+            .to_std()
+            .map(|d| d.as_secs_f64())
+            .unwrap_or_default()
+    }
+}
+
+impl AppliedRules {
+    fn add_exclusion_rule(&mut self, index: usize, settings: &AnalysisSettings) {
+        self.exclusion_rules
+            .entry(index)
+            .or_insert_with(|| settings.damage_out_exclusion_rules[index].clone());
+    }
+
+    fn merge(&mut self, other: &Self) {
+        self.exclusion_rules.extend(other.exclusion_rules.clone());
+        self.grouping_rules.extend(other.grouping_rules.clone());
+        self.reversal_rules.extend(other.reversal_rules.clone());
+        self.combat_name_rules
+            .extend(other.combat_name_rules.clone());
+    }
+}
+
+/// extends the `range` to include the `millis`
+fn extend_millis(range: &mut Option<Range<u32>>, millis: u32) {
+    *range = Some(match range.take() {
+        Some(range) => range.start.min(millis)..range.end.max(millis),
+        None => millis..millis,
+    });
+}
+
+/// from the earlier start to the later end of both ranges
+fn union_times(
+    a: Option<Range<NaiveDateTime>>,
+    b: Option<Range<NaiveDateTime>>,
+) -> Option<Range<NaiveDateTime>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.start.min(b.start)..a.end.max(b.end)),
+        (a, b) => a.or(b),
+    }
+}
+
+impl HealInSplit {
+    /// the sub groups of `heal_in` are the sources of the healing
+    fn new(heal_in: &HealGroup) -> Self {
+        let from_self = heal_in
+            .sub_groups
+            .get(&heal_in.name())
+            .map(|s| s.total_heal)
+            .unwrap_or_default();
+        let from_others = heal_in
+            .sub_groups
+            .values()
+            .filter(|s| s.name() != heal_in.name())
+            .map(|s| s.total_heal)
+            .sum();
+        Self {
+            from_self,
+            from_others,
+        }
+    }
+
+    pub fn self_percentage(&self) -> Option<f64> {
+        let total = self.from_self.all + self.from_others.all;
+        if total <= 0.0 {
+            return None;
+        }
+        Some(self.from_self.all / total * 100.0)
+    }
+}
+
+impl Combat {
+    /// Parses the records of the combat again from the log at `file_path` and calls `on_record` for each of them,
+    /// until it returns false. The records are passed on as they are in the log, no settings are applied to them.
+    /// Returns false if the combat has no [`Combat::log_pos`] or the log can not be read.
+    pub fn read_log_records(
+        &self,
+        file_path: &Path,
+        mut on_record: impl FnMut(&Record) -> bool,
+    ) -> bool {
+        let Some(pos) = self.log_pos.clone() else {
+            return false;
+        };
+        let Some(mut parser) = Parser::new(file_path) else {
+            return false;
+        };
+        if let Err(err) = parser.set_range(pos) {
+            warn!("failed to seek in the log: {}", err);
+            return false;
+        }
+
+        loop {
+            match parser.parse_next() {
+                Ok(record) => {
+                    if !on_record(&record) {
+                        return true;
+                    }
+                }
+                Err(RecordError::EndReached) => return true,
+                Err(RecordError::InvalidRecord(invalid_record)) => {
+                    warn!("failed to parse record: {}", invalid_record);
+                }
+                Err(RecordError::Io(err)) => {
+                    warn!("failed to read the log: {}", err);
+                    return false;
+                }
+            }
+        }
+    }
+
+    pub fn read_log_combat_data(&self, file_path: &Path) -> Option<Vec<u8>> {
+        let pos = match self.log_pos.clone() {
+            Some(p) => p,
+            None => return None,
+        };
+
+        let file = match File::options().create(false).read(true).open(file_path) {
+            Ok(f) => f,
+            Err(_) => return None,
+        };
+
+        let mut combat_data = Vec::new();
+        combat_data.resize((pos.end - pos.start) as _, 0);
+        let mut reader = BufReader::with_capacity(1 << 20, file);
+        reader.seek(SeekFrom::Start(pos.start)).ok()?;
+
+        reader.read_exact(&mut combat_data).ok()?;
+
+        Some(combat_data)
+    }
+}
+
+impl CombatName {
+    fn new(rule: &CombatNameRule, name_manager: &NameManager) -> Self {
+        let additional_infos: Vec<_> = rule
+            .additional_info_rules
+            .iter()
+            .filter(|r| name_manager.matches(r))
+            .map(|r| r.name.clone())
+            .collect();
+        Self {
+            name: rule.name_rule.name.clone(),
+            additional_infos,
+        }
+    }
+
+    fn format(&self) -> Cow<String> {
+        if self.additional_infos.len() == 0 {
+            return Cow::Borrowed(&self.name);
+        }
+
+        let name = format!(
+            "{} ({})",
+            self.name,
+            self.additional_infos.iter().join(", ")
+        );
+        Cow::Owned(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_log::*;
+
+    fn ayel() -> TestEntity {
+        TestEntity::player("Ayel", "greyblizzard", 1)
+    }
+
+    fn kira() -> TestEntity {
+        TestEntity::player("Kira", "somebody", 2)
+    }
+
+    fn cube() -> TestEntity {
+        TestEntity::npc("Borg Cube", 3)
+    }
+
+    fn sphere() -> TestEntity {
+        TestEntity::npc("Borg Sphere", 4)
+    }
+
+    #[test]
+    #[ignore = "manual test"]
+    fn analyze_log() {
+        let mut analyzer = Analyzer::new(AnalysisSettings {
+            combatlog_file:
+                r"D:\Games\Star Trek Online_en\Star Trek Online\Live\logs\GameClient\combatlog.log"
+                    .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        analyzer.update(|| false, |_, _| {}).unwrap();
+        let result = analyzer.result();
+        let combats: Vec<_> = result.iter().map(|c| c.identifier()).collect();
+        println!("combats: {:?}", combats);
+    }
+
+    #[test]
+    fn replaced_log_is_detected() {
+        let log = TestLog::new().with(TestRecord::damage(
+            0.0,
+            &ayel(),
+            &kira(),
+            "Phaser Beam",
+            100.0,
+        ));
+        let file = log.write();
+        let analyzer = file.analyzer(Default::default());
+        let replaced_before = analyzer.is_log_replaced();
+
+        let replacement = log.write();
+        std::fs::rename(replacement.path(), file.path()).unwrap();
+        let replaced_after = analyzer.is_log_replaced();
+
+        assert!(!replaced_before);
+        assert!(replaced_after);
+    }
+
+    #[test]
+    fn refresh_keeps_the_values_of_groups_without_new_hits() {
+        let fighter = TestEntity::npc("Support Fighter", 20);
+        let file = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Torpedo", 100.0))
+            .with(TestRecord::damage(
+                0.5,
+                &kira(),
+                &cube(),
+                "Phaser Beam",
+                50.0,
+            ))
+            .with(TestRecord::damage(1.0, &kira(), &cube(), "Phaser", 40.0).by_pet(&fighter))
+            .write();
+        let mut analyzer = file.analyzer(Default::default());
+        let ayel_hits =
+            |combat: &Combat| format!("{:?}", combat.test_player(&ayel()).damage_out.hits);
+        let hit_times = |combat: &Combat, group: &DamageGroup| -> Vec<_> {
+            let mut times: Vec<_> = group
+                .hits
+                .get(&combat.hits_manger)
+                .iter()
+                .map(|h| h.time_millis)
+                .collect();
+            times.sort_unstable();
+            times
+        };
+        let ayel_before = ayel_hits(&analyzer.result()[0]);
+
+        // only Kira gets new hits, the ones of the fighter are added again along with them,
+        // until the values left behind get dropped with the third refresh
+        for second in 2..5 {
+            let values_before = analyzer.result()[0].hits_manger.len();
+            let appended = TestRecord::damage(second as f64, &kira(), &cube(), "Phaser Beam", 50.0);
+            file.append(appended.line(TestLog::start()));
+            analyzer.update(|| false, |_, _| {}).unwrap();
+
+            let combat = &analyzer.result()[0];
+            let kira_values = combat
+                .test_player(&kira())
+                .damage_out
+                .hits
+                .get(&combat.hits_manger)
+                .len();
+            if second < 4 {
+                assert_eq!(ayel_hits(combat), ayel_before);
+                assert_eq!(combat.hits_manger.len(), values_before + kira_values);
+            } else {
+                assert_eq!(combat.hits_manger.len(), 1 + kira_values);
+            }
+        }
+
+        let combat = &analyzer.result()[0];
+        assert_eq!(
+            hit_times(combat, &combat.test_player(&ayel()).damage_out),
+            [0]
+        );
+        let kira = combat.test_player(&kira());
+        assert_eq!(
+            hit_times(combat, &kira.damage_out),
+            [500, 1000, 2000, 3000, 4000]
+        );
+        let fighter = kira.damage_out.test_group(combat, &["Support Fighter"]);
+        assert_eq!(hit_times(combat, fighter), [1000]);
+    }
+
+    #[test]
+    fn out_of_order_records_are_counted_and_clamped() {
+        let combats = TestLog::new()
+            .with_all(
+                [2.1, 2.5, 1.8, 3.0]
+                    .map(|time| TestRecord::damage(time, &ayel(), &kira(), "Phaser Beam", 100.0)),
+            )
+            .analyze(Default::default());
+        let combat = &combats[0];
+
+        assert_eq!(combats.len(), 1);
+        assert_eq!(combat.out_of_order_records, 1);
+        assert_eq!(combat.total_damage_out.all, 400.0);
+        let hits_start = combat
+            .players
+            .values()
+            .flat_map(|p| p.damage_out.hits.get(&combat.hits_manger))
+            .map(|h| h.time_millis)
+            .min();
+        assert_eq!(hits_start, Some(0));
+        assert_eq!(
+            combat.active_time.end - combat.active_time.start,
+            Duration::milliseconds(900)
+        );
+    }
+
+    #[test]
+    fn compressed_logs_are_analyzed_without_log_positions() {
+        let log = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &kira(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                5.0,
+                &ayel(),
+                &kira(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                420.0,
+                &ayel(),
+                &kira(),
+                "Phaser Beam",
+                50.0,
+            ));
+
+        let analyze = |file: &TestLogFile| {
+            let analyzer = file.analyzer(Default::default());
+            (analyzer.is_log_compressed(), analyzer.result().clone())
+        };
+        let (plain_is_compressed, plain) = analyze(&log.write());
+        let (is_compressed, compressed) = analyze(&log.write_compressed());
+
+        assert!(!plain_is_compressed);
+        assert!(is_compressed);
+        assert_eq!(compressed.len(), 2);
+        assert_eq!(plain.len(), compressed.len());
+        for (plain, compressed) in plain.iter().zip(compressed.iter()) {
+            assert_eq!(plain.total_damage_out.all, compressed.total_damage_out.all);
+            assert!(plain.log_pos.is_some());
+            assert!(compressed.log_pos.is_none());
+        }
+    }
+
+    #[test]
+    fn overlong_lines_of_compressed_logs_are_skipped() {
+        let line = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &kira(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .to_log();
+        let file = TestLogFile::compressed(format!("{}{}\n{}", line, "x".repeat(3 << 20), line));
+        let analyzer = file.analyzer(Default::default());
+
+        assert_eq!(analyzer.result().len(), 1);
+        assert_eq!(analyzer.result()[0].total_damage_out.all, 200.0);
+    }
+
+    #[test]
+    fn out_of_order_last_record_of_a_player_keeps_its_times() {
+        let combat = TestLog::new()
+            .with_all(
+                [2.1, 3.0, 1.8]
+                    .map(|time| TestRecord::damage(time, &ayel(), &kira(), "Phaser Beam", 100.0)),
+            )
+            .analyze_single();
+        let player = combat.test_player(&ayel());
+
+        let start = TestLog::time(1.8);
+        let end = TestLog::time(3.0);
+        assert_eq!(combat.out_of_order_records, 1);
+        assert_eq!(player.active_time, Some(start..end));
+        assert_eq!(player.combat_time, Some(start..end));
+        assert_eq!(player.damage_out.dps.all, 300.0 / 1.2);
+    }
+
+    fn pre_combat_settings() -> AnalysisSettings {
+        AnalysisSettings {
+            combat_start_damage_threshold: 1000.0,
+            combat_start_window_seconds: 5.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pre_combat_damage_does_not_start_the_combat_time() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Torpedo", 100.0))
+            .with_all(
+                [20.0, 21.0, 30.0]
+                    .map(|time| TestRecord::damage(time, &ayel(), &cube(), "Phaser Beam", 600.0)),
+            )
+            .analyze(pre_combat_settings())
+            .remove(0);
+        let player = combat.test_player(&ayel());
+
+        assert_eq!(
+            player.combat_time,
+            Some(TestLog::time(20.0)..TestLog::time(30.0))
+        );
+        assert_eq!(player.pre_combat_damage_start, Some(TestLog::time(0.0)));
+    }
+
+    #[test]
+    fn the_start_delay_counts_from_the_first_damage_below_the_threshold() {
+        let ayel = TestEntity::player("Ayel", "greyblizzard", 1);
+        let cube = TestEntity::npc("Borg Cube", 10);
+        let combat = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel, &cube, "Torpedo", 100.0))
+            .with(TestRecord::damage(20.0, &ayel, &cube, "Phaser Beam", 600.0))
+            .with(TestRecord::damage(21.0, &ayel, &cube, "Phaser Beam", 600.0))
+            .analyze(pre_combat_settings())
+            .pop()
+            .unwrap();
+        let player = combat.test_player(&ayel);
+
+        assert_eq!(
+            player.combat_time.as_ref().map(|t| t.start),
+            Some(TestLog::start() + Duration::seconds(20))
+        );
+        assert_eq!(
+            player.start_delay(TestLog::start() - Duration::seconds(1)),
+            Some(Duration::seconds(1))
+        );
+    }
+
+    #[test]
+    fn the_combat_time_starts_with_the_group() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(0.0, &kira(), &cube(), "Torpedo", 100.0))
+            .with(TestRecord::damage(
+                20.0,
+                &ayel(),
+                &cube(),
+                "Torpedo",
+                1500.0,
+            ))
+            .with(TestRecord::damage(
+                22.0,
+                &kira(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                30.0,
+                &kira(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .analyze(pre_combat_settings())
+            .remove(0);
+        let kira = combat.test_player(&kira());
+        let ayel = combat.test_player(&ayel());
+
+        assert_eq!(
+            kira.combat_time.as_ref().map(|t| t.start),
+            Some(TestLog::time(22.0))
+        );
+        assert!(kira.pre_combat_damage_start.is_some());
+        assert!(ayel.pre_combat_damage_start.is_none());
+    }
+
+    #[test]
+    fn the_combat_time_falls_back_to_the_first_damage_below_the_threshold() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Torpedo", 100.0))
+            .with(TestRecord::damage(10.0, &ayel(), &cube(), "Torpedo", 100.0))
+            .analyze(pre_combat_settings())
+            .remove(0);
+        let player = combat.test_player(&ayel());
+
+        assert_eq!(
+            player.combat_time,
+            Some(TestLog::time(0.0)..TestLog::time(10.0))
+        );
+        assert!(player.pre_combat_damage_start.is_none());
+    }
+
+    #[test]
+    fn merged_combats_add_up_without_the_time_between_them() {
+        let combats = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Torpedo", 100.0))
+            .with(TestRecord::damage(10.0, &ayel(), &cube(), "Torpedo", 100.0).kill())
+            .with(TestRecord::damage(
+                420.0,
+                &ayel(),
+                &sphere(),
+                "Torpedo",
+                100.0,
+            ))
+            .with(TestRecord::damage(440.0, &ayel(), &sphere(), "Torpedo", 100.0).kill())
+            .analyze(Default::default());
+        let merged = Analyzer::analyze_merged(&Default::default(), &combats);
+
+        assert_eq!(combats.len(), 2);
+        let merged = merged.unwrap();
+        let player = merged.test_player(&ayel());
+        assert_eq!(player.damage_out.total_damage.all, 400.0);
+        assert!((player.damage_out.dps.all - 400.0 / 30.0).abs() < 1e-9);
+        assert_eq!(merged.total_kills, 2);
+        let handle = |name| merged.name_manager.get_handle(name).unwrap();
+        assert_eq!(
+            player.damage_out.kill_times,
+            [
+                (handle("Borg Cube"), 10_000),
+                (handle("Borg Sphere"), 30_000)
+            ]
+        );
+        assert_eq!(merged.active_time, TestLog::time(0.0)..TestLog::time(30.0));
+        assert_eq!(merged.merged_combats.len(), 2);
+        assert!(merged.log_pos.is_none());
+    }
+
+    #[test]
+    fn combats_of_compressed_logs_can_be_merged() {
+        let file = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Torpedo", 100.0))
+            .with(TestRecord::heal(
+                10.0,
+                &kira(),
+                &ayel(),
+                "Hazard Emitters",
+                50.0,
+            ))
+            .with(TestRecord::damage(
+                420.0,
+                &ayel(),
+                &sphere(),
+                "Phaser Beam",
+                70.0,
+            ))
+            .with(TestRecord::heal(
+                425.0,
+                &kira(),
+                &ayel(),
+                "Hazard Emitters",
+                30.0,
+            ))
+            .write_compressed();
+        let combats = TestLog::analyze_file(&file, Default::default());
+        let merged = Analyzer::analyze_merged(&Default::default(), &combats);
+
+        assert_eq!(combats.len(), 2);
+        let merged = merged.unwrap();
+        let ayel = merged.test_player(&ayel());
+        let kira = merged.test_player(&kira());
+        assert_eq!(ayel.damage_out.total_damage.all, 170.0);
+        assert_eq!(ayel.damage_out.sub_groups.len(), 2);
+        assert_eq!(ayel.heal_in.total_heal.all, 80.0);
+        assert_eq!(kira.heal_out.total_heal.all, 80.0);
+        assert_eq!(kira.heal_out.target_split.to_others.all, 80.0);
+        assert_eq!(kira.heal_out.ticks.get(&merged.heal_ticks_manger).len(), 2);
+    }
+
+    #[test]
+    fn players_with_the_same_character_name_are_ambiguous() {
+        let cube = TestEntity::npc("Borg Cube", 5);
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &TestEntity::player("Worf", "klingon", 3),
+                &cube,
+                "Torpedo",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                1.0,
+                &TestEntity::player("Worf", "federation", 4),
+                &cube,
+                "Torpedo",
+                100.0,
+            ))
+            .with(TestRecord::damage(2.0, &ayel(), &cube, "Torpedo", 100.0))
+            .analyze_single();
+        let handle = |name| combat.name_manager.get_handle(name).unwrap();
+
+        assert_eq!(combat.ambiguous_players.len(), 2);
+        assert!(combat.ambiguous_players.contains(&handle("Worf@klingon")));
+        assert!(combat
+            .ambiguous_players
+            .contains(&handle("Worf@federation")));
+        assert!(!combat
+            .ambiguous_players
+            .contains(&handle("Ayel@greyblizzard")));
+    }
+
+    #[test]
+    fn records_of_a_combat_are_read_again() {
+        let file = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Torpedo", 100.0))
+            .with(TestRecord::damage(
+                1.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                50.0,
+            ))
+            .with(TestRecord::damage(420.0, &ayel(), &cube(), "Torpedo", 70.0))
+            .write();
+        let combat = TestLog::analyze_file(&file, Default::default()).remove(0);
+        let mut value_names = Vec::new();
+        let read_all = combat.read_log_records(file.path(), |r| {
+            value_names.push(r.value_name.to_string());
+            true
+        });
+        let mut count = 0;
+        let read_first = combat.read_log_records(file.path(), |_| {
+            count += 1;
+            false
+        });
+
+        assert!(read_all);
+        assert_eq!(value_names, ["Torpedo", "Phaser Beam"]);
+        assert!(read_first);
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn only_the_records_of_the_time_slice_are_analyzed() {
+        let combat = TestLog::new()
+            .with_all(
+                [0.0, 10.0, 20.0, 30.0]
+                    .map(|time| TestRecord::damage(time, &ayel(), &cube(), "Torpedo", 100.0)),
+            )
+            .analyze_single();
+        let settings = AnalysisSettings::default();
+        let sliced = Analyzer::analyze_time_slice(&settings, &combat, 5_000..25_000).unwrap();
+        let empty = Analyzer::analyze_time_slice(&settings, &combat, 40_000..50_000);
+
+        let player = sliced.test_player(&ayel());
+        assert_eq!(player.damage_out.total_damage.all, 200.0);
+        assert!((player.damage_out.dps.all - 20.0).abs() < 1e-9);
+        assert_eq!(sliced.time_slice, Some(5_000..25_000));
+        assert_eq!(sliced.active_time.start, TestLog::time(5.0));
+        assert_eq!(sliced.identity(), combat.identity());
+        assert!(empty.is_none());
+
+        // merged combats have no position in the log, but can be sliced all the same
+        let merged = Analyzer::analyze_merged(&settings, [&combat, &combat]).unwrap();
+        let sliced = Analyzer::analyze_time_slice(&settings, &merged, 35_000..55_000).unwrap();
+        let player = sliced.test_player(&ayel());
+        assert_eq!(player.damage_out.total_damage.all, 200.0);
+        assert_eq!(sliced.identity(), merged.identity());
+    }
+
+    #[test]
+    fn name_override_keeps_the_identity_of_the_combat() {
+        let mut combat = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Torpedo", 100.0))
+            .analyze_single();
+        let identity = combat.identity();
+        let detected_name = combat.name();
+
+        combat.name_override = Some("Practice Run".to_string());
+        assert_eq!(combat.name(), "Practice Run");
+        assert!(combat.file_identifier().starts_with("Practice Run"));
+        assert_eq!(combat.detected_name(), detected_name);
+        assert_eq!(combat.identity(), identity);
+    }
+
+    #[test]
+    fn duplicated_records_are_dropped() {
+        let block = [0.0, 1.0, 2.0]
+            .map(|time| TestRecord::damage(time, &ayel(), &cube(), "Torpedo", 100.0));
+        // the game duplicated the whole block
+        let log = TestLog::new().with_all(block.clone()).with_all(block);
+
+        let combat = log.analyze(Default::default()).remove(0);
+        assert_eq!(combat.total_damage_out.all, 600.0);
+        assert_eq!(combat.dropped_duplicate_records, 0);
+
+        let combat = log.analyze(drop_duplicates_settings()).remove(0);
+        assert_eq!(combat.total_damage_out.all, 300.0);
+        assert_eq!(combat.dropped_duplicate_records, 3);
+    }
+
+    fn drop_duplicates_settings() -> AnalysisSettings {
+        AnalysisSettings {
+            drop_duplicate_records: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn identical_ticks_right_after_each_other_are_kept() {
+        let ayel = TestEntity::player("Ayel", "greyblizzard", 1);
+        let cube = TestEntity::npc("Borg Cube", 10);
+        // two stacks of the same DoT tick at once
+        let dot_tick = TestRecord::damage(1.0, &ayel, &cube, "Plasma Fire", 50.0);
+        let combat = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel, &cube, "Torpedo", 100.0))
+            .with(dot_tick.clone())
+            .with(dot_tick)
+            .analyze(drop_duplicates_settings())
+            .remove(0);
+
+        assert_eq!(combat.total_damage_out.all, 200.0);
+        assert_eq!(combat.dropped_duplicate_records, 0);
+    }
+
+    #[test]
+    fn duplicated_blocks_with_identical_ticks_are_dropped() {
+        let ayel = TestEntity::player("Ayel", "greyblizzard", 1);
+        let cube = TestEntity::npc("Borg Cube", 10);
+        let dot_tick = TestRecord::damage(1.0, &ayel, &cube, "Plasma Fire", 50.0);
+        let block = [
+            TestRecord::damage(0.0, &ayel, &cube, "Torpedo", 100.0),
+            dot_tick.clone(),
+            dot_tick,
+        ];
+        // the game duplicated the whole block
+        let combat = TestLog::new()
+            .with_all(block.clone())
+            .with_all(block)
+            .analyze(drop_duplicates_settings())
+            .remove(0);
+
+        assert_eq!(combat.total_damage_out.all, 200.0);
+        assert_eq!(combat.dropped_duplicate_records, 3);
+    }
+
+    #[test]
+    fn anomalies_are_collected_per_combat() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Torpedo", 100.0))
+            .with(TestRecord::damage(1.0, &ayel(), &cube(), "Torpedo", 1e12))
+            .with(TestRecord::damage(2.0, &ayel(), &cube(), "Phaser", 2e12))
+            .analyze(AnalysisSettings {
+                anomaly_threshold: 1e9,
+                ..Default::default()
+            })
+            .remove(0);
+
+        let anomalies: Vec<_> = combat.anomalies.iter().map(|a| a.amount).collect();
+        assert_eq!(anomalies, [1e12, 2e12]);
+        assert_eq!(combat.logged_anomalies, 2);
+    }
+
+    #[test]
+    fn applied_rules_are_copied_from_the_settings() {
+        let rule = |aspect, expression: &str| MatchRule {
+            aspect,
+            expression: expression.to_string(),
+            ..Default::default()
+        };
+        let group = |name: &str, rules| RulesGroup {
+            name: name.to_string(),
+            rules,
+            ..Default::default()
+        };
+        let settings = AnalysisSettings {
+            damage_out_exclusion_rules: vec![
+                rule(MatchAspect::DamageOrHealName, "Tricobalt"),
+                rule(MatchAspect::DamageOrHealName, "Feedback Pulse"),
+            ],
+            custom_group_rules: vec![
+                group(
+                    "Torpedoes",
+                    vec![rule(MatchAspect::DamageOrHealName, "Torpedo")],
+                ),
+                group(
+                    "Energy Weapons",
+                    vec![rule(MatchAspect::DamageOrHealName, "Phaser")],
+                ),
+            ],
+            combat_name_rules: vec![CombatNameRule {
+                name_rule: group(
+                    "Borg",
+                    vec![rule(MatchAspect::SourceOrTargetName, "Borg Cube")],
+                ),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let log = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Phaser", 100.0))
+            .with(TestRecord::damage(
+                1.0,
+                &ayel(),
+                &cube(),
+                "Feedback Pulse",
+                50.0,
+            ));
+        let combat = log.analyze(settings).remove(0);
+
+        let applied = &combat.applied_rules;
+        let exclusion_rules: Vec<_> = applied
+            .exclusion_rules
+            .iter()
+            .map(|(i, r)| (*i, r.expression.as_str()))
+            .collect();
+        assert_eq!(exclusion_rules, [(1, "Feedback Pulse")]);
+        let grouping_rules: Vec<_> = applied.grouping_rules.iter().collect();
+        assert_eq!(grouping_rules, [(&1, &"Energy Weapons".to_string())]);
+        assert!(applied.reversal_rules.is_empty());
+        let combat_name_rules: Vec<_> = applied.combat_name_rules.iter().collect();
+        assert_eq!(combat_name_rules, [(&0, &"Borg".to_string())]);
+    }
+
+    #[test]
+    fn match_rules_ignore_case() {
+        let rule = |method, expression: &str, ignore_case| MatchRule {
+            method,
+            expression: expression.to_string(),
+            ignore_case,
+            ..Default::default()
+        };
+        let name = "Borg Queen Octahedron";
+
+        assert!(rule(MatchMethod::Equals, "borg queen octahedron", true)
+            .matches_damage_or_heal_name(name));
+        assert!(rule(MatchMethod::StartsWith, "borg QUEEN", true).matches_damage_or_heal_name(name));
+        assert!(rule(MatchMethod::EndsWith, "octahedron", true).matches_damage_or_heal_name(name));
+        assert!(rule(MatchMethod::Contains, "queen", true).matches_damage_or_heal_name(name));
+        assert!(!rule(MatchMethod::Contains, "queens", true).matches_damage_or_heal_name(name));
+        assert!(
+            !rule(MatchMethod::StartsWith, "borg queen", false).matches_damage_or_heal_name(name)
+        );
+
+        // the lowercase expression follows edits of the expression
+        let mut rule = rule(MatchMethod::Equals, "borg", true);
+        assert!(rule.matches_damage_or_heal_name("BORG"));
+        rule.expression = "queen".to_string();
+        assert!(rule.matches_damage_or_heal_name("Queen"));
+        assert!(!rule.matches_damage_or_heal_name("Borg"));
+        assert!(rule.clone().matches_damage_or_heal_name("Queen"));
+    }
+
+    #[test]
+    fn invalid_records_are_counted() {
+        let record = |time| {
+            TestLog::new()
+                .with(TestRecord::damage(time, &ayel(), &cube(), "Torpedo", 100.0))
+                .to_log()
+        };
+        let file = TestLogFile::new(record(0.0) + "not a record\n" + &record(1.0), "log");
+        let mut analyzer = file.analyzer(Default::default());
+        let invalid_records = analyzer.invalid_records().clone();
+        let total_damage = analyzer.result()[0].total_damage_out.all;
+
+        file.append("still not a record\n");
+        analyzer.update(|| false, |_, _| {}).unwrap();
+
+        assert_eq!(invalid_records.count, 1);
+        assert_eq!(invalid_records.lines, ["not a record"]);
+        assert_eq!(total_damage, 200.0);
+        assert_eq!(analyzer.invalid_records().count, 2);
+    }
+
+    #[test]
+    fn analyzing_the_range_of_a_combat_reproduces_it() {
+        let pet = TestEntity::npc("Kira's Pet", 5);
+        let file = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Phaser Beam", 150.0).critical())
+            .with(TestRecord::damage(
+                1.0,
+                &cube(),
+                &ayel(),
+                "Plasma Torpedo",
+                80.0,
+            ))
+            .with(TestRecord::damage(
+                2.5,
+                &kira(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                419.0,
+                &ayel(),
+                &sphere(),
+                "Phaser Beam",
+                120.0,
+            ))
+            .with(
+                TestRecord::damage(420.2, &kira(), &sphere(), "Plasma Beam", 60.0)
+                    .by_pet(&pet)
+                    .flag("Flank"),
+            )
+            .with(TestRecord::damage(
+                421.4,
+                &sphere(),
+                &kira(),
+                "Plasma Torpedo",
+                40.0,
+            ))
+            .write();
+        let analyzer = file.analyzer(Default::default());
+        let ranged: Vec<_> = analyzer
+            .result()
+            .iter()
+            .map(|c| analyzer.analyze_range(c.log_pos.clone().unwrap()))
+            .collect();
+
+        assert_eq!(analyzer.result().len(), 2);
+        for (full, ranged) in analyzer.result().iter().zip(ranged) {
+            assert_eq!(ranged.len(), 1);
+            let ranged = &ranged[0];
+            assert_eq!(ranged.log_pos, full.log_pos);
+            assert_eq!(ranged.active_time, full.active_time);
+            assert_eq!(ranged.combat_time, full.combat_time);
+            assert_eq!(ranged.total_damage_out.all, full.total_damage_out.all);
+            assert_eq!(ranged.total_damage_in.all, full.total_damage_in.all);
+            assert_eq!(ranged.name(), full.name());
+            assert_eq!(ranged.players.len(), full.players.len());
+            for (handle, player) in full.players.iter() {
+                let name = full.name_manager.name(*handle);
+                let ranged_player = ranged
+                    .players
+                    .iter()
+                    .find(|(h, _)| ranged.name_manager.name(**h) == name)
+                    .map(|(_, p)| p)
+                    .unwrap();
+                assert_eq!(ranged_player.damage_out.dps.all, player.damage_out.dps.all);
+                assert_eq!(
+                    ranged_player.damage_out.damage_metrics.hits.all,
+                    player.damage_out.damage_metrics.hits.all
+                );
+                assert_eq!(
+                    ranged_player.damage_in.total_damage.all,
+                    player.damage_in.total_damage.all
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "slow"]
+    fn refresh_of_a_large_combat() {
+        const RECORDS: u32 = 1_000_000;
+        const PLAYERS: u32 = 5;
+        const ABILITIES: u32 = 40;
+        const TARGETS: u32 = 10;
+
+        let players: Vec<_> = (0..PLAYERS)
+            .map(|p| TestEntity::player(&format!("Player{}", p), "handle", p as u64))
+            .collect();
+        let targets: Vec<_> = (0..TARGETS)
+            .map(|t| TestEntity::npc(&format!("Borg Cube {}", t), 100 + t as u64))
+            .collect();
+        let record = |i: u32| {
+            TestRecord::damage(
+                i as f64 / 100.0,
+                &players[(i % PLAYERS) as usize],
+                &targets[(i % TARGETS) as usize],
+                &format!("Ability {}", i / PLAYERS % ABILITIES),
+                (100 + i % 7) as f64,
+            )
+        };
+        let file = TestLog::new().with_all((0..RECORDS).map(record)).write();
+        let mut analyzer = file.analyzer(Default::default());
+        let ranges = |combat: &Combat| -> Vec<_> {
+            players[1..]
+                .iter()
+                .map(|p| format!("{:?}", combat.test_player(p).damage_out.hits))
+                .collect()
+        };
+        let mut untouched = ranges(&analyzer.result()[0]);
+
+        // only the first player gets new hits, the values of the others are kept where they are,
+        // until the ones left behind by the first player get dropped
+        for refresh in 0..10 {
+            let appended = TestLog::new()
+                .with_all((0..10).map(|i| record(RECORDS + (refresh * 10 + i) * PLAYERS)));
+            file.append(appended.to_log());
+            analyzer.update(|| false, |_, _| {}).unwrap();
+            let combat = &analyzer.result()[0];
+            let ranges = ranges(combat);
+            if ranges != untouched {
+                let hits = (RECORDS + (refresh + 1) * 10) as usize;
+                assert_eq!(combat.hits_manger.len(), hits);
+                untouched = ranges;
+            }
+        }
+
+        assert_eq!(analyzer.result().len(), 1);
+        let combat = &analyzer.result()[0];
+        let hits: u64 = combat
+            .players
+            .values()
+            .map(|p| p.damage_out.damage_metrics.hits.all)
+            .sum();
+        assert_eq!(hits, RECORDS as u64 + 100);
+        assert!(combat.hits_manger.len() <= 2 * (RECORDS as usize + 100));
+    }
+
+    #[test]
+    fn truncated_log_is_detected() {
+        let file = TestLog::new()
+            .with_all(
+                [0.0, 1.0].map(|time| TestRecord::damage(time, &ayel(), &cube(), "Torpedo", 100.0)),
+            )
+            .write();
+        let mut analyzer = Analyzer::new(AnalysisSettings {
+            combatlog_file: file.path().to_string_lossy().into_owned(),
+            ..Default::default()
+        })
+        .unwrap();
+        let truncated_before_update = analyzer.is_log_truncated();
+        analyzer.update(|| false, |_, _| {}).unwrap();
+        let truncated_after_update = analyzer.is_log_truncated();
+
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(file.path())
+            .unwrap()
+            .set_len(10)
+            .unwrap();
+        let truncated = analyzer.is_log_truncated();
+
+        assert!(!truncated_before_update);
+        assert!(!truncated_after_update);
+        assert!(truncated);
+    }
+}
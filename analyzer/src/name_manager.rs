@@ -12,6 +12,9 @@ use super::settings::RulesGroup;
 pub struct NameManager {
     name_infos: NameMap<NameInfo>,
     name_to_handle: FxHashMap<String, NameHandle>,
+    /// names of the instances of an entity (name and entity id), see [`NameManager::insert_instance`]
+    instances: FxHashMap<(NameHandle, u64), NameHandle>,
+    instance_counts: NameMap<u32>,
 
     handle_source: u32,
 }
@@ -33,7 +36,7 @@ pub struct NameInfoRef<'a> {
 
 bitflags! {
     #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
-    pub struct NameFlags : u8{
+    pub struct NameFlags : u16{
         const NONE = 0;
         const PLAYER = 1<<0;
         const SOURCE = 1<<1;
@@ -43,6 +46,8 @@ bitflags! {
         const TARGET = 1<<5;
         const TARGET_UNIQUE = 1<<6;
         const VALUE = 1<<7;
+        /// a name made up for an instance of an entity, see [`NameManager::insert_instance`]
+        const INSTANCE = 1<<8;
     }
 }
 
@@ -84,6 +89,26 @@ impl NameManager {
         handle
     }
 
+    /// inserts a name for the instance with the entity `id` of the entity called `name`, like "To'Duj Fighter #3".
+    /// The instances are numbered in the order they first appear, rather than by their id,
+    /// so that the numbers stay small and the same instance always gets the same name.
+    /// The name is flagged as [`NameFlags::INSTANCE`], as it does not occur in the log.
+    pub fn insert_instance(&mut self, name: &str, id: u64, flags: NameFlags) -> NameHandle {
+        let flags = flags | NameFlags::INSTANCE;
+        let name_handle = self.insert(name, NameFlags::NONE);
+        if let Some(handle) = self.instances.get(&(name_handle, id)).copied() {
+            self.name_infos.get_mut(&handle).unwrap().flags |= flags;
+            return handle;
+        }
+
+        let count = self.instance_counts.entry(name_handle).or_default();
+        *count += 1;
+        let instance_name = format!("{} #{}", name, count);
+        let handle = self.insert(&instance_name, flags);
+        self.instances.insert((name_handle, id), handle);
+        handle
+    }
+
     #[inline]
     pub fn name(&self, handle: NameHandle) -> &str {
         self.info(handle).name
@@ -133,6 +158,33 @@ impl NameManager {
         self.name_to_handle.get(name).copied()
     }
 
+    /// Inserts the names of `other` with their flags.
+    /// Returns the handles of the names in this manager, keyed by their handles in `other`.
+    pub fn merge(&mut self, other: &NameManager) -> NameMap<NameHandle> {
+        other
+            .name_infos
+            .iter()
+            .map(|(handle, info)| (*handle, self.insert(&info.name, info.flags)))
+            .collect()
+    }
+
+    /// The `players`, whose character name (see [`character_name`]) is the same as the one of another player.
+    /// These can only be told apart by their full names.
+    pub fn ambiguous_players(&self, players: impl Iterator<Item = NameHandle>) -> NameSet {
+        let mut by_character_name: FxHashMap<&str, Vec<NameHandle>> = FxHashMap::default();
+        for player in players {
+            by_character_name
+                .entry(character_name(self.name(player)))
+                .or_default()
+                .push(player);
+        }
+        by_character_name
+            .into_values()
+            .filter(|players| players.len() > 1)
+            .flatten()
+            .collect()
+    }
+
     pub fn matches(&self, rule: &RulesGroup) -> bool {
         rule.matches_source_or_target_names(self.source_targets())
             || rule.matches_source_or_target_unique_names(self.source_targets_unique())
@@ -166,15 +218,21 @@ impl NameManager {
         self.names_by_flags(NameFlags::VALUE)
     }
 
+    /// the names occurring in the log with any of the `flags`, leaving out the made up instance names
     #[inline]
     fn names_by_flags(&self, flags: NameFlags) -> impl Iterator<Item = &str> + '_ {
         self.name_infos
             .values()
-            .filter(move |i| i.flags.intersects(flags))
+            .filter(move |i| i.flags.intersects(flags) && !i.flags.contains(NameFlags::INSTANCE))
             .map(|i| i.name.as_str())
     }
 }
 
+/// the part of the full name of a player before the '@' of the account handle
+pub fn character_name(full_name: &str) -> &str {
+    full_name.split('@').next().unwrap_or(full_name)
+}
+
 impl NameHandle {
     pub const UNKNOWN: Self = Self(u32::MAX);
 
@@ -239,3 +297,39 @@ impl<'a> From<&'a NameInfo> for NameInfoRef<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn instances_are_numbered_by_first_appearance() {
+        let mut name_manager = NameManager::default();
+
+        let first = name_manager.insert_instance("To'Duj Fighter", 42, NameFlags::INDIRECT_SOURCE);
+        let second = name_manager.insert_instance("To'Duj Fighter", 7, NameFlags::INDIRECT_SOURCE);
+        let other = name_manager.insert_instance("Elite Fighter", 7, NameFlags::INDIRECT_SOURCE);
+        let first_again =
+            name_manager.insert_instance("To'Duj Fighter", 42, NameFlags::INDIRECT_SOURCE);
+
+        assert_eq!(name_manager.name(first), "To'Duj Fighter #1");
+        assert_eq!(name_manager.name(second), "To'Duj Fighter #2");
+        assert_eq!(name_manager.name(other), "Elite Fighter #1");
+        assert_eq!(first_again, first);
+        assert_eq!(
+            name_manager.info(first).flags,
+            NameFlags::INDIRECT_SOURCE | NameFlags::INSTANCE
+        );
+    }
+
+    #[test]
+    fn instance_names_are_not_suggested() {
+        let mut name_manager = NameManager::default();
+        name_manager.insert("To'Duj Fighter", NameFlags::INDIRECT_SOURCE);
+        name_manager.insert_instance("To'Duj Fighter", 42, NameFlags::INDIRECT_SOURCE);
+
+        let indirect_sources: Vec<_> = name_manager.indirect_sources().collect();
+        assert_eq!(indirect_sources, ["To'Duj Fighter"]);
+        assert!(name_manager.get_handle("To'Duj Fighter #1").is_some());
+    }
+}
@@ -0,0 +1,910 @@
+use std::{
+    borrow::{Borrow, BorrowMut},
+    collections::BTreeMap,
+    fmt,
+    path::Path,
+    sync::RwLock,
+};
+
+#[cfg(feature = "serde")]
+use serde::*;
+
+use super::parser::*;
+
+pub const MAX_RECENT_COMBATLOG_FILES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AnalysisSettings {
+    pub combatlog_file: String,
+    pub combat_separation_time_seconds: f64,
+    pub indirect_source_grouping_revers_rules: Vec<MatchRule>,
+    pub custom_group_rules: Vec<RulesGroup>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub damage_out_exclusion_rules: Vec<MatchRule>,
+    /// also exclude the matching records from the incoming damage and healing of the targets
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub exclude_from_incoming: bool,
+    pub combat_name_rules: Vec<CombatNameRule>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub heal_out_grouping: HealOutGrouping,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub damage_out_grouping: DamageOutGrouping,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub dps_denominator: DpsDenominator,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub player_merges: Vec<PlayerMerge>,
+    /// hits or ticks closer than this are considered as one continuous uptime interval
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "AnalysisSettings::default_uptime_gap_seconds")
+    )]
+    pub uptime_gap_seconds: f64,
+    /// damage or heal values above this are considered implausible (e.g. from corrupted lines) and flagged as anomalies
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "AnalysisSettings::default_anomaly_threshold")
+    )]
+    pub anomaly_threshold: f64,
+    /// leave anomalies out of the max one-hit and the graphs, they are still counted otherwise
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub exclude_anomalies: bool,
+    /// group every instance of a pet separately, e.g. "To'Duj Fighter #3", instead of merging them by name
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub separate_pet_instances: bool,
+    /// treat positive shield values with a non-zero second value as heals instead of damage,
+    /// see [`crate::parser::Record::reclassify_ally_shield_heal`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub detect_ally_shield_heals: bool,
+    /// A player's combat time only starts, once they dealt at least this much damage within
+    /// [`Self::combat_start_window_seconds`], or with their first damage after another player did so.
+    /// Keeps single shots while buffing before the pull out of the combat time. 0 disables it.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub combat_start_damage_threshold: f64,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default = "AnalysisSettings::default_combat_start_window_seconds")
+    )]
+    pub combat_start_window_seconds: f64,
+    /// drop blocks of records, which repeat the recent ones,
+    /// as the game occasionally duplicates parts of the log
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub drop_duplicate_records: bool,
+    /// watch the directory of the combatlog file and switch to newer `combatlog*.log` files appearing there
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub follow_newest_combatlog_file: bool,
+    /// most recent first, at most [`MAX_RECENT_COMBATLOG_FILES`]
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub recent_combatlog_files: Vec<String>,
+}
+
+/// the rules of the [`AnalysisSettings`], e.g. to share them with others
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AnalysisRules {
+    pub indirect_source_grouping_revers_rules: Vec<MatchRule>,
+    pub custom_group_rules: Vec<RulesGroup>,
+    pub damage_out_exclusion_rules: Vec<MatchRule>,
+    pub combat_name_rules: Vec<CombatNameRule>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct PlayerMerge {
+    pub old_name: String,
+    pub new_name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CombatNameRule {
+    pub name_rule: RulesGroup,
+    pub additional_info_rules: Vec<RulesGroup>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct MatchRule {
+    pub aspect: MatchAspect,
+    pub expression: String,
+    pub method: MatchMethod,
+    pub enabled: bool,
+    /// rules of older settings keep matching case sensitive
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub ignore_case: bool,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub(crate) lowercase_expression: LowercaseExpression,
+}
+
+/// lazily lowercased copy of the expression along with the expression it was made from,
+/// so matching does not allocate per record. It is made again once the expression got edited.
+#[derive(Default)]
+pub(crate) struct LowercaseExpression(RwLock<Option<(String, String)>>);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MatchAspect {
+    SourceOrTargetName,
+    SourceOrTargetUniqueName,
+    IndirectSourceName,
+    IndirectUniqueSourceName,
+    #[default]
+    DamageOrHealName,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum MatchMethod {
+    #[default]
+    Equals,
+    StartsWith,
+    EndsWith,
+    Contains,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum HealOutGrouping {
+    #[default]
+    TargetThenAbility,
+    AbilityThenTarget,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DamageOutGrouping {
+    #[default]
+    AbilityThenTarget,
+    TargetThenAbility,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DpsDenominator {
+    #[default]
+    PlayerCombatTime,
+    PlayerActiveTime,
+    CombatDuration,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CustomGroupingRule {
+    pub group_name: String,
+    pub match_rule: MatchRule,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RulesGroup {
+    pub name: String,
+    pub rules: Vec<MatchRule>,
+    pub enabled: bool,
+}
+
+impl AnalysisSettings {
+    pub fn combatlog_file(&self) -> &Path {
+        Path::new(&self.combatlog_file)
+    }
+
+    /// moves the `file` to the front of the recent combatlog files
+    pub fn add_recent_combatlog_file(&mut self, file: &str) {
+        if file.is_empty() {
+            return;
+        }
+
+        self.recent_combatlog_files.retain(|f| f != file);
+        self.recent_combatlog_files.insert(0, file.to_string());
+        self.recent_combatlog_files
+            .truncate(MAX_RECENT_COMBATLOG_FILES);
+    }
+
+    /// adds the combat name `rules`, whose names are not used by any of the current rules yet
+    pub fn add_combat_name_rules(&mut self, rules: &[CombatNameRule]) {
+        let existing: Vec<_> = self
+            .combat_name_rules
+            .iter()
+            .map(|r| r.name_rule.name.clone())
+            .collect();
+        self.combat_name_rules.extend(
+            rules
+                .iter()
+                .filter(|r| !existing.contains(&r.name_rule.name))
+                .cloned(),
+        );
+    }
+
+    pub fn rules(&self) -> AnalysisRules {
+        AnalysisRules {
+            indirect_source_grouping_revers_rules: self
+                .indirect_source_grouping_revers_rules
+                .clone(),
+            custom_group_rules: self.custom_group_rules.clone(),
+            damage_out_exclusion_rules: self.damage_out_exclusion_rules.clone(),
+            combat_name_rules: self.combat_name_rules.clone(),
+        }
+    }
+
+    pub fn replace_rules(&mut self, rules: AnalysisRules) {
+        self.indirect_source_grouping_revers_rules = rules.indirect_source_grouping_revers_rules;
+        self.custom_group_rules = rules.custom_group_rules;
+        self.damage_out_exclusion_rules = rules.damage_out_exclusion_rules;
+        self.combat_name_rules = rules.combat_name_rules;
+    }
+
+    /// adds the `rules`, which do not exist yet. Groups are considered existing by their name.
+    pub fn merge_rules(&mut self, rules: &AnalysisRules) {
+        let add_missing = |existing: &mut Vec<MatchRule>, rules: &[MatchRule]| {
+            for rule in rules.iter() {
+                if !existing.contains(rule) {
+                    existing.push(rule.clone());
+                }
+            }
+        };
+        add_missing(
+            &mut self.indirect_source_grouping_revers_rules,
+            &rules.indirect_source_grouping_revers_rules,
+        );
+        add_missing(
+            &mut self.damage_out_exclusion_rules,
+            &rules.damage_out_exclusion_rules,
+        );
+        for group in rules.custom_group_rules.iter() {
+            if !self.custom_group_rules.iter().any(|g| g.name == group.name) {
+                self.custom_group_rules.push(group.clone());
+            }
+        }
+        self.add_combat_name_rules(&rules.combat_name_rules);
+    }
+
+    /// replaces the combat name rules with the names of the `rules` by them, other rules are kept
+    pub fn reset_combat_name_rules(&mut self, rules: &[CombatNameRule]) {
+        self.combat_name_rules
+            .retain(|r| !rules.iter().any(|d| d.name_rule.name == r.name_rule.name));
+        self.combat_name_rules.extend_from_slice(rules);
+    }
+
+    /// short hash of the settings, which affect the analysis, to compare them at a glance.
+    /// The combat log files and disabled rules are left out, see [`Self::digest_fields`].
+    pub fn digest(&self) -> String {
+        // FNV-1a, so that the digest does not depend on the platform
+        let hash = self
+            .digest_fields()
+            .iter()
+            .flat_map(|(name, value)| [name, "=", value, "\n"])
+            .flat_map(str::bytes)
+            .fold(0xcbf29ce484222325_u64, |hash, byte| {
+                (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+            });
+        format!("{:08x}", (hash >> 32) as u32 ^ hash as u32)
+    }
+
+    /// The settings, which affect the analysis, written out by name. Settings at their default value are
+    /// left out, so settings added later on do not change the digests of the analyses made before.
+    fn digest_fields(&self) -> BTreeMap<&'static str, String> {
+        let default = Self::default();
+        let mut fields = BTreeMap::new();
+        macro_rules! field {
+            ($field:ident, $write:expr) => {
+                let value = $write(&self.$field);
+                if value != $write(&default.$field) {
+                    fields.insert(stringify!($field), value);
+                }
+            };
+        }
+        let number = |value: &f64| value.to_string();
+        let flag = |value: &bool| value.to_string();
+        // enums by their variant name, as in the settings file
+        let variant = |value: &dyn fmt::Debug| format!("{:?}", value);
+        let rules = |rules: &Vec<MatchRule>| digest_rules(rules);
+
+        field!(combat_separation_time_seconds, number);
+        field!(indirect_source_grouping_revers_rules, rules);
+        field!(custom_group_rules, |g: &Vec<RulesGroup>| {
+            digest_list(g.iter().filter(|g| g.enabled).map(RulesGroup::digest))
+        });
+        field!(damage_out_exclusion_rules, rules);
+        field!(exclude_from_incoming, flag);
+        field!(combat_name_rules, |r: &Vec<CombatNameRule>| {
+            digest_list(r.iter().filter(|r| r.name_rule.enabled).map(|r| {
+                format!(
+                    "{}{}",
+                    r.name_rule.digest(),
+                    digest_list(r.additional_info_rules.iter().map(RulesGroup::digest))
+                )
+            }))
+        });
+        field!(heal_out_grouping, |g: &HealOutGrouping| variant(g));
+        field!(damage_out_grouping, |g: &DamageOutGrouping| variant(g));
+        field!(dps_denominator, |d: &DpsDenominator| variant(d));
+        field!(player_merges, |m: &Vec<PlayerMerge>| {
+            digest_list(
+                m.iter()
+                    .map(|m| format!("{}>{}", digest_text(&m.old_name), digest_text(&m.new_name))),
+            )
+        });
+        field!(uptime_gap_seconds, number);
+        field!(anomaly_threshold, number);
+        field!(exclude_anomalies, flag);
+        field!(separate_pet_instances, flag);
+        field!(detect_ally_shield_heals, flag);
+        field!(combat_start_damage_threshold, number);
+        field!(combat_start_window_seconds, number);
+        field!(drop_duplicate_records, flag);
+        fields
+    }
+
+    fn default_uptime_gap_seconds() -> f64 {
+        3.0
+    }
+
+    fn default_combat_start_window_seconds() -> f64 {
+        5.0
+    }
+
+    fn default_anomaly_threshold() -> f64 {
+        1_000_000_000.0
+    }
+
+    /// index of the first exclusion rule matching the `record`
+    pub(crate) fn exclusion_rule(&self, record: &Record) -> Option<usize> {
+        self.damage_out_exclusion_rules
+            .iter()
+            .position(|r| r.matches_record(record))
+    }
+}
+
+impl RulesGroup {
+    pub fn matches_record(&self, record: &Record) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        self.rules.iter().any(|r| r.matches_record(record))
+    }
+
+    pub fn matches_source_or_target_names<'a>(
+        &self,
+        mut names: impl Iterator<Item = &'a str>,
+    ) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        names.any(|n| {
+            self.rules
+                .iter()
+                .any(|r| r.matches_source_or_target_name(n))
+        })
+    }
+
+    pub fn matches_source_or_target_unique_names<'a>(
+        &self,
+        mut names: impl Iterator<Item = &'a str>,
+    ) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        names.any(|n| {
+            self.rules
+                .iter()
+                .any(|r| r.matches_source_or_target_unique_name(n))
+        })
+    }
+
+    pub fn matches_indirect_source_names<'a>(
+        &self,
+        mut names: impl Iterator<Item = &'a str>,
+    ) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        names.any(|n| self.rules.iter().any(|r| r.matches_indirect_source_name(n)))
+    }
+
+    pub fn matches_indirect_source_unique_names<'a>(
+        &self,
+        mut names: impl Iterator<Item = &'a str>,
+    ) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        names.any(|n| {
+            self.rules
+                .iter()
+                .any(|r| r.matches_indirect_source_unique_name(n))
+        })
+    }
+
+    pub fn matches_damage_or_heal_names<'a>(
+        &self,
+        mut names: impl Iterator<Item = &'a str>,
+    ) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        names.any(|n| self.rules.iter().any(|r| r.matches_damage_or_heal_name(n)))
+    }
+}
+
+impl MatchRule {
+    pub fn matches_record(&self, record: &Record) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        match self.aspect {
+            MatchAspect::SourceOrTargetName => {
+                self.check_match_or_false(record.source.name())
+                    || self.check_match_or_false(record.target.name())
+            }
+            MatchAspect::SourceOrTargetUniqueName => {
+                self.check_match_or_false(record.source.unique_name())
+                    || self.check_match_or_false(record.target.unique_name())
+            }
+            MatchAspect::IndirectSourceName => {
+                self.check_match_or_false(record.indirect_source.name())
+            }
+            MatchAspect::IndirectUniqueSourceName => {
+                self.check_match_or_false(record.indirect_source.unique_name())
+            }
+            MatchAspect::DamageOrHealName => self.check_match(&record.value_name),
+        }
+    }
+
+    pub fn matches_source_or_target_name(&self, name: &str) -> bool {
+        if !self.enabled || self.aspect != MatchAspect::SourceOrTargetName {
+            return false;
+        }
+
+        self.check_match(name)
+    }
+
+    pub fn matches_source_or_target_unique_name(&self, name: &str) -> bool {
+        if !self.enabled || self.aspect != MatchAspect::SourceOrTargetUniqueName {
+            return false;
+        }
+
+        self.check_match(name)
+    }
+
+    pub fn matches_indirect_source_name(&self, name: &str) -> bool {
+        if !self.enabled || self.aspect != MatchAspect::IndirectSourceName {
+            return false;
+        }
+
+        self.check_match(name)
+    }
+
+    pub fn matches_indirect_source_unique_name(&self, name: &str) -> bool {
+        if !self.enabled || self.aspect != MatchAspect::IndirectUniqueSourceName {
+            return false;
+        }
+
+        self.check_match(name)
+    }
+
+    pub fn matches_damage_or_heal_name(&self, name: &str) -> bool {
+        if !self.enabled || self.aspect != MatchAspect::DamageOrHealName {
+            return false;
+        }
+
+        self.check_match(name)
+    }
+
+    fn check_match(&self, value: &str) -> bool {
+        if !self.ignore_case {
+            return self.method.check_match(&self.expression, value);
+        }
+
+        if let Some((source, expression)) = &*self.lowercase_expression.0.read().unwrap() {
+            if *source == self.expression {
+                return self.method.check_match_ignore_case(expression, value);
+            }
+        }
+
+        let expression = self.expression.to_lowercase();
+        let matches = self.method.check_match_ignore_case(&expression, value);
+        *self.lowercase_expression.0.write().unwrap() = Some((self.expression.clone(), expression));
+        matches
+    }
+
+    fn check_match_or_false(&self, value: Option<&str>) -> bool {
+        match value {
+            Some(value) => self.check_match(value),
+            None => false,
+        }
+    }
+}
+
+impl Clone for LowercaseExpression {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl PartialEq for LowercaseExpression {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for LowercaseExpression {}
+
+impl fmt::Debug for LowercaseExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("..")
+    }
+}
+
+impl MatchAspect {
+    pub const fn display(self) -> &'static str {
+        match self {
+            MatchAspect::SourceOrTargetName => "Source or Target Name",
+            MatchAspect::SourceOrTargetUniqueName => "Source or Target Unique Name",
+            MatchAspect::IndirectSourceName => "Indirect Source Name",
+            MatchAspect::DamageOrHealName => "Damage / Heal Name",
+            MatchAspect::IndirectUniqueSourceName => "Indirect Source Unique Name",
+        }
+    }
+}
+
+impl HealOutGrouping {
+    pub const fn display(self) -> &'static str {
+        match self {
+            HealOutGrouping::TargetThenAbility => "Target → Ability",
+            HealOutGrouping::AbilityThenTarget => "Ability → Target",
+        }
+    }
+}
+
+impl DamageOutGrouping {
+    pub const fn display(self) -> &'static str {
+        match self {
+            DamageOutGrouping::AbilityThenTarget => "Ability → Target",
+            DamageOutGrouping::TargetThenAbility => "Target → Ability",
+        }
+    }
+}
+
+impl DpsDenominator {
+    pub const fn display(self) -> &'static str {
+        match self {
+            DpsDenominator::PlayerCombatTime => "Player Combat Time",
+            DpsDenominator::PlayerActiveTime => "Player Active Time",
+            DpsDenominator::CombatDuration => "Combat Duration",
+        }
+    }
+
+    pub const fn column_name(self) -> &'static str {
+        match self {
+            DpsDenominator::PlayerCombatTime => "DPS",
+            DpsDenominator::PlayerActiveTime => "DPS (active)",
+            DpsDenominator::CombatDuration => "DPS (combat)",
+        }
+    }
+
+    pub const fn description(self) -> &'static str {
+        match self {
+            DpsDenominator::PlayerCombatTime => "Damage Per Second\nCalculated from the first damage of the player to the last damage in the log",
+            DpsDenominator::PlayerActiveTime => "Damage Per Second\nCalculated from the first action of the player to the last action in the log",
+            DpsDenominator::CombatDuration => "Damage Per Second\nCalculated over the combat duration, from the first damage of any player to the last damage of any player",
+        }
+    }
+}
+
+impl MatchMethod {
+    fn check_match(&self, expression: &str, value: &str) -> bool {
+        match self {
+            MatchMethod::Equals => value == expression,
+            MatchMethod::StartsWith => value.starts_with(expression),
+            MatchMethod::EndsWith => value.ends_with(expression),
+            MatchMethod::Contains => value.contains(expression),
+        }
+    }
+
+    /// `expression` has to be lowercase already, `value` is lowercased while comparing
+    fn check_match_ignore_case(&self, expression: &str, value: &str) -> bool {
+        match self {
+            MatchMethod::Equals => lowercase_chars(value).eq(expression.chars()),
+            MatchMethod::StartsWith => starts_with(lowercase_chars(value), expression.chars()),
+            MatchMethod::EndsWith => {
+                starts_with(lowercase_chars(value).rev(), expression.chars().rev())
+            }
+            MatchMethod::Contains => {
+                expression.is_empty()
+                    || value
+                        .char_indices()
+                        .any(|(i, _)| starts_with(lowercase_chars(&value[i..]), expression.chars()))
+            }
+        }
+    }
+
+    pub const fn display(self) -> &'static str {
+        match self {
+            MatchMethod::Equals => "Equals",
+            MatchMethod::StartsWith => "Starts with",
+            MatchMethod::EndsWith => "Ends with",
+            MatchMethod::Contains => "Contains",
+        }
+    }
+}
+
+impl Default for AnalysisSettings {
+    fn default() -> Self {
+        Self {
+            combatlog_file: Default::default(),
+            combat_separation_time_seconds: 1.5 * 60.0,
+            indirect_source_grouping_revers_rules: Default::default(),
+            custom_group_rules: Default::default(),
+            damage_out_exclusion_rules: Default::default(),
+            exclude_from_incoming: false,
+            combat_name_rules: Default::default(),
+            heal_out_grouping: Default::default(),
+            damage_out_grouping: Default::default(),
+            dps_denominator: Default::default(),
+            player_merges: Default::default(),
+            uptime_gap_seconds: Self::default_uptime_gap_seconds(),
+            anomaly_threshold: Self::default_anomaly_threshold(),
+            exclude_anomalies: false,
+            separate_pet_instances: false,
+            detect_ally_shield_heals: false,
+            combat_start_damage_threshold: 0.0,
+            combat_start_window_seconds: Self::default_combat_start_window_seconds(),
+            drop_duplicate_records: false,
+            follow_newest_combatlog_file: false,
+            recent_combatlog_files: Default::default(),
+        }
+    }
+}
+
+impl Default for MatchRule {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            aspect: Default::default(),
+            expression: Default::default(),
+            method: Default::default(),
+            ignore_case: true,
+            lowercase_expression: Default::default(),
+        }
+    }
+}
+
+impl RulesGroup {
+    /// see [`AnalysisSettings::digest_fields`]
+    fn digest(&self) -> String {
+        format!("{}{}", digest_text(&self.name), digest_rules(&self.rules))
+    }
+}
+
+impl MatchRule {
+    /// see [`AnalysisSettings::digest_fields`], rules of older settings are written as before they could ignore the case
+    fn digest(&self) -> String {
+        format!(
+            "{:?} {:?}{} {}",
+            self.aspect,
+            self.method,
+            if self.ignore_case { " ignore_case" } else { "" },
+            digest_text(&self.expression)
+        )
+    }
+}
+
+/// the enabled `rules`
+fn digest_rules(rules: &[MatchRule]) -> String {
+    digest_list(rules.iter().filter(|r| r.enabled).map(MatchRule::digest))
+}
+
+fn digest_list(items: impl Iterator<Item = String>) -> String {
+    format!("[{}]", items.collect::<Vec<_>>().join(","))
+}
+
+/// quoted, so the names and expressions can not be confused with the text around them
+fn digest_text(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn lowercase_chars(value: &str) -> impl DoubleEndedIterator<Item = char> + '_ {
+    value.chars().flat_map(char::to_lowercase)
+}
+
+fn starts_with(mut value: impl Iterator<Item = char>, prefix: impl Iterator<Item = char>) -> bool {
+    prefix.into_iter().all(|c| value.next() == Some(c))
+}
+
+impl Default for RulesGroup {
+    fn default() -> Self {
+        Self {
+            name: Default::default(),
+            rules: Default::default(),
+            enabled: true,
+        }
+    }
+}
+
+impl Borrow<RulesGroup> for CombatNameRule {
+    fn borrow(&self) -> &RulesGroup {
+        &self.name_rule
+    }
+}
+
+impl BorrowMut<RulesGroup> for CombatNameRule {
+    fn borrow_mut(&mut self) -> &mut RulesGroup {
+        &mut self.name_rule
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn combat_name_rule(name: &str, expression: &str) -> CombatNameRule {
+        CombatNameRule {
+            name_rule: RulesGroup {
+                name: name.to_string(),
+                rules: vec![MatchRule {
+                    expression: expression.to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            additional_info_rules: Vec::new(),
+        }
+    }
+
+    fn default_combat_name_rules() -> [CombatNameRule; 2] {
+        [
+            combat_name_rule("Infected Space", "Borg Cube"),
+            combat_name_rule("Hive Space", "Borg Queen Octahedron"),
+        ]
+    }
+
+    #[test]
+    fn adding_the_combat_name_rules_twice_changes_nothing() {
+        let mut settings = AnalysisSettings {
+            combat_name_rules: vec![combat_name_rule("Infected Space", "edited")],
+            ..Default::default()
+        };
+
+        settings.add_combat_name_rules(&default_combat_name_rules());
+        let added = settings.combat_name_rules.clone();
+        settings.add_combat_name_rules(&default_combat_name_rules());
+
+        assert_eq!(settings.combat_name_rules, added);
+        assert_eq!(
+            added,
+            [
+                combat_name_rule("Infected Space", "edited"),
+                default_combat_name_rules()[1].clone()
+            ]
+        );
+    }
+
+    #[test]
+    fn resetting_the_combat_name_rules_keeps_the_custom_ones() {
+        let mut settings = AnalysisSettings {
+            combat_name_rules: vec![
+                combat_name_rule("Infected Space", "edited"),
+                combat_name_rule("Custom", "Gorn Minefield"),
+            ],
+            ..Default::default()
+        };
+
+        settings.reset_combat_name_rules(&default_combat_name_rules());
+
+        let [infected_space, hive_space] = default_combat_name_rules();
+        assert_eq!(
+            settings.combat_name_rules,
+            [
+                combat_name_rule("Custom", "Gorn Minefield"),
+                infected_space,
+                hive_space
+            ]
+        );
+    }
+
+    fn exclusion_rule(expression: &str) -> MatchRule {
+        MatchRule {
+            expression: expression.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn shared_rules() -> AnalysisRules {
+        AnalysisRules {
+            indirect_source_grouping_revers_rules: Vec::new(),
+            custom_group_rules: vec![RulesGroup {
+                name: "Energy Weapons".to_string(),
+                rules: vec![exclusion_rule("Phaser")],
+                ..Default::default()
+            }],
+            damage_out_exclusion_rules: vec![exclusion_rule("Feedback Pulse")],
+            combat_name_rules: default_combat_name_rules().to_vec(),
+        }
+    }
+
+    #[test]
+    fn merging_the_rules_twice_changes_nothing() {
+        let mut settings = AnalysisSettings::default();
+
+        settings.merge_rules(&shared_rules());
+        let merged = settings.rules();
+        settings.merge_rules(&shared_rules());
+
+        assert_eq!(settings.rules(), merged);
+        assert_eq!(merged, shared_rules());
+    }
+
+    #[test]
+    fn merging_the_rules_keeps_the_existing_ones() {
+        let mut settings = AnalysisSettings {
+            custom_group_rules: vec![RulesGroup {
+                name: "Energy Weapons".to_string(),
+                rules: vec![exclusion_rule("Disruptor")],
+                ..Default::default()
+            }],
+            damage_out_exclusion_rules: vec![exclusion_rule("Plasma Fire")],
+            ..Default::default()
+        };
+
+        settings.merge_rules(&shared_rules());
+
+        assert_eq!(settings.custom_group_rules.len(), 1);
+        assert_eq!(
+            settings.custom_group_rules[0].rules,
+            [exclusion_rule("Disruptor")]
+        );
+        assert_eq!(
+            settings.damage_out_exclusion_rules,
+            [
+                exclusion_rule("Plasma Fire"),
+                exclusion_rule("Feedback Pulse")
+            ]
+        );
+        assert_eq!(
+            settings.combat_name_rules,
+            default_combat_name_rules().to_vec()
+        );
+    }
+
+    #[test]
+    fn digests_are_stable() {
+        let settings = AnalysisSettings {
+            combat_separation_time_seconds: 60.0,
+            damage_out_exclusion_rules: vec![exclusion_rule("Feedback Pulse")],
+            combat_name_rules: default_combat_name_rules().to_vec(),
+            ..Default::default()
+        };
+
+        assert_eq!(AnalysisSettings::default().digest(), "4fd0bfc1");
+        assert_eq!(settings.digest(), "26bb1989");
+    }
+
+    #[test]
+    fn digests_ignore_the_files_and_disabled_rules() {
+        let settings = AnalysisSettings {
+            damage_out_exclusion_rules: vec![exclusion_rule("Feedback Pulse")],
+            ..Default::default()
+        };
+        let mut disabled_rule = exclusion_rule("Plasma Fire");
+        disabled_rule.enabled = false;
+        let same = AnalysisSettings {
+            combatlog_file: "combatlog.log".to_string(),
+            recent_combatlog_files: vec!["combatlog.log".to_string()],
+            damage_out_exclusion_rules: vec![exclusion_rule("Feedback Pulse"), disabled_rule],
+            ..Default::default()
+        };
+        let different = AnalysisSettings {
+            damage_out_exclusion_rules: vec![exclusion_rule("Feedback\" Pulse")],
+            ..Default::default()
+        };
+
+        assert_eq!(settings.digest(), same.digest());
+        assert_ne!(settings.digest(), different.digest());
+    }
+}
@@ -0,0 +1,1376 @@
+//! Builds synthetic combat logs, to test the parser and the analyzer without real logs.
+//! Available to the tests of dependent crates with the `test-log` feature.
+
+use std::{
+    fmt::Write as _,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use flate2::write::GzEncoder;
+
+use super::{settings::*, *};
+
+/// source, target or indirect source of a [`TestRecord`]
+#[derive(Clone, Debug)]
+pub struct TestEntity {
+    name: String,
+    id_and_unique_name: String,
+}
+
+/// a line of a [`TestLog`]
+#[derive(Clone, Debug)]
+pub struct TestRecord {
+    time_millis: i64,
+    source: TestEntity,
+    indirect_source: TestEntity,
+    target: TestEntity,
+    value_name: String,
+    value_type: String,
+    flags: Vec<&'static str>,
+    value1: f64,
+    value2: f64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TestLog {
+    records: Vec<TestRecord>,
+}
+
+impl TestEntity {
+    pub fn none() -> Self {
+        Self {
+            name: String::new(),
+            id_and_unique_name: "*".to_string(),
+        }
+    }
+
+    /// a player with the full name `name@handle`
+    pub fn player(name: &str, handle: &str, id: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            id_and_unique_name: format!("P[{}@{} {}@{}]", id, id, name, handle),
+        }
+    }
+
+    /// an NPC or a pet, `id` identifies the instance
+    pub fn npc(name: &str, id: u64) -> Self {
+        Self {
+            name: name.to_string(),
+            id_and_unique_name: format!("C[{} {}]", id, name.replace(' ', "_")),
+        }
+    }
+
+    /// the name the analyzer knows the entity by
+    pub fn full_name(&self) -> &str {
+        match self.id_and_unique_name.strip_prefix("P[") {
+            Some(id) => id.split_once(' ').unwrap().1.trim_end_matches(']'),
+            None => &self.name,
+        }
+    }
+}
+
+impl TestRecord {
+    /// hull damage, which the resistances didn't reduce
+    pub fn damage(
+        time_seconds: f64,
+        source: &TestEntity,
+        target: &TestEntity,
+        ability: &str,
+        damage: f64,
+    ) -> Self {
+        Self {
+            time_millis: (time_seconds * 1e3).round() as _,
+            source: source.clone(),
+            indirect_source: TestEntity::none(),
+            target: target.clone(),
+            value_name: ability.to_string(),
+            value_type: "Phaser".to_string(),
+            flags: Vec::new(),
+            value1: damage,
+            value2: damage,
+        }
+    }
+
+    /// damage to the shields, which prevented the same amount of damage to the hull
+    pub fn shield_damage(
+        time_seconds: f64,
+        source: &TestEntity,
+        target: &TestEntity,
+        ability: &str,
+        damage: f64,
+    ) -> Self {
+        Self {
+            value_type: "Shield".to_string(),
+            value2: -damage,
+            ..Self::damage(time_seconds, source, target, ability, damage)
+        }
+    }
+
+    pub fn heal(
+        time_seconds: f64,
+        source: &TestEntity,
+        target: &TestEntity,
+        ability: &str,
+        heal: f64,
+    ) -> Self {
+        Self {
+            value_type: "HitPoints".to_string(),
+            value1: -heal,
+            value2: 0.0,
+            ..Self::damage(time_seconds, source, target, ability, heal)
+        }
+    }
+
+    pub fn shield_heal(
+        time_seconds: f64,
+        source: &TestEntity,
+        target: &TestEntity,
+        ability: &str,
+        heal: f64,
+    ) -> Self {
+        Self {
+            value_type: "Shield".to_string(),
+            ..Self::heal(time_seconds, source, target, ability, heal)
+        }
+    }
+
+    /// logged like the pet of the source caused the value
+    pub fn by_pet(mut self, pet: &TestEntity) -> Self {
+        self.indirect_source = pet.clone();
+        self
+    }
+
+    pub fn critical(self) -> Self {
+        self.flag("Critical")
+    }
+
+    pub fn kill(self) -> Self {
+        self.flag("Kill")
+    }
+
+    pub fn immune(mut self) -> Self {
+        self.value1 = 0.0;
+        self.value2 = 0.0;
+        self.flag("Immune")
+    }
+
+    pub fn flag(mut self, flag: &'static str) -> Self {
+        self.flags.push(flag);
+        self
+    }
+
+    /// the record as a line of a log, whose first record is at `start`
+    pub fn line(&self, start: NaiveDateTime) -> String {
+        let time = start + Duration::milliseconds(self.time_millis);
+        let mut line = String::new();
+        // the logs only contain tenths of seconds
+        write!(
+            line,
+            "{}.{}::",
+            time.format("%y:%m:%d:%H:%M:%S"),
+            time.format("%3f").to_string().chars().next().unwrap()
+        )
+        .unwrap();
+        writeln!(
+            line,
+            "{},{},{},{},{},{},{},Pn.test,{},{},{},{}",
+            self.source.name,
+            self.source.id_and_unique_name,
+            self.indirect_source.name,
+            self.indirect_source.id_and_unique_name,
+            self.target.name,
+            self.target.id_and_unique_name,
+            self.value_name,
+            self.value_type,
+            self.flags.join("|"),
+            self.value1,
+            self.value2
+        )
+        .unwrap();
+        line
+    }
+}
+
+impl TestLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, record: TestRecord) -> Self {
+        self.records.push(record);
+        self
+    }
+
+    pub fn with_all(mut self, records: impl IntoIterator<Item = TestRecord>) -> Self {
+        self.records.extend(records);
+        self
+    }
+
+    /// time of the records at 0 seconds
+    pub fn start() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 7)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap()
+    }
+
+    /// time of the records at `seconds`
+    pub fn time(seconds: f64) -> NaiveDateTime {
+        Self::start() + Duration::milliseconds((seconds * 1e3).round() as _)
+    }
+
+    pub fn to_log(&self) -> String {
+        self.records.iter().map(|r| r.line(Self::start())).collect()
+    }
+
+    /// writes the log to a temporary file, which is removed again when the returned guard is dropped
+    pub fn write(&self) -> TestLogFile {
+        TestLogFile::new(self.to_log(), "log")
+    }
+
+    /// like [`Self::write`], but gzip compressed
+    pub fn write_compressed(&self) -> TestLogFile {
+        TestLogFile::compressed(self.to_log())
+    }
+
+    /// analyzes the whole log with the `settings`, ignoring their log file
+    pub fn analyze(&self, settings: AnalysisSettings) -> Vec<Combat> {
+        Self::analyze_file(&self.write(), settings)
+    }
+
+    pub fn analyze_file(file: &TestLogFile, settings: AnalysisSettings) -> Vec<Combat> {
+        file.analyzer(settings).into_result()
+    }
+
+    /// analyzes the whole log with the default settings, expecting exactly one combat
+    pub fn analyze_single(&self) -> Combat {
+        let mut combats = self.analyze(Default::default());
+        assert_eq!(combats.len(), 1);
+        combats.pop().unwrap()
+    }
+}
+
+/// a temporary file, which is removed again when dropped
+pub struct TestLogFile(PathBuf);
+
+/// a temporary directory, which is removed again with its files when dropped
+pub struct TestLogDirectory(PathBuf);
+
+/// a path in the temporary directory, which no other test (also of other test runs) uses
+fn unique_temp_path(extension: &str) -> PathBuf {
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+    std::env::temp_dir().join(format!(
+        "sto_cla_test_log_{}_{}.{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed),
+        extension
+    ))
+}
+
+impl TestLogFile {
+    /// writes the `data` to a new temporary file with the `extension`
+    pub fn new(data: impl AsRef<[u8]>, extension: &str) -> Self {
+        let file = unique_temp_path(extension);
+        std::fs::write(&file, data).unwrap();
+        Self(file)
+    }
+
+    /// writes the `data` gzip compressed to a new temporary file
+    pub fn compressed(data: impl AsRef<[u8]>) -> Self {
+        let mut encoder = GzEncoder::new(Vec::new(), Default::default());
+        encoder.write_all(data.as_ref()).unwrap();
+        Self::new(encoder.finish().unwrap(), "log.gz")
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+
+    /// appends the `data`, like the game does while the log is analyzed
+    pub fn append(&self, data: impl AsRef<[u8]>) {
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&self.0)
+            .unwrap()
+            .write_all(data.as_ref())
+            .unwrap();
+    }
+
+    /// an analyzer of the file with the `settings`, which already analyzed it once
+    pub fn analyzer(&self, settings: AnalysisSettings) -> Analyzer {
+        let mut analyzer = Analyzer::new(AnalysisSettings {
+            combatlog_file: self.0.to_string_lossy().into_owned(),
+            ..settings
+        })
+        .unwrap();
+        assert!(analyzer.update(|| false, |_, _| {}).unwrap());
+        analyzer
+    }
+}
+
+impl Drop for TestLogFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+impl TestLogDirectory {
+    pub fn new() -> Self {
+        let directory = unique_temp_path("dir");
+        std::fs::create_dir(&directory).unwrap();
+        Self(directory)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Default for TestLogDirectory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TestLogDirectory {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+impl Combat {
+    pub fn test_player(&self, player: &TestEntity) -> &Player {
+        let handle = self.name_manager.get_handle(player.full_name()).unwrap();
+        &self.players[&handle]
+    }
+}
+
+impl DamageGroup {
+    /// the sub group reached by the names of the `path`
+    pub fn test_group(&self, combat: &Combat, path: &[&str]) -> &Self {
+        path.iter().fold(self, |group, name| {
+            let handle = combat.name_manager.get_handle(name).unwrap();
+            &group.sub_groups[&handle]
+        })
+    }
+}
+
+impl HealGroup {
+    /// the sub group reached by the names of the `path`
+    pub fn test_group(&self, combat: &Combat, path: &[&str]) -> &Self {
+        path.iter().fold(self, |group, name| {
+            let handle = combat.name_manager.get_handle(name).unwrap();
+            &group.sub_groups[&handle]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ayel() -> TestEntity {
+        TestEntity::player("Ayel", "greyblizzard", 1)
+    }
+
+    fn kira() -> TestEntity {
+        TestEntity::player("Kira", "somebody", 2)
+    }
+
+    fn cube() -> TestEntity {
+        TestEntity::npc("Borg Cube", 10)
+    }
+
+    fn sphere() -> TestEntity {
+        TestEntity::npc("Borg Sphere", 11)
+    }
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} is not {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn lines_are_parsed_back() {
+        let line = TestRecord::shield_damage(12.3, &ayel(), &cube(), "Phaser Beam", 50.0)
+            .by_pet(&TestEntity::npc("Support Fighter", 20))
+            .critical()
+            .kill()
+            .line(TestLog::start());
+        let record = Parser::parse_from_line(&line, &mut String::new(), None, false).unwrap();
+
+        assert_eq!(
+            record.time,
+            TestLog::start() + Duration::milliseconds(12300)
+        );
+        assert_eq!(record.source.name(), Some("Ayel@greyblizzard"));
+        assert_eq!(record.indirect_source.name(), Some("Support Fighter"));
+        assert_eq!(record.target.name(), Some("Borg Cube"));
+        assert_eq!(record.value_name, "Phaser Beam");
+        assert!(record
+            .value_flags
+            .contains(ValueFlags::CRITICAL | ValueFlags::KILL));
+        assert!(record.value.is_damage());
+        assert_eq!(record.value.amount(), 50.0);
+    }
+
+    #[test]
+    fn heal_lines_are_parsed_as_heals() {
+        for record in [
+            TestRecord::heal(0.0, &ayel(), &kira(), "Hazard Emitters", 100.0),
+            TestRecord::shield_heal(0.0, &ayel(), &kira(), "Science Team", 100.0),
+        ] {
+            let line = record.line(TestLog::start());
+            let record = Parser::parse_from_line(&line, &mut String::new(), None, false).unwrap();
+            assert!(!record.value.is_damage());
+            assert_eq!(record.value.amount(), 100.0);
+        }
+    }
+
+    #[test]
+    fn records_closer_than_the_separation_time_are_one_combat() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                80.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .analyze_single();
+        assert_eq!(combat.total_damage_out.all, 200.0);
+    }
+
+    #[test]
+    fn a_gap_longer_than_the_separation_time_starts_a_new_combat() {
+        let combats = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                100.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                50.0,
+            ))
+            .with(TestRecord::damage(
+                101.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                50.0,
+            ))
+            .analyze(Default::default());
+        assert_eq!(combats.len(), 2);
+        assert_eq!(combats[0].total_damage_out.all, 100.0);
+        assert_eq!(combats[1].total_damage_out.all, 100.0);
+    }
+
+    #[test]
+    fn the_separation_time_is_configurable() {
+        let log = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                20.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ));
+        let combats = log.analyze(AnalysisSettings {
+            combat_separation_time_seconds: 10.0,
+            ..Default::default()
+        });
+        assert_eq!(combats.len(), 2);
+    }
+
+    #[test]
+    fn outgoing_damage_is_grouped_by_ability_then_target() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                1.0,
+                &ayel(),
+                &sphere(),
+                "Phaser Beam",
+                60.0,
+            ))
+            .with(TestRecord::damage(2.0, &ayel(), &cube(), "Torpedo", 40.0))
+            .analyze_single();
+        let damage_out = &combat.test_player(&ayel()).damage_out;
+
+        let phaser = damage_out.test_group(&combat, &["Phaser Beam"]);
+        assert_eq!(phaser.total_damage.all, 160.0);
+        assert_eq!(phaser.sub_groups.len(), 2);
+        let phaser_cube = phaser.test_group(&combat, &["Borg Cube"]);
+        assert_eq!(phaser_cube.total_damage.all, 100.0);
+        assert!(phaser_cube.sub_groups.is_empty());
+        assert_eq!(
+            damage_out
+                .test_group(&combat, &["Torpedo"])
+                .total_damage
+                .all,
+            40.0
+        );
+    }
+
+    #[test]
+    fn outgoing_damage_can_be_grouped_by_target_then_ability() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                1.0,
+                &ayel(),
+                &sphere(),
+                "Phaser Beam",
+                60.0,
+            ))
+            .with(TestRecord::damage(2.0, &ayel(), &cube(), "Torpedo", 40.0).kill())
+            .analyze(AnalysisSettings {
+                damage_out_grouping: DamageOutGrouping::TargetThenAbility,
+                ..Default::default()
+            })
+            .pop()
+            .unwrap();
+        let damage_out = &combat.test_player(&ayel()).damage_out;
+
+        let cube = damage_out.test_group(&combat, &["Borg Cube"]);
+        assert_eq!(cube.total_damage.all, 140.0);
+        assert_eq!(cube.sub_groups.len(), 2);
+        assert_eq!(
+            cube.test_group(&combat, &["Torpedo"]).total_damage.all,
+            40.0
+        );
+        let cube_handle = combat.name_manager.get_handle("Borg Cube").unwrap();
+        assert_eq!(damage_out.kills.len(), 1);
+        assert_eq!(damage_out.kills.get(&cube_handle), Some(&1));
+        assert_eq!(damage_out.kill_times, [(cube_handle, 2000)]);
+        assert_eq!(cube.kills.get(&cube_handle), Some(&1));
+    }
+
+    #[test]
+    fn pet_damage_is_grouped_below_the_pet() {
+        let fighter = TestEntity::npc("Support Fighter", 20);
+        let combat = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Pulse Wave", 30.0).by_pet(&fighter))
+            .with(TestRecord::damage(1.0, &ayel(), &cube(), "Pulse Wave", 20.0).by_pet(&fighter))
+            .with(TestRecord::damage(
+                2.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .analyze_single();
+        let damage_out = &combat.test_player(&ayel()).damage_out;
+
+        let pet = damage_out.test_group(&combat, &["Support Fighter"]);
+        assert_eq!(pet.total_damage.all, 50.0);
+        let pet_ability = pet.test_group(&combat, &["Pulse Wave", "Borg Cube"]);
+        assert_eq!(pet_ability.damage_metrics.hits.all, 2);
+        assert_eq!(damage_out.total_damage.all, 150.0);
+    }
+
+    #[test]
+    fn reversal_rules_group_pets_below_the_ability() {
+        let fighter = TestEntity::npc("Support Fighter", 20);
+        let combat = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Pulse Wave", 30.0).by_pet(&fighter))
+            .analyze(AnalysisSettings {
+                indirect_source_grouping_revers_rules: vec![MatchRule {
+                    aspect: MatchAspect::IndirectSourceName,
+                    expression: "Support Fighter".to_string(),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })
+            .pop()
+            .unwrap();
+        let damage_out = &combat.test_player(&ayel()).damage_out;
+
+        let ability = damage_out.test_group(&combat, &["Pulse Wave"]);
+        assert_eq!(
+            ability
+                .test_group(&combat, &["Support Fighter", "Borg Cube"])
+                .total_damage
+                .all,
+            30.0
+        );
+    }
+
+    #[test]
+    fn custom_group_rules_add_a_top_level_group() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(1.0, &ayel(), &cube(), "Torpedo", 40.0))
+            .analyze(AnalysisSettings {
+                custom_group_rules: vec![RulesGroup {
+                    name: "Energy Weapons".to_string(),
+                    rules: vec![MatchRule {
+                        expression: "Phaser".to_string(),
+                        method: MatchMethod::StartsWith,
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            })
+            .pop()
+            .unwrap();
+        let damage_out = &combat.test_player(&ayel()).damage_out;
+
+        let group = damage_out.test_group(&combat, &["Energy Weapons"]);
+        assert_eq!(group.total_damage.all, 100.0);
+        assert_eq!(
+            group.test_group(&combat, &["Phaser Beam"]).total_damage.all,
+            100.0
+        );
+    }
+
+    #[test]
+    fn incoming_damage_is_grouped_by_source() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &cube(),
+                &ayel(),
+                "Plasma Torpedo",
+                300.0,
+            ))
+            .with(TestRecord::damage(
+                1.0,
+                &sphere(),
+                &ayel(),
+                "Plasma Beam",
+                200.0,
+            ))
+            .analyze_single();
+        let damage_in = &combat.test_player(&ayel()).damage_in;
+
+        assert_eq!(damage_in.total_damage.all, 500.0);
+        assert_eq!(
+            damage_in
+                .test_group(&combat, &["Borg Cube", "Plasma Torpedo"])
+                .total_damage
+                .all,
+            300.0
+        );
+        assert_eq!(combat.total_damage_in.all, 500.0);
+    }
+
+    #[test]
+    fn dps_uses_the_combat_time_of_the_player() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &kira(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                5.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                15.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .analyze_single();
+
+        assert_close(combat.test_player(&ayel()).damage_out.dps.all, 20.0);
+    }
+
+    #[test]
+    fn dps_can_use_the_combat_duration() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &kira(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                5.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                20.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .analyze(AnalysisSettings {
+                dps_denominator: DpsDenominator::CombatDuration,
+                ..Default::default()
+            })
+            .pop()
+            .unwrap();
+
+        assert_close(combat.test_player(&ayel()).damage_out.dps.all, 10.0);
+    }
+
+    #[test]
+    fn immune_hits_do_not_extend_the_combat_time() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                10.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(30.0, &ayel(), &cube(), "Phaser Beam", 100.0).immune())
+            .analyze_single();
+        let player = combat.test_player(&ayel());
+
+        let combat_time = player.combat_time.clone().unwrap();
+        assert_eq!(
+            combat_time.end.signed_duration_since(combat_time.start),
+            Duration::seconds(10)
+        );
+        assert_eq!(player.damage_out.immunes, 1);
+        assert_close(player.damage_out.dps.all, 20.0);
+    }
+
+    #[test]
+    fn kills_and_deaths_are_counted() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Phaser Beam", 100.0).kill())
+            .with(TestRecord::damage(1.0, &ayel(), &sphere(), "Torpedo", 100.0).kill())
+            .with(TestRecord::damage(2.0, &kira(), &ayel(), "Phaser Beam", 100.0).kill())
+            .analyze_single();
+        let ayel = combat.test_player(&ayel());
+
+        assert_eq!(ayel.damage_out.kills.values().sum::<u32>(), 2);
+        assert_eq!(ayel.damage_in.kills.values().sum::<u32>(), 1);
+        assert_eq!(combat.test_player(&kira()).damage_out.kills.len(), 1);
+        assert_eq!(combat.total_kills, 3);
+        assert_eq!(combat.total_deaths, 1);
+    }
+
+    #[test]
+    fn kill_times_are_sorted() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Torpedo", 100.0))
+            .with(TestRecord::damage(3.0, &ayel(), &sphere(), "Phaser Beam", 100.0).kill())
+            .with(TestRecord::damage(5.0, &ayel(), &cube(), "Torpedo", 100.0).kill())
+            .analyze_single();
+        let damage_out = &combat.test_player(&ayel()).damage_out;
+
+        let times: Vec<_> = damage_out.kill_times.iter().map(|(_, t)| *t).collect();
+        assert_eq!(times, vec![3000, 5000]);
+    }
+
+    #[test]
+    fn damage_percentages_of_the_players_add_up() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                300.0,
+            ))
+            .with(TestRecord::damage(
+                1.0,
+                &kira(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                2.0,
+                &kira(),
+                &sphere(),
+                "Torpedo",
+                100.0,
+            ))
+            .analyze_single();
+
+        let sum: f64 = combat
+            .players
+            .values()
+            .map(|p| p.damage_out.damage_percentage.all.unwrap())
+            .sum();
+        assert_close(sum, 100.0);
+        assert_close(
+            combat
+                .test_player(&ayel())
+                .damage_out
+                .damage_percentage
+                .all
+                .unwrap(),
+            60.0,
+        );
+    }
+
+    #[test]
+    fn percentages_of_sub_groups_add_up() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                1.0,
+                &ayel(),
+                &sphere(),
+                "Phaser Beam",
+                50.0,
+            ))
+            .with(TestRecord::damage(2.0, &ayel(), &cube(), "Torpedo", 250.0))
+            .analyze_single();
+        let damage_out = &combat.test_player(&ayel()).damage_out;
+
+        let sum: f64 = damage_out
+            .sub_groups
+            .values()
+            .map(|g| g.damage_percentage.all.unwrap())
+            .sum();
+        assert_close(sum, 100.0);
+        let phaser = damage_out.test_group(&combat, &["Phaser Beam"]);
+        let sum: f64 = phaser
+            .sub_groups
+            .values()
+            .map(|g| g.damage_percentage.all.unwrap())
+            .sum();
+        assert_close(sum, 100.0);
+        assert_close(phaser.combat_damage_percentage.all.unwrap(), 37.5);
+        let sum: f64 = phaser
+            .sub_groups
+            .values()
+            .map(|g| g.combat_damage_percentage.all.unwrap())
+            .sum();
+        assert_close(sum, 37.5);
+    }
+
+    #[test]
+    fn hits_percentages_add_up() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                1.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(2.0, &ayel(), &cube(), "Torpedo", 100.0))
+            .analyze_single();
+        let damage_out = &combat.test_player(&ayel()).damage_out;
+
+        let sum: f64 = damage_out
+            .sub_groups
+            .values()
+            .map(|g| g.hits_percentage.all.unwrap())
+            .sum();
+        assert_close(sum, 100.0);
+    }
+
+    #[test]
+    fn shield_and_hull_damage_are_split() {
+        let combat = TestLog::new()
+            .with(TestRecord::shield_damage(
+                0.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                40.0,
+            ))
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &cube(),
+                "Phaser Beam",
+                60.0,
+            ))
+            .analyze_single();
+        let damage_out = &combat.test_player(&ayel()).damage_out;
+
+        assert_eq!(damage_out.total_damage.shield, 40.0);
+        assert_eq!(damage_out.total_damage.hull, 60.0);
+        assert_eq!(damage_out.total_damage.all, 100.0);
+    }
+
+    #[test]
+    fn critical_percentage_of_hull_hits() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Phaser Beam", 100.0).critical())
+            .with_all(
+                (1..4).map(|i| TestRecord::damage(i as _, &ayel(), &cube(), "Phaser Beam", 50.0)),
+            )
+            .analyze_single();
+
+        assert_close(
+            combat
+                .test_player(&ayel())
+                .damage_out
+                .critical_percentage
+                .unwrap(),
+            25.0,
+        );
+    }
+
+    #[test]
+    fn outgoing_heal_can_be_grouped_by_target_or_ability() {
+        let log = TestLog::new()
+            .with(TestRecord::heal(
+                0.0,
+                &ayel(),
+                &kira(),
+                "Hazard Emitters",
+                100.0,
+            ))
+            .with(TestRecord::shield_heal(
+                1.0,
+                &ayel(),
+                &kira(),
+                "Science Team",
+                50.0,
+            ));
+
+        let combat = log.analyze_single();
+        let heal_out = &combat.test_player(&ayel()).heal_out;
+        let kira_name = kira().full_name().to_string();
+        assert_eq!(
+            heal_out.test_group(&combat, &[&kira_name]).total_heal.all,
+            150.0
+        );
+        assert_eq!(heal_out.total_heal.shield, 50.0);
+
+        let combat = log
+            .analyze(AnalysisSettings {
+                heal_out_grouping: HealOutGrouping::AbilityThenTarget,
+                ..Default::default()
+            })
+            .pop()
+            .unwrap();
+        let heal_out = &combat.test_player(&ayel()).heal_out;
+        assert_eq!(
+            heal_out
+                .test_group(&combat, &["Science Team", &kira_name])
+                .total_heal
+                .all,
+            50.0
+        );
+    }
+
+    #[test]
+    fn heal_percentages_and_split_add_up() {
+        let combat = TestLog::new()
+            .with(TestRecord::heal(
+                0.0,
+                &ayel(),
+                &ayel(),
+                "Hazard Emitters",
+                100.0,
+            ))
+            .with(TestRecord::heal(
+                1.0,
+                &kira(),
+                &ayel(),
+                "Hazard Emitters",
+                300.0,
+            ))
+            .analyze_single();
+
+        let sum: f64 = combat
+            .players
+            .values()
+            .map(|p| p.heal_out.heal_percentage.all.unwrap())
+            .sum();
+        assert_close(sum, 100.0);
+        let split = combat.test_player(&ayel()).heal_in_split;
+        assert_eq!(split.from_self.all, 100.0);
+        assert_eq!(split.from_others.all, 300.0);
+    }
+
+    #[test]
+    fn outgoing_heal_is_split_by_self_and_others() {
+        let combat = TestLog::new()
+            .with(TestRecord::heal(
+                0.0,
+                &ayel(),
+                &ayel(),
+                "Hazard Emitters",
+                100.0,
+            ))
+            .with(TestRecord::heal(
+                1.0,
+                &ayel(),
+                &kira(),
+                "Hazard Emitters",
+                200.0,
+            ))
+            .with(TestRecord::shield_heal(
+                2.0,
+                &ayel(),
+                &kira(),
+                "Science Team",
+                100.0,
+            ))
+            .analyze_single();
+        let heal_out = &combat.test_player(&ayel()).heal_out;
+
+        let split = heal_out.target_split;
+        assert_eq!(split.to_self.all, 100.0);
+        assert_eq!(split.to_others.all, 300.0);
+        assert_eq!(split.to_others.shield, 100.0);
+        assert_eq!((split.self_ticks, split.others_ticks), (1, 2));
+        assert_close(split.self_percentage().unwrap(), 25.0);
+        assert_close(split.others_percentage().unwrap(), 75.0);
+
+        let kira_name = kira().full_name().to_string();
+        let to_kira = heal_out.test_group(&combat, &[&kira_name]).target_split;
+        assert_eq!(to_kira.to_self.all, 0.0);
+        assert_eq!(to_kira.to_others.all, 300.0);
+    }
+
+    #[test]
+    fn avoided_incoming_hits_are_counted() {
+        let combat = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &cube(),
+                &ayel(),
+                "Plasma Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(1.0, &cube(), &ayel(), "Plasma Beam", 100.0).immune())
+            .with(TestRecord::damage(2.0, &cube(), &ayel(), "Plasma Beam", 0.0).flag("Miss"))
+            .with(TestRecord::damage(3.0, &cube(), &ayel(), "Plasma Beam", 0.0).flag("Dodge"))
+            .with(TestRecord::damage(4.0, &cube(), &ayel(), "Plasma Beam", 100.0).flag("Unknown"))
+            .analyze_single();
+        let damage_in = &combat.test_player(&ayel()).damage_in;
+
+        assert_eq!(damage_in.immunes, 1);
+        assert_eq!(damage_in.misses, 1);
+        assert_eq!(damage_in.dodges, 1);
+        assert_eq!(damage_in.avoided, 3);
+        assert_close(damage_in.avoided_percentage.unwrap(), 60.0);
+    }
+
+    #[test]
+    fn avoided_incoming_hits_count_against_the_attacks_on_shields() {
+        let attack_on_shields = |time_seconds| {
+            [
+                TestRecord::shield_damage(time_seconds, &cube(), &ayel(), "Plasma Beam", 50.0),
+                TestRecord::damage(time_seconds, &cube(), &ayel(), "Plasma Beam", 100.0),
+            ]
+        };
+        let combat = TestLog::new()
+            .with_all(attack_on_shields(0.0))
+            .with_all(attack_on_shields(1.0))
+            .with(TestRecord::damage(2.0, &cube(), &ayel(), "Plasma Beam", 0.0).flag("Miss"))
+            .with(TestRecord::damage(3.0, &cube(), &ayel(), "Plasma Beam", 0.0).flag("Dodge"))
+            .analyze_single();
+        let damage_in = &combat.test_player(&ayel()).damage_in;
+
+        assert_eq!(damage_in.avoided, 2);
+        assert_close(damage_in.avoided_percentage.unwrap(), 50.0);
+    }
+
+    #[test]
+    fn identity_does_not_change_with_the_detected_name() {
+        let settings = AnalysisSettings {
+            combat_name_rules: vec![CombatNameRule {
+                name_rule: RulesGroup {
+                    name: "Sphere Fight".to_string(),
+                    rules: vec![MatchRule {
+                        aspect: MatchAspect::SourceOrTargetName,
+                        expression: "Borg Sphere".to_string(),
+                        ..Default::default()
+                    }],
+                    enabled: true,
+                },
+                additional_info_rules: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        let file = TestLog::new()
+            .with(TestRecord::damage(0.0, &ayel(), &cube(), "Torpedo", 100.0))
+            .write();
+        let started = TestLog::analyze_file(&file, settings.clone())
+            .pop()
+            .unwrap();
+        let appended = TestRecord::damage(5.0, &ayel(), &sphere(), "Torpedo", 100.0);
+        file.append(appended.line(TestLog::start()));
+        let grown = TestLog::analyze_file(&file, settings).pop().unwrap();
+
+        assert_ne!(started.detected_name(), grown.detected_name());
+        assert_eq!(started.identity(), grown.identity());
+    }
+
+    #[test]
+    fn combats_of_different_logs_starting_at_the_same_time_have_different_identities() {
+        let log = TestLog::new().with(TestRecord::damage(0.0, &ayel(), &cube(), "Torpedo", 100.0));
+        let first = log.analyze_single();
+        let second = log.analyze_single();
+
+        assert_eq!(first.active_time, second.active_time);
+        assert_ne!(first.identity(), second.identity());
+    }
+
+    #[test]
+    fn exclusion_rules_apply_to_incoming_damage() {
+        let log = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &ayel(),
+                &kira(),
+                "Phaser Beam",
+                100.0,
+            ))
+            .with(TestRecord::damage(
+                0.4,
+                &ayel(),
+                &kira(),
+                "Feedback Pulse",
+                50.0,
+            ))
+            .with(TestRecord::damage(
+                0.9,
+                &kira(),
+                &ayel(),
+                "Phaser Beam",
+                200.0,
+            ));
+        let analyze = |exclude_from_incoming| {
+            log.analyze(AnalysisSettings {
+                damage_out_exclusion_rules: vec![MatchRule {
+                    expression: "Feedback Pulse".to_string(),
+                    ..Default::default()
+                }],
+                exclude_from_incoming,
+                ..Default::default()
+            })
+            .pop()
+            .unwrap()
+        };
+
+        let combat = analyze(false);
+        assert_eq!(combat.total_damage_out.all, 300.0);
+        assert_eq!(combat.total_damage_in.all, 350.0);
+
+        let combat = analyze(true);
+        assert_eq!(combat.total_damage_out.all, 300.0);
+        assert_eq!(combat.total_damage_in.all, 300.0);
+        // every player only damaged the other one, so the percentages of both tabs have to match
+        for (source, target) in [(ayel(), kira()), (kira(), ayel())] {
+            assert_close(
+                combat
+                    .test_player(&target)
+                    .damage_in
+                    .damage_percentage
+                    .all
+                    .unwrap(),
+                combat
+                    .test_player(&source)
+                    .damage_out
+                    .damage_percentage
+                    .all
+                    .unwrap(),
+            );
+        }
+    }
+
+    /// compares everything a refresh updates, which is not expected to differ in the floating point rounding
+    fn assert_same_damage_groups(refreshed: &DamageGroup, full: &DamageGroup) {
+        assert_eq!(
+            format!("{:?}", refreshed.damage_metrics),
+            format!("{:?}", full.damage_metrics)
+        );
+        assert_eq!(
+            format!(
+                "{:?}",
+                (
+                    refreshed.damage_percentage,
+                    refreshed.combat_damage_percentage,
+                    refreshed.hits_percentage,
+                )
+            ),
+            format!(
+                "{:?}",
+                (
+                    full.damage_percentage,
+                    full.combat_damage_percentage,
+                    full.hits_percentage,
+                )
+            )
+        );
+        assert_eq!(refreshed.uptime_percentage, full.uptime_percentage);
+        assert_eq!(refreshed.activity_times, full.activity_times);
+        assert_eq!(refreshed.kills, full.kills);
+        assert_eq!(refreshed.kill_times, full.kill_times);
+        assert_eq!(refreshed.max_one_hit.damage, full.max_one_hit.damage);
+        assert_eq!(refreshed.sub_groups.len(), full.sub_groups.len());
+        for (name, sub_group) in full.sub_groups.iter() {
+            assert_same_damage_groups(&refreshed.sub_groups[name], sub_group);
+        }
+    }
+
+    fn assert_same_heal_groups(refreshed: &HealGroup, full: &HealGroup) {
+        assert_eq!(
+            format!("{:?}", refreshed.heal_metrics),
+            format!("{:?}", full.heal_metrics)
+        );
+        assert_eq!(
+            format!(
+                "{:?}",
+                (
+                    refreshed.heal_percentage,
+                    refreshed.combat_heal_percentage,
+                    refreshed.ticks_percentage,
+                )
+            ),
+            format!(
+                "{:?}",
+                (
+                    full.heal_percentage,
+                    full.combat_heal_percentage,
+                    full.ticks_percentage,
+                )
+            )
+        );
+        assert_eq!(refreshed.uptime_percentage, full.uptime_percentage);
+        assert_eq!(refreshed.sub_groups.len(), full.sub_groups.len());
+        for (name, sub_group) in full.sub_groups.iter() {
+            assert_same_heal_groups(&refreshed.sub_groups[name], sub_group);
+        }
+    }
+
+    #[test]
+    fn refreshing_an_appended_log_matches_the_full_analysis() {
+        let fighter = TestEntity::npc("Support Fighter", 20);
+        let first = [
+            TestRecord::damage(0.0, &ayel(), &cube(), "Phaser", 100.0),
+            TestRecord::damage(1.0, &ayel(), &cube(), "Phaser", 100.0),
+            TestRecord::damage(2.0, &ayel(), &cube(), "Phaser", 100.0),
+            TestRecord::damage(3.0, &ayel(), &cube(), "Torpedo", 300.0).critical(),
+            TestRecord::damage(1.0, &kira(), &sphere(), "Phaser", 50.0),
+            TestRecord::damage(2.0, &kira(), &sphere(), "Phaser", 50.0),
+            TestRecord::damage(2.0, &cube(), &ayel(), "Cutting Beam", 80.0),
+            TestRecord::heal(4.0, &ayel(), &kira(), "Hazard Emitters", 40.0),
+        ];
+        // hits of existing groups after an uptime gap, new groups and kills
+        let second = [
+            TestRecord::damage(12.0, &ayel(), &cube(), "Phaser", 100.0),
+            TestRecord::damage(13.0, &ayel(), &cube(), "Phaser", 100.0),
+            TestRecord::damage(14.0, &ayel(), &sphere(), "Torpedo", 200.0).kill(),
+            TestRecord::damage(15.0, &kira(), &sphere(), "Phaser", 60.0).by_pet(&fighter),
+            TestRecord::damage(16.0, &kira(), &cube(), "Disruptor", 70.0).kill(),
+            TestRecord::heal(17.0, &kira(), &ayel(), "Hazard Emitters", 30.0),
+            TestRecord::heal(18.0, &ayel(), &kira(), "Hazard Emitters", 40.0),
+            TestRecord::damage(19.0, &cube(), &ayel(), "Cutting Beam", 90.0),
+        ];
+        let file = TestLog::new().with_all(first.clone()).write();
+        let mut analyzer = file.analyzer(Default::default());
+        file.append(TestLog::new().with_all(second.clone()).to_log());
+        assert!(analyzer.update(|| false, |_, _| {}).unwrap());
+
+        let refreshed = analyzer.into_result();
+        let full = TestLog::new()
+            .with_all(first)
+            .with_all(second)
+            .analyze(Default::default());
+        assert_eq!(refreshed.len(), 1);
+        assert_eq!(full.len(), 1);
+        let (refreshed, full) = (&refreshed[0], &full[0]);
+        assert_eq!(refreshed.total_kills, full.total_kills);
+        assert_eq!(refreshed.players.len(), full.players.len());
+        for player in [ayel(), kira()] {
+            // the groups with new hits got them added again to the hits manager
+            let hit_times = |combat: &Combat| {
+                let player = combat.test_player(&player);
+                let mut times: Vec<_> = [&player.damage_out, &player.damage_in]
+                    .iter()
+                    .flat_map(|g| g.hits.get(&combat.hits_manger))
+                    .map(|h| h.time_millis)
+                    .collect();
+                times.sort_unstable();
+                times
+            };
+            assert_eq!(hit_times(refreshed), hit_times(full));
+            let (refreshed, full) = (refreshed.test_player(&player), full.test_player(&player));
+            assert_same_damage_groups(&refreshed.damage_out, &full.damage_out);
+            assert_same_damage_groups(&refreshed.damage_in, &full.damage_in);
+            assert_same_heal_groups(&refreshed.heal_out, &full.heal_out);
+            assert_same_heal_groups(&refreshed.heal_in, &full.heal_in);
+            assert_eq!(
+                format!("{:?}", refreshed.peak_dps),
+                format!("{:?}", full.peak_dps)
+            );
+        }
+    }
+}
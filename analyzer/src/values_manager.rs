@@ -44,12 +44,29 @@ impl<T: Clone> ValuesManager<T> {
         }
     }
 
-    pub fn add_leaf(&mut self, values: &[T]) {
-        self.values.extend_from_slice(values);
+    /// adds the values of a sub group to the group being tracked, which don't change within the manager
+    pub fn add_group(&mut self, values: &Values<T>) {
+        match values {
+            Values::Leaf(values) => self.values.extend_from_slice(values),
+            Values::Branch(range) => self.values.extend_from_within(range.clone()),
+        }
+    }
+
+    /// adds the values of the `range` of the `other` manager, returns where they start in this manager
+    pub fn add_from(&mut self, other: &Self, range: Range<usize>) -> usize {
+        let start = self.values.len();
+        self.values.extend_from_slice(&other.values[range]);
+        start
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.values.len()
     }
 
-    pub fn clear(&mut self) {
-        self.values.clear();
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
     }
 }
 
@@ -95,6 +112,14 @@ impl<T: Clone> Values<T> {
         values.push(value);
     }
 
+    /// moves the range of a branch, whose values got moved from `from` to `to` within the manager
+    #[inline]
+    pub fn move_branch(&mut self, from: usize, to: usize) {
+        if let Self::Branch(range) = self {
+            *range = range.start - from + to..range.end - from + to;
+        }
+    }
+
     #[inline]
     pub fn get_leaf(&self) -> &[T] {
         let Self::Leaf(values) = self else {
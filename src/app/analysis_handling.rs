@@ -1,30 +1,50 @@
 use std::{
     fs::File,
-    io::Write,
+    io::{self, Read, Seek, SeekFrom, Write},
+    ops::Range,
     path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, AtomicU32, Ordering},
-        Arc,
+        Arc, Mutex,
     },
-    time::SystemTime,
+    time::{Instant, SystemTime},
 };
 
-use chrono::Duration;
+use chrono::{Duration, NaiveDateTime};
 use crossbeam_channel::{unbounded, Receiver, Sender};
 use eframe::egui::{Context, ViewportId};
+use itertools::Itertools;
 use log::info;
 use notify::{recommended_watcher, RecommendedWatcher, Watcher};
+use rustc_hash::FxHashMap;
 use timer::{Guard, Timer};
 
 use crate::{
-    analyzer::{settings::AnalysisSettings, Analyzer, Combat},
+    analyzer::{
+        settings::{AnalysisSettings, MatchRule},
+        Analyzer, Combat, DateTimeFormat, InvalidRecords,
+    },
+    helpers::time_range_to_duration_or_zero,
     unwrap_or_return,
+    upload::UploadCombat,
+};
+
+use super::{
+    exports::{run_exports, ExportResult},
+    record_query::{query_records, RecordQueryResult},
+    settings::{ExportPreset, ExportSettings, Settings, SummaryCopySettings, SummarySettings},
 };
 
+/// how often clearing the log is tried, while it is locked or written to by the game
+const CLEAR_LOG_ATTEMPTS: u32 = 10;
+const CLEAR_LOG_RETRY_DELAY: std::time::Duration = std::time::Duration::from_millis(200);
+/// lines at the end of the cleared log, that have to be parsable
+const CLEAR_LOG_VERIFIED_LINES: usize = 10;
+
 pub struct AnalysisHandler {
     tx: Sender<Instruction>,
     rx: Receiver<AnalysisInfo>,
-    is_busy: Arc<AtomicBool>,
+    shared: Arc<SharedState>,
     id: u32,
     id_counter: Arc<AtomicU32>,
 }
@@ -34,10 +54,36 @@ struct AnalysisContext {
     instruction_tx: Sender<Instruction>,
     handlers: Vec<HandlerContext>,
     analyzer: Option<Analyzer>,
+    combatlog_file: PathBuf,
+    settings: AnalysisSettings,
+    /// combats of saved logs, listed before the ones of the live log
+    imported: Vec<ImportedCombat>,
     ctx: Context,
-    is_busy: Arc<AtomicBool>,
-    auto_refresh_interval: Duration,
+    shared: Arc<SharedState>,
+    auto_refresh_timing: AutoRefreshTiming,
     auto_refresh: Option<AutoRefreshContext>,
+    export_settings: ExportSettings,
+    /// the text summary exports are built like the summary copy
+    summary_copy_settings: SummaryCopySettings,
+    summary_settings: SummarySettings,
+    /// amount of combats, that are finished and were considered for the automatic exports
+    finished_combats: Option<usize>,
+    /// names given to combats by the user, keyed by the combat identity
+    name_overrides: FxHashMap<String, String>,
+}
+
+/// state shared between the handlers and the analysis thread
+struct SharedState {
+    busy_state: Mutex<BusyState>,
+    handlers_alive: AtomicU32,
+    /// set by [`AnalysisHandler::cancel`], reset at the start of every refresh
+    cancel_requested: AtomicBool,
+}
+
+struct ImportedCombat {
+    combat: Combat,
+    file: PathBuf,
+    origin: String,
 }
 
 #[derive(Debug)]
@@ -53,10 +99,18 @@ struct AutoRefreshContext {
     _watcher: RecommendedWatcher,
     timer: Timer,
     state: AutoRefreshState,
-    interval: Duration,
+    /// switching to a newer combatlog file waits for the current one to become idle
+    newest_combatlog_check: Option<Guard>,
+    timing: AutoRefreshTiming,
     last_refresh: SystemTime,
 }
 
+#[derive(Clone, Copy)]
+struct AutoRefreshTiming {
+    interval: Duration,
+    debounce: Duration,
+}
+
 enum AutoRefreshState {
     Idle,
     RefreshScheduled(#[allow(dead_code)] Guard),
@@ -65,14 +119,52 @@ enum AutoRefreshState {
 enum Instruction {
     Refresh(bool),
     AutoRefresh,
+    CheckNewestCombatlog,
     GetCombat(usize, u32),
+    GetMergedCombat(Vec<usize>, u32),
+    GetCombatSlice(Vec<usize>, Range<u32>, u32),
     ClearLog,
     SaveCombat(usize, PathBuf),
     EnableAutoRefresh(bool, u32),
     SetAutoRefreshInterval(f64),
+    SetAutoRefreshDebounce(f64),
     AddHandler(HandlerContext),
     RemoveHandler(u32),
     SetSettings(Arc<AnalysisSettings>),
+    SetExportSettings(ExportSettings, SummaryCopySettings, SummarySettings),
+    SetCombatNameOverrides(FxHashMap<String, String>),
+    RunExports(usize, Vec<ExportPreset>, u32),
+    QueryRecords(usize, Vec<MatchRule>, u32),
+    ImportFiles(Vec<PathBuf>),
+    ReadCombatsForUpload(Vec<String>, u32),
+    ReadCombatForUpload(usize, u32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BusyPhase {
+    #[default]
+    Idle,
+    Parsing,
+    Recalculating {
+        combat: usize,
+        combats: usize,
+    },
+    SavingCombat,
+    MergingCombats,
+    SlicingCombat,
+    ClearingLog,
+    Exporting,
+    QueryingRecords,
+    Importing {
+        file: usize,
+        files: usize,
+    },
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BusyState {
+    pub phase: BusyPhase,
+    pub since: Instant,
 }
 
 #[derive(Clone)]
@@ -80,10 +172,96 @@ pub enum AnalysisInfo {
     Combat(Arc<Combat>),
     Refreshed {
         latest_combat: Arc<Combat>,
-        combats: Vec<String>,
+        combats: Vec<CombatListEntry>,
         file_size: Option<u64>,
+        /// skipped lines of the log since it was opened
+        invalid_records: InvalidRecords,
     },
-    RefreshError,
+    RefreshError(RefreshError),
+    /// the refresh got cancelled through [`AnalysisHandler::cancel`], the log will be analyzed from scratch on the next refresh,
+    /// the combat list contains only the imported combats until then, or those and the combats of the log
+    /// if an import got cancelled
+    Cancelled(Vec<CombatListEntry>),
+    /// clearing the log failed, see [`AnalysisContext::replace_log`]
+    ClearLogError(String),
+    /// the combat requested with [`AnalysisHandler::get_combat_slice`] could not be analyzed
+    SliceError(String),
+    ExportsDone(Vec<ExportResult>),
+    RecordQueryDone(Result<RecordQueryResult, String>),
+    /// the combats requested with [`AnalysisHandler::read_combats_for_upload`]
+    CombatsReadForUpload(Vec<Result<UploadCombat, String>>),
+    /// the combat requested with [`AnalysisHandler::read_combat_for_upload`]
+    CombatReadForUpload(Result<UploadCombat, String>),
+    /// the analysis followed a newer combatlog file of the directory, see
+    /// [`AnalysisSettings::follow_newest_combatlog_file`]
+    CombatlogFileSwitched(String),
+    /// the log got shorter than what was analyzed or the file at its path got replaced (e.g. rotated),
+    /// so it gets analyzed from the start again,
+    /// the combat list contains only the imported combats until the refresh completed
+    LogRestarted {
+        combats: Vec<CombatListEntry>,
+        /// false if the log got truncated
+        replaced: bool,
+    },
+    /// the names of the combat list changed, its indices stay the same
+    CombatListRenamed(Vec<CombatListEntry>),
+    /// saved logs got imported, the combat list includes their combats now
+    Imported {
+        combats: Vec<CombatListEntry>,
+        /// names of the logs, that could not be read or contained no combats
+        failed: Vec<String>,
+        /// the logs were imported before and got analyzed again with changed settings
+        reanalyzed: bool,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub enum RefreshError {
+    FileNotFound(String),
+    EmptyLog,
+    NoCombatsFound,
+    ReadError(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct CombatListEntry {
+    /// index of the combat in the analyzer, to be used with [`AnalysisHandler::get_combat`]
+    pub index: usize,
+    pub name: String,
+    pub active_time: Range<NaiveDateTime>,
+    pub identity: String,
+    pub group_dps: f64,
+    pub duration: Duration,
+    /// file name of the imported log, `None` for combats of the live log
+    pub origin: Option<String>,
+}
+
+impl CombatListEntry {
+    fn new(index: usize, combat: &Combat, origin: Option<&str>) -> Self {
+        let duration = time_range_to_duration_or_zero(&combat.combat_time);
+        let seconds = duration.num_milliseconds() as f64 / 1e3;
+        Self {
+            index,
+            name: combat.name(),
+            active_time: combat.active_time.clone(),
+            identity: combat.identity(),
+            group_dps: if seconds > 0.0 {
+                combat.total_damage_out.all / seconds
+            } else {
+                0.0
+            },
+            duration,
+            origin: origin.map(str::to_string),
+        }
+    }
+
+    pub fn identifier(&self, format: &DateTimeFormat) -> String {
+        let identifier = format.combat_identifier(&self.name, &self.active_time);
+        match &self.origin {
+            Some(origin) => format!("{} ({})", identifier, origin),
+            None => identifier,
+        }
+    }
 }
 
 impl AnalysisHandler {
@@ -91,11 +269,16 @@ impl AnalysisHandler {
         settings: AnalysisSettings,
         ctx: Context,
         auto_refresh_interval_seconds: f64,
+        auto_refresh_debounce_seconds: f64,
         enable_auto_refresh: bool,
     ) -> Self {
         let (instruction_tx, instruction_rx) = unbounded();
         let (info_tx, info_rx) = unbounded();
-        let is_busy = Arc::new(AtomicBool::new(false));
+        let shared = Arc::new(SharedState {
+            busy_state: Mutex::new(BusyState::idle()),
+            handlers_alive: AtomicU32::new(1),
+            cancel_requested: AtomicBool::new(false),
+        });
         let handler_ctx = HandlerContext {
             auto_refresh: enable_auto_refresh,
             id: 0,
@@ -109,23 +292,32 @@ impl AnalysisHandler {
             instruction_tx.clone(),
             settings,
             ctx,
-            is_busy.clone(),
-            auto_refresh_interval_seconds,
+            shared.clone(),
+            AutoRefreshTiming {
+                interval: AutoRefreshContext::interval(auto_refresh_interval_seconds),
+                debounce: AutoRefreshContext::interval(auto_refresh_debounce_seconds),
+            },
         );
+        // the thread is detached, it stops by itself once all handlers are dropped
         std::thread::spawn(move || {
             analysis_context.run();
         });
         Self {
             tx: instruction_tx,
             rx: info_rx,
-            is_busy,
+            shared,
             id: 0,
             id_counter: AtomicU32::new(1).into(),
         }
     }
 
-    pub fn is_busy(&self) -> bool {
-        self.is_busy.load(Ordering::Relaxed)
+    /// cancels the currently running parsing or recalculation
+    pub fn cancel(&self) {
+        self.shared.cancel_requested.store(true, Ordering::Relaxed);
+    }
+
+    pub fn busy_state(&self) -> BusyState {
+        *self.shared.busy_state.lock().unwrap()
     }
 
     pub fn check_for_info(&self) -> impl Iterator<Item = AnalysisInfo> + '_ {
@@ -142,6 +334,27 @@ impl AnalysisHandler {
             .unwrap();
     }
 
+    /// Analyzes the combats at the indices of the combat list as one combat, which is reported with
+    /// [`AnalysisInfo::Combat`]. Its players are matched by name across the combats, see [`Analyzer::analyze_merged`].
+    pub fn get_merged_combat(&self, combat_indices: Vec<usize>) {
+        self.tx
+            .send(Instruction::GetMergedCombat(combat_indices, self.id))
+            .unwrap();
+    }
+
+    /// Analyzes only the part of the combat at the indices of the combat list within the `time_slice`, given in
+    /// milliseconds since the start of the combat. Several combats are merged first, see
+    /// [`AnalysisHandler::get_merged_combat`]. Reported with [`AnalysisInfo::Combat`].
+    pub fn get_combat_slice(&self, combat_indices: Vec<usize>, time_slice: Range<u32>) {
+        self.tx
+            .send(Instruction::GetCombatSlice(
+                combat_indices,
+                time_slice,
+                self.id,
+            ))
+            .unwrap();
+    }
+
     pub fn clear_log(&self) {
         self.tx.send(Instruction::ClearLog).unwrap();
     }
@@ -152,6 +365,62 @@ impl AnalysisHandler {
             .unwrap();
     }
 
+    /// analyzes the saved logs one after another and adds their combats to the combat list,
+    /// reported with [`AnalysisInfo::Imported`]
+    pub fn import_files(&self, files: Vec<PathBuf>) {
+        self.tx.send(Instruction::ImportFiles(files)).unwrap();
+    }
+
+    /// sets the settings of the exports and of the summary copy, which the text summary exports use
+    pub fn set_export_settings(&self, settings: &Settings) {
+        self.tx
+            .send(Instruction::SetExportSettings(
+                settings.exports.clone(),
+                settings.summary_copy.clone(),
+                settings.summary.clone(),
+            ))
+            .unwrap();
+    }
+
+    /// runs the `presets` for the combat, the results are reported with [`AnalysisInfo::ExportsDone`]
+    pub fn run_exports(&self, combat_index: usize, presets: Vec<ExportPreset>) {
+        self.tx
+            .send(Instruction::RunExports(combat_index, presets, self.id))
+            .unwrap();
+    }
+
+    /// counts the records of the combat matching all `rules`, reported with [`AnalysisInfo::RecordQueryDone`]
+    pub fn query_records(&self, combat_index: usize, rules: Vec<MatchRule>) {
+        self.tx
+            .send(Instruction::QueryRecords(combat_index, rules, self.id))
+            .unwrap();
+    }
+
+    /// reads the records of the combats with the [`CombatListEntry::identity`]s, so they can be queued for the upload.
+    /// Takes the identities, as the indices change when combats get imported while the queue is being filled.
+    pub fn read_combats_for_upload(&self, combat_identities: Vec<String>) {
+        self.tx
+            .send(Instruction::ReadCombatsForUpload(
+                combat_identities,
+                self.id,
+            ))
+            .unwrap();
+    }
+
+    /// reads the records of the combat from the log it came from, so it can be uploaded
+    pub fn read_combat_for_upload(&self, combat_index: usize) {
+        self.tx
+            .send(Instruction::ReadCombatForUpload(combat_index, self.id))
+            .unwrap();
+    }
+
+    /// names given to combats by the user, keyed by the combat identity, see [`Combat::name_override`]
+    pub fn set_combat_name_overrides(&self, name_overrides: FxHashMap<String, String>) {
+        self.tx
+            .send(Instruction::SetCombatNameOverrides(name_overrides))
+            .unwrap();
+    }
+
     pub fn set_settings(&self, settings: AnalysisSettings) {
         self.tx
             .send(Instruction::SetSettings(settings.into()))
@@ -170,6 +439,12 @@ impl AnalysisHandler {
             .unwrap();
     }
 
+    pub fn set_auto_refresh_debounce(&self, debounce: f64) {
+        self.tx
+            .send(Instruction::SetAutoRefreshDebounce(debounce))
+            .unwrap();
+    }
+
     pub fn get_handler(&self, auto_refresh: bool, viewport: ViewportId) -> Self {
         let (tx, rx) = unbounded();
         let id = self.id_counter.fetch_add(1, Ordering::Relaxed);
@@ -179,11 +454,12 @@ impl AnalysisHandler {
             tx,
             viewport,
         };
+        self.shared.handlers_alive.fetch_add(1, Ordering::Relaxed);
         self.tx.send(Instruction::AddHandler(ctx)).unwrap();
         Self {
             tx: self.tx.clone(),
             rx,
-            is_busy: self.is_busy.clone(),
+            shared: self.shared.clone(),
             id,
             id_counter: self.id_counter.clone(),
         }
@@ -192,6 +468,9 @@ impl AnalysisHandler {
 
 impl Drop for AnalysisHandler {
     fn drop(&mut self) {
+        // once the last handler is gone, the analysis thread cancels any running work and stops,
+        // it is not waited for, as that would block the UI
+        self.shared.handlers_alive.fetch_sub(1, Ordering::Relaxed);
         let _ = self.tx.send(Instruction::RemoveHandler(self.id));
     }
 }
@@ -203,18 +482,26 @@ impl AnalysisContext {
         instruction_tx: Sender<Instruction>,
         settings: AnalysisSettings,
         ctx: Context,
-        is_busy: Arc<AtomicBool>,
-        auto_refresh_interval_seconds: f64,
+        shared: Arc<SharedState>,
+        auto_refresh_timing: AutoRefreshTiming,
     ) -> Self {
         let mut _self = Self {
             instruction_rx,
             instruction_tx,
             handlers: vec![handler_ctx],
-            analyzer: Analyzer::new(settings),
+            combatlog_file: settings.combatlog_file().to_path_buf(),
+            analyzer: Analyzer::new(settings.clone()),
+            settings,
+            imported: Vec::new(),
             ctx,
-            is_busy,
-            auto_refresh_interval: AutoRefreshContext::interval(auto_refresh_interval_seconds),
+            shared,
+            auto_refresh_timing,
             auto_refresh: None,
+            export_settings: Default::default(),
+            summary_copy_settings: Default::default(),
+            summary_settings: Default::default(),
+            finished_combats: None,
+            name_overrides: Default::default(),
         };
         _self.update_auto_refresh();
         _self
@@ -227,12 +514,23 @@ impl AnalysisContext {
                 Err(_) => return,
             };
 
+            if self.shared.is_shutting_down() {
+                return;
+            }
+
             match instruction {
                 Instruction::Refresh(auto_refresh) => self.refresh(auto_refresh),
                 Instruction::AutoRefresh => self.auto_refresh(),
+                Instruction::CheckNewestCombatlog => self.check_newest_combatlog(),
                 Instruction::GetCombat(combat_index, handler) => {
                     self.get_combat(combat_index, handler);
                 }
+                Instruction::GetMergedCombat(combat_indices, handler) => {
+                    self.get_merged_combat(&combat_indices, handler);
+                }
+                Instruction::GetCombatSlice(combat_indices, time_slice, handler) => {
+                    self.get_combat_slice(&combat_indices, time_slice, handler);
+                }
                 Instruction::ClearLog => self.clear_log(),
                 Instruction::SaveCombat(combat_index, file) => self.save_combat(combat_index, file),
                 Instruction::EnableAutoRefresh(enable, handler) => {
@@ -242,6 +540,9 @@ impl AnalysisContext {
                 Instruction::SetAutoRefreshInterval(refresh_interval) => {
                     self.set_auto_refresh_interval(refresh_interval)
                 }
+                Instruction::SetAutoRefreshDebounce(debounce) => {
+                    self.set_auto_refresh_debounce(debounce)
+                }
                 Instruction::AddHandler(tx) => {
                     self.handlers.push(tx);
                     self.update_auto_refresh();
@@ -256,16 +557,49 @@ impl AnalysisContext {
                     }
                 }
                 Instruction::SetSettings(settings) => {
-                    self.analyzer = Analyzer::new(Arc::into_inner(settings).unwrap())
+                    self.combatlog_file = settings.combatlog_file().to_path_buf();
+                    self.settings = Arc::into_inner(settings).unwrap();
+                    self.analyzer = Analyzer::new(self.settings.clone());
+                    self.finished_combats = None;
+                    self.update_auto_refresh();
+                    self.reanalyze_imported();
+                }
+                Instruction::SetExportSettings(
+                    export_settings,
+                    summary_copy_settings,
+                    summary_settings,
+                ) => {
+                    self.export_settings = export_settings;
+                    self.summary_copy_settings = summary_copy_settings;
+                    self.summary_settings = summary_settings;
+                }
+                Instruction::SetCombatNameOverrides(name_overrides) => {
+                    self.name_overrides = name_overrides;
+                    self.send_info_all(AnalysisInfo::CombatListRenamed(self.combat_list()));
+                }
+                Instruction::RunExports(combat_index, presets, handler) => {
+                    self.run_exports(combat_index, &presets, handler);
+                }
+                Instruction::QueryRecords(combat_index, rules, handler) => {
+                    self.query_records(combat_index, &rules, handler);
+                }
+                Instruction::ImportFiles(files) => self.import_files(&files, false),
+                Instruction::ReadCombatsForUpload(combat_identities, handler) => {
+                    self.read_combats_for_upload(&combat_identities, handler);
+                }
+                Instruction::ReadCombatForUpload(combat_index, handler) => {
+                    let combat = self.read_combat_for_upload(combat_index);
+                    self.send_info(AnalysisInfo::CombatReadForUpload(combat), handler);
                 }
             }
 
-            Self::set_is_busy(&self.is_busy, false);
+            self.set_busy_phase(BusyPhase::Idle);
         }
     }
 
     fn refresh(&mut self, only_when_auto_refresh: bool) {
-        Self::set_is_busy(&self.is_busy, true);
+        self.set_busy_phase(BusyPhase::Parsing);
+        self.restart_if_log_changed();
         let info = self.try_refresh();
         if only_when_auto_refresh {
             for handler in self.handlers.iter().filter(|h| h.auto_refresh) {
@@ -278,28 +612,258 @@ impl AnalysisContext {
             ctx.state = AutoRefreshState::Idle;
             ctx.last_refresh = SystemTime::now();
         }
+        self.run_exports_of_finished_combats();
+    }
+
+    /// the combats found so far are not in a truncated or replaced log anymore
+    fn restart_if_log_changed(&mut self) {
+        let analyzer = unwrap_or_return!(self.analyzer.as_mut());
+        let replaced = analyzer.is_log_replaced();
+        if replaced {
+            info!("combatlog file got replaced, analyzing the new one from the start");
+        } else if analyzer.is_log_truncated() {
+            info!("combatlog file got truncated, analyzing it from the start");
+        } else {
+            return;
+        }
+        self.analyzer = Analyzer::new(analyzer.settings().clone());
+        self.finished_combats = None;
+        self.send_info_all(AnalysisInfo::LogRestarted {
+            combats: self.combat_list(),
+            replaced,
+        });
+    }
+
+    /// every combat except for the last one is finished, as the next combat already started
+    fn run_exports_of_finished_combats(&mut self) {
+        let analyzer = unwrap_or_return!(&self.analyzer);
+        let finished_combats = analyzer.result().len().saturating_sub(1);
+        // combats that were already finished when the log got opened are not exported
+        let previously_finished = *self.finished_combats.get_or_insert(finished_combats);
+        self.finished_combats = Some(finished_combats);
+        if !self.export_settings.run_when_combat_finished
+            || self.export_settings.presets.is_empty()
+            || finished_combats <= previously_finished
+        {
+            return;
+        }
+
+        self.set_busy_phase(BusyPhase::Exporting);
+        let results = analyzer.result()[previously_finished..finished_combats]
+            .iter()
+            .flat_map(|c| {
+                run_exports(
+                    &self.export_settings.presets,
+                    &Self::with_name_override(&self.name_overrides, c),
+                    &self.combatlog_file,
+                    &self.summary_copy_settings,
+                    &self.summary_settings,
+                )
+            })
+            .collect();
+        self.send_info_all(AnalysisInfo::ExportsDone(results));
+    }
+
+    fn run_exports(&self, combat_index: usize, presets: &[ExportPreset], handler: u32) {
+        let (combat, combatlog_file) = unwrap_or_return!(self.combat(combat_index));
+        self.set_busy_phase(BusyPhase::Exporting);
+        let results = run_exports(
+            presets,
+            &Self::with_name_override(&self.name_overrides, combat),
+            combatlog_file,
+            &self.summary_copy_settings,
+            &self.summary_settings,
+        );
+        self.send_info(AnalysisInfo::ExportsDone(results), handler);
     }
 
     fn try_refresh(&mut self) -> AnalysisInfo {
         let analyzer = match self.analyzer.as_mut() {
             Some(a) => a,
-            None => return AnalysisInfo::RefreshError,
+            None => return AnalysisInfo::RefreshError(Self::open_error(&self.combatlog_file)),
         };
-        analyzer.update();
+        let shared = &self.shared;
+        shared.cancel_requested.store(false, Ordering::Relaxed);
+        let completed = analyzer.update(
+            || shared.is_shutting_down() || shared.cancel_requested.load(Ordering::Relaxed),
+            |combat, combats| {
+                shared.set_busy_phase(BusyPhase::Recalculating { combat, combats });
+            },
+        );
+        let completed = match completed {
+            Ok(completed) => completed,
+            Err(err) => {
+                log::error!("failed to read the log: {}", err);
+                // the position within the log is unknown, so start over on the next refresh
+                self.analyzer = Analyzer::new(analyzer.settings().clone());
+                return AnalysisInfo::RefreshError(RefreshError::ReadError(err.to_string()));
+            }
+        };
+        if !completed {
+            // the combats are left partially analyzed, so start over on the next refresh
+            self.analyzer = Analyzer::new(analyzer.settings().clone());
+            return AnalysisInfo::Cancelled(self.combat_list());
+        }
+        let file_size = std::fs::metadata(&self.combatlog_file)
+            .ok()
+            .map(|m| m.len());
         let latest_combat = match analyzer.result().last() {
-            Some(c) => c.clone(),
-            None => return AnalysisInfo::RefreshError,
+            Some(c) => Self::with_name_override(&self.name_overrides, c),
+            None if file_size == Some(0) => {
+                return AnalysisInfo::RefreshError(RefreshError::EmptyLog)
+            }
+            None => return AnalysisInfo::RefreshError(RefreshError::NoCombatsFound),
         };
+        let invalid_records = analyzer.invalid_records().clone();
         let info = AnalysisInfo::Refreshed {
             latest_combat: latest_combat.into(),
-            combats: analyzer.result().iter().map(|c| c.identifier()).collect(),
-            file_size: std::fs::metadata(&analyzer.settings().combatlog_file)
-                .ok()
-                .map(|m| m.len()),
+            combats: self.combat_list(),
+            file_size,
+            invalid_records,
         };
         info
     }
 
+    /// the imported combats followed by the ones of the live log, so the latest combat stays last
+    fn combat_list(&self) -> Vec<CombatListEntry> {
+        let imported = self
+            .imported
+            .iter()
+            .map(|i| (&i.combat, Some(i.origin.as_str())));
+        let live = self
+            .analyzer
+            .iter()
+            .flat_map(|a| a.result().iter())
+            .map(|c| (c, None));
+        imported
+            .chain(live)
+            .enumerate()
+            .map(|(index, (combat, origin))| {
+                let mut entry = CombatListEntry::new(index, combat, origin);
+                if let Some(name) = self.name_overrides.get(&entry.identity) {
+                    entry.name = name.clone();
+                }
+                entry
+            })
+            .collect()
+    }
+
+    /// a copy of the `combat` with the name given by the user, if there is one
+    fn with_name_override(name_overrides: &FxHashMap<String, String>, combat: &Combat) -> Combat {
+        let mut combat = combat.clone();
+        combat.name_override = name_overrides.get(&combat.identity()).cloned();
+        combat
+    }
+
+    /// the combat at the index of the combat list and the log it was read from
+    fn combat(&self, combat_index: usize) -> Option<(&Combat, &Path)> {
+        match self.imported.get(combat_index) {
+            Some(imported) => Some((&imported.combat, imported.file.as_path())),
+            None => self
+                .analyzer
+                .as_ref()?
+                .result()
+                .get(combat_index - self.imported.len())
+                .map(|c| (c, self.combatlog_file.as_path())),
+        }
+    }
+
+    fn combat_by_identity(&self, identity: &str) -> Option<(&Combat, &Path)> {
+        let imported = self.imported.iter().map(|i| (&i.combat, i.file.as_path()));
+        let live = self
+            .analyzer
+            .iter()
+            .flat_map(|a| a.result().iter())
+            .map(|c| (c, self.combatlog_file.as_path()));
+        imported.chain(live).find(|(c, _)| c.identity() == identity)
+    }
+
+    /// the imported combats were analyzed with the previous settings, so their logs get imported again
+    fn reanalyze_imported(&mut self) {
+        if self.imported.is_empty() {
+            return;
+        }
+        let files = self
+            .imported
+            .drain(..)
+            .map(|i| i.file)
+            .dedup()
+            .collect_vec();
+        self.import_files(&files, true);
+    }
+
+    fn import_files(&mut self, files: &[PathBuf], reanalyzed: bool) {
+        let shared = self.shared.clone();
+        shared.cancel_requested.store(false, Ordering::Relaxed);
+        let mut failed = Vec::new();
+        for (index, file) in files.iter().enumerate() {
+            self.set_busy_phase(BusyPhase::Importing {
+                file: index,
+                files: files.len(),
+            });
+            let origin = file
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| file.to_string_lossy().into_owned());
+            let settings = AnalysisSettings {
+                combatlog_file: file.to_string_lossy().into(),
+                ..self.settings.clone()
+            };
+            let mut analyzer = match Analyzer::new(settings) {
+                Some(a) => a,
+                None => {
+                    failed.push(origin);
+                    continue;
+                }
+            };
+            let completed = match analyzer.update(
+                || shared.is_shutting_down() || shared.cancel_requested.load(Ordering::Relaxed),
+                |_, _| (),
+            ) {
+                Ok(completed) => completed,
+                Err(err) => {
+                    log::error!("failed to read {}: {}", origin, err);
+                    failed.push(origin);
+                    continue;
+                }
+            };
+            if !completed {
+                // the logs imported so far are kept
+                self.send_info_all(AnalysisInfo::Cancelled(self.combat_list()));
+                break;
+            }
+            let combats = analyzer.into_result();
+            if combats.is_empty() {
+                failed.push(origin);
+                continue;
+            }
+            self.imported
+                .extend(combats.into_iter().map(|combat| ImportedCombat {
+                    combat,
+                    file: file.clone(),
+                    origin: origin.clone(),
+                }));
+        }
+        self.send_info_all(AnalysisInfo::Imported {
+            combats: self.combat_list(),
+            failed,
+            reanalyzed,
+        });
+    }
+
+    fn open_error(combatlog_file: &Path) -> RefreshError {
+        match std::fs::metadata(combatlog_file) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                RefreshError::FileNotFound(err.to_string())
+            }
+            Err(err) => RefreshError::ReadError(err.to_string()),
+            Ok(_) => match File::open(combatlog_file) {
+                Err(err) => RefreshError::ReadError(err.to_string()),
+                Ok(_) => RefreshError::ReadError("the log could not be opened".to_string()),
+            },
+        }
+    }
+
     fn auto_refresh(&mut self) {
         if let Some(ctx) = &mut self.auto_refresh {
             if let AutoRefreshState::RefreshScheduled(_) = ctx.state {
@@ -314,13 +878,15 @@ impl AnalysisContext {
                 }
             };
 
-            if delta_time >= ctx.interval {
+            // the interval limits how often refreshes happen, the debounce delays the refresh
+            // after the first change, so the rest of a burst of writes is picked up by it as well
+            let delay = (ctx.timing.interval - delta_time).max(ctx.timing.debounce);
+            if delay <= Duration::zero() {
                 ctx.state = AutoRefreshState::Idle;
                 self.refresh(true);
                 return;
             }
 
-            let delay = ctx.interval - delta_time;
             let tx = ctx.tx.clone();
             let guard = ctx
                 .timer
@@ -329,66 +895,305 @@ impl AnalysisContext {
         }
     }
 
-    fn get_combat(&self, combat_index: usize, handler: u32) {
-        let analyzer = match &self.analyzer {
-            Some(a) => a,
-            None => return,
-        };
+    /// Switches to the newest combatlog file in the directory of the current one.
+    /// Waits until the current file was idle for the combat separation time, so a combat is not cut off.
+    fn check_newest_combatlog(&mut self) {
+        let ctx = unwrap_or_return!(&mut self.auto_refresh);
+        ctx.newest_combatlog_check = None;
+        let newest = unwrap_or_return!(newest_combatlog_file(&self.combatlog_file));
+        let separation_time = std::time::Duration::from_secs_f64(
+            self.settings.combat_separation_time_seconds.max(0.0),
+        );
+        let idle_time = std::fs::metadata(&self.combatlog_file)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|m| m.elapsed().ok())
+            .unwrap_or(separation_time);
+        if idle_time < separation_time {
+            let tx = ctx.tx.clone();
+            let delay = Duration::from_std(separation_time - idle_time).unwrap_or(Duration::zero());
+            ctx.newest_combatlog_check = Some(ctx.timer.schedule_with_delay(delay, move || {
+                _ = tx.send(Instruction::CheckNewestCombatlog)
+            }));
+            return;
+        }
+
+        info!(
+            "switching to the newest combatlog file {}",
+            newest.display()
+        );
+        self.settings.combatlog_file = newest.display().to_string();
+        self.combatlog_file = newest;
+        self.analyzer = Analyzer::new(self.settings.clone());
+        self.finished_combats = None;
+        self.update_auto_refresh();
+        self.send_info_all(AnalysisInfo::CombatlogFileSwitched(
+            self.settings.combatlog_file.clone(),
+        ));
+        self.refresh(false);
+    }
 
-        let combat = match analyzer.result().get(combat_index) {
-            Some(c) => c.clone(),
+    fn get_combat(&self, combat_index: usize, handler: u32) {
+        let combat = match self.combat(combat_index) {
+            Some((c, _)) => Self::with_name_override(&self.name_overrides, c),
             None => return,
         };
 
         self.send_info(AnalysisInfo::Combat(combat.into()), handler);
     }
 
+    fn get_merged_combat(&self, combat_indices: &[usize], handler: u32) {
+        if let Some(merged) = self.merged_combat(combat_indices) {
+            self.send_info(AnalysisInfo::Combat(merged.into()), handler);
+        }
+    }
+
+    fn merged_combat(&self, combat_indices: &[usize]) -> Option<Combat> {
+        let mut combats: Vec<_> = combat_indices
+            .iter()
+            .filter_map(|i| self.combat(*i))
+            .map(|(c, _)| c)
+            .collect();
+        combats.sort_by_key(|c| c.active_time.start);
+
+        self.set_busy_phase(BusyPhase::MergingCombats);
+        Analyzer::analyze_merged(&self.settings, combats)
+    }
+
+    fn get_combat_slice(&self, combat_indices: &[usize], time_slice: Range<u32>, handler: u32) {
+        let merged;
+        let combat = match combat_indices {
+            [combat_index] => unwrap_or_return!(self.combat(*combat_index)).0,
+            _ => {
+                merged = unwrap_or_return!(self.merged_combat(combat_indices));
+                &merged
+            }
+        };
+        self.set_busy_phase(BusyPhase::SlicingCombat);
+        let info = match Analyzer::analyze_time_slice(&self.settings, combat, time_slice) {
+            Some(sliced) => {
+                AnalysisInfo::Combat(Self::with_name_override(&self.name_overrides, &sliced).into())
+            }
+            None => AnalysisInfo::SliceError("nothing happened within the time slice".to_string()),
+        };
+        self.send_info(info, handler);
+    }
+
     fn clear_log(&mut self) {
         let analyzer = match &self.analyzer {
             Some(a) => a,
             None => return,
         };
+        // the compressed data would be replaced by plain text
+        if analyzer.is_log_compressed() {
+            self.send_info_all(AnalysisInfo::ClearLogError(
+                "compressed logs can not be cleared".to_string(),
+            ));
+            return;
+        }
+        self.shared.set_busy_phase(BusyPhase::ClearingLog);
         let settings = analyzer.settings().clone();
 
-        let last_combat = analyzer.result().last();
-        let last_combat_data = last_combat
-            .map(|c| c.read_log_combat_data(settings.combatlog_file()))
-            .flatten();
+        // everything from the start of the last combat on is kept, including what was not analyzed yet
+        let keep_from = analyzer
+            .result()
+            .last()
+            .and_then(|c| c.log_pos.as_ref())
+            .map(|p| p.start);
 
         self.analyzer = None;
 
-        let mut file = match File::options()
-            .write(true)
-            .truncate(true)
-            .create(false)
-            .open(settings.combatlog_file())
-        {
-            Ok(f) => f,
-            Err(_) => return,
-        };
-
-        if let Some(last_combat_data) = last_combat_data {
-            let _ = file.write_all(last_combat_data.as_slice());
+        if let Err(err) = Self::replace_log(settings.combatlog_file(), keep_from) {
+            log::error!("failed to clear the log: {}", err);
+            self.send_info_all(AnalysisInfo::ClearLogError(err.to_string()));
         }
 
-        drop(file);
         self.analyzer = Analyzer::new(settings);
+        self.finished_combats = None;
         self.refresh(false);
     }
 
+    /// Removes everything before the byte `keep_from` from the log, or everything if it is `None`.
+    /// Swapping in a new file by renaming does not work, as the game keeps the log open:
+    /// on Windows the rename fails and elsewhere the game would continue writing into the renamed file.
+    /// So the log is truncated and rewritten in place. The kept part is copied to a backup next to the log
+    /// first, which is restored if writing it back fails or leaves a broken end, see [`Self::restore_log`].
+    /// The log is only truncated if it did not grow while the kept part was read, otherwise it is read again.
+    /// Only what the game appends in the moment between that check and the truncation can still get lost.
+    /// The kept part is written at the end of the truncated log, so a line the game writes right after the
+    /// truncation ends up in front of it instead of being overwritten by it.
+    fn replace_log(combatlog_file: &Path, keep_from: Option<u64>) -> io::Result<()> {
+        Self::replace_log_with(combatlog_file, keep_from, || ())
+    }
+
+    /// [`Self::replace_log`], which calls `after_read` each time the kept part was read,
+    /// before it checks whether the game appended to the log meanwhile
+    fn replace_log_with(
+        combatlog_file: &Path,
+        keep_from: Option<u64>,
+        mut after_read: impl FnMut(),
+    ) -> io::Result<()> {
+        let backup = Self::log_backup_path(combatlog_file);
+        let mut file = Self::open_log_for_writing(combatlog_file)?;
+        let mut attempt = 1;
+        let kept = loop {
+            let len = file.metadata()?.len();
+            let start = keep_from.unwrap_or(len).min(len);
+            let mut kept = Vec::new();
+            file.seek(SeekFrom::Start(start))?;
+            (&mut file).take(len - start).read_to_end(&mut kept)?;
+            Self::write_log_backup(&backup, &kept)?;
+            after_read();
+            if file.metadata()?.len() == len {
+                file.set_len(0)?;
+                break kept;
+            }
+            if attempt >= CLEAR_LOG_ATTEMPTS {
+                let _ = std::fs::remove_file(&backup);
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "the game kept writing to the log, try again once the combat is over",
+                ));
+            }
+            attempt += 1;
+            std::thread::sleep(CLEAR_LOG_RETRY_DELAY);
+        };
+        let written_at = file.seek(SeekFrom::End(0))?;
+        // the game continues writing after the kept part, so that seam is what could break
+        let result = file
+            .write_all(&kept)
+            .and_then(|()| Self::check_log_end(combatlog_file));
+        if let Err(err) = result {
+            let kept_range = written_at..written_at + kept.len() as u64;
+            if let Err(restore_err) = Self::restore_log(&mut file, &backup, kept_range) {
+                return Err(io::Error::new(
+                    restore_err.kind(),
+                    format!(
+                        "{}, restoring the log failed too ({}), the last combat was kept in {}",
+                        err,
+                        restore_err,
+                        backup.display()
+                    ),
+                ));
+            }
+            let _ = std::fs::remove_file(&backup);
+            return Err(err);
+        }
+        let _ = std::fs::remove_file(&backup);
+        Ok(())
+    }
+
+    fn log_backup_path(combatlog_file: &Path) -> PathBuf {
+        let mut path = combatlog_file.as_os_str().to_owned();
+        path.push(".backup");
+        PathBuf::from(path)
+    }
+
+    fn write_log_backup(backup: &Path, kept: &[u8]) -> io::Result<()> {
+        let mut backup = File::create(backup)?;
+        backup.write_all(kept)?;
+        backup.sync_all()
+    }
+
+    fn check_log_end(combatlog_file: &Path) -> io::Result<()> {
+        if Analyzer::is_log_end_valid(combatlog_file, CLEAR_LOG_VERIFIED_LINES) {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "the end of the cleared log could not be parsed",
+            ))
+        }
+    }
+
+    /// Puts the `backup` of the kept part back at the start of the log, followed by what the game appended
+    /// since the truncation. The bytes in the `kept_range`, to which writing the kept part failed, are dropped.
+    fn restore_log(file: &mut File, backup: &Path, kept_range: Range<u64>) -> io::Result<()> {
+        let kept = std::fs::read(backup)?;
+        let mut appended = Vec::new();
+        file.seek(SeekFrom::Start(0))?;
+        file.read_to_end(&mut appended)?;
+        let len = appended.len() as u64;
+        appended.drain(kept_range.start.min(len) as usize..kept_range.end.min(len) as usize);
+
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(&kept)?;
+        file.write_all(&appended)
+    }
+
+    /// the game may hold the log open for a moment, which prevents writing to it on Windows
+    fn open_log_for_writing(combatlog_file: &Path) -> io::Result<File> {
+        let mut attempt = 1;
+        loop {
+            match File::options().read(true).write(true).open(combatlog_file) {
+                Ok(f) => return Ok(f),
+                Err(err) if attempt >= CLEAR_LOG_ATTEMPTS => return Err(err),
+                Err(_) => {
+                    attempt += 1;
+                    std::thread::sleep(CLEAR_LOG_RETRY_DELAY);
+                }
+            }
+        }
+    }
+
     fn save_combat(&self, combat_index: usize, file: PathBuf) {
-        let analyzer = unwrap_or_return!(&self.analyzer);
-        let combat = unwrap_or_return!(analyzer.result().get(combat_index));
-        Self::set_is_busy(&self.is_busy, true);
-        let combat_data = match combat.read_log_combat_data(analyzer.settings().combatlog_file()) {
+        let (combat, combatlog_file) = unwrap_or_return!(self.combat(combat_index));
+        self.set_busy_phase(BusyPhase::SavingCombat);
+        let combat_data = match combat.read_log_combat_data(combatlog_file) {
             Some(d) => d,
             None => {
-                Self::set_is_busy(&self.is_busy, false);
+                self.set_busy_phase(BusyPhase::Idle);
                 return;
             }
         };
         let _ = std::fs::write(file, combat_data.as_slice());
-        Self::set_is_busy(&self.is_busy, false);
+        self.set_busy_phase(BusyPhase::Idle);
+    }
+
+    fn query_records(&self, combat_index: usize, rules: &[MatchRule], handler: u32) {
+        let result = match self.combat(combat_index) {
+            Some((combat, combatlog_file)) => {
+                self.set_busy_phase(BusyPhase::QueryingRecords);
+                query_records(rules, combat, combatlog_file)
+            }
+            None => Err("the combat is not available anymore".to_string()),
+        };
+        self.send_info(AnalysisInfo::RecordQueryDone(result), handler);
+    }
+
+    fn read_combats_for_upload(&self, combat_identities: &[String], handler: u32) {
+        let combats = combat_identities
+            .iter()
+            .map(|identity| {
+                let (combat, combatlog_file) = self
+                    .combat_by_identity(identity)
+                    .ok_or_else(|| "the combat is not available anymore".to_string())?;
+                Self::read_upload_combat(&self.name_overrides, combat, combatlog_file)
+            })
+            .collect();
+        self.send_info(AnalysisInfo::CombatsReadForUpload(combats), handler);
+    }
+
+    fn read_combat_for_upload(&self, combat_index: usize) -> Result<UploadCombat, String> {
+        let (combat, combatlog_file) = self
+            .combat(combat_index)
+            .ok_or_else(|| "the combat is not available anymore".to_string())?;
+        Self::read_upload_combat(&self.name_overrides, combat, combatlog_file)
+    }
+
+    fn read_upload_combat(
+        name_overrides: &FxHashMap<String, String>,
+        combat: &Combat,
+        combatlog_file: &Path,
+    ) -> Result<UploadCombat, String> {
+        let combat = Self::with_name_override(name_overrides, combat);
+        UploadCombat::read(&combat, combatlog_file).ok_or_else(|| {
+            format!(
+                "failed to read the combat \"{}\" from the log",
+                combat.name()
+            )
+        })
     }
 
     fn send_info(&self, info: AnalysisInfo, handler: u32) {
@@ -413,28 +1218,33 @@ impl AnalysisContext {
         }
     }
 
-    fn set_is_busy(is_busy: &AtomicBool, value: bool) {
-        is_busy.store(value, Ordering::Relaxed);
+    fn set_busy_phase(&self, phase: BusyPhase) {
+        self.shared.set_busy_phase(phase);
     }
 
     fn set_auto_refresh_interval(&mut self, refresh_interval: f64) {
-        self.auto_refresh_interval = AutoRefreshContext::interval(refresh_interval);
+        self.auto_refresh_timing.interval = AutoRefreshContext::interval(refresh_interval);
+        self.update_auto_refresh();
+    }
+
+    fn set_auto_refresh_debounce(&mut self, debounce: f64) {
+        self.auto_refresh_timing.debounce = AutoRefreshContext::interval(debounce);
         self.update_auto_refresh();
     }
 
     fn update_auto_refresh(&mut self) {
-        let settings = match &self.analyzer {
-            Some(analyzer) => analyzer.settings(),
-            None => return,
-        };
+        if self.analyzer.is_none() && !self.settings.follow_newest_combatlog_file {
+            return;
+        }
         if !self.auto_refresh_enabled() {
             self.auto_refresh = None;
             return;
         }
         self.auto_refresh = AutoRefreshContext::new(
             self.instruction_tx.clone(),
-            self.auto_refresh_interval,
-            &PathBuf::from(&settings.combatlog_file),
+            self.auto_refresh_timing,
+            &self.combatlog_file,
+            self.settings.follow_newest_combatlog_file,
         );
     }
 
@@ -444,22 +1254,50 @@ impl AnalysisContext {
 }
 
 impl AutoRefreshContext {
-    fn new(tx: Sender<Instruction>, interval: Duration, file: &Path) -> Option<Self> {
+    /// `watch_directory` also watches the directory of the `file` for newer combatlog files
+    fn new(
+        tx: Sender<Instruction>,
+        timing: AutoRefreshTiming,
+        file: &Path,
+        watch_directory: bool,
+    ) -> Option<Self> {
         let tx_watcher = tx.clone();
-        let mut watcher = recommended_watcher(move |_| {
-            let _ = tx_watcher.send(Instruction::AutoRefresh);
+        let file_name = file.file_name()?.to_os_string();
+        // the directory is watched, as the log might get replaced (e.g. when it got cleared),
+        // which ends the watch of the file itself on some platforms
+        let mut watcher = recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else {
+                return;
+            };
+            let file_names = event.paths.iter().filter_map(|p| p.file_name());
+            let instruction = if file_names.clone().any(|n| n == file_name) {
+                Instruction::AutoRefresh
+            } else if watch_directory
+                && file_names
+                    .map(|n| n.to_string_lossy())
+                    .any(|n| is_combatlog_file_name(&n))
+            {
+                Instruction::CheckNewestCombatlog
+            } else {
+                return;
+            };
+            let _ = tx_watcher.send(instruction);
         })
         .ok()?;
-
         watcher
-            .watch(file, notify::RecursiveMode::NonRecursive)
+            .watch(file.parent()?, notify::RecursiveMode::NonRecursive)
             .ok()?;
+        if watch_directory {
+            // a newer file might have appeared while nothing was watched
+            let _ = tx.send(Instruction::CheckNewestCombatlog);
+        }
 
         Some(Self {
             tx,
             timer: Timer::new(),
             state: AutoRefreshState::Idle,
-            interval,
+            newest_combatlog_check: None,
+            timing,
             _watcher: watcher,
             last_refresh: SystemTime::now(),
         })
@@ -470,6 +1308,87 @@ impl AutoRefreshContext {
     }
 }
 
+/// like `combatlog*.log`, the name the game and logger mods give the combatlog files
+pub fn is_combatlog_file_name(file_name: &str) -> bool {
+    let file_name = file_name.to_lowercase();
+    file_name.starts_with("combatlog") && file_name.ends_with(".log")
+}
+
+/// The most recently modified combatlog file in the directory of `current_file`, if it differs from it.
+/// Empty files did not receive any writes yet, so they are not considered.
+fn newest_combatlog_file(current_file: &Path) -> Option<PathBuf> {
+    let modified = |metadata: &std::fs::Metadata| metadata.modified().ok();
+    let current_modified = std::fs::metadata(current_file)
+        .ok()
+        .and_then(|m| modified(&m));
+    let (newest, newest_modified) = std::fs::read_dir(current_file.parent()?)
+        .ok()?
+        .flatten()
+        .filter(|e| is_combatlog_file_name(&e.file_name().to_string_lossy()))
+        .filter_map(|e| {
+            let metadata = e.metadata().ok().filter(|m| m.is_file() && m.len() > 0)?;
+            Some((e.path(), modified(&metadata)?))
+        })
+        .max_by_key(|(_, modified)| *modified)?;
+    if newest.file_name() == current_file.file_name()
+        || current_modified.is_some_and(|m| m >= newest_modified)
+    {
+        return None;
+    }
+    Some(newest)
+}
+
+impl SharedState {
+    fn is_shutting_down(&self) -> bool {
+        self.handlers_alive.load(Ordering::Relaxed) == 0
+    }
+
+    fn set_busy_phase(&self, phase: BusyPhase) {
+        let mut busy_state = self.busy_state.lock().unwrap();
+        if std::mem::discriminant(&busy_state.phase) != std::mem::discriminant(&phase) {
+            busy_state.since = Instant::now();
+        }
+        busy_state.phase = phase;
+    }
+}
+
+impl BusyState {
+    fn idle() -> Self {
+        Self {
+            phase: BusyPhase::Idle,
+            since: Instant::now(),
+        }
+    }
+}
+
+impl BusyPhase {
+    pub fn is_cancelable(self) -> bool {
+        matches!(
+            self,
+            BusyPhase::Parsing | BusyPhase::Recalculating { .. } | BusyPhase::Importing { .. }
+        )
+    }
+
+    pub fn display(self) -> String {
+        match self {
+            BusyPhase::Idle => "Idle".to_string(),
+            BusyPhase::Parsing => "Parsing log..".to_string(),
+            BusyPhase::Recalculating { combat, combats } => {
+                format!("Recalculating combat {} of {}..", combat + 1, combats)
+            }
+            BusyPhase::SavingCombat => "Saving combat..".to_string(),
+            BusyPhase::MergingCombats => "Merging combats..".to_string(),
+            BusyPhase::SlicingCombat => "Analyzing time slice..".to_string(),
+            BusyPhase::ClearingLog => "Clearing log..".to_string(),
+            BusyPhase::Exporting => "Exporting..".to_string(),
+            BusyPhase::QueryingRecords => "Querying records..".to_string(),
+            BusyPhase::Importing { file, files } => {
+                format!("Importing log {} of {}..", file + 1, files)
+            }
+        }
+    }
+}
+
 impl HandlerContext {
     fn send(&self, info: AnalysisInfo, ctx: &Context) {
         match self.tx.send(info) {
@@ -478,3 +1397,177 @@ impl HandlerContext {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::RecvTimeoutError;
+
+    use super::*;
+    use crate::analyzer::test_log::*;
+
+    /// a record of the log at `time_seconds`, like the game writes it
+    fn test_record(time_seconds: f64) -> String {
+        TestLog::new()
+            .with(TestRecord::damage(
+                time_seconds,
+                &TestEntity::npc("Borg Queen Octahedron", 25),
+                &TestEntity::player("Ayel", "greyblizzard", 1),
+                "Plasma Fire",
+                2086.87,
+            ))
+            .to_log()
+    }
+
+    #[test]
+    fn analysis_thread_stops_after_last_handler_dropped() {
+        let combatlog_file = TestLogFile::new(test_record(0.0).repeat(100_000), "log");
+
+        let settings = AnalysisSettings {
+            combatlog_file: combatlog_file.path().to_string_lossy().into(),
+            ..Default::default()
+        };
+        let handler = AnalysisHandler::new(settings, Context::default(), 1.0, 0.5, false);
+        let overlay_handler = handler.get_handler(false, ViewportId::ROOT);
+        // disconnects once the analysis thread let go of the handler
+        let info_rx = handler.rx.clone();
+        handler.refresh();
+
+        drop(overlay_handler);
+        drop(handler);
+        let stopped = loop {
+            match info_rx.recv_timeout(std::time::Duration::from_secs(10)) {
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Disconnected) => break true,
+                Err(RecvTimeoutError::Timeout) => break false,
+            }
+        };
+
+        assert!(stopped, "analysis thread did not stop");
+    }
+
+    #[test]
+    fn replace_log_keeps_the_last_combat_and_what_the_game_appends() {
+        let record = test_record(0.0);
+        let last_combat = test_record(420.0);
+        let combatlog_file = TestLogFile::new(record.repeat(3) + &last_combat, "log");
+
+        // like the game, which keeps the log open and appends to it
+        let mut game = File::options()
+            .append(true)
+            .open(combatlog_file.path())
+            .unwrap();
+
+        let keep_from = 3 * record.len() as u64;
+        AnalysisContext::replace_log(combatlog_file.path(), Some(keep_from)).unwrap();
+        game.write_all(record.as_bytes()).unwrap();
+        drop(game);
+
+        let content = std::fs::read_to_string(combatlog_file.path()).unwrap();
+        assert_eq!(content, last_combat + &record);
+    }
+
+    #[test]
+    fn replace_log_reports_a_broken_end() {
+        let record = test_record(0.0);
+        let combatlog_file = TestLogFile::new(record.repeat(2) + "\0\0\0\n", "log");
+
+        let result = AnalysisContext::replace_log(combatlog_file.path(), Some(record.len() as u64));
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::InvalidData);
+        let content = std::fs::read_to_string(combatlog_file.path()).unwrap();
+        assert_eq!(content, record + "\0\0\0\n");
+        assert!(!AnalysisContext::log_backup_path(combatlog_file.path()).exists());
+    }
+
+    #[test]
+    fn restore_log_puts_the_backup_in_front_of_what_the_game_appended() {
+        let appended_before = test_record(430.0);
+        let appended_after = test_record(440.0);
+        let last_combat = test_record(420.0);
+        let backup = TestLogFile::new(&last_combat, "log.backup");
+        // the kept part got written between the lines the game appended, but broke on the way
+        let broken = "\0".repeat(last_combat.len());
+        let combatlog_file =
+            TestLogFile::new(appended_before.clone() + &broken + &appended_after, "log");
+        let written_at = appended_before.len() as u64;
+
+        let mut file = AnalysisContext::open_log_for_writing(combatlog_file.path()).unwrap();
+        AnalysisContext::restore_log(
+            &mut file,
+            backup.path(),
+            written_at..written_at + last_combat.len() as u64,
+        )
+        .unwrap();
+        drop(file);
+
+        let content = std::fs::read_to_string(combatlog_file.path()).unwrap();
+        assert_eq!(content, last_combat + &appended_before + &appended_after);
+    }
+
+    #[test]
+    fn replace_log_keeps_what_the_game_appends_while_clearing() {
+        let record = test_record(0.0);
+        let appended_while_reading = test_record(430.0);
+        let appended_after_clearing = test_record(440.0);
+        let last_combat = test_record(420.0);
+        let combatlog_file = TestLogFile::new(record.repeat(3) + &last_combat, "log");
+        let keep_from = 3 * record.len() as u64;
+
+        // the game appends, whenever the clearing hands over to it, and reports back once it did
+        let (append_tx, append_rx) = crossbeam_channel::bounded::<String>(0);
+        let (appended_tx, appended_rx) = crossbeam_channel::bounded(0);
+        let game = std::thread::spawn({
+            let mut game = File::options()
+                .append(true)
+                .open(combatlog_file.path())
+                .unwrap();
+            move || {
+                for line in append_rx {
+                    game.write_all(line.as_bytes()).unwrap();
+                    appended_tx.send(()).unwrap();
+                }
+            }
+        });
+        let append = |line: &str| {
+            append_tx.send(line.to_string()).unwrap();
+            appended_rx.recv().unwrap();
+        };
+
+        let mut reads = 0;
+        AnalysisContext::replace_log_with(combatlog_file.path(), Some(keep_from), || {
+            reads += 1;
+            if reads == 1 {
+                append(&appended_while_reading);
+            }
+        })
+        .unwrap();
+        append(&appended_after_clearing);
+        drop(append_tx);
+        game.join().unwrap();
+
+        let content = std::fs::read_to_string(combatlog_file.path()).unwrap();
+        assert_eq!(reads, 2);
+        assert_eq!(
+            content,
+            last_combat + &appended_while_reading + &appended_after_clearing
+        );
+        assert!(!AnalysisContext::log_backup_path(combatlog_file.path()).exists());
+    }
+
+    #[test]
+    fn newest_combatlog_file_skips_empty_and_other_files() {
+        let test_directory = TestLogDirectory::new();
+        let directory = test_directory.path();
+        let current = directory.join("combatlog.log");
+        std::fs::write(&current, "old").unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        std::fs::write(directory.join("notes.log"), "other").unwrap();
+        std::fs::write(directory.join("Combatlog_2.log"), "").unwrap();
+        assert_eq!(newest_combatlog_file(&current), None);
+
+        std::fs::write(directory.join("Combatlog_2.log"), "new").unwrap();
+        assert_eq!(
+            newest_combatlog_file(&current),
+            Some(directory.join("Combatlog_2.log"))
+        );
+    }
+}
@@ -0,0 +1,352 @@
+use std::cmp::Reverse;
+
+use chrono::NaiveDate;
+use educe::Educe;
+use eframe::egui::*;
+use rustc_hash::FxHashSet;
+
+use crate::{analyzer::DateTimeFormat, helpers::number_formatting::NumberFormatter};
+
+use super::{analysis_handling::CombatListEntry, combat_notes::CombatNotes};
+
+const POPUP_WIDTH: f32 = 500.0;
+const MAX_LIST_HEIGHT: f32 = 400.0;
+
+/// Popup to select a combat of the combat list.
+/// Only the visible rows are built, so it stays fast for hundreds of combats.
+#[derive(Default)]
+pub struct CombatList {
+    sort: CombatListSort,
+    /// matches the name or the tags of a combat
+    filter: String,
+    /// days of the chronological list, which are collapsed
+    collapsed_days: FxHashSet<NaiveDate>,
+    /// the list starts at the newest combats, whenever it gets opened
+    scroll_to_top: bool,
+    /// rows of the previous frames, built again once anything they are made of changed
+    rows: Option<RowsCache>,
+}
+
+struct RowsCache {
+    combats_version: u64,
+    notes_version: u64,
+    sort: CombatListSort,
+    filter: String,
+    rows: Vec<Row>,
+}
+
+/// the combats of the list, which count their changes, so that the list is only sorted again after one
+#[derive(Default, Educe)]
+#[educe(Deref)]
+pub struct CombatListEntries {
+    #[educe(Deref)]
+    entries: Vec<CombatListEntry>,
+    version: u64,
+}
+
+/// a combat of the list got clicked
+pub struct CombatListClick {
+    pub combat_index: usize,
+    pub was_selected: bool,
+    /// ctrl-click, to merge multiple combats into one
+    pub merge: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum CombatListSort {
+    #[default]
+    Chronological,
+    GroupDps,
+    Duration,
+}
+
+enum Row {
+    Day {
+        date: NaiveDate,
+        combats: usize,
+    },
+    /// index of the combat in the combat list
+    Combat(usize),
+}
+
+impl CombatList {
+    pub fn show_sort(&mut self, ui: &mut Ui) {
+        ComboBox::new("combat list sort", "")
+            .width(100.0)
+            .selected_text(self.sort.display())
+            .show_ui(ui, |ui| {
+                for sort in CombatListSort::ALL {
+                    ui.selectable_value(&mut self.sort, sort, sort.display());
+                }
+            })
+            .response
+            .on_hover_text("sort the combat list");
+    }
+
+    /// `selected_text` is shown on the button, `is_selected` tells whether a combat of the list is shown
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        combats: &CombatListEntries,
+        combat_notes: &CombatNotes,
+        date_time_format: &DateTimeFormat,
+        selected_text: String,
+        is_selected: impl Fn(usize) -> bool,
+    ) -> Option<CombatListClick> {
+        let popup_id = ui.make_persistent_id("combat list");
+        let button = ui.add(
+            Button::new(format!("{} ⏷", selected_text))
+                .wrap(false)
+                .min_size(vec2(400.0, 0.0)),
+        );
+        ui.label("Combats");
+        if button.clicked() {
+            ui.memory_mut(|m| m.toggle_popup(popup_id));
+            self.scroll_to_top = true;
+        }
+        if !ui.memory(|m| m.is_popup_open(popup_id)) {
+            return None;
+        }
+
+        let mut click = None;
+        let area = Area::new(popup_id)
+            .order(Order::Foreground)
+            .constrain(true)
+            .fixed_pos(button.rect.left_bottom())
+            .show(ui.ctx(), |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.set_width(POPUP_WIDTH);
+                    click =
+                        self.show_list(ui, combats, combat_notes, date_time_format, &is_selected);
+                });
+            });
+
+        let clicked_outside = ui.input(|i| {
+            i.pointer.any_click()
+                && i.pointer
+                    .interact_pos()
+                    .is_some_and(|p| !area.response.rect.contains(p) && !button.rect.contains(p))
+        });
+        let selected = click.as_ref().is_some_and(|c| !c.merge);
+        if clicked_outside || selected || ui.input(|i| i.key_pressed(Key::Escape)) {
+            ui.memory_mut(|m| m.close_popup());
+        }
+        click
+    }
+
+    fn show_list(
+        &mut self,
+        ui: &mut Ui,
+        combats: &CombatListEntries,
+        combat_notes: &CombatNotes,
+        date_time_format: &DateTimeFormat,
+        is_selected: &impl Fn(usize) -> bool,
+    ) -> Option<CombatListClick> {
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            TextEdit::singleline(&mut self.filter)
+                .hint_text("name or tag")
+                .desired_width(200.0)
+                .show(ui);
+        });
+        ui.weak("Ctrl-click to merge multiple combats into one");
+        ui.separator();
+
+        let mut scroll_area = ScrollArea::vertical();
+        if std::mem::take(&mut self.scroll_to_top) {
+            scroll_area = scroll_area.vertical_scroll_offset(0.0);
+        }
+        self.update_rows(combats, combat_notes);
+        let rows = &self.rows.as_ref().unwrap().rows;
+        let row_height = ui.spacing().interact_size.y;
+        let mut number_formatter = NumberFormatter::new();
+        let mut click = None;
+        let mut toggled_day = None;
+        scroll_area
+            .max_height(MAX_LIST_HEIGHT)
+            .auto_shrink([false, true])
+            .show_rows(ui, row_height, rows.len(), |ui, range| {
+                for row in &rows[range] {
+                    match row {
+                        Row::Day { date, combats } => {
+                            let collapsed = self.collapsed_days.contains(date);
+                            let text = format!(
+                                "{} {} ({})",
+                                if collapsed { "⏵" } else { "⏷" },
+                                date_time_format.format_date(*date),
+                                combats
+                            );
+                            if ui
+                                .add(Button::new(RichText::new(text).strong()).frame(false))
+                                .clicked()
+                            {
+                                toggled_day = Some(*date);
+                            }
+                        }
+                        Row::Combat(index) => {
+                            let combat = &combats[*index];
+                            let note = combat_notes.get(&combat.identity);
+                            let mut text = format!(
+                                "{} | {} DPS",
+                                combat.identifier(date_time_format),
+                                number_formatter.format_with_automated_suffixes(combat.group_dps)
+                            );
+                            if let Some(n) = note.filter(|n| !n.tags.is_empty()) {
+                                text = format!("{} [{}]", text, n.tags.join(", "));
+                            }
+                            let selected = is_selected(combat.index);
+                            if ui.selectable_label(selected, text).clicked() {
+                                click = Some(CombatListClick {
+                                    combat_index: combat.index,
+                                    was_selected: selected,
+                                    merge: ui.input(|i| i.modifiers.command),
+                                });
+                            }
+                        }
+                    }
+                }
+            });
+
+        if let Some(day) = toggled_day {
+            if !self.collapsed_days.remove(&day) {
+                self.collapsed_days.insert(day);
+            }
+            self.rows = None;
+        }
+        click
+    }
+
+    /// the rows are only built again, if the combats, their notes, the sort or the filter changed
+    fn update_rows(&mut self, combats: &CombatListEntries, combat_notes: &CombatNotes) {
+        let up_to_date = self.rows.as_ref().is_some_and(|r| {
+            r.combats_version == combats.version
+                && r.notes_version == combat_notes.version()
+                && r.sort == self.sort
+                && r.filter == self.filter
+        });
+        if up_to_date {
+            return;
+        }
+
+        self.rows = Some(RowsCache {
+            combats_version: combats.version,
+            notes_version: combat_notes.version(),
+            sort: self.sort,
+            filter: self.filter.clone(),
+            rows: self.build_rows(combats, combat_notes),
+        });
+    }
+
+    /// the filtered and sorted combats, grouped by day when sorted chronologically
+    fn build_rows(&self, combats: &[CombatListEntry], combat_notes: &CombatNotes) -> Vec<Row> {
+        let filter = self.filter.trim().to_lowercase();
+        let combats = self.sort.sorted(combats).into_iter().filter(|(_, combat)| {
+            filter.is_empty()
+                || combat.name.to_lowercase().contains(&filter)
+                || combat_notes
+                    .get(&combat.identity)
+                    .is_some_and(|n| n.has_tag_containing(&filter))
+        });
+        if self.sort != CombatListSort::Chronological {
+            return combats.map(|(i, _)| Row::Combat(i)).collect();
+        }
+
+        let mut rows = Vec::new();
+        let mut day_row = 0;
+        for (index, combat) in combats {
+            let date = combat.active_time.start.date();
+            let same_day =
+                matches!(rows.get(day_row), Some(Row::Day { date: d, .. }) if *d == date);
+            if !same_day {
+                day_row = rows.len();
+                rows.push(Row::Day { date, combats: 0 });
+            }
+            if let Some(Row::Day { combats, .. }) = rows.get_mut(day_row) {
+                *combats += 1;
+            }
+            if !self.collapsed_days.contains(&date) {
+                rows.push(Row::Combat(index));
+            }
+        }
+        rows
+    }
+}
+
+impl CombatListEntries {
+    /// returns the previous combats
+    pub fn replace(&mut self, entries: Vec<CombatListEntry>) -> Vec<CombatListEntry> {
+        self.version += 1;
+        std::mem::replace(&mut self.entries, entries)
+    }
+}
+
+impl CombatListSort {
+    const ALL: [Self; 3] = [Self::Chronological, Self::GroupDps, Self::Duration];
+
+    const fn display(&self) -> &'static str {
+        match self {
+            CombatListSort::Chronological => "Latest first",
+            CombatListSort::GroupDps => "Group DPS",
+            CombatListSort::Duration => "Duration",
+        }
+    }
+
+    /// the combats with their index in the list
+    fn sorted<'a>(&self, combats: &'a [CombatListEntry]) -> Vec<(usize, &'a CombatListEntry)> {
+        let mut sorted: Vec<_> = combats.iter().enumerate().rev().collect();
+        match self {
+            // the imported combats are listed before the ones of the log, but can be of any day
+            CombatListSort::Chronological => {
+                sorted.sort_by_key(|(_, c)| Reverse(c.active_time.start))
+            }
+            CombatListSort::GroupDps => {
+                sorted.sort_by(|(_, c1), (_, c2)| c2.group_dps.total_cmp(&c1.group_dps))
+            }
+            CombatListSort::Duration => sorted.sort_by_key(|(_, c)| Reverse(c.duration)),
+        }
+        sorted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDateTime};
+
+    use super::*;
+
+    fn entry(index: usize, start: &str, origin: Option<&str>) -> CombatListEntry {
+        let start = NaiveDateTime::parse_from_str(start, "%Y-%m-%d %H:%M").unwrap();
+        CombatListEntry {
+            index,
+            name: "Infected Space".to_string(),
+            active_time: start..start + Duration::minutes(5),
+            identity: start.to_string(),
+            group_dps: 0.0,
+            duration: Duration::minutes(5),
+            origin: origin.map(|o| o.to_string()),
+        }
+    }
+
+    #[test]
+    fn imported_combats_are_grouped_with_the_days_of_the_log() {
+        let mut combats = CombatListEntries::default();
+        // the imported combats are listed first
+        combats.replace(vec![
+            entry(0, "2024-01-06 20:00", Some("saved.log")),
+            entry(1, "2024-01-07 09:00", Some("saved.log")),
+            entry(2, "2024-01-06 21:00", None),
+            entry(3, "2024-01-07 10:00", None),
+        ]);
+        let list = CombatList::default();
+
+        let rows: Vec<_> = list
+            .build_rows(&combats, &CombatNotes::default())
+            .into_iter()
+            .map(|r| match r {
+                Row::Day { combats, .. } => format!("day of {}", combats),
+                Row::Combat(index) => index.to_string(),
+            })
+            .collect();
+        assert_eq!(rows, ["day of 2", "3", "1", "day of 2", "2", "0"]);
+    }
+}
@@ -0,0 +1,126 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+/// notes, tags and bookmarks of combats, keyed by the combat identity (log file name and start time),
+/// stored next to the settings, so that they survive re-parses and restarts
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CombatNotes {
+    combats: BTreeMap<String, CombatNote>,
+    /// changes with every save, so that views of the notes know when to update
+    #[serde(skip)]
+    version: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CombatNote {
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub bookmarks: Vec<Bookmark>,
+    /// replaces the detected name of the combat, see [`Combat::name_override`](crate::analyzer::Combat::name_override)
+    #[serde(default)]
+    pub name_override: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// seconds since the start of the combat, same as the time axis of the diagrams
+    pub time_seconds: f64,
+    pub label: String,
+}
+
+impl CombatNotes {
+    fn file_path() -> Option<PathBuf> {
+        let mut path = std::env::current_exe().ok()?;
+        path.pop();
+        path.push("STO_CombatLogAnalyzer_CombatNotes.json");
+        Some(path)
+    }
+
+    pub fn load_or_default() -> Self {
+        Self::file_path()
+            .and_then(|f| std::fs::read_to_string(f).ok())
+            .and_then(|d| serde_json::from_str(&d).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&mut self) {
+        self.combats.retain(|_, n| !n.is_empty());
+        self.version += 1;
+
+        let file_path = match Self::file_path() {
+            Some(p) => p,
+            None => {
+                return;
+            }
+        };
+        let data = match serde_json::to_string_pretty(self) {
+            Ok(d) => d,
+            Err(_) => {
+                return;
+            }
+        };
+
+        let _ = std::fs::write(file_path, data);
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn get(&self, combat_identity: &str) -> Option<&CombatNote> {
+        self.combats.get(combat_identity)
+    }
+
+    pub fn get_mut(&mut self, combat_identity: &str) -> &mut CombatNote {
+        self.combats.entry(combat_identity.to_string()).or_default()
+    }
+
+    /// the name overrides of all combats, keyed by the combat identity
+    pub fn name_overrides(&self) -> FxHashMap<String, String> {
+        self.combats
+            .iter()
+            .filter_map(|(identity, note)| Some((identity.clone(), note.name_override.clone()?)))
+            .collect()
+    }
+}
+
+impl CombatNote {
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty()
+            && self.tags.is_empty()
+            && self.bookmarks.is_empty()
+            && self.name_override.is_none()
+    }
+
+    pub fn add_bookmark(&mut self, time_seconds: f64) {
+        let label = format!("Bookmark {}", self.bookmarks.len() + 1);
+        self.bookmarks.push(Bookmark {
+            time_seconds,
+            label,
+        });
+        self.sort_bookmarks();
+    }
+
+    pub fn sort_bookmarks(&mut self) {
+        self.bookmarks
+            .sort_by(|b1, b2| b1.time_seconds.total_cmp(&b2.time_seconds));
+    }
+
+    pub fn add_tag(&mut self, tag: &str) {
+        let tag = tag.trim();
+        if tag.is_empty() || self.tags.iter().any(|t| t == tag) {
+            return;
+        }
+        self.tags.push(tag.to_string());
+    }
+
+    pub fn has_tag_containing(&self, filter: &str) -> bool {
+        let filter = filter.to_lowercase();
+        self.tags.iter().any(|t| t.to_lowercase().contains(&filter))
+    }
+}
@@ -0,0 +1,84 @@
+use eframe::egui::*;
+
+use crate::analyzer::Combat;
+
+use super::state::AppState;
+
+/// gives the selected combat a name of the user, which replaces the one from the combat name rules
+#[derive(Default)]
+pub struct CombatRename {
+    name: String,
+    /// identity of the combat the name was last reset for
+    combat: Option<String>,
+}
+
+impl CombatRename {
+    /// `combat_index` is the one of the selected `combat`, `None` for merged or sliced combats
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        combat: Option<&Combat>,
+        combat_index: Option<usize>,
+        state: &mut AppState,
+    ) {
+        let Some((combat, combat_index)) = combat.zip(combat_index) else {
+            ui.add_enabled(false, Button::new("✏"))
+                .on_disabled_hover_text("only whole combats can be renamed");
+            return;
+        };
+        let identity = combat.identity();
+        if self.combat.as_ref() != Some(&identity) {
+            self.combat = Some(identity.clone());
+            self.name = combat.name();
+        }
+
+        ui.menu_button("✏", |ui| {
+            ui.label(format!("detected name: {}", combat.detected_name()));
+            let edit = TextEdit::singleline(&mut self.name)
+                .desired_width(250.0)
+                .show(ui)
+                .response;
+            let submitted = edit.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+            ui.horizontal(|ui| {
+                let name = self.name.trim();
+                if (ui
+                    .add_enabled(!name.is_empty(), Button::new("Rename"))
+                    .clicked()
+                    || submitted)
+                    && !name.is_empty()
+                {
+                    let name = name.to_string();
+                    Self::set_name_override(state, &identity, combat_index, Some(name));
+                    ui.close_menu();
+                }
+                if ui
+                    .add_enabled(
+                        combat.name_override.is_some(),
+                        Button::new("Reset to Detected Name"),
+                    )
+                    .clicked()
+                {
+                    self.name = combat.detected_name();
+                    Self::set_name_override(state, &identity, combat_index, None);
+                    ui.close_menu();
+                }
+            });
+        })
+        .response
+        .on_hover_text("rename the combat");
+    }
+
+    fn set_name_override(
+        state: &mut AppState,
+        identity: &str,
+        combat_index: usize,
+        name: Option<String>,
+    ) {
+        state.combat_notes.get_mut(identity).name_override = name;
+        state.combat_notes.save();
+        state
+            .analysis_handler
+            .set_combat_name_overrides(state.combat_notes.name_overrides());
+        state.analysis_handler.get_combat(combat_index);
+    }
+}
@@ -0,0 +1,309 @@
+use std::path::{Path, PathBuf};
+
+use eframe::egui::*;
+use itertools::Itertools;
+use serde_json::json;
+
+use crate::{analyzer::*, helpers::time_range_to_duration_or_zero};
+
+use super::{
+    settings::{
+        ExportDestination, ExportFormat, ExportPreset, SummaryCopySettings, SummarySettings,
+    },
+    summary_copy::SummaryCopy,
+};
+
+#[derive(Clone, Debug)]
+pub struct ExportResult {
+    pub preset: String,
+    pub outcome: Result<ExportOutput, String>,
+}
+
+#[derive(Clone, Debug)]
+pub enum ExportOutput {
+    File(PathBuf),
+    Clipboard(String),
+}
+
+/// shows the results of the last exports, until it gets closed
+#[derive(Default)]
+pub struct ExportResultsWindow {
+    results: Vec<ExportResult>,
+}
+
+struct SummaryRow {
+    name: String,
+    dps: f64,
+    damage_out: f64,
+    damage_out_percentage: Option<f64>,
+    damage_in: f64,
+    heal_out: f64,
+    kills: u32,
+    deaths: u32,
+    combat_duration: f64,
+}
+
+/// the text summaries are built like the summary copy, with the `summary_copy` settings
+pub fn run_exports(
+    presets: &[ExportPreset],
+    combat: &Combat,
+    combatlog_file: &Path,
+    summary_copy: &SummaryCopySettings,
+    summary: &SummarySettings,
+) -> Vec<ExportResult> {
+    let summary_text = || SummaryCopy::new(summary).build(combat, None, summary_copy);
+    presets
+        .iter()
+        .map(|preset| ExportResult {
+            preset: preset.name.clone(),
+            outcome: run_export(preset, combat, combatlog_file, summary_text),
+        })
+        .collect()
+}
+
+fn run_export(
+    preset: &ExportPreset,
+    combat: &Combat,
+    combatlog_file: &Path,
+    summary_text: impl Fn() -> String,
+) -> Result<ExportOutput, String> {
+    let data = match preset.format {
+        ExportFormat::SummaryCsv => summary_csv(combat).into_bytes(),
+        ExportFormat::SummaryJson => summary_json(combat).into_bytes(),
+        ExportFormat::SummaryText => summary_text().into_bytes(),
+        ExportFormat::CombatLog => combat
+            .read_log_combat_data(combatlog_file)
+            .ok_or_else(|| "failed to read the combat from the log".to_string())?,
+    };
+
+    match preset.destination {
+        ExportDestination::Clipboard => {
+            let text = String::from_utf8(data).map_err(|e| e.to_string())?;
+            Ok(ExportOutput::Clipboard(text))
+        }
+        ExportDestination::File => {
+            let file = export_file(&preset.file_pattern, combat, combatlog_file);
+            if let Some(folder) = file.parent().filter(|f| !f.as_os_str().is_empty()) {
+                std::fs::create_dir_all(folder).map_err(|e| e.to_string())?;
+            }
+            std::fs::write(&file, data).map_err(|e| e.to_string())?;
+            Ok(ExportOutput::File(file))
+        }
+    }
+}
+
+/// Replaces the placeholders of the `file_pattern`. Relative paths are placed in the directory of the log.
+/// Without the `{time}` placeholder, the start of the combat is added to the file name,
+/// so the exports of different combats don't overwrite each other.
+fn export_file(file_pattern: &str, combat: &Combat, combatlog_file: &Path) -> PathBuf {
+    let combat_name: String = combat
+        .name()
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c => c,
+        })
+        .collect();
+    let start = combat.active_time.start;
+    let file = file_pattern
+        .replace("{combat_name}", &combat_name)
+        .replace("{date}", &start.format("%Y-%m-%d").to_string())
+        .replace("{time}", &start.format("%H-%M-%S").to_string());
+    let mut file = PathBuf::from(file);
+    if !file_pattern.contains("{time}") {
+        let start = start.format("%Y-%m-%d_%H-%M-%S");
+        let file_name = match (file.file_stem(), file.extension()) {
+            (Some(stem), Some(extension)) => format!(
+                "{}_{}.{}",
+                stem.to_string_lossy(),
+                start,
+                extension.to_string_lossy()
+            ),
+            (Some(stem), None) => format!("{}_{}", stem.to_string_lossy(), start),
+            _ => start.to_string(),
+        };
+        file.set_file_name(file_name);
+    }
+    match combatlog_file.parent() {
+        Some(directory) if file.is_relative() => directory.join(file),
+        _ => file,
+    }
+}
+
+fn summary_rows(combat: &Combat) -> Vec<SummaryRow> {
+    combat
+        .players
+        .values()
+        .map(|p| SummaryRow {
+            name: p.damage_out.name().get(&combat.name_manager).to_string(),
+            dps: p.damage_out.dps.all,
+            damage_out: p.damage_out.total_damage.all,
+            damage_out_percentage: p.damage_out.damage_percentage.all,
+            damage_in: p.damage_in.total_damage.all,
+            heal_out: p.heal_out.total_heal.all,
+            kills: p.damage_out.kills.values().copied().sum(),
+            deaths: p.damage_in.kills.values().copied().sum(),
+            combat_duration: time_range_to_duration_or_zero(&p.combat_time).num_milliseconds()
+                as f64
+                / 1e3,
+        })
+        .sorted_by(|r1, r2| r2.dps.total_cmp(&r1.dps))
+        .collect()
+}
+
+fn summary_csv(combat: &Combat) -> String {
+    let header = "Player,DPS,Total Damage Out,Damage Out %,Total Damage In,Total Heal Out,Kills,Deaths,Combat Duration (s),Settings Digest";
+    let rows = summary_rows(combat).into_iter().map(|r| {
+        format!(
+            "\"{}\",{},{},{},{},{},{},{},{},{}",
+            r.name.replace('"', "\"\""),
+            r.dps,
+            r.damage_out,
+            r.damage_out_percentage
+                .map(|p| p.to_string())
+                .unwrap_or_default(),
+            r.damage_in,
+            r.heal_out,
+            r.kills,
+            r.deaths,
+            r.combat_duration,
+            combat.settings_digest
+        )
+    });
+    std::iter::once(header.to_string())
+        .chain(rows)
+        .map(|l| l + "\n")
+        .collect()
+}
+
+fn summary_json(combat: &Combat) -> String {
+    let players: Vec<_> = summary_rows(combat)
+        .into_iter()
+        .map(|r| {
+            json!({
+                "name": r.name,
+                "dps": r.dps,
+                "total_damage_out": r.damage_out,
+                "damage_out_percentage": r.damage_out_percentage,
+                "total_damage_in": r.damage_in,
+                "total_heal_out": r.heal_out,
+                "kills": r.kills,
+                "deaths": r.deaths,
+                "combat_duration_seconds": r.combat_duration,
+            })
+        })
+        .collect();
+    let summary = json!({
+        "name": combat.name(),
+        "start": combat.active_time.start.to_string(),
+        "combat_duration_seconds":
+            time_range_to_duration_or_zero(&combat.combat_time).num_milliseconds() as f64 / 1e3,
+        "total_damage_out": combat.total_damage_out.all,
+        "total_damage_in": combat.total_damage_in.all,
+        "total_kills": combat.total_kills,
+        "total_deaths": combat.total_deaths,
+        "settings_digest": combat.settings_digest,
+        "players": players,
+    });
+    serde_json::to_string_pretty(&summary).unwrap_or_default()
+}
+
+impl ExportResultsWindow {
+    /// copies the clipboard exports and shows the results
+    pub fn set_results(&mut self, results: Vec<ExportResult>, ctx: &Context) {
+        let clipboard = results
+            .iter()
+            .filter_map(|r| match &r.outcome {
+                Ok(ExportOutput::Clipboard(text)) => Some(text.as_str()),
+                _ => None,
+            })
+            .join("\n\n");
+        if !clipboard.is_empty() {
+            ctx.output_mut(|o| o.copied_text = clipboard);
+        }
+        self.results = results;
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        if self.results.is_empty() {
+            return;
+        }
+
+        let mut close = false;
+        Window::new("Export Results")
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                let succeeded = self.results.iter().filter(|r| r.outcome.is_ok()).count();
+                ui.label(format!(
+                    "{} of {} exports succeeded",
+                    succeeded,
+                    self.results.len()
+                ));
+                ui.add_space(10.0);
+                for result in self.results.iter() {
+                    ui.horizontal(|ui| match &result.outcome {
+                        Ok(output) => {
+                            ui.label(WidgetText::from("✔").color(Color32::GREEN));
+                            ui.label(&result.preset);
+                            match output {
+                                ExportOutput::File(file) => {
+                                    ui.label(file.to_string_lossy());
+                                }
+                                ExportOutput::Clipboard(_) => {
+                                    ui.label("copied to clipboard");
+                                }
+                            }
+                        }
+                        Err(err) => {
+                            ui.label(WidgetText::from("✖").color(Color32::RED));
+                            ui.label(&result.preset);
+                            ui.label(err);
+                        }
+                    });
+                }
+                ui.add_space(10.0);
+                close = ui.button("Close").clicked();
+            });
+
+        if close {
+            self.results.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::test_log::*;
+
+    #[test]
+    fn export_files_are_placed_next_to_the_log_and_named_by_the_combat_start() {
+        let file = TestLog::new()
+            .with(TestRecord::damage(
+                0.0,
+                &TestEntity::npc("Borg Queen Octahedron", 25),
+                &TestEntity::player("Ayel", "greyblizzard", 1),
+                "Plasma Fire",
+                2086.87,
+            ))
+            .write();
+        let combat = TestLog::analyze_file(&file, Default::default()).remove(0);
+        let combatlog_file = file.path();
+        let directory = combatlog_file.parent().unwrap();
+
+        assert_eq!(
+            export_file("exports/{date}_summary.csv", &combat, combatlog_file),
+            directory.join("exports/2024-01-07_summary_2024-01-07_10-00-00.csv")
+        );
+        assert_eq!(
+            export_file("{date}_{time}.json", &combat, combatlog_file),
+            directory.join("2024-01-07_10-00-00.json")
+        );
+        let absolute = directory.join("summary.txt");
+        assert_eq!(
+            export_file(&absolute.to_string_lossy(), &combat, combatlog_file),
+            directory.join("summary_2024-01-07_10-00-00.txt")
+        );
+    }
+}
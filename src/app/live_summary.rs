@@ -0,0 +1,118 @@
+use std::time::{Duration, Instant};
+
+use eframe::egui::*;
+
+use crate::{
+    analyzer::{character_name, AnalysisGroup, Combat, Player},
+    helpers::{
+        format_duration, number_formatting::NumberFormatter, time_range_to_duration_or_zero,
+    },
+};
+
+/// after this time without the latest combat changing, it is not considered current anymore
+const STALE_AFTER: Duration = Duration::from_secs(3 * 60);
+
+/// one line summary of the latest combat for the toolbar
+#[derive(Default)]
+pub struct LiveSummary {
+    current: Option<CurrentCombat>,
+}
+
+struct CurrentCombat {
+    text: String,
+    active_time_end: chrono::NaiveDateTime,
+    changed_at: Instant,
+}
+
+impl LiveSummary {
+    pub fn update(&mut self, latest_combat: &Combat, my_player: &str) {
+        if let Some(current) = &self.current {
+            if current.active_time_end == latest_combat.active_time.end {
+                return;
+            }
+        }
+
+        let combat_duration = time_range_to_duration_or_zero(&latest_combat.combat_time);
+        let seconds = combat_duration.num_milliseconds() as f64 / 1e3;
+        let group_dps = if seconds > 0.0 {
+            latest_combat.total_damage_out.all / seconds
+        } else {
+            0.0
+        };
+
+        let player = latest_combat
+            .players
+            .values()
+            .find(|p| {
+                is_my_player(
+                    p.damage_out.name().get(&latest_combat.name_manager),
+                    my_player,
+                )
+            })
+            .or_else(|| {
+                latest_combat
+                    .players
+                    .values()
+                    .max_by(|p1, p2| p1.damage_out.dps.all.total_cmp(&p2.damage_out.dps.all))
+            });
+
+        let mut number_formatter = NumberFormatter::new();
+        let player_text = match player {
+            Some(p) => format!(
+                "{}: {} DPS | ",
+                Self::display_name(p, latest_combat),
+                number_formatter.format_with_automated_suffixes(p.damage_out.dps.all)
+            ),
+            None => String::new(),
+        };
+        self.current = Some(CurrentCombat {
+            text: format!(
+                "{}Group: {} DPS | {}",
+                player_text,
+                number_formatter.format_with_automated_suffixes(group_dps),
+                format_duration(combat_duration)
+            ),
+            active_time_end: latest_combat.active_time.end,
+            changed_at: Instant::now(),
+        });
+    }
+
+    /// the character name, unless another player has the same one
+    fn display_name(player: &Player, combat: &Combat) -> String {
+        let name = player.damage_out.name().get(&combat.name_manager);
+        if combat.ambiguous_players.contains(&player.damage_out.name()) {
+            return name.to_string();
+        }
+        character_name(name).to_string()
+    }
+
+    /// returns true if the summary got clicked
+    pub fn show(&mut self, ui: &mut Ui) -> bool {
+        let current = match &self.current {
+            Some(c) => c,
+            None => return false,
+        };
+        let elapsed = current.changed_at.elapsed();
+        if elapsed >= STALE_AFTER {
+            self.current = None;
+            return false;
+        }
+        ui.ctx().request_repaint_after(STALE_AFTER - elapsed);
+
+        ui.add(Label::new(&current.text).sense(Sense::click()))
+            .on_hover_text("latest combat, click to show its summary")
+            .clicked()
+    }
+}
+
+/// `my_player` can either be the full name (`name@handle`) or only the handle (`@handle`)
+pub fn is_my_player(full_name: &str, my_player: &str) -> bool {
+    let my_player = my_player.trim();
+    if my_player.is_empty() {
+        return false;
+    }
+    if my_player.starts_with('@') {
+        return full_name.ends_with(my_player);
+    }
+    full_name == my_player
+}
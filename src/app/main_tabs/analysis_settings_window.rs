@@ -0,0 +1,174 @@
+use eframe::egui::*;
+
+use crate::analyzer::settings::*;
+
+/// read-only view of the effective analysis rules, to compare them with the ones of someone else
+#[derive(Default)]
+pub struct AnalysisSettingsWindow {
+    is_open: bool,
+}
+
+impl AnalysisSettingsWindow {
+    pub fn open(&mut self) {
+        self.is_open = true;
+    }
+
+    /// `combat_digest` is the digest of the settings, which produced the shown combat
+    pub fn show(&mut self, settings: &AnalysisSettings, combat_digest: &str, ctx: &Context) {
+        Window::new("Effective Analysis Settings")
+            .open(&mut self.is_open)
+            .scroll2(true)
+            .default_size([500.0, 400.0])
+            .show(ctx, |ui| {
+                let digest = settings.digest();
+                ui.label(format!("Digest: {}", digest));
+                if digest != combat_digest {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "The settings changed since the combat got analyzed with {}",
+                            combat_digest
+                        ),
+                    );
+                }
+                ui.separator();
+
+                Self::show_general(settings, ui);
+                Self::show_rules(
+                    "Indirect Source Grouping Reversal Rules",
+                    settings
+                        .indirect_source_grouping_revers_rules
+                        .iter()
+                        .filter(|r| r.enabled)
+                        .map(rule_description),
+                    ui,
+                );
+                Self::show_rules(
+                    "Custom Grouping Rules",
+                    settings
+                        .custom_group_rules
+                        .iter()
+                        .filter(|g| g.enabled)
+                        .map(group_description),
+                    ui,
+                );
+                Self::show_rules(
+                    "Damage Out Exclusion Rules",
+                    settings
+                        .damage_out_exclusion_rules
+                        .iter()
+                        .filter(|r| r.enabled)
+                        .map(rule_description),
+                    ui,
+                );
+                Self::show_rules(
+                    "Combat Name Detection Rules",
+                    settings
+                        .combat_name_rules
+                        .iter()
+                        .filter(|r| r.name_rule.enabled)
+                        .map(|r| group_description(&r.name_rule)),
+                    ui,
+                );
+                Self::show_rules(
+                    "Player Merges",
+                    settings
+                        .player_merges
+                        .iter()
+                        .map(|m| format!("{} → {}", m.old_name, m.new_name)),
+                    ui,
+                );
+            });
+    }
+
+    fn show_general(settings: &AnalysisSettings, ui: &mut Ui) {
+        Grid::new("effective analysis settings")
+            .num_columns(2)
+            .striped(true)
+            .show(ui, |ui| {
+                let mut row = |name: &str, value: String| {
+                    ui.label(name);
+                    ui.label(value);
+                    ui.end_row();
+                };
+                row(
+                    "Combat Separation Time",
+                    format!("{} s", settings.combat_separation_time_seconds),
+                );
+                row("DPS Denominator", settings.dps_denominator.display().into());
+                row(
+                    "Outgoing Damage Grouping",
+                    settings.damage_out_grouping.display().into(),
+                );
+                row(
+                    "Outgoing Heal Grouping",
+                    settings.heal_out_grouping.display().into(),
+                );
+                row("Uptime Gap", format!("{} s", settings.uptime_gap_seconds));
+                row(
+                    "Combat Start Damage Threshold",
+                    format!(
+                        "{} within {} s",
+                        settings.combat_start_damage_threshold,
+                        settings.combat_start_window_seconds
+                    ),
+                );
+                row("Anomaly Threshold", settings.anomaly_threshold.to_string());
+                row("Exclude Anomalies", settings.exclude_anomalies.to_string());
+                row(
+                    "Exclude from Incoming",
+                    settings.exclude_from_incoming.to_string(),
+                );
+                row(
+                    "Separate Pet Instances",
+                    settings.separate_pet_instances.to_string(),
+                );
+                row(
+                    "Detect Ally Shield Heals",
+                    settings.detect_ally_shield_heals.to_string(),
+                );
+                row(
+                    "Drop Duplicate Records",
+                    settings.drop_duplicate_records.to_string(),
+                );
+            });
+        ui.add_space(5.0);
+    }
+
+    fn show_rules(title: &str, rules: impl Iterator<Item = String>, ui: &mut Ui) {
+        ui.strong(title);
+        let mut rules = rules.peekable();
+        if rules.peek().is_none() {
+            ui.weak("none");
+        }
+        for rule in rules {
+            ui.label(rule);
+        }
+        ui.add_space(5.0);
+    }
+}
+
+fn rule_description(rule: &MatchRule) -> String {
+    format!(
+        "{} {} \"{}\"{}",
+        rule.aspect.display(),
+        rule.method.display().to_lowercase(),
+        rule.expression,
+        if rule.ignore_case {
+            " ignoring case"
+        } else {
+            ""
+        }
+    )
+}
+
+fn group_description(group: &RulesGroup) -> String {
+    let rules = group
+        .rules
+        .iter()
+        .filter(|r| r.enabled)
+        .map(rule_description)
+        .collect::<Vec<_>>()
+        .join(" or ");
+    format!("{}: {}", group.name, rules)
+}
@@ -0,0 +1,73 @@
+use eframe::egui::*;
+
+use crate::{app::combat_notes::Bookmark, custom_widgets::popup_button::PopupButton};
+
+use super::AppState;
+
+/// bookmarks of the combat with the `identity`
+pub fn bookmarks(state: &AppState, identity: Option<&str>) -> Vec<Bookmark> {
+    identity
+        .and_then(|i| state.combat_notes.get(i))
+        .map(|n| n.bookmarks.clone())
+        .unwrap_or_default()
+}
+
+pub fn add_bookmark(state: &mut AppState, identity: Option<&str>, time_seconds: f64) {
+    let Some(identity) = identity else {
+        return;
+    };
+    state
+        .combat_notes
+        .get_mut(identity)
+        .add_bookmark(time_seconds);
+    state.combat_notes.save();
+}
+
+/// button with a popup to edit the bookmarks of the combat with the `identity`
+pub fn show_bookmarks_button(state: &mut AppState, identity: Option<&str>, ui: &mut Ui) {
+    let Some(identity) = identity else {
+        return;
+    };
+    let count = state
+        .combat_notes
+        .get(identity)
+        .map(|n| n.bookmarks.len())
+        .unwrap_or(0);
+
+    PopupButton::new(format!("🔖 Bookmarks ({})", count)).show(ui, |ui| {
+        ui.label("Right-click a diagram to add a bookmark at that time.");
+        let note = state.combat_notes.get_mut(identity);
+        let mut changed = false;
+        let mut remove = None;
+        for (i, bookmark) in note.bookmarks.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                changed |= ui
+                    .add(
+                        DragValue::new(&mut bookmark.time_seconds)
+                            .speed(0.1)
+                            .clamp_range(0.0..=f64::MAX)
+                            .suffix(" s"),
+                    )
+                    .drag_stopped();
+                changed |= TextEdit::singleline(&mut bookmark.label)
+                    .desired_width(160.0)
+                    .show(ui)
+                    .response
+                    .lost_focus();
+                if ui.button("❌").on_hover_text("remove bookmark").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+
+        if let Some(remove) = remove {
+            note.bookmarks.remove(remove);
+            changed = true;
+        }
+
+        if changed {
+            note.sort_bookmarks();
+            state.combat_notes.save();
+        }
+    });
+}
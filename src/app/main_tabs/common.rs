@@ -3,11 +3,13 @@ use eframe::egui::*;
 
 use crate::{
     analyzer::*,
+    app::{main_tabs::diagrams::GraphSampling, settings::VisualsTab},
     custom_widgets::{slider_text_edit::SliderTextEdit, table::*},
     helpers::{format_duration, number_formatting::NumberFormatter},
 };
 
 pub const ROW_HEIGHT: f32 = 25.0;
+pub const STACKED_ROW_HEIGHT: f32 = 38.0;
 pub const HEADER_HEIGHT: f32 = 15.0;
 
 #[derive(Default)]
@@ -73,7 +75,33 @@ impl ShieldAndHullTextValue {
     }
 
     pub fn show(&self, row: &mut TableRow) {
-        let response = self.all.show(row);
+        let Some(text) = &self.all.text else {
+            row.cell(|_| {});
+            return;
+        };
+
+        let response = row.cell_with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if !VisualsTab::stacked_shield_hull_values(ui.ctx()) {
+                ui.label(text);
+                return;
+            }
+
+            ui.with_layout(Layout::top_down(Align::Max), |ui| {
+                ui.spacing_mut().item_spacing.y = 0.0;
+                ui.add_space(2.0);
+                ui.label(text);
+                ui.label(
+                    RichText::new(format!("{}/{}", self.shield, self.hull))
+                        .small()
+                        .weak(),
+                );
+            });
+        });
+        show_shield_hull_values_tool_tip(response, &self.shield, &self.hull);
+    }
+
+    pub fn show_percentage(&self, row: &mut TableRow) {
+        let response = self.all.show_percentage(row);
         if let Some(response) = response {
             show_shield_hull_values_tool_tip(response, &self.shield, &self.hull);
         }
@@ -114,6 +142,28 @@ impl TextValue {
         row.cell(|_| {});
         None
     }
+
+    pub fn show_percentage(&self, row: &mut TableRow) -> Option<Response> {
+        let (Some(text), Some(value)) = (&self.text, self.value) else {
+            row.cell(|_| {});
+            return None;
+        };
+
+        let response = row.cell_with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if VisualsTab::percentage_bars(ui.ctx()) {
+                let rect = ui.max_rect();
+                let width = rect.width() * (value / 100.0).clamp(0.0, 1.0) as f32;
+                let color = ui.visuals().selection.bg_fill.gamma_multiply(0.35);
+                ui.painter().rect_filled(
+                    Rect::from_min_size(rect.min, vec2(width, rect.height())),
+                    0.0,
+                    color,
+                );
+            }
+            ui.label(text);
+        });
+        Some(response)
+    }
 }
 
 impl TextCount {
@@ -158,7 +208,16 @@ impl TextDuration {
     }
 }
 
-fn show_value_text(row: &mut TableRow, value_text: &str) -> Response {
+/// row height for tables showing [`ShieldAndHullTextValue`]s, which get taller when the split is stacked below the value
+pub fn row_height(ctx: &Context) -> f32 {
+    if VisualsTab::stacked_shield_hull_values(ctx) {
+        STACKED_ROW_HEIGHT
+    } else {
+        ROW_HEIGHT
+    }
+}
+
+pub fn show_value_text(row: &mut TableRow, value_text: &str) -> Response {
     row.cell_with_layout(Layout::right_to_left(Align::Center), |ui| {
         ui.label(value_text);
     })
@@ -199,9 +258,9 @@ pub fn show_time_slice_setting(time_slice: &mut f64, ui: &mut Ui) -> bool {
     .inner
 }
 
-pub fn show_time_filter_setting(filter: &mut f64, ui: &mut Ui) -> bool {
+pub fn show_graph_sampling_setting(sampling: &mut GraphSampling, ui: &mut Ui) -> bool {
     ui.horizontal(|ui| {
-        let changed = SliderTextEdit::new(filter, 0.4..=6.0, "filter slider")
+        let mut changed = SliderTextEdit::new(&mut sampling.filter, 0.4..=6.0, "filter slider")
             .clamp_min(0.1)
             .clamp_max(120.0)
             .desired_text_edit_width(30.0)
@@ -210,6 +269,36 @@ pub fn show_time_filter_setting(filter: &mut f64, ui: &mut Ui) -> bool {
             .show(ui)
             .changed();
         ui.label("Gauss Filter Standard Deviation (how much to smooth the graph)");
+        ui.menu_button("Advanced", |ui| {
+            changed |= ui
+                .checkbox(
+                    &mut sampling.at_value_times,
+                    "Sample at the Times of the Values",
+                )
+                .on_hover_text("instead of on a uniform grid")
+                .changed();
+            ui.add_enabled_ui(!sampling.at_value_times, |ui| {
+                ui.horizontal(|ui| {
+                    changed |= SliderTextEdit::new(
+                        &mut sampling.samples_per_second,
+                        1.0..=50.0,
+                        "samples per second slider",
+                    )
+                    .clamp_min(0.1)
+                    .clamp_max(1000.0)
+                    .desired_text_edit_width(30.0)
+                    .display_precision(4)
+                    .step_by(0.1)
+                    .show(ui)
+                    .changed();
+                    ui.label("Samples per Second");
+                });
+            });
+            if ui.button("Reset").clicked() {
+                *sampling = Default::default();
+                changed = true;
+            }
+        });
         changed
     })
     .inner
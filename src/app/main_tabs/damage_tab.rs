@@ -1,65 +1,169 @@
+use chrono::NaiveDateTime;
 use eframe::egui::*;
 
-use crate::{analyzer::*, custom_widgets::splitter::Splitter};
+use crate::{
+    analyzer::{settings::DamageOutGrouping, *},
+    app::settings::{RuleRequest, VisualsTab},
+    custom_widgets::splitter::Splitter,
+};
 
-use super::{common::*, diagrams::*, tables::*};
+use super::{bookmarks::*, common::*, diagrams::*, tables::*, AppState};
 
 pub struct DamageTab {
     table: DamageTable,
+    quick_filter: QuickFilter,
     dmg_main_diagrams: DamageDiagrams,
     dmg_selection_diagrams: Option<DamageDiagrams>,
     damage_group: for<'a> fn(&'a Player) -> &'a DamageGroup,
-    dps_filter: f64,
+    dps_sampling: GraphSampling,
     diagram_time_slice: f64,
     active_diagram: ActiveDamageDiagram,
+    is_damage_out: bool,
+    identity: Option<String>,
+    combat_start: Option<NaiveDateTime>,
 }
 
 impl DamageTab {
-    pub fn empty(damage_group: fn(&Player) -> &DamageGroup) -> Self {
+    pub fn empty(damage_group: fn(&Player) -> &DamageGroup, is_damage_out: bool) -> Self {
         Self {
             table: DamageTable::empty(),
+            quick_filter: Default::default(),
             dmg_main_diagrams: DamageDiagrams::empty(),
             damage_group: damage_group,
-            dps_filter: 0.4,
+            dps_sampling: Default::default(),
             diagram_time_slice: 1.0,
             dmg_selection_diagrams: None,
             active_diagram: ActiveDamageDiagram::Damage,
+            is_damage_out,
+            identity: None,
+            combat_start: None,
         }
     }
 
-    pub fn update(&mut self, combat: &Combat) {
-        self.table = DamageTable::new(combat, self.damage_group);
+    pub fn update(&mut self, combat: &Combat, is_refresh: bool) {
+        self.identity = Some(combat.identity());
+        self.combat_start = Some(combat.active_time.start);
+        let previous_table =
+            std::mem::replace(&mut self.table, DamageTable::new(combat, self.damage_group));
+        if is_refresh {
+            self.table.mark_rows_with_increased_damage(&previous_table);
+        }
+        if self.is_damage_out {
+            self.table.show_dps_denominator(combat.dps_denominator);
+            self.table.show_drain_out_columns();
+        } else {
+            self.table.show_drain_taken_columns();
+            self.table.show_avoided_columns();
+        }
+        self.table.mark_weapons(&self.quick_filter);
+        if self.quick_filter.is_active() {
+            self.table.apply_quick_filter(&self.quick_filter);
+        }
         self.dmg_main_diagrams = DamageDiagrams::from_damage_groups(
             combat.players.values().map(self.damage_group),
             combat,
-            self.dps_filter,
+            self.dps_sampling,
             self.diagram_time_slice,
         );
         self.dmg_selection_diagrams = None;
     }
 
-    pub fn show(&mut self, ui: &mut Ui) {
+    /// selects the row of the `player` and shows the diagrams of the parts below it
+    pub fn select_player(&mut self, player: &str) {
+        self.table.select_player(player, |p| {
+            Self::process_diagram_change(
+                &mut self.dmg_selection_diagrams,
+                p,
+                self.dps_sampling,
+                self.diagram_time_slice,
+            );
+        });
+    }
+
+    /// returns the rule requested through the context menu of a row
+    pub fn show(&mut self, state: &mut AppState, ui: &mut Ui) -> Option<RuleRequest> {
+        if self.is_damage_out {
+            Self::show_grouping_setting(state, ui);
+        }
+
         Splitter::horizontal()
             .initial_ratio(0.6)
             .ratio_bounds(0.1..=0.9)
             .show(ui, |top_ui, bottom_ui| {
-                self.table.show(top_ui, |p| {
+                let weapon_names_changed = self
+                    .quick_filter
+                    .update_weapon_names(&state.settings.quick_filters.weapon_names);
+                self.table
+                    .show_combat_percentage_columns(VisualsTab::combat_percentage_columns(
+                        top_ui.ctx(),
+                    ));
+                if self.is_damage_out {
+                    self.table
+                        .show_hit_interval_columns(VisualsTab::hit_interval_column(top_ui.ctx()));
+                }
+                self.table
+                    .show_activity_columns(VisualsTab::activity_columns(top_ui.ctx()));
+                if weapon_names_changed {
+                    self.table.mark_weapons(&self.quick_filter);
+                }
+                if self.quick_filter.show(top_ui) || weapon_names_changed {
+                    self.table.apply_quick_filter(&self.quick_filter);
+                }
+
+                let table_key = if self.is_damage_out {
+                    "damage out"
+                } else {
+                    "damage in"
+                };
+                let columns_config = state.settings.tables.columns_mut(table_key);
+                let columns_changed = self.table.show(top_ui, columns_config, |p| {
                     Self::process_diagram_change(
                         &mut self.dmg_selection_diagrams,
                         p,
-                        self.dps_filter,
+                        self.dps_sampling,
                         self.diagram_time_slice,
                     );
                 });
+                if columns_changed {
+                    state.settings.save();
+                }
 
-                self.show_diagrams(bottom_ui);
+                self.show_diagrams(state, bottom_ui);
             });
+        self.table.take_rule_request()
+    }
+
+    fn show_grouping_setting(state: &mut AppState, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Group by");
+            let mut changed = false;
+            for grouping in [
+                DamageOutGrouping::AbilityThenTarget,
+                DamageOutGrouping::TargetThenAbility,
+            ] {
+                changed |= ui
+                    .selectable_value(
+                        &mut state.settings.analysis.damage_out_grouping,
+                        grouping,
+                        grouping.display(),
+                    )
+                    .changed();
+            }
+
+            if changed {
+                state
+                    .analysis_handler
+                    .set_settings(state.settings.analysis.clone());
+                state.analysis_handler.refresh();
+                state.settings.save();
+            }
+        });
     }
 
     fn process_diagram_change(
         diagram: &mut Option<DamageDiagrams>,
         selection: TableSelectionEvent<DamageTablePartData>,
-        dps_filter: f64,
+        dps_sampling: GraphSampling,
         damage_time_slice: f64,
     ) {
         match selection {
@@ -67,14 +171,14 @@ impl DamageTab {
             TableSelectionEvent::Group(part) => {
                 *diagram = Some(Self::make_sub_parts_diagram_selection(
                     part,
-                    dps_filter,
+                    dps_sampling,
                     damage_time_slice,
                 ))
             }
             TableSelectionEvent::Single(part) => {
                 *diagram = Some(Self::make_single_diagram_selection(
                     part,
-                    dps_filter,
+                    dps_sampling,
                     damage_time_slice,
                 ))
             }
@@ -82,14 +186,14 @@ impl DamageTab {
                 Some(diagram) => {
                     diagram.add_data(
                         Self::make_single_data_set(part),
-                        dps_filter,
+                        dps_sampling,
                         damage_time_slice,
                     );
                 }
                 None => {
                     *diagram = Some(Self::make_single_diagram_selection(
                         part,
-                        dps_filter,
+                        dps_sampling,
                         damage_time_slice,
                     ))
                 }
@@ -104,7 +208,7 @@ impl DamageTab {
 
     fn make_sub_parts_diagram_selection(
         part: &DamageTablePart,
-        dps_filter: f64,
+        dps_sampling: GraphSampling,
         damage_time_slice: f64,
     ) -> DamageDiagrams {
         DamageDiagrams::from_data(
@@ -116,19 +220,19 @@ impl DamageTab {
                     p.source_hits.iter(),
                 )
             }),
-            dps_filter,
+            dps_sampling,
             damage_time_slice,
         )
     }
 
     fn make_single_diagram_selection(
         part: &DamageTablePart,
-        dps_filter: f64,
+        dps_sampling: GraphSampling,
         damage_time_slice: f64,
     ) -> DamageDiagrams {
         return DamageDiagrams::from_data(
             [Self::make_single_data_set(part)].into_iter(),
-            dps_filter,
+            dps_sampling,
             damage_time_slice,
         );
     }
@@ -144,13 +248,15 @@ impl DamageTab {
 
     fn update_diagrams(&mut self) {
         self.dmg_main_diagrams
-            .update(self.dps_filter, self.diagram_time_slice);
+            .update(self.dps_sampling, self.diagram_time_slice);
         if let Some(selection_plot) = &mut self.dmg_selection_diagrams {
-            selection_plot.update(self.dps_filter, self.diagram_time_slice);
+            selection_plot.update(self.dps_sampling, self.diagram_time_slice);
         }
     }
 
-    fn show_diagrams(&mut self, ui: &mut Ui) {
+    fn show_diagrams(&mut self, state: &mut AppState, ui: &mut Ui) {
+        let identity = self.identity.clone();
+        let identity = identity.as_deref();
         ui.horizontal(|ui| {
             ui.selectable_value(
                 &mut self.active_diagram,
@@ -162,28 +268,47 @@ impl DamageTab {
                 ActiveDamageDiagram::Dps,
                 ActiveDamageDiagram::Dps.display(),
             );
+            ui.selectable_value(
+                &mut self.active_diagram,
+                ActiveDamageDiagram::StackedDps,
+                ActiveDamageDiagram::StackedDps.display(),
+            );
             ui.selectable_value(
                 &mut self.active_diagram,
                 ActiveDamageDiagram::DamageResistance,
                 ActiveDamageDiagram::DamageResistance.display(),
             );
+            ui.separator();
+            show_bookmarks_button(state, identity, ui);
         });
 
         let updated_required = match self.active_diagram {
             ActiveDamageDiagram::Damage | ActiveDamageDiagram::DamageResistance => {
                 show_time_slice_setting(&mut self.diagram_time_slice, ui)
             }
-            ActiveDamageDiagram::Dps => show_time_filter_setting(&mut self.dps_filter, ui),
+            ActiveDamageDiagram::Dps | ActiveDamageDiagram::StackedDps => {
+                show_graph_sampling_setting(&mut self.dps_sampling, ui)
+            }
         };
 
         if updated_required {
             self.update_diagrams();
         }
 
-        if let Some(selection_diagrams) = &mut self.dmg_selection_diagrams {
-            selection_diagrams.show(ui, self.active_diagram);
+        let bookmarks = bookmarks(state, identity);
+        let time_axis = TimeAxis::new(
+            self.combat_start,
+            VisualsTab::absolute_time_axis(ui.ctx()),
+            VisualsTab::date_time_format(ui.ctx()),
+        );
+        let new_bookmark = if let Some(selection_diagrams) = &mut self.dmg_selection_diagrams {
+            selection_diagrams.show(ui, self.active_diagram, &bookmarks, time_axis)
         } else {
-            self.dmg_main_diagrams.show(ui, self.active_diagram);
+            self.dmg_main_diagrams
+                .show(ui, self.active_diagram, &bookmarks, time_axis)
+        };
+        if let Some(time_seconds) = new_bookmark {
+            add_bookmark(state, identity, time_seconds);
         }
     }
 }
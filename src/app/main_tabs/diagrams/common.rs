@@ -1,10 +1,15 @@
 use std::{ops::RangeInclusive, sync::Arc};
 
+use chrono::{Duration, NaiveDateTime};
 use educe::Educe;
+use eframe::egui::*;
 use egui_plot::*;
+use itertools::Itertools;
+use rustc_hash::FxHashMap;
 
 use crate::{
-    analyzer::{HealTick, Hit, SpecificHit, ValueFlags},
+    analyzer::{DateTimeFormat, HealTick, Hit, SpecificHealTick, SpecificHit, ValueFlags},
+    app::combat_notes::Bookmark,
     helpers::number_formatting::NumberFormatter,
 };
 
@@ -44,10 +49,30 @@ pub struct PreparedHitValue {
 #[derive(Clone, Copy)]
 pub struct PreparedHealValue {
     pub heal: f64,
+    pub shield_heal: f64,
+    pub hull_heal: f64,
+}
+
+/// how the points of the per second graphs are computed
+#[derive(Clone, Copy, PartialEq)]
+pub struct GraphSampling {
+    /// standard deviation of the gauss filter in seconds
+    pub filter: f64,
+    /// points per second on a uniform grid
+    pub samples_per_second: f64,
+    /// samples at the times of the values instead of on a uniform grid
+    pub at_value_times: bool,
+}
+
+/// colors of the players, which are the same in all summary diagrams of a combat
+pub struct PlayerColors {
+    colors: FxHashMap<String, Color32>,
 }
 
 pub trait PreparedValue: Clone + 'static {
     fn value(&self) -> f64;
+    /// the parts of the value, which went to the shields and to the hull
+    fn shield_and_hull(&self) -> (f64, f64);
     fn merge(&mut self, other: &Self);
 }
 
@@ -85,6 +110,44 @@ impl<T: PreparedValue> PreparedDataSet<T> {
     }
 }
 
+impl Default for GraphSampling {
+    fn default() -> Self {
+        Self {
+            filter: 0.4,
+            samples_per_second: 10.0,
+            at_value_times: false,
+        }
+    }
+}
+
+impl PlayerColors {
+    /// Assigns the colors in the order of the players' DPS, like the plot assigns its automatic colors
+    /// to the bars of the DPS chart. So that chart keeps its colors and the others match it.
+    pub fn new<'a>(players_dps: impl Iterator<Item = (&'a str, f64)>) -> Self {
+        let players = players_dps
+            .sorted_by(|(n1, dps1), (n2, dps2)| dps2.total_cmp(dps1).then_with(|| n1.cmp(n2)))
+            .map(|(n, _)| n)
+            .dedup();
+        Self {
+            colors: players
+                .enumerate()
+                .map(|(i, n)| (n.to_string(), Self::color(i)))
+                .collect(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Color32 {
+        self.colors.get(name).copied().unwrap_or(Color32::GRAY)
+    }
+
+    /// same sequence as the automatic colors of the plots
+    fn color(index: usize) -> Color32 {
+        let golden_ratio = (5.0_f32.sqrt() - 1.0) / 2.0;
+        let h = index as f32 * golden_ratio;
+        ecolor::Hsva::new(h, 0.85, 0.5, 1.0).into()
+    }
+}
+
 impl PreparedDamageDataSet {
     pub fn new<'a>(
         name: &str,
@@ -154,6 +217,10 @@ impl PreparedValue for PreparedHitValue {
         self.damage
     }
 
+    fn shield_and_hull(&self) -> (f64, f64) {
+        (self.shield_damage, self.hull_damage)
+    }
+
     fn merge(&mut self, other: &Self) {
         self.damage += other.damage;
         self.shield_damage += other.shield_damage;
@@ -166,7 +233,18 @@ impl PreparedValue for PreparedHitValue {
 impl<'a> From<&'a HealTick> for PreparedHealTick {
     fn from(tick: &'a HealTick) -> Self {
         Self {
-            value: PreparedHealValue { heal: tick.amount },
+            value: match tick.specific {
+                SpecificHealTick::Shield => PreparedHealValue {
+                    heal: tick.amount,
+                    shield_heal: tick.amount,
+                    hull_heal: 0.0,
+                },
+                SpecificHealTick::Hull => PreparedHealValue {
+                    heal: tick.amount,
+                    shield_heal: 0.0,
+                    hull_heal: tick.amount,
+                },
+            },
             time_millis: tick.time_millis,
         }
     }
@@ -177,8 +255,14 @@ impl PreparedValue for PreparedHealValue {
         self.heal
     }
 
+    fn shield_and_hull(&self) -> (f64, f64) {
+        (self.shield_heal, self.hull_heal)
+    }
+
     fn merge(&mut self, other: &Self) {
         self.heal += other.heal;
+        self.shield_heal += other.shield_heal;
+        self.hull_heal += other.hull_heal;
     }
 }
 
@@ -198,6 +282,64 @@ pub fn format_axis(mark: GridMark, _: usize, _: &RangeInclusive<f64>) -> String
     formatter.format(mark.value, 0)
 }
 
+type ElementFormatter = Box<dyn Fn(&Bar, &BarChart) -> String>;
+
+/// labels the time axis of the diagrams either with the seconds since the combat start or with the wall-clock time
+#[derive(Clone, Copy, Default)]
+pub struct TimeAxis {
+    absolute_start: Option<NaiveDateTime>,
+    format: DateTimeFormat,
+}
+
+impl TimeAxis {
+    pub fn new(
+        combat_start: Option<NaiveDateTime>,
+        absolute: bool,
+        format: DateTimeFormat,
+    ) -> Self {
+        Self {
+            absolute_start: combat_start.filter(|_| absolute),
+            format,
+        }
+    }
+
+    fn absolute_time(self, seconds: f64) -> Option<NaiveDateTime> {
+        let start = self.absolute_start?;
+        Some(start + Duration::milliseconds(seconds_to_millis(seconds.max(0.0)) as _))
+    }
+
+    pub fn axis_formatter(self) -> impl Fn(GridMark, usize, &RangeInclusive<f64>) -> String {
+        move |mark, max_len, range| match self.absolute_time(mark.value) {
+            Some(time) if mark.value >= 0.0 => self.format.format_time(time.time()),
+            _ => format_axis(mark, max_len, range),
+        }
+    }
+
+    /// the seconds since the combat start, together with the wall-clock time if enabled
+    pub fn format_time(self, seconds: f64) -> String {
+        let mut formatter = NumberFormatter::new();
+        let offset = formatter.format(seconds, 2);
+        match self.absolute_time(seconds) {
+            Some(time) => format!("{} (+{} s)", self.format.format_time(time.time()), offset),
+            None => offset,
+        }
+    }
+
+    /// appends the time of the bar to the label of the `element_formatter`
+    pub fn element_formatter(
+        self,
+        element_formatter: fn(&Bar, &BarChart) -> String,
+    ) -> ElementFormatter {
+        Box::new(move |bar, chart| {
+            format!(
+                "{}\nTime: {}",
+                element_formatter(bar, chart),
+                self.format_time(bar.argument)
+            )
+        })
+    }
+}
+
 pub fn format_element(bar: &Bar, _: &BarChart) -> String {
     let mut formatter = NumberFormatter::new();
     format!("{}\n{}", bar.name, formatter.format(bar.value, 2))
@@ -228,3 +370,57 @@ pub fn time_slices<'a, T: PreparedValue>(
 
     sliced_values
 }
+
+/// shows the plot with the `bookmarks` as labeled vertical lines.
+/// Returns the time of a new bookmark, if one was requested through the context menu of the plot.
+pub fn show_plot_with_bookmarks(
+    plot: Plot,
+    ui: &mut Ui,
+    bookmarks: &[Bookmark],
+    add_content: impl FnOnce(&mut PlotUi),
+) -> Option<f64> {
+    let color = ui.visuals().warn_fg_color;
+    let response = plot.show(ui, |p| {
+        add_content(p);
+        for bookmark in bookmarks.iter() {
+            p.vline(
+                VLine::new(bookmark.time_seconds)
+                    .color(color)
+                    .style(LineStyle::dashed_loose()),
+            );
+            // placed at 0, so that the labels don't extend the bounds of the plot
+            p.text(
+                Text::new(
+                    PlotPoint::new(bookmark.time_seconds, 0.0),
+                    RichText::new(&bookmark.label).small(),
+                )
+                .anchor(Align2::LEFT_BOTTOM)
+                .color(color),
+            );
+        }
+
+        if p.response().secondary_clicked() {
+            p.pointer_coordinate().map(|c| c.x)
+        } else {
+            None
+        }
+    });
+
+    let time_id = response.response.id.with("bookmark time");
+    if let Some(time) = response.inner {
+        ui.data_mut(|d| d.insert_temp(time_id, time));
+    }
+
+    let mut new_bookmark = None;
+    response.response.context_menu(|ui| {
+        let time = ui.data(|d| d.get_temp::<f64>(time_id));
+        if let Some(time) = time {
+            if ui.button("add bookmark here").clicked() {
+                new_bookmark = Some(time.max(0.0));
+                ui.close_menu();
+            }
+        }
+    });
+
+    new_bookmark
+}
@@ -4,7 +4,9 @@ use eframe::egui::*;
 use egui_plot::*;
 use itertools::Itertools;
 
-use crate::{analyzer::*, helpers::number_formatting::NumberFormatter};
+use crate::{
+    analyzer::*, app::combat_notes::Bookmark, helpers::number_formatting::NumberFormatter,
+};
 
 use super::common::*;
 
@@ -52,7 +54,12 @@ impl DamageResistanceChart {
         self.updated_time_slice = Some(time_slice);
     }
 
-    pub fn show(&mut self, ui: &mut Ui) {
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        bookmarks: &[Bookmark],
+        time_axis: TimeAxis,
+    ) -> Option<f64> {
         if let Some(time_slice) = self.updated_time_slice.take() {
             self.bars.iter_mut().for_each(|b| b.update(time_slice));
         }
@@ -60,7 +67,7 @@ impl DamageResistanceChart {
         let mut plot = Plot::new("damage resistance chart")
             .auto_bounds(true.into())
             .y_axis_formatter(Self::format_axis)
-            .x_axis_formatter(Self::format_axis)
+            .x_axis_formatter(time_axis.axis_formatter())
             .legend(Legend::default());
 
         if self.newly_created {
@@ -72,11 +79,11 @@ impl DamageResistanceChart {
             plot = plot.include_x(60.0);
         }
 
-        plot.show(ui, |p| {
+        show_plot_with_bookmarks(plot, ui, bookmarks, |p| {
             for bars in self.bars.iter() {
-                p.bar_chart(bars.chart());
+                p.bar_chart(bars.chart(time_axis));
             }
-        });
+        })
     }
 
     fn format_axis(mark: GridMark, _: usize, _: &RangeInclusive<f64>) -> String {
@@ -129,9 +136,9 @@ impl DamageResistanceBars {
         self.bars = bars;
     }
 
-    fn chart(&self) -> BarChart {
+    fn chart(&self, time_axis: TimeAxis) -> BarChart {
         BarChart::new(self.bars.clone())
-            .element_formatter(Box::new(Self::format_element_percentage))
+            .element_formatter(time_axis.element_formatter(Self::format_element_percentage))
             .name(&self.data.name)
     }
 
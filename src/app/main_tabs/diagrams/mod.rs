@@ -1,17 +1,22 @@
 mod common;
 mod damage_resistance_chart;
+mod share_bar;
 mod summary_chart;
 mod value_per_second_graph;
 mod values_chart;
 
+pub use common::GraphSampling;
+pub use common::PlayerColors;
 pub use common::PreparedDamageDataSet;
 pub use common::PreparedHealDataSet;
+pub use common::TimeAxis;
 use eframe::egui::Ui;
 use itertools::Itertools;
+pub use share_bar::ShareBar;
 pub use summary_chart::SummaryChart;
 pub use value_per_second_graph::ValuePerSecondGraph;
 
-use crate::analyzer::*;
+use crate::{analyzer::*, app::combat_notes::Bookmark};
 
 use self::{damage_resistance_chart::*, value_per_second_graph::*, values_chart::*};
 
@@ -30,6 +35,7 @@ pub struct HealDiagrams {
 pub enum ActiveDamageDiagram {
     Damage,
     Dps,
+    StackedDps,
     DamageResistance,
 }
 
@@ -51,7 +57,7 @@ impl DamageDiagrams {
     pub fn from_damage_groups<'a>(
         groups: impl Iterator<Item = &'a DamageGroup>,
         combat: &Combat,
-        dps_filter: f64,
+        dps_sampling: GraphSampling,
         damage_time_slice: f64,
     ) -> Self {
         let data = groups.map(|g| {
@@ -59,21 +65,24 @@ impl DamageDiagrams {
                 g.name().get(&combat.name_manager),
                 g.dps.all,
                 g.total_damage.all,
-                g.hits.get(&combat.hits_manger).iter(),
+                g.hits
+                    .get(&combat.hits_manger)
+                    .iter()
+                    .filter(|h| combat.is_shown_in_graphs(h.flags)),
             )
         });
 
-        Self::from_data(data, dps_filter, damage_time_slice)
+        Self::from_data(data, dps_sampling, damage_time_slice)
     }
 
     pub fn from_data(
         data: impl Iterator<Item = PreparedDamageDataSet>,
-        dps_filter: f64,
+        dps_sampling: GraphSampling,
         damage_time_slice: f64,
     ) -> Self {
         let data = data.collect_vec();
         Self {
-            dps_graph: DpsGraph::from_data(data.iter().cloned(), dps_filter),
+            dps_graph: DpsGraph::from_data(data.iter().cloned(), dps_sampling),
             damage_chart: DamageChart::from_data(data.iter().cloned(), damage_time_slice),
             damage_resistance_chart: DamageResistanceChart::from_data(
                 data.into_iter(),
@@ -82,8 +91,13 @@ impl DamageDiagrams {
         }
     }
 
-    pub fn add_data(&mut self, data: PreparedDamageDataSet, dps_filter: f64, time_slice: f64) {
-        self.dps_graph.add_line(data.clone(), dps_filter);
+    pub fn add_data(
+        &mut self,
+        data: PreparedDamageDataSet,
+        dps_sampling: GraphSampling,
+        time_slice: f64,
+    ) {
+        self.dps_graph.add_line(data.clone(), dps_sampling);
         self.damage_chart.add_bars(data.clone(), time_slice);
         self.damage_resistance_chart.add_bars(data, time_slice);
     }
@@ -94,17 +108,29 @@ impl DamageDiagrams {
         self.damage_resistance_chart.remove_bars(data);
     }
 
-    pub fn update(&mut self, dps_filter: f64, time_slice: f64) {
-        self.dps_graph.update(dps_filter);
+    pub fn update(&mut self, dps_sampling: GraphSampling, time_slice: f64) {
+        self.dps_graph.update(dps_sampling);
         self.damage_chart.update(time_slice);
         self.damage_resistance_chart.update(time_slice);
     }
 
-    pub fn show(&mut self, ui: &mut Ui, active_diagram: ActiveDamageDiagram) {
+    /// returns the time of a bookmark requested through a diagram
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        active_diagram: ActiveDamageDiagram,
+        bookmarks: &[Bookmark],
+        time_axis: TimeAxis,
+    ) -> Option<f64> {
         match active_diagram {
-            ActiveDamageDiagram::Damage => self.damage_chart.show(ui),
-            ActiveDamageDiagram::Dps => self.dps_graph.show(ui),
-            ActiveDamageDiagram::DamageResistance => self.damage_resistance_chart.show(ui),
+            ActiveDamageDiagram::Damage => self.damage_chart.show(ui, bookmarks, time_axis, false),
+            ActiveDamageDiagram::Dps => self.dps_graph.show(ui, bookmarks, time_axis),
+            ActiveDamageDiagram::StackedDps => {
+                self.dps_graph.show_stacked(ui, bookmarks, time_axis)
+            }
+            ActiveDamageDiagram::DamageResistance => {
+                self.damage_resistance_chart.show(ui, bookmarks, time_axis)
+            }
         }
     }
 }
@@ -120,7 +146,7 @@ impl HealDiagrams {
     pub fn from_heal_groups<'a>(
         groups: impl Iterator<Item = &'a HealGroup>,
         combat: &Combat,
-        dps_filter: f64,
+        dps_sampling: GraphSampling,
         damage_time_slice: f64,
     ) -> Self {
         let data = groups.map(|g| {
@@ -128,27 +154,35 @@ impl HealDiagrams {
                 g.name().get(&combat.name_manager),
                 g.hps.all,
                 g.total_heal.all,
-                g.ticks.get(&combat.heal_ticks_manger).iter(),
+                g.ticks
+                    .get(&combat.heal_ticks_manger)
+                    .iter()
+                    .filter(|t| combat.is_shown_in_graphs(t.flags)),
             )
         });
 
-        Self::from_data(data, dps_filter, damage_time_slice)
+        Self::from_data(data, dps_sampling, damage_time_slice)
     }
 
     pub fn from_data(
         data: impl Iterator<Item = PreparedHealDataSet>,
-        hps_filter: f64,
+        hps_sampling: GraphSampling,
         heal_time_slice: f64,
     ) -> Self {
         let data = data.collect_vec();
         Self {
-            hps_graph: HpsGraph::from_data(data.iter().cloned(), hps_filter),
+            hps_graph: HpsGraph::from_data(data.iter().cloned(), hps_sampling),
             heal_chart: HealChart::from_data(data.iter().cloned(), heal_time_slice),
         }
     }
 
-    pub fn add_data(&mut self, data: PreparedHealDataSet, hps_filter: f64, time_slice: f64) {
-        self.hps_graph.add_line(data.clone(), hps_filter);
+    pub fn add_data(
+        &mut self,
+        data: PreparedHealDataSet,
+        hps_sampling: GraphSampling,
+        time_slice: f64,
+    ) {
+        self.hps_graph.add_line(data.clone(), hps_sampling);
         self.heal_chart.add_bars(data.clone(), time_slice);
     }
 
@@ -157,15 +191,27 @@ impl HealDiagrams {
         self.heal_chart.remove_bars(data);
     }
 
-    pub fn update(&mut self, hps_filter: f64, time_slice: f64) {
-        self.hps_graph.update(hps_filter);
+    pub fn update(&mut self, hps_sampling: GraphSampling, time_slice: f64) {
+        self.hps_graph.update(hps_sampling);
         self.heal_chart.update(time_slice);
     }
 
-    pub fn show(&mut self, ui: &mut Ui, active_diagram: ActiveHealDiagram) {
+    /// returns the time of a bookmark requested through a diagram.
+    /// `split_shield_and_hull` stacks the shield and hull parts in the heal chart.
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        active_diagram: ActiveHealDiagram,
+        bookmarks: &[Bookmark],
+        time_axis: TimeAxis,
+        split_shield_and_hull: bool,
+    ) -> Option<f64> {
         match active_diagram {
-            ActiveHealDiagram::Heal => self.heal_chart.show(ui),
-            ActiveHealDiagram::Hps => self.hps_graph.show(ui),
+            ActiveHealDiagram::Heal => {
+                self.heal_chart
+                    .show(ui, bookmarks, time_axis, split_shield_and_hull)
+            }
+            ActiveHealDiagram::Hps => self.hps_graph.show(ui, bookmarks, time_axis),
         }
     }
 }
@@ -175,6 +221,7 @@ impl ActiveDamageDiagram {
         match self {
             ActiveDamageDiagram::Damage => "Damage",
             ActiveDamageDiagram::Dps => "DPS",
+            ActiveDamageDiagram::StackedDps => "Stacked DPS",
             ActiveDamageDiagram::DamageResistance => "Damage Resistance",
         }
     }
@@ -0,0 +1,114 @@
+use eframe::egui::*;
+
+use crate::helpers::number_formatting::NumberFormatter;
+
+use super::common::PlayerColors;
+
+const HEIGHT: f32 = 28.0;
+
+/// horizontal 100% stacked bar of the share each player has
+pub struct ShareBar {
+    segments: Vec<Segment>,
+}
+
+struct Segment {
+    name: String,
+    share: f64,
+    color: Color32,
+    percentage: String,
+}
+
+impl ShareBar {
+    pub fn empty() -> Self {
+        Self {
+            segments: Vec::new(),
+        }
+    }
+
+    pub fn from_data<'a>(
+        players: impl Iterator<Item = (&'a str, Option<f64>)>,
+        colors: &PlayerColors,
+    ) -> Self {
+        let mut formatter = NumberFormatter::new();
+        let mut segments: Vec<_> = players
+            .filter_map(|(name, percentage)| {
+                let percentage = percentage.filter(|p| *p > 0.0)?;
+                Some(Segment {
+                    name: name.to_string(),
+                    share: percentage,
+                    color: colors.get(name),
+                    percentage: format!("{}%", formatter.format(percentage, 1)),
+                })
+            })
+            .collect();
+        segments.sort_unstable_by(|s1, s2| s1.share.total_cmp(&s2.share).reverse());
+
+        // the percentages may not add up exactly
+        let total: f64 = segments.iter().map(|s| s.share).sum();
+        segments.iter_mut().for_each(|s| s.share /= total);
+
+        Self { segments }
+    }
+
+    /// returns the name of the player, whose segment got clicked
+    pub fn show(&self, ui: &mut Ui) -> Option<&str> {
+        let (rect, _) = ui.allocate_exact_size(vec2(ui.available_width(), HEIGHT), Sense::hover());
+        if self.segments.is_empty() {
+            ui.painter().text(
+                rect.center(),
+                Align2::CENTER_CENTER,
+                "no data",
+                TextStyle::Body.resolve(ui.style()),
+                ui.visuals().weak_text_color(),
+            );
+            return None;
+        }
+
+        let mut clicked = None;
+        let mut left = rect.left();
+        for (i, segment) in self.segments.iter().enumerate() {
+            let width = segment.share as f32 * rect.width();
+            let segment_rect =
+                Rect::from_min_size(pos2(left, rect.top()), vec2(width, rect.height()));
+            left += width;
+
+            let response = ui
+                .interact(segment_rect, ui.id().with(("share bar", i)), Sense::click())
+                .on_hover_text(format!("{}\n{}", segment.name, segment.percentage));
+            let color = if response.hovered() {
+                segment.color.gamma_multiply(1.3)
+            } else {
+                segment.color
+            };
+            ui.painter().rect_filled(segment_rect, 0.0, color);
+            Self::show_label(ui, segment, segment_rect);
+
+            if response.clicked() {
+                clicked = Some(segment.name.as_str());
+            }
+        }
+
+        clicked
+    }
+
+    fn show_label(ui: &Ui, segment: &Segment, rect: Rect) {
+        let font = TextStyle::Small.resolve(ui.style());
+        let text = format!("{} {}", segment.name, segment.percentage);
+        let galley = ui
+            .painter()
+            .layout_no_wrap(text, font.clone(), Color32::WHITE);
+        if galley.size().x + 6.0 <= rect.width() {
+            ui.painter()
+                .galley(rect.center() - galley.size() / 2.0, galley, Color32::WHITE);
+            return;
+        }
+
+        let galley = ui
+            .painter()
+            .layout_no_wrap(segment.percentage.clone(), font, Color32::WHITE);
+        if galley.size().x + 6.0 <= rect.width() {
+            ui.painter()
+                .galley(rect.center() - galley.size() / 2.0, galley, Color32::WHITE);
+        }
+    }
+}
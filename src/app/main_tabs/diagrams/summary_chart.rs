@@ -1,4 +1,4 @@
-use eframe::egui::Ui;
+use eframe::egui::{CursorIcon, Ui};
 use egui_plot::*;
 
 use super::common::*;
@@ -16,8 +16,14 @@ impl SummaryChart {
         }
     }
 
-    pub fn from_data<'a>(identifier: &str, players: impl Iterator<Item = (&'a str, f64)>) -> Self {
-        let mut players: Vec<_> = players.map(|(n, v)| Bar::new(0.0, v).name(n)).collect();
+    pub fn from_data<'a>(
+        identifier: &str,
+        players: impl Iterator<Item = (&'a str, f64)>,
+        colors: &PlayerColors,
+    ) -> Self {
+        let mut players: Vec<_> = players
+            .map(|(n, v)| Bar::new(0.0, v).name(n).fill(colors.get(n)))
+            .collect();
 
         players.sort_unstable_by(|p1, p2| p1.value.total_cmp(&p2.value).reverse());
 
@@ -31,8 +37,9 @@ impl SummaryChart {
         }
     }
 
-    pub fn show(&mut self, ui: &mut Ui) {
-        Plot::new(&self.identifier)
+    /// returns the name of the player, whose bar got clicked
+    pub fn show(&mut self, ui: &mut Ui) -> Option<String> {
+        let response = Plot::new(&self.identifier)
             .auto_bounds(true.into())
             .y_axis_formatter(|_, _, _| String::new())
             .x_axis_formatter(format_axis)
@@ -44,9 +51,26 @@ impl SummaryChart {
                     let chart = BarChart::new(vec![player.clone()])
                         .element_formatter(Box::new(format_element))
                         .name(&player.name)
+                        .color(player.fill)
                         .horizontal();
                     p.bar_chart(chart);
                 }
+
+                p.pointer_coordinate()
+                    .and_then(|c| self.player_at(c))
+                    .map(|player| (player, p.response().clicked()))
             });
+
+        let (player, clicked) = response.inner?;
+        response.response.on_hover_cursor(CursorIcon::PointingHand);
+        clicked.then(|| self.players[player].name.clone())
+    }
+
+    fn player_at(&self, coordinate: PlotPoint) -> Option<usize> {
+        self.players.iter().position(|p| {
+            (coordinate.y - p.argument).abs() <= p.bar_width / 2.0
+                && coordinate.x >= p.value.min(0.0)
+                && coordinate.x <= p.value.max(0.0)
+        })
     }
 }
@@ -4,17 +4,16 @@ use eframe::egui::*;
 use egui_plot::*;
 use itertools::Itertools;
 
-use crate::helpers::number_formatting::NumberFormatter;
+use crate::{app::combat_notes::Bookmark, helpers::number_formatting::NumberFormatter};
 
 use super::common::*;
 
-const SAMPLE_RATE: f64 = 10.0;
-
 pub struct ValuePerSecondGraph<T: PreparedValue> {
     lines: Vec<GraphLine<T>>,
     largest_point: f64,
     newly_created: bool,
-    updated_filter: Option<f64>,
+    sampling: GraphSampling,
+    updated_sampling: Option<GraphSampling>,
 }
 
 pub type DpsGraph = ValuePerSecondGraph<PreparedHitValue>;
@@ -31,15 +30,19 @@ impl<T: PreparedValue> ValuePerSecondGraph<T> {
             lines: Vec::new(),
             largest_point: 100_000.0,
             newly_created: true,
-            updated_filter: None,
+            sampling: Default::default(),
+            updated_sampling: None,
         }
     }
 
-    pub fn from_data<'a>(lines: impl Iterator<Item = PreparedDataSet<T>>, filter: f64) -> Self {
+    pub fn from_data(
+        lines: impl Iterator<Item = PreparedDataSet<T>>,
+        sampling: GraphSampling,
+    ) -> Self {
         let lines: Vec<_> = lines.map(|l| GraphLine::new(l)).collect();
         let mut _self = Self {
             lines,
-            updated_filter: Some(filter),
+            updated_sampling: Some(sampling),
             ..Self::empty()
         };
         _self.compute_largest_point();
@@ -47,10 +50,10 @@ impl<T: PreparedValue> ValuePerSecondGraph<T> {
         _self
     }
 
-    pub fn add_line(&mut self, line: PreparedDataSet<T>, filter: f64) {
+    pub fn add_line(&mut self, line: PreparedDataSet<T>, sampling: GraphSampling) {
         self.lines.push(GraphLine::new(line));
         self.compute_largest_point();
-        self.update(filter);
+        self.update(sampling);
     }
 
     pub fn remove_line(&mut self, line: &str) {
@@ -59,21 +62,31 @@ impl<T: PreparedValue> ValuePerSecondGraph<T> {
         }
     }
 
-    pub fn update(&mut self, filter: f64) {
-        self.updated_filter = Some(filter);
+    pub fn update(&mut self, sampling: GraphSampling) {
+        self.updated_sampling = Some(sampling);
     }
 
-    pub fn show(&mut self, ui: &mut Ui) {
-        if let Some(filter) = self.updated_filter.take() {
-            self.lines.iter_mut().for_each(|l| l.update(filter));
+    fn apply_updated_sampling(&mut self) {
+        if let Some(sampling) = self.updated_sampling.take() {
+            self.sampling = sampling;
+            self.lines.iter_mut().for_each(|l| l.update(&sampling));
             self.compute_largest_point();
         }
+    }
+
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        bookmarks: &[Bookmark],
+        time_axis: TimeAxis,
+    ) -> Option<f64> {
+        self.apply_updated_sampling();
 
         let mut plot = Plot::new("dps graph")
             .auto_bounds(true.into())
             .y_axis_formatter(format_axis)
-            .x_axis_formatter(format_axis)
-            .label_formatter(Self::format_label)
+            .x_axis_formatter(time_axis.axis_formatter())
+            .label_formatter(move |name, point| Self::format_label(name, point, time_axis))
             .include_y(self.largest_point)
             .legend(Legend::default());
 
@@ -86,22 +99,140 @@ impl<T: PreparedValue> ValuePerSecondGraph<T> {
             plot = plot.include_x(60.0);
         }
 
-        plot.show(ui, |p| {
+        show_plot_with_bookmarks(plot, ui, bookmarks, |p| {
             for line in self.lines.iter() {
                 p.line(line.to_line());
             }
-        });
+        })
+    }
+
+    /// shows the lines stacked on top of each other, so that the top most line is the sum of all lines.
+    /// Lines hidden through the legend are left out of the stack.
+    pub fn show_stacked(
+        &mut self,
+        ui: &mut Ui,
+        bookmarks: &[Bookmark],
+        time_axis: TimeAxis,
+    ) -> Option<f64> {
+        self.apply_updated_sampling();
+
+        let plot_id = ui.make_persistent_id("stacked dps graph");
+        let hidden_lines = PlotMemory::load(ui.ctx(), plot_id)
+            .map(|m| m.hidden_items)
+            .unwrap_or_default();
+
+        let times = self.stacked_times();
+        let mut stack = vec![0.0; times.len()];
+        let lines = self
+            .lines
+            .iter()
+            .map(|line| {
+                let values = times.iter().map(|t| line.sample_at(*t));
+                let points = if hidden_lines.contains(&line.data.name) {
+                    times
+                        .iter()
+                        .copied()
+                        .zip(values)
+                        .map(|(t, v)| [t, v])
+                        .collect()
+                } else {
+                    stack.iter_mut().zip(values).for_each(|(s, v)| *s += v);
+                    times
+                        .iter()
+                        .copied()
+                        .zip(stack.iter().copied())
+                        .map(|(t, v)| [t, v])
+                        .collect()
+                };
+                Line::new(PlotPoints::new(points))
+                    .name(&line.data.name)
+                    .width(2.0)
+                    .fill(0.0)
+            })
+            .collect_vec();
+        let largest_point = stack.iter().copied().fold(0.0, f64::max);
+
+        let mut plot = Plot::new("stacked dps graph")
+            .id(plot_id)
+            .auto_bounds(true.into())
+            .y_axis_formatter(format_axis)
+            .x_axis_formatter(time_axis.axis_formatter())
+            .label_formatter(move |name, point| Self::format_stacked_label(name, point, time_axis))
+            .include_y(largest_point)
+            .legend(Legend::default());
+
+        if self.lines.is_empty() {
+            plot = plot.include_x(60.0);
+        }
+
+        show_plot_with_bookmarks(plot, ui, bookmarks, |p| {
+            for line in lines {
+                p.line(line);
+            }
+        })
+    }
+
+    /// common sample times of all lines, so that they can be stacked
+    fn stacked_times(&self) -> Vec<f64> {
+        if self.sampling.at_value_times {
+            return self
+                .lines
+                .iter()
+                .flat_map(|l| l.points.iter().map(|p| p[0]))
+                .sorted_unstable_by(f64::total_cmp)
+                .dedup()
+                .collect();
+        }
+
+        let start = self
+            .lines
+            .iter()
+            .map(|l| l.data.start_time_s)
+            .min_by(f64::total_cmp);
+        let end = self
+            .lines
+            .iter()
+            .map(|l| l.data.start_time_s + l.data.duration_s.max(1.0))
+            .max_by(f64::total_cmp);
+        let (Some(start), Some(end)) = (start, end) else {
+            return Vec::new();
+        };
+
+        let sample_rate = self.sampling.samples_per_second;
+        let points_count = ((end - start) * sample_rate).round().max(1.0) as usize + 1;
+        (0..points_count)
+            .map(|i| start + i as f64 / sample_rate)
+            .collect()
     }
 
-    pub fn format_label(name: &str, point: &PlotPoint) -> String {
+    fn format_label(name: &str, point: &PlotPoint, time_axis: TimeAxis) -> String {
         if point.x < 0.0 || point.y < 0.0 {
             return String::new();
         }
 
         let mut formatter = NumberFormatter::new();
-        let x = formatter.format(point.x, 2);
         let y = formatter.format(point.y, 2);
-        format!("{}\nDPS: {}\nTime: {}", name, y, x)
+        format!(
+            "{}\nDPS: {}\nTime: {}",
+            name,
+            y,
+            time_axis.format_time(point.x)
+        )
+    }
+
+    fn format_stacked_label(name: &str, point: &PlotPoint, time_axis: TimeAxis) -> String {
+        if point.x < 0.0 || point.y < 0.0 {
+            return String::new();
+        }
+
+        let mut formatter = NumberFormatter::new();
+        let y = formatter.format(point.y, 2);
+        format!(
+            "{}\nStacked DPS: {}\nTime: {}",
+            name,
+            y,
+            time_axis.format_time(point.x)
+        )
     }
 
     fn compute_largest_point(&mut self) {
@@ -123,16 +254,32 @@ impl<T: PreparedValue> GraphLine<T> {
         }
     }
 
-    fn update(&mut self, filter: f64) {
+    fn update(&mut self, sampling: &GraphSampling) {
+        if sampling.at_value_times {
+            self.points = self
+                .data
+                .values
+                .iter()
+                .map(|v| {
+                    let time = millis_to_seconds(v.time_millis);
+                    [
+                        time,
+                        Self::get_sample_gauss_filtered(&self.data.values, time, sampling.filter),
+                    ]
+                })
+                .collect();
+            return;
+        }
+
         let duration = self.data.duration_s.max(1.0);
-        let points_count = (duration * SAMPLE_RATE).round().max(1.0) as _;
+        let points_count = (duration * sampling.samples_per_second).round().max(1.0) as _;
         let mut points = Vec::with_capacity(points_count);
         for i in 0..points_count {
             let start_offset = i as f64 / (points_count - 1) as f64;
             let time = self.data.start_time_s + duration * start_offset;
             let point = [
                 time,
-                Self::get_sample_gauss_filtered(&self.data.values, time, filter),
+                Self::get_sample_gauss_filtered(&self.data.values, time, sampling.filter),
             ];
             points.push(point);
         }
@@ -140,6 +287,25 @@ impl<T: PreparedValue> GraphLine<T> {
         self.points = points;
     }
 
+    /// linearly interpolated value of the line at `time`, zero outside of the line
+    fn sample_at(&self, time: f64) -> f64 {
+        let index = self.points.partition_point(|p| p[0] < time);
+        let (Some(after), Some(before)) = (
+            self.points.get(index),
+            index.checked_sub(1).and_then(|i| self.points.get(i)),
+        ) else {
+            return self
+                .points
+                .get(index)
+                .filter(|p| p[0] == time)
+                .map(|p| p[1])
+                .unwrap_or(0.0);
+        };
+
+        let t = (time - before[0]) / (after[0] - before[0]);
+        before[1] + (after[1] - before[1]) * t
+    }
+
     fn get_sample_entry(points: &[PreparedPoint<T>], time_millis: u32) -> usize {
         match points.binary_search_by_key(&time_millis, |h| h.time_millis) {
             Ok(i) => i,
@@ -2,6 +2,8 @@ use eframe::egui::*;
 use egui_plot::*;
 use itertools::Itertools;
 
+use crate::app::combat_notes::Bookmark;
+
 use super::common::*;
 
 pub struct ValuesChart<T: PreparedValue> {
@@ -16,6 +18,9 @@ pub type HealChart = ValuesChart<PreparedHealValue>;
 struct Bars<T: PreparedValue> {
     data: PreparedDataSet<T>,
     bars: Vec<Bar>,
+    /// same time slices as `bars`, so they can be stacked
+    shield_bars: Vec<Bar>,
+    hull_bars: Vec<Bar>,
 }
 
 impl<T: PreparedValue> ValuesChart<T> {
@@ -54,7 +59,14 @@ impl<T: PreparedValue> ValuesChart<T> {
         self.updated_time_slice = Some(time_slice);
     }
 
-    pub fn show(&mut self, ui: &mut Ui) {
+    /// `split_shield_and_hull` shows the shield and hull parts of each bar stacked on each other
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        bookmarks: &[Bookmark],
+        time_axis: TimeAxis,
+        split_shield_and_hull: bool,
+    ) -> Option<f64> {
         if let Some(time_slice) = self.updated_time_slice.take() {
             self.bars.iter_mut().for_each(|b| b.update(time_slice));
         }
@@ -62,7 +74,7 @@ impl<T: PreparedValue> ValuesChart<T> {
         let mut plot = Plot::new("damage chart")
             .auto_bounds(true.into())
             .y_axis_formatter(format_axis)
-            .x_axis_formatter(format_axis)
+            .x_axis_formatter(time_axis.axis_formatter())
             .legend(Legend::default());
 
         if self.newly_created {
@@ -74,11 +86,18 @@ impl<T: PreparedValue> ValuesChart<T> {
             plot = plot.include_x(60.0);
         }
 
-        plot.show(ui, |p| {
+        show_plot_with_bookmarks(plot, ui, bookmarks, |p| {
             for bars in self.bars.iter() {
-                p.bar_chart(bars.chart());
+                if split_shield_and_hull {
+                    let shield = bars.shield_chart(time_axis);
+                    let hull = bars.hull_chart(time_axis).stack_on(&[&shield]);
+                    p.bar_chart(shield);
+                    p.bar_chart(hull);
+                } else {
+                    p.bar_chart(bars.chart(time_axis));
+                }
             }
-        });
+        })
     }
 
     fn sort(&mut self) {
@@ -96,27 +115,47 @@ impl<T: PreparedValue> Bars<T> {
         Self {
             data,
             bars: Vec::new(),
+            shield_bars: Vec::new(),
+            hull_bars: Vec::new(),
         }
     }
 
     fn update(&mut self, time_slice: f64) {
-        let bars = time_slices(&self.data, time_slice)
-            .filter_map(|(m, s)| {
-                let value = s.iter().map(|p| p.value()).sum();
-                if value == 0.0 {
-                    return None;
-                }
-
-                Some(Bar::new(m, value).name(&self.data.name).width(time_slice))
-            })
-            .collect();
-
-        self.bars = bars;
+        self.bars.clear();
+        self.shield_bars.clear();
+        self.hull_bars.clear();
+        for (m, s) in time_slices(&self.data, time_slice) {
+            let value = s.iter().map(|p| p.value()).sum();
+            if value == 0.0 {
+                continue;
+            }
+            let (shield, hull) = s
+                .iter()
+                .map(|p| p.shield_and_hull())
+                .fold((0.0, 0.0), |(s1, h1), (s2, h2)| (s1 + s2, h1 + h2));
+
+            let bar = |value| Bar::new(m, value).name(&self.data.name).width(time_slice);
+            self.bars.push(bar(value));
+            self.shield_bars.push(bar(shield));
+            self.hull_bars.push(bar(hull));
+        }
     }
 
-    fn chart(&self) -> BarChart {
+    fn chart(&self, time_axis: TimeAxis) -> BarChart {
         BarChart::new(self.bars.clone())
-            .element_formatter(Box::new(format_element))
+            .element_formatter(time_axis.element_formatter(format_element))
             .name(&self.data.name)
     }
+
+    fn shield_chart(&self, time_axis: TimeAxis) -> BarChart {
+        BarChart::new(self.shield_bars.clone())
+            .element_formatter(time_axis.element_formatter(format_element))
+            .name(format!("{} (Shield)", self.data.name))
+    }
+
+    fn hull_chart(&self, time_axis: TimeAxis) -> BarChart {
+        BarChart::new(self.hull_bars.clone())
+            .element_formatter(time_axis.element_formatter(format_element))
+            .name(format!("{} (Hull)", self.data.name))
+    }
 }
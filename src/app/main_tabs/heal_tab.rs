@@ -1,65 +1,204 @@
-use eframe::egui::Ui;
+use chrono::NaiveDateTime;
+use eframe::egui::{RichText, Ui};
+use itertools::Itertools;
 
-use crate::{analyzer::*, custom_widgets::splitter::Splitter};
+use crate::{
+    analyzer::{settings::HealOutGrouping, *},
+    app::{
+        live_summary::is_my_player,
+        settings::{RuleRequest, VisualsTab},
+        state::AppState,
+    },
+    custom_widgets::splitter::Splitter,
+    helpers::number_formatting::NumberFormatter,
+};
 
-use super::{common::*, diagrams::*, tables::*};
+use super::{bookmarks::*, common::*, diagrams::*, heal_ticks_window::HealTicksWindow, tables::*};
 
 pub struct HealTab {
     table: HealTable,
     main_diagrams: HealDiagrams,
     selection_diagrams: Option<HealDiagrams>,
     heal_group: fn(&Player) -> &HealGroup,
-    hps_filter: f64,
+    hps_sampling: GraphSampling,
     diagram_time_slice: f64,
     active_diagram: ActiveHealDiagram,
+    /// stacks the shield and hull heal in the heal chart
+    split_shield_and_hull: bool,
+    show_grouping_setting: bool,
+    /// `None` if the split should not be shown
+    heal_in_splits: Option<Vec<(String, HealInSplit)>>,
+    /// `None` if the ticks of the rows can not be listed
+    ticks_window: Option<HealTicksWindow>,
+    /// identifies the table in the column settings
+    table_key: &'static str,
+    identity: Option<String>,
+    combat_start: Option<NaiveDateTime>,
 }
 
 impl HealTab {
-    pub fn empty(heal_group: fn(&Player) -> &HealGroup) -> Self {
+    pub fn empty(
+        heal_group: fn(&Player) -> &HealGroup,
+        table_key: &'static str,
+        show_grouping_setting: bool,
+        show_heal_in_split: bool,
+    ) -> Self {
         Self {
             table: HealTable::empty(),
             heal_group,
             main_diagrams: HealDiagrams::empty(),
             selection_diagrams: None,
-            hps_filter: 0.4,
+            hps_sampling: Default::default(),
             diagram_time_slice: 1.0,
             active_diagram: ActiveHealDiagram::Heal,
+            split_shield_and_hull: false,
+            show_grouping_setting,
+            heal_in_splits: show_heal_in_split.then(Vec::new),
+            ticks_window: show_heal_in_split.then(Default::default),
+            table_key,
+            identity: None,
+            combat_start: None,
         }
     }
 
     pub fn update(&mut self, combat: &Combat) {
+        self.identity = Some(combat.identity());
+        self.combat_start = Some(combat.active_time.start);
         self.table = HealTable::new(combat, self.heal_group);
+        // the tab of the incoming heal shows the split by source instead
+        self.table
+            .show_target_split_columns(self.heal_in_splits.is_none());
+        if self.ticks_window.is_some() {
+            self.table.enable_details("list heal ticks");
+        }
         self.main_diagrams = HealDiagrams::from_heal_groups(
             combat.players.values().map(self.heal_group),
             combat,
-            self.hps_filter,
+            self.hps_sampling,
             self.diagram_time_slice,
         );
         self.selection_diagrams = None;
+        if let Some(splits) = &mut self.heal_in_splits {
+            *splits = combat
+                .players
+                .values()
+                .map(|p| {
+                    (
+                        p.heal_in.name().get(&combat.name_manager).to_string(),
+                        p.heal_in_split,
+                    )
+                })
+                .sorted_unstable_by(|(n1, _), (n2, _)| n1.cmp(n2))
+                .collect();
+        }
     }
 
-    pub fn show(&mut self, ui: &mut Ui) {
+    /// returns the rule requested through the context menu of a row
+    pub fn show(&mut self, state: &mut AppState, ui: &mut Ui) -> Option<RuleRequest> {
+        if self.show_grouping_setting {
+            Self::show_grouping_setting(state, ui);
+        }
+        if let Some(splits) = &self.heal_in_splits {
+            Self::show_heal_in_splits(splits, &state.settings.my_player, ui);
+        }
+
         Splitter::horizontal()
             .initial_ratio(0.6)
             .ratio_bounds(0.1..=0.9)
             .show(ui, |top_ui, bottom_ui| {
-                self.table.show(top_ui, |p| {
+                self.table
+                    .show_combat_percentage_columns(VisualsTab::combat_percentage_columns(
+                        top_ui.ctx(),
+                    ));
+                let columns_config = state.settings.tables.columns_mut(self.table_key);
+                let columns_changed = self.table.show(top_ui, columns_config, |p| {
                     Self::process_diagram_change(
                         &mut self.selection_diagrams,
                         p,
-                        self.hps_filter,
+                        self.hps_sampling,
                         self.diagram_time_slice,
                     );
                 });
+                if columns_changed {
+                    state.settings.save();
+                }
+                if let Some(ticks_window) = &mut self.ticks_window {
+                    if let Some(part) = self.table.take_details_request() {
+                        ticks_window.open(part);
+                    }
+                    ticks_window.show(top_ui.ctx());
+                }
 
-                self.show_diagrams(bottom_ui);
+                self.show_diagrams(state, bottom_ui);
             });
+        self.table.take_rule_request()
+    }
+
+    fn show_grouping_setting(state: &mut AppState, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Group by");
+            let mut changed = false;
+            for grouping in [
+                HealOutGrouping::TargetThenAbility,
+                HealOutGrouping::AbilityThenTarget,
+            ] {
+                changed |= ui
+                    .selectable_value(
+                        &mut state.settings.analysis.heal_out_grouping,
+                        grouping,
+                        grouping.display(),
+                    )
+                    .changed();
+            }
+
+            if changed {
+                state
+                    .analysis_handler
+                    .set_settings(state.settings.analysis.clone());
+                state.analysis_handler.refresh();
+                state.settings.save();
+            }
+        });
+    }
+
+    /// shows how much of the incoming healing came from the players themselves.
+    /// Only the own player is shown, if it is set and part of the combat.
+    fn show_heal_in_splits(splits: &[(String, HealInSplit)], my_player: &str, ui: &mut Ui) {
+        let show_only_my_player = splits.iter().any(|(n, _)| is_my_player(n, my_player));
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Healing from self:");
+            for (name, split) in splits
+                .iter()
+                .filter(|(n, _)| !show_only_my_player || is_my_player(n, my_player))
+            {
+                let Some(self_percentage) = split.self_percentage() else {
+                    continue;
+                };
+                let mut formatter = NumberFormatter::new();
+                let text = format!(
+                    "{}: {}% self / {}% others",
+                    name,
+                    formatter.format(self_percentage, 1),
+                    formatter.format(100.0 - self_percentage, 1),
+                );
+                let tooltip = format!(
+                    "from self: {} (shield {}, hull {})\nfrom others: {} (shield {}, hull {})",
+                    formatter.format(split.from_self.all, 2),
+                    formatter.format(split.from_self.shield, 2),
+                    formatter.format(split.from_self.hull, 2),
+                    formatter.format(split.from_others.all, 2),
+                    formatter.format(split.from_others.shield, 2),
+                    formatter.format(split.from_others.hull, 2),
+                );
+                ui.label(RichText::new(text).small()).on_hover_text(tooltip);
+            }
+        });
     }
 
     fn process_diagram_change(
         diagram: &mut Option<HealDiagrams>,
         selection: TableSelectionEvent<HealTablePartData>,
-        hps_filter: f64,
+        hps_sampling: GraphSampling,
         heal_time_slice: f64,
     ) {
         match selection {
@@ -67,14 +206,14 @@ impl HealTab {
             TableSelectionEvent::Group(part) => {
                 *diagram = Some(Self::make_sub_parts_diagram_selection(
                     part,
-                    hps_filter,
+                    hps_sampling,
                     heal_time_slice,
                 ))
             }
             TableSelectionEvent::Single(part) => {
                 *diagram = Some(Self::make_single_diagram_selection(
                     part,
-                    hps_filter,
+                    hps_sampling,
                     heal_time_slice,
                 ))
             }
@@ -82,14 +221,14 @@ impl HealTab {
                 Some(diagram) => {
                     diagram.add_data(
                         Self::make_single_data_set(part),
-                        hps_filter,
+                        hps_sampling,
                         heal_time_slice,
                     );
                 }
                 None => {
                     *diagram = Some(Self::make_single_diagram_selection(
                         part,
-                        hps_filter,
+                        hps_sampling,
                         heal_time_slice,
                     ))
                 }
@@ -104,7 +243,7 @@ impl HealTab {
 
     fn make_sub_parts_diagram_selection(
         part: &HealTablePart,
-        hps_filter: f64,
+        hps_sampling: GraphSampling,
         heal_time_slice: f64,
     ) -> HealDiagrams {
         HealDiagrams::from_data(
@@ -116,19 +255,19 @@ impl HealTab {
                     p.source_ticks.iter(),
                 )
             }),
-            hps_filter,
+            hps_sampling,
             heal_time_slice,
         )
     }
 
     fn make_single_diagram_selection(
         part: &HealTablePart,
-        hps_filter: f64,
+        hps_sampling: GraphSampling,
         heal_time_slice: f64,
     ) -> HealDiagrams {
         return HealDiagrams::from_data(
             [Self::make_single_data_set(part)].into_iter(),
-            hps_filter,
+            hps_sampling,
             heal_time_slice,
         );
     }
@@ -144,13 +283,15 @@ impl HealTab {
 
     fn update_diagrams(&mut self) {
         self.main_diagrams
-            .update(self.hps_filter, self.diagram_time_slice);
+            .update(self.hps_sampling, self.diagram_time_slice);
         if let Some(selection_plot) = &mut self.selection_diagrams {
-            selection_plot.update(self.hps_filter, self.diagram_time_slice);
+            selection_plot.update(self.hps_sampling, self.diagram_time_slice);
         }
     }
 
-    fn show_diagrams(&mut self, ui: &mut Ui) {
+    fn show_diagrams(&mut self, state: &mut AppState, ui: &mut Ui) {
+        let identity = self.identity.clone();
+        let identity = identity.as_deref();
         ui.horizontal(|ui| {
             ui.selectable_value(
                 &mut self.active_diagram,
@@ -162,21 +303,47 @@ impl HealTab {
                 ActiveHealDiagram::Hps,
                 ActiveHealDiagram::Hps.display(),
             );
+            ui.separator();
+            show_bookmarks_button(state, identity, ui);
         });
 
         let update_required = match self.active_diagram {
-            ActiveHealDiagram::Heal => show_time_slice_setting(&mut self.diagram_time_slice, ui),
-            ActiveHealDiagram::Hps => show_time_filter_setting(&mut self.hps_filter, ui),
+            ActiveHealDiagram::Heal => {
+                ui.checkbox(&mut self.split_shield_and_hull, "Split Shield and Hull");
+                show_time_slice_setting(&mut self.diagram_time_slice, ui)
+            }
+            ActiveHealDiagram::Hps => show_graph_sampling_setting(&mut self.hps_sampling, ui),
         };
 
         if update_required {
             self.update_diagrams();
         }
 
-        if let Some(selection_diagrams) = &mut self.selection_diagrams {
-            selection_diagrams.show(ui, self.active_diagram);
+        let bookmarks = bookmarks(state, identity);
+        let time_axis = TimeAxis::new(
+            self.combat_start,
+            VisualsTab::absolute_time_axis(ui.ctx()),
+            VisualsTab::date_time_format(ui.ctx()),
+        );
+        let new_bookmark = if let Some(selection_diagrams) = &mut self.selection_diagrams {
+            selection_diagrams.show(
+                ui,
+                self.active_diagram,
+                &bookmarks,
+                time_axis,
+                self.split_shield_and_hull,
+            )
         } else {
-            self.main_diagrams.show(ui, self.active_diagram);
+            self.main_diagrams.show(
+                ui,
+                self.active_diagram,
+                &bookmarks,
+                time_axis,
+                self.split_shield_and_hull,
+            )
+        };
+        if let Some(time_seconds) = new_bookmark {
+            add_bookmark(state, identity, time_seconds);
         }
     }
 }
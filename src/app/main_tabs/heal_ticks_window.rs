@@ -0,0 +1,154 @@
+use std::path::Path;
+
+use eframe::egui::*;
+use itertools::Itertools;
+use rfd::FileDialog;
+
+use crate::{
+    analyzer::*, custom_widgets::table::Table, helpers::number_formatting::NumberFormatter,
+};
+
+use super::{common::row_height, tables::HealTablePart};
+
+/// lists every heal tick of a row of the incoming healing, with the time since the combat start
+#[derive(Default)]
+pub struct HealTicksWindow {
+    title: String,
+    ticks: Vec<TickRow>,
+    total_heal: String,
+    is_open: bool,
+}
+
+struct TickRow {
+    time_seconds: f64,
+    time: String,
+    amount: f64,
+    amount_text: String,
+    kind: &'static str,
+    is_critical: bool,
+    source: String,
+}
+
+impl HealTicksWindow {
+    pub fn open(&mut self, part: &HealTablePart) {
+        let mut formatter = NumberFormatter::new();
+        self.title = format!("Heal Ticks of {}", part.name);
+        self.ticks = part
+            .ticks_with_source_path()
+            .into_iter()
+            .sorted_by_key(|(t, _)| t.time_millis)
+            .map(|(tick, source)| {
+                let time_seconds = tick.time_millis as f64 / 1e3;
+                TickRow {
+                    time_seconds,
+                    time: formatter.format(time_seconds, 2),
+                    amount: tick.amount,
+                    amount_text: formatter.format(tick.amount, 2),
+                    kind: match tick.specific {
+                        SpecificHealTick::Shield => "Shield",
+                        SpecificHealTick::Hull => "Hull",
+                    },
+                    is_critical: tick.flags.contains(ValueFlags::CRITICAL),
+                    source,
+                }
+            })
+            .collect();
+        self.total_heal = formatter.format(self.ticks.iter().map(|t| t.amount).sum(), 2);
+        self.is_open = true;
+    }
+
+    pub fn show(&mut self, ctx: &Context) {
+        let mut is_open = self.is_open;
+        Window::new(&self.title)
+            .id(Id::new("heal ticks window"))
+            .open(&mut is_open)
+            .default_size([700.0, 500.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(format!(
+                        "{} ticks, {} total heal",
+                        self.ticks.len(),
+                        self.total_heal
+                    ));
+                    if ui.button("Export CSV").clicked() {
+                        self.export_csv();
+                    }
+                });
+                ui.separator();
+                self.show_table(ui);
+            });
+        self.is_open = is_open;
+    }
+
+    fn show_table(&self, ui: &mut Ui) {
+        let row_height = row_height(ui.ctx());
+        Table::new(ui)
+            .id("heal ticks table")
+            .min_scroll_height(300.0)
+            .max_scroll_height(f32::MAX)
+            .cell_spacing(10.0)
+            .header(15.0, |r| {
+                for header in ["Time (s)", "Heal", "Kind", "Source"] {
+                    r.cell(|ui| {
+                        ui.label(header);
+                    });
+                }
+            })
+            .body(row_height, |t| {
+                for tick in self.ticks.iter() {
+                    t.row(|r| {
+                        r.cell(|ui| {
+                            ui.label(&tick.time);
+                        });
+                        r.cell(|ui| {
+                            if tick.is_critical {
+                                ui.label(RichText::new(&tick.amount_text).strong())
+                                    .on_hover_text("critical");
+                            } else {
+                                ui.label(&tick.amount_text);
+                            }
+                        });
+                        r.cell(|ui| {
+                            ui.label(tick.kind);
+                        });
+                        r.cell(|ui| {
+                            ui.label(&tick.source);
+                        });
+                    });
+                }
+            });
+    }
+
+    fn export_csv(&self) {
+        let Some(file) = FileDialog::new()
+            .set_title("Export Heal Ticks")
+            .add_filter("csv", &["csv"])
+            .set_file_name("heal_ticks.csv")
+            .save_file()
+        else {
+            return;
+        };
+
+        if let Err(err) = self.write_csv(&file) {
+            log::error!("failed to export heal ticks to {:?}: {}", file, err);
+        }
+    }
+
+    fn write_csv(&self, file: &Path) -> std::io::Result<()> {
+        let rows = self.ticks.iter().map(|t| {
+            format!(
+                "{},{},{},{},\"{}\"",
+                t.time_seconds,
+                t.amount,
+                t.kind,
+                t.is_critical,
+                t.source.replace('"', "\"\"")
+            )
+        });
+        let csv: String = std::iter::once("Time (s),Heal,Kind,Critical,Source".to_string())
+            .chain(rows)
+            .map(|l| l + "\n")
+            .collect();
+        std::fs::write(file, csv)
+    }
+}
@@ -1,18 +1,29 @@
+use std::{ops::Range, sync::Arc};
+
 use eframe::egui::*;
 
-use crate::analyzer::Combat;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::analyzer::{Combat, DateTimeFormat};
+
+use super::{settings::RuleRequest, state::AppState};
 
 use self::{damage_tab::DamageTab, heal_tab::HealTab, summary_tab::SummaryTab};
 
+mod analysis_settings_window;
+mod bookmarks;
 mod common;
 mod damage_tab;
 mod diagrams;
 mod heal_tab;
+mod heal_ticks_window;
+mod summary_image;
 mod summary_tab;
 mod tables;
 
 pub struct MainTabs {
-    pub identifier: String,
+    combat_name: String,
     pub summary_tab: SummaryTab,
     pub damage_out_tab: DamageTab,
     pub damage_in_tab: DamageTab,
@@ -20,9 +31,13 @@ pub struct MainTabs {
     pub heal_in_tab: HealTab,
 
     active_tab: MainTab,
+    combat_start: Option<NaiveDateTime>,
+    active_time: Option<Range<NaiveDateTime>>,
+    /// amount of combats the shown one got merged from, 0 if it is not merged
+    merged_combats: usize,
 }
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MainTab {
     #[default]
     Summary,
@@ -32,45 +47,107 @@ pub enum MainTab {
     HealIn,
 }
 
+impl MainTab {
+    pub const ALL: [Self; 5] = [
+        Self::Summary,
+        Self::DamageOut,
+        Self::DamageIn,
+        Self::HealOut,
+        Self::HealIn,
+    ];
+
+    pub const fn display(self) -> &'static str {
+        match self {
+            MainTab::Summary => "Summary",
+            MainTab::DamageOut => "Outgoing Damage",
+            MainTab::DamageIn => "Incoming Damage",
+            MainTab::HealOut => "Outgoing Healing",
+            MainTab::HealIn => "Incoming Healing",
+        }
+    }
+}
+
 impl MainTabs {
     pub fn empty() -> Self {
         Self {
-            identifier: String::new(),
-            damage_out_tab: DamageTab::empty(|p| &p.damage_out),
-            damage_in_tab: DamageTab::empty(|p| &p.damage_in),
-            heal_out_tab: HealTab::empty(|p| &p.heal_out),
-            heal_in_tab: HealTab::empty(|p| &p.heal_in),
+            combat_name: String::new(),
+            damage_out_tab: DamageTab::empty(|p| &p.damage_out, true),
+            damage_in_tab: DamageTab::empty(|p| &p.damage_in, false),
+            heal_out_tab: HealTab::empty(|p| &p.heal_out, "heal out", true, false),
+            heal_in_tab: HealTab::empty(|p| &p.heal_in, "heal in", false, true),
             active_tab: Default::default(),
             summary_tab: SummaryTab::empty(),
+            combat_start: None,
+            active_time: None,
+            merged_combats: 0,
         }
     }
 
-    pub fn update(&mut self, combat: &Combat) {
-        self.identifier = combat.identifier();
+    pub fn update(&mut self, combat: &Arc<Combat>) {
+        // a refresh of the same combat, rather than a different one getting selected
+        let is_refresh = self.combat_start == Some(combat.active_time.start)
+            && self.merged_combats == combat.merged_combats.len();
+        self.combat_start = Some(combat.active_time.start);
+        self.merged_combats = combat.merged_combats.len();
+
+        self.combat_name = combat.name();
+        self.active_time = Some(combat.active_time.clone());
         self.summary_tab.update(combat);
-        self.damage_out_tab.update(combat);
-        self.damage_in_tab.update(combat);
+        self.damage_out_tab.update(combat, is_refresh);
+        self.damage_in_tab.update(combat, is_refresh);
         self.heal_out_tab.update(combat);
         self.heal_in_tab.update(combat);
     }
 
-    pub fn show(&mut self, ui: &mut Ui) {
-        ui.horizontal(|ui| {
-            ui.selectable_value(&mut self.active_tab, MainTab::Summary, "Summary");
+    /// identifier of the shown combat, formatted on demand so that format changes apply immediately
+    pub fn identifier(&self, format: &DateTimeFormat) -> String {
+        match &self.active_time {
+            // the end of merged combats is shifted by the time between them
+            Some(_) if self.merged_combats > 0 => {
+                format!(
+                    "{} | {} combats merged",
+                    self.combat_name, self.merged_combats
+                )
+            }
+            Some(active_time) => format.combat_identifier(&self.combat_name, active_time),
+            None => String::new(),
+        }
+    }
 
-            ui.selectable_value(&mut self.active_tab, MainTab::DamageOut, "Outgoing Damage");
-            ui.selectable_value(&mut self.active_tab, MainTab::DamageIn, "Incoming Damage");
+    pub fn select_tab(&mut self, tab: MainTab) {
+        self.active_tab = tab;
+    }
+
+    /// switches to the `tab` with the row of the `player` selected
+    fn drill_down(&mut self, tab: MainTab, player: &str) {
+        let damage_tab = match tab {
+            MainTab::DamageOut => &mut self.damage_out_tab,
+            MainTab::DamageIn => &mut self.damage_in_tab,
+            _ => return,
+        };
+        damage_tab.select_player(player);
+        self.active_tab = tab;
+    }
 
-            ui.selectable_value(&mut self.active_tab, MainTab::HealOut, "Outgoing Healing");
-            ui.selectable_value(&mut self.active_tab, MainTab::HealIn, "Incoming Healing");
+    /// returns the rule requested through the context menu of a table row
+    pub fn show(&mut self, state: &mut AppState, ui: &mut Ui) -> Option<RuleRequest> {
+        ui.horizontal(|ui| {
+            for tab in MainTab::ALL {
+                ui.selectable_value(&mut self.active_tab, tab, tab.display());
+            }
         });
 
         match self.active_tab {
-            MainTab::Summary => self.summary_tab.show(ui),
-            MainTab::DamageOut => self.damage_out_tab.show(ui),
-            MainTab::DamageIn => self.damage_in_tab.show(ui),
-            MainTab::HealOut => self.heal_out_tab.show(ui),
-            MainTab::HealIn => self.heal_in_tab.show(ui),
+            MainTab::Summary => {
+                if let Some((tab, player)) = self.summary_tab.show(state, ui) {
+                    self.drill_down(tab, &player);
+                }
+                None
+            }
+            MainTab::DamageOut => self.damage_out_tab.show(state, ui),
+            MainTab::DamageIn => self.damage_in_tab.show(state, ui),
+            MainTab::HealOut => self.heal_out_tab.show(state, ui),
+            MainTab::HealIn => self.heal_in_tab.show(state, ui),
         }
     }
 }
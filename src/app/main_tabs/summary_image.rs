@@ -0,0 +1,239 @@
+use std::{
+    borrow::Cow,
+    fs::File,
+    io::BufWriter,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use eframe::egui::*;
+use rfd::FileDialog;
+
+use crate::app::settings::TableColumn;
+
+use super::{common::row_height, tables::SummaryTable};
+
+/// minimal width of the image, so that it looks the same regardless of the window size
+const IMAGE_WIDTH: f32 = 1000.0;
+/// space taken by the title, header and margins of a page
+const PAGE_OVERHEAD: f32 = 120.0;
+/// frames a page is shown before taking the screenshot, so that its size settled
+const SETTLE_FRAMES: u32 = 3;
+
+/// saves the summary table as PNG or copies it to the clipboard,
+/// by taking a screenshot of it shown in front of everything else.
+/// Long player lists are split into pages, which are saved as separate images or copied as one.
+#[derive(Default)]
+pub struct SummaryImageExport {
+    capture: Option<Capture>,
+    /// kept, as on some platforms the copied image is only available while the clipboard exists
+    clipboard: Option<arboard::Clipboard>,
+}
+
+struct Capture {
+    target: CaptureTarget,
+    page: usize,
+    rows_per_page: usize,
+    frames: u32,
+    rect: Rect,
+    screenshot_requested: bool,
+}
+
+enum CaptureTarget {
+    File(PathBuf),
+    /// the pages captured so far
+    Clipboard(Vec<ColorImage>),
+}
+
+impl SummaryImageExport {
+    pub fn show_buttons(&mut self, file_identifier: &str, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(self.capture.is_none(), |ui| {
+                if ui
+                    .button("🖼 Save as Image")
+                    .on_hover_text(
+                        "Saves the table as PNG. Long player lists are split into multiple images.",
+                    )
+                    .clicked()
+                {
+                    if let Some(file) = FileDialog::new()
+                        .set_title("Save Summary Image")
+                        .add_filter("png", &["png"])
+                        .set_file_name(format!("{}.png", file_identifier))
+                        .save_file()
+                    {
+                        self.start_capture(CaptureTarget::File(file), ui.ctx());
+                    }
+                }
+
+                if ui
+                    .button("📋 Copy as Image")
+                    .on_hover_text(
+                        "Copies the table as image. Long player lists become one tall image.",
+                    )
+                    .clicked()
+                {
+                    self.start_capture(CaptureTarget::Clipboard(Vec::new()), ui.ctx());
+                }
+            });
+        });
+    }
+
+    fn start_capture(&mut self, target: CaptureTarget, ctx: &Context) {
+        let rows_per_page =
+            ((ctx.screen_rect().height() - PAGE_OVERHEAD) / row_height(ctx)).floor() as usize;
+        self.capture = Some(Capture {
+            target,
+            page: 0,
+            rows_per_page: rows_per_page.max(1),
+            frames: 0,
+            rect: Rect::NOTHING,
+            screenshot_requested: false,
+        });
+    }
+
+    /// shows the current page while capturing and saves it once the screenshot arrived
+    pub fn show_capture(
+        &mut self,
+        title: &str,
+        table: &SummaryTable,
+        columns_config: &[TableColumn],
+        ctx: &Context,
+    ) {
+        let Some(capture) = &mut self.capture else {
+            return;
+        };
+        let pages = table.players_count().div_ceil(capture.rows_per_page).max(1);
+
+        if let Some(image) = Self::screenshot(ctx).filter(|_| capture.screenshot_requested) {
+            let image = image.region(&capture.rect, Some(ctx.pixels_per_point()));
+            match &mut capture.target {
+                CaptureTarget::File(file) => {
+                    let file = Self::page_file(file, capture.page);
+                    if let Err(err) = Self::save(&image, &file) {
+                        log::error!("failed to save summary image {:?}: {}", file, err);
+                    }
+                }
+                CaptureTarget::Clipboard(images) => images.push(image),
+            }
+
+            capture.page += 1;
+            capture.frames = 0;
+            capture.screenshot_requested = false;
+            if capture.page >= pages {
+                if let Some(Capture {
+                    target: CaptureTarget::Clipboard(images),
+                    ..
+                }) = self.capture.take()
+                {
+                    let image = Self::stack(&images);
+                    if let Err(err) = self.copy(&image) {
+                        log::error!("failed to copy the summary image: {}", err);
+                    }
+                }
+                return;
+            }
+        }
+
+        let rows = capture.page * capture.rows_per_page
+            ..((capture.page + 1) * capture.rows_per_page).min(table.players_count());
+        let response = Area::new(Id::new("summary image"))
+            .order(Order::Foreground)
+            .fixed_pos(Pos2::ZERO)
+            .show(ctx, |ui| {
+                Self::show_page(title, table, columns_config, rows, capture.page, pages, ui);
+            });
+
+        capture.rect = response.response.rect.intersect(ctx.screen_rect());
+        capture.frames += 1;
+        if capture.frames >= SETTLE_FRAMES && !capture.screenshot_requested {
+            ctx.send_viewport_cmd(ViewportCommand::Screenshot);
+            capture.screenshot_requested = true;
+        }
+        ctx.request_repaint();
+    }
+
+    fn show_page(
+        title: &str,
+        table: &SummaryTable,
+        columns_config: &[TableColumn],
+        rows: Range<usize>,
+        page: usize,
+        pages: usize,
+        ui: &mut Ui,
+    ) {
+        Frame::none()
+            .fill(ui.visuals().panel_fill)
+            .inner_margin(10.0)
+            .show(ui, |ui| {
+                ui.set_min_width(IMAGE_WIDTH);
+                ui.horizontal(|ui| {
+                    ui.heading(title);
+                    if pages > 1 {
+                        ui.label(format!("({}/{})", page + 1, pages));
+                    }
+                });
+                ui.add_space(10.0);
+                table.show_rows(rows, columns_config, ui);
+            });
+    }
+
+    fn screenshot(ctx: &Context) -> Option<std::sync::Arc<ColorImage>> {
+        ctx.input(|i| {
+            i.events.iter().find_map(|e| match e {
+                Event::Screenshot { image, .. } => Some(image.clone()),
+                _ => None,
+            })
+        })
+    }
+
+    /// the first page is saved to the chosen file, the following ones get numbered
+    fn page_file(file: &Path, page: usize) -> PathBuf {
+        if page == 0 {
+            return file.to_path_buf();
+        }
+
+        let stem = file
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        file.with_file_name(format!("{}_{}.png", stem, page + 1))
+    }
+
+    /// the `images` one below the other
+    fn stack(images: &[ColorImage]) -> ColorImage {
+        let width = images.iter().map(|i| i.width()).max().unwrap_or_default();
+        let height = images.iter().map(|i| i.height()).sum();
+        let mut stacked = ColorImage::new([width, height], Color32::TRANSPARENT);
+        let mut top = 0;
+        for image in images {
+            for y in 0..image.height() {
+                let row = &image.pixels[y * image.width()..(y + 1) * image.width()];
+                let start = (top + y) * width;
+                stacked.pixels[start..start + row.len()].copy_from_slice(row);
+            }
+            top += image.height();
+        }
+        stacked
+    }
+
+    fn copy(&mut self, image: &ColorImage) -> Result<(), arboard::Error> {
+        let clipboard = match &mut self.clipboard {
+            Some(c) => c,
+            None => self.clipboard.insert(arboard::Clipboard::new()?),
+        };
+        clipboard.set_image(arboard::ImageData {
+            width: image.width(),
+            height: image.height(),
+            bytes: Cow::Borrowed(image.as_raw()),
+        })
+    }
+
+    fn save(image: &ColorImage, file: &Path) -> Result<(), png::EncodingError> {
+        let writer = BufWriter::new(File::create(file)?);
+        let mut encoder = png::Encoder::new(writer, image.width() as _, image.height() as _);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(image.as_raw())
+    }
+}
@@ -1,16 +1,33 @@
+use std::{ops::Range, sync::Arc};
+
+use chrono::NaiveDateTime;
 use eframe::egui::*;
 
 use crate::{
     analyzer::*,
+    app::{settings::VisualsTab, summary_copy::players_under_threshold},
     custom_widgets::{splitter::Splitter, table::*},
     helpers::{number_formatting::NumberFormatter, *},
 };
 
-use super::{common::*, diagrams::SummaryChart, tables::SummaryTable};
+use super::{
+    analysis_settings_window::AnalysisSettingsWindow,
+    common::*,
+    diagrams::{PlayerColors, ShareBar, SummaryChart},
+    summary_image::SummaryImageExport,
+    tables::{DamageSourcesTable, SummaryTable},
+    AppState, MainTab,
+};
+
+/// identifies the players table in the column settings
+const SUMMARY_TABLE_KEY: &str = "summary";
 
 pub struct SummaryTab {
-    identifier: String,
+    /// formatted when shown, so that format changes apply immediately
+    active_time: Option<Range<NaiveDateTime>>,
+    identity: Option<String>,
     name: String,
+    new_tag: String,
 
     combat_duration: TextDuration,
     active_duration: TextDuration,
@@ -19,11 +36,28 @@ pub struct SummaryTab {
     total_kills: TextCount,
     total_deaths: TextCount,
     summary_table: SummaryTable,
+    damage_sources_table: DamageSourcesTable,
     summary_dps_chart: SummaryChart,
     summary_damage_out_chart: SummaryChart,
     summary_damage_in_chart: SummaryChart,
+    dropped_duplicate_records: u32,
+    /// to count the players under the threshold, which can be changed while the combat is shown
+    combat: Option<Arc<Combat>>,
+    damage_share: ShareBar,
+    heal_share: ShareBar,
 
     chart_tab: ChartTab,
+    share_tab: ShareTab,
+    image_export: SummaryImageExport,
+    settings_digest: String,
+    settings_window: AnalysisSettingsWindow,
+}
+
+#[derive(Default, Clone, Copy, PartialEq)]
+enum ShareTab {
+    #[default]
+    Damage,
+    Healing,
 }
 
 #[derive(Default, Clone, Copy, PartialEq)]
@@ -38,9 +72,12 @@ impl SummaryTab {
     pub fn empty() -> Self {
         let nothing_loaded = "<no data loaded>".to_string();
         Self {
-            identifier: nothing_loaded.clone(),
+            active_time: None,
+            identity: None,
             name: nothing_loaded,
+            new_tag: String::new(),
             summary_table: SummaryTable::empty(),
+            damage_sources_table: DamageSourcesTable::empty(),
             combat_duration: Default::default(),
             active_duration: Default::default(),
             total_damage_out: Default::default(),
@@ -50,13 +87,23 @@ impl SummaryTab {
             summary_dps_chart: SummaryChart::empty(),
             summary_damage_out_chart: SummaryChart::empty(),
             summary_damage_in_chart: SummaryChart::empty(),
+            dropped_duplicate_records: 0,
+            combat: None,
+            damage_share: ShareBar::empty(),
+            heal_share: ShareBar::empty(),
             chart_tab: Default::default(),
+            share_tab: Default::default(),
+            image_export: Default::default(),
+            settings_digest: String::new(),
+            settings_window: Default::default(),
         }
     }
 
-    pub fn update(&mut self, combat: &Combat) {
-        self.identifier = combat.identifier();
+    pub fn update(&mut self, combat: &Arc<Combat>) {
+        self.active_time = Some(combat.active_time.clone());
+        self.identity = Some(combat.identity());
         self.name = combat.name();
+        self.settings_digest = combat.settings_digest.clone();
 
         self.combat_duration =
             TextDuration::new(time_range_to_duration_or_zero(&combat.combat_time));
@@ -70,7 +117,17 @@ impl SummaryTab {
         self.total_kills = TextCount::new(combat.total_kills as _);
         self.total_deaths = TextCount::new(combat.total_deaths as _);
 
+        self.dropped_duplicate_records = combat.dropped_duplicate_records;
+        self.combat = Some(combat.clone());
+
         self.summary_table = SummaryTable::new(combat);
+        self.damage_sources_table = DamageSourcesTable::new(combat);
+        let player_colors = PlayerColors::new(combat.players.values().map(|p| {
+            (
+                p.damage_out.name().get(&combat.name_manager),
+                p.damage_out.dps.all,
+            )
+        }));
         self.summary_dps_chart = SummaryChart::from_data(
             "summary dps chart",
             combat.players.values().map(|p| {
@@ -79,6 +136,7 @@ impl SummaryTab {
                     p.damage_out.dps.all,
                 )
             }),
+            &player_colors,
         );
         self.summary_damage_out_chart = SummaryChart::from_data(
             "summary damage in chart",
@@ -88,6 +146,7 @@ impl SummaryTab {
                     p.damage_out.total_damage.all,
                 )
             }),
+            &player_colors,
         );
         self.summary_damage_in_chart = SummaryChart::from_data(
             "summary damage out chart",
@@ -97,12 +156,33 @@ impl SummaryTab {
                     p.damage_in.total_damage.all,
                 )
             }),
+            &player_colors,
+        );
+        self.damage_share = ShareBar::from_data(
+            combat.players.values().map(|p| {
+                (
+                    p.damage_out.name().get(&combat.name_manager),
+                    p.damage_out.damage_percentage.all,
+                )
+            }),
+            &player_colors,
+        );
+        self.heal_share = ShareBar::from_data(
+            combat.players.values().map(|p| {
+                (
+                    p.heal_out.name().get(&combat.name_manager),
+                    p.heal_out.heal_percentage.all,
+                )
+            }),
+            &player_colors,
         );
     }
 
-    pub fn show(&mut self, top_ui: &mut Ui) {
+    /// returns the tab and player to drill down into, if a bar of the charts got clicked
+    pub fn show(&mut self, state: &mut AppState, top_ui: &mut Ui) -> Option<(MainTab, String)> {
         top_ui.heading(&self.name);
 
+        let mut drill_down = None;
         Splitter::horizontal()
             .initial_ratio(0.7)
             .show(top_ui, |top_ui, bottom_ui| {
@@ -117,7 +197,36 @@ impl SummaryTab {
 
                         ui.add_space(20.0);
 
-                        self.summary_table.show(ui);
+                        self.show_share(ui);
+
+                        ui.add_space(20.0);
+
+                        self.image_export
+                            .show_buttons(&self.file_identifier(ui.ctx()), ui);
+                        self.summary_table
+                            .show_rate_columns(VisualsTab::rate_columns(ui.ctx()));
+                        let columns_config = state.settings.tables.columns_mut(SUMMARY_TABLE_KEY);
+                        if self.summary_table.show(columns_config, ui) {
+                            state.settings.save();
+                        }
+
+                        ui.add_space(20.0);
+
+                        CollapsingHeader::new("Incoming Damage by Source")
+                            .id_source("incoming damage by source")
+                            .show(ui, |ui| {
+                                self.damage_sources_table.show(ui);
+                            });
+
+                        ui.add_space(20.0);
+
+                        self.show_dropped_duplicates(ui);
+
+                        self.show_players_under_threshold(state, ui);
+
+                        self.show_notes(state, ui);
+
+                        self.show_settings_digest(ui);
                     });
 
                 bottom_ui.horizontal(|ui| {
@@ -126,12 +235,186 @@ impl SummaryTab {
                     ui.selectable_value(&mut self.chart_tab, ChartTab::DamageIn, "Damage In");
                 });
 
-                match self.chart_tab {
-                    ChartTab::Dps => self.summary_dps_chart.show(bottom_ui),
-                    ChartTab::DamageOut => self.summary_damage_out_chart.show(bottom_ui),
-                    ChartTab::DamageIn => self.summary_damage_in_chart.show(bottom_ui),
-                }
+                drill_down = match self.chart_tab {
+                    ChartTab::Dps => self
+                        .summary_dps_chart
+                        .show(bottom_ui)
+                        .map(|p| (MainTab::DamageOut, p)),
+                    ChartTab::DamageOut => self
+                        .summary_damage_out_chart
+                        .show(bottom_ui)
+                        .map(|p| (MainTab::DamageOut, p)),
+                    ChartTab::DamageIn => self
+                        .summary_damage_in_chart
+                        .show(bottom_ui)
+                        .map(|p| (MainTab::DamageIn, p)),
+                };
             });
+
+        self.settings_window.show(
+            &state.settings.analysis,
+            &self.settings_digest,
+            top_ui.ctx(),
+        );
+        self.image_export.show_capture(
+            &self.identifier(top_ui.ctx()),
+            &self.summary_table,
+            state.settings.tables.columns_mut(SUMMARY_TABLE_KEY),
+            top_ui.ctx(),
+        );
+
+        drill_down
+    }
+
+    fn identifier(&self, ctx: &Context) -> String {
+        match &self.active_time {
+            Some(active_time) => {
+                VisualsTab::date_time_format(ctx).combat_identifier(&self.name, active_time)
+            }
+            None => self.name.clone(),
+        }
+    }
+
+    fn file_identifier(&self, ctx: &Context) -> String {
+        match &self.active_time {
+            Some(active_time) => {
+                VisualsTab::date_time_format(ctx).combat_file_identifier(&self.name, active_time)
+            }
+            None => String::new(),
+        }
+    }
+
+    fn show_share(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Share of");
+            ui.selectable_value(&mut self.share_tab, ShareTab::Damage, "Damage Out");
+            ui.selectable_value(&mut self.share_tab, ShareTab::Healing, "Healing Out");
+        });
+
+        let share = match self.share_tab {
+            ShareTab::Damage => &self.damage_share,
+            ShareTab::Healing => &self.heal_share,
+        };
+        if let Some(player) = share.show(ui) {
+            self.summary_table.select_player(player);
+        }
+    }
+
+    fn show_dropped_duplicates(&self, ui: &mut Ui) {
+        if self.dropped_duplicate_records == 0 {
+            return;
+        }
+
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            format!(
+                "⚠ {} duplicated records were dropped",
+                self.dropped_duplicate_records
+            ),
+        )
+        .on_hover_text(
+            "The game duplicated parts of the log for this combat. The duplicates are not counted.",
+        );
+        ui.add_space(20.0);
+    }
+
+    fn show_players_under_threshold(&self, state: &mut AppState, ui: &mut Ui) {
+        let Some(combat) = self.combat.as_ref().filter(|c| !c.players.is_empty()) else {
+            return;
+        };
+
+        let threshold = &mut state.settings.summary.damage_share_threshold;
+        let count = players_under_threshold(combat, *threshold);
+        let response = ui
+            .horizontal(|ui| {
+                ui.label(format!("players under threshold: {}", count));
+                ui.add(
+                    DragValue::new(threshold)
+                        .clamp_range(0.0..=100.0)
+                        .speed(0.5)
+                        .suffix(" %"),
+                )
+                .on_hover_text("share of the damage out, also used by the summary copy")
+            })
+            .inner;
+        // not while dragging, as that changes the value every frame
+        if response.drag_stopped() || response.lost_focus() {
+            state.settings.save();
+        }
+        ui.add_space(20.0);
+    }
+
+    fn show_settings_digest(&mut self, ui: &mut Ui) {
+        if self.settings_digest.is_empty() {
+            return;
+        }
+
+        ui.add_space(20.0);
+        ui.horizontal(|ui| {
+            ui.weak("Analysis Settings");
+            if ui
+                .link(&self.settings_digest)
+                .on_hover_text(
+                    "Digest of the analysis settings used for this combat.\nThe same digest means the same rules. Click to show them.",
+                )
+                .clicked()
+            {
+                self.settings_window.open();
+            }
+        });
+    }
+
+    fn show_notes(&mut self, state: &mut AppState, ui: &mut Ui) {
+        let identity = match &self.identity {
+            Some(i) => i,
+            None => return,
+        };
+        let note = state.combat_notes.get_mut(identity);
+        let mut changed = false;
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Tags");
+            let mut remove = None;
+            for (i, tag) in note.tags.iter().enumerate() {
+                if ui
+                    .button(format!("{} ❌", tag))
+                    .on_hover_text("remove tag")
+                    .clicked()
+                {
+                    remove = Some(i);
+                }
+            }
+            if let Some(remove) = remove {
+                note.tags.remove(remove);
+                changed = true;
+            }
+
+            let response = TextEdit::singleline(&mut self.new_tag)
+                .hint_text("new tag")
+                .desired_width(120.0)
+                .show(ui)
+                .response;
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+            if (ui.button("Add Tag").clicked() || submitted) && !self.new_tag.trim().is_empty() {
+                note.add_tag(&self.new_tag);
+                self.new_tag.clear();
+                changed = true;
+            }
+        });
+
+        ui.label("Notes");
+        let response = TextEdit::multiline(&mut note.notes)
+            .desired_rows(3)
+            .desired_width(f32::INFINITY)
+            .show(ui)
+            .response;
+        if response.lost_focus() {
+            changed = true;
+        }
+
+        if changed {
+            state.combat_notes.save();
+        }
     }
 
     fn show_combat_summary_table(&mut self, ui: &mut Ui) {
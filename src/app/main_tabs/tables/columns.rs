@@ -0,0 +1,104 @@
+use eframe::egui::*;
+
+use crate::app::settings::TableColumn;
+
+/// merges the available columns of a table into the configured ones.
+/// Columns without configuration are enabled and placed after the column before them in `names`.
+pub fn arranged_columns<'a>(
+    config: &[TableColumn],
+    names: impl Iterator<Item = &'a str>,
+) -> Vec<TableColumn> {
+    let mut arranged = config.to_vec();
+    let mut previous: Option<&str> = None;
+    for name in names {
+        if !arranged.iter().any(|c| c.name == name) {
+            let index = previous
+                .and_then(|p| arranged.iter().position(|c| c.name == p))
+                .map(|i| i + 1)
+                .unwrap_or(0);
+            arranged.insert(
+                index,
+                TableColumn {
+                    name: name.to_string(),
+                    enabled: true,
+                },
+            );
+        }
+        previous = Some(name);
+    }
+    arranged
+}
+
+/// the indices of the enabled columns in the configured order
+pub fn visible_columns<'a>(
+    config: &[TableColumn],
+    names: impl Iterator<Item = &'a str> + Clone,
+) -> Vec<usize> {
+    arranged_columns(config, names.clone())
+        .iter()
+        .filter(|c| c.enabled)
+        .filter_map(|c| names.clone().position(|n| n == c.name))
+        .collect()
+}
+
+/// lets the columns of a table be hidden and reordered, returns whether the `config` changed
+pub fn show_columns_menu<'a>(
+    config: &mut Vec<TableColumn>,
+    names: impl Iterator<Item = &'a str> + Clone,
+    ui: &mut Ui,
+) -> bool {
+    let mut arranged = arranged_columns(config, names.clone());
+    let available: Vec<_> = arranged
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| names.clone().any(|n| n == c.name))
+        .map(|(i, _)| i)
+        .collect();
+
+    let mut changed = false;
+    let mut swap = None;
+    ui.label("Columns");
+    for (position, &index) in available.iter().enumerate() {
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(position > 0, Button::new("⏶").small())
+                .clicked()
+            {
+                swap = Some((index, available[position - 1]));
+            }
+            if ui
+                .add_enabled(position + 1 < available.len(), Button::new("⏷").small())
+                .clicked()
+            {
+                swap = Some((index, available[position + 1]));
+            }
+            let column = &mut arranged[index];
+            changed |= ui.checkbox(&mut column.enabled, &column.name).changed();
+        });
+    }
+    if let Some((a, b)) = swap {
+        arranged.swap(a, b);
+        changed = true;
+    }
+
+    ui.separator();
+    if ui.button("Reset Columns").clicked() {
+        config.clear();
+        ui.close_menu();
+        return true;
+    }
+
+    if changed {
+        *config = arranged;
+    }
+    changed
+}
+
+/// the column name with an arrow, if the rows are sorted by the column
+pub fn sort_indicator(name: &str, sorted_ascending: Option<bool>) -> String {
+    match sorted_ascending {
+        Some(true) => format!("{} ⏶", name),
+        Some(false) => format!("{} ⏷", name),
+        None => name.to_string(),
+    }
+}
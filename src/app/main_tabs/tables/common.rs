@@ -1,11 +1,24 @@
 use eframe::egui::*;
 
-use crate::{analyzer::*, app::main_tabs::common::ROW_HEIGHT, custom_widgets::table::*};
+use chrono::Duration;
+
+use crate::{
+    analyzer::*, app::main_tabs::common::ROW_HEIGHT, custom_widgets::table::*,
+    helpers::format_duration,
+};
+
+/// shown next to the names of players, which have the same character name as another player
+pub fn show_ambiguous_player_warning(ui: &mut Ui) {
+    ui.label("⚠").on_hover_text(
+        "another player has the same character name, they can only be told apart by their account handle",
+    );
+}
 
 pub struct Kills {
     total: String,
     pub total_count: u32,
     kills: Vec<(String, String)>,
+    kill_times: Vec<(String, String)>,
 }
 
 impl Kills {
@@ -17,10 +30,21 @@ impl Kills {
             .iter()
             .map(|(n, k)| (name_manager.name(*n).to_string(), k.to_string()))
             .collect();
+        let kill_times = source
+            .kill_times
+            .iter()
+            .map(|(n, t)| {
+                (
+                    name_manager.name(*n).to_string(),
+                    format_duration(Duration::milliseconds(*t as _)),
+                )
+            })
+            .collect();
         Self {
             total: total_kills.to_string(),
             total_count: total_kills,
             kills,
+            kill_times,
         }
     }
 
@@ -43,6 +67,23 @@ impl Kills {
                         });
                     }
                 });
+
+                ui.separator();
+                ui.label("Kill Times");
+                ui.push_id("kill times", |ui| {
+                    Table::new(ui).body(ROW_HEIGHT, |b| {
+                        for (name, time) in self.kill_times.iter() {
+                            b.row(|r| {
+                                r.cell(|ui| {
+                                    ui.label(name.as_str());
+                                });
+                                r.cell(|ui| {
+                                    ui.label(time.as_str());
+                                });
+                            });
+                        }
+                    });
+                });
             });
         }
     }
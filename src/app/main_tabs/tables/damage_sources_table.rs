@@ -0,0 +1,170 @@
+use std::cmp::Reverse;
+
+use eframe::egui::*;
+use rustc_hash::FxHashMap;
+
+use crate::{
+    analyzer::*,
+    app::main_tabs::common::*,
+    custom_widgets::table::*,
+    helpers::{number_formatting::NumberFormatter, F64TotalOrd},
+};
+
+struct ColumnDescriptor {
+    name: &'static str,
+    name_info: Option<&'static str>,
+    sort: fn(&mut DamageSourcesTable),
+    show: fn(&Source, &mut TableRow),
+}
+
+static COLUMNS: &[ColumnDescriptor] = &[
+    ColumnDescriptor {
+        name: "Total Damage",
+        name_info: None,
+        sort: |t| t.sort_by_key(|s| F64TotalOrd(s.total_damage.all.value.unwrap_or(0.0))),
+        show: |s, r| s.total_damage.show(r),
+    },
+    ColumnDescriptor {
+        name: "Damage %",
+        name_info: Some("Share of the total incoming damage of the combat"),
+        sort: |t| t.sort_by_key(|s| F64TotalOrd(s.damage_percentage.value.unwrap_or(0.0))),
+        show: |s, r| {
+            s.damage_percentage.show_percentage(r);
+        },
+    },
+    ColumnDescriptor {
+        name: "Hits",
+        name_info: None,
+        sort: |t| t.sort_by_key(|s| s.hits.count),
+        show: |s, r| {
+            s.hits.show(r);
+        },
+    },
+    ColumnDescriptor {
+        name: "Max One-Hit",
+        name_info: None,
+        sort: |t| t.sort_by_key(|s| F64TotalOrd(s.max_one_hit.value.unwrap_or(0.0))),
+        show: |s, r| {
+            s.max_one_hit.show(r);
+        },
+    },
+    ColumnDescriptor {
+        name: "Deaths Caused",
+        name_info: Some("Deaths of the players, whose killing blow came from the source"),
+        sort: |t| t.sort_by_key(|s| s.deaths.count),
+        show: |s, r| {
+            s.deaths.show(r);
+        },
+    },
+];
+
+/// incoming damage of all players merged by its source
+pub struct DamageSourcesTable {
+    sources: Vec<Source>,
+}
+
+struct Source {
+    name: String,
+    total_damage: ShieldAndHullTextValue,
+    damage_percentage: TextValue,
+    hits: TextCount,
+    max_one_hit: TextValue,
+    deaths: TextCount,
+}
+
+#[derive(Default)]
+struct SourceSums {
+    total_damage: ShieldHullValues,
+    hits: u64,
+    max_one_hit: f64,
+    deaths: u64,
+}
+
+impl DamageSourcesTable {
+    pub fn empty() -> Self {
+        Self {
+            sources: Vec::new(),
+        }
+    }
+
+    pub fn new(combat: &Combat) -> Self {
+        let mut sums: FxHashMap<NameHandle, SourceSums> = Default::default();
+        for source in combat
+            .players
+            .values()
+            .flat_map(|p| p.damage_in.sub_groups.values())
+        {
+            let sum = sums.entry(source.name()).or_default();
+            sum.total_damage += source.total_damage;
+            sum.hits += source.damage_metrics.hits.all;
+            sum.max_one_hit = sum.max_one_hit.max(source.max_one_hit.damage);
+            sum.deaths += source.kills.values().copied().sum::<u32>() as u64;
+        }
+
+        let mut number_formatter = NumberFormatter::new();
+        let mut table = Self {
+            sources: sums
+                .into_iter()
+                .map(|(name, sum)| Source {
+                    name: combat.name_manager.name(name).to_string(),
+                    total_damage: ShieldAndHullTextValue::new(
+                        &sum.total_damage,
+                        2,
+                        &mut number_formatter,
+                    ),
+                    damage_percentage: TextValue::option(
+                        (combat.total_damage_in.all > 0.0)
+                            .then(|| sum.total_damage.all / combat.total_damage_in.all * 100.0),
+                        3,
+                        &mut number_formatter,
+                    ),
+                    hits: TextCount::new(sum.hits),
+                    max_one_hit: TextValue::new(sum.max_one_hit, 2, &mut number_formatter),
+                    deaths: TextCount::new(sum.deaths),
+                })
+                .collect(),
+        };
+        (COLUMNS[0].sort)(&mut table);
+        table
+    }
+
+    pub fn show(&mut self, ui: &mut Ui) {
+        let row_height = row_height(ui.ctx());
+        ScrollArea::new([true, false]).show(ui, |ui| {
+            Table::new(ui)
+                .header(HEADER_HEIGHT, |r| {
+                    r.cell(|ui| {
+                        ui.label("Source");
+                    });
+
+                    for column in COLUMNS.iter() {
+                        let response = r.selectable_cell(false, |ui| {
+                            ui.label(column.name);
+                        });
+                        if response.clicked() {
+                            (column.sort)(self);
+                        }
+                        if let Some(info) = column.name_info {
+                            response.on_hover_text(info);
+                        }
+                    }
+                })
+                .body(row_height, |t| {
+                    for source in self.sources.iter() {
+                        t.row(|r| {
+                            r.cell(|ui| {
+                                ui.label(&source.name);
+                            });
+                            for column in COLUMNS.iter() {
+                                (column.show)(source, r);
+                            }
+                        });
+                    }
+                });
+        });
+    }
+
+    fn sort_by_key<K: Ord>(&mut self, mut key: impl FnMut(&Source) -> K) {
+        self.sources.sort_unstable_by_key(|s| Reverse(key(s)));
+    }
+}
@@ -1,6 +1,12 @@
+use chrono::Duration;
+use eframe::egui::*;
+
 use crate::{
-    analyzer::*, app::main_tabs::common::*, col, custom_widgets::table::*,
-    helpers::number_formatting::NumberFormatter,
+    analyzer::{settings::DpsDenominator, *},
+    app::main_tabs::common::*,
+    col,
+    custom_widgets::table::*,
+    helpers::{format_duration, number_formatting::NumberFormatter},
 };
 
 use super::{common::Kills, metrics_table::*};
@@ -9,90 +15,124 @@ static COLUMNS: &[ColumnDescriptor<DamageTablePartData>] = &[
     col!(
         "DPS",
         "Damage Per Second\nCalculated from the first damage of the player to the last damage in the log",
-        |t| t.sort_by_option_f64_desc(|p| p.dps.all.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.dps.all.value),
         |t, r| t.dps.show(r),
     ),
     col!(
         "Total Damage",
-        |t| t.sort_by_option_f64_desc(|p| p.total_damage.all.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.total_damage.all.value),
         |t, r| t.total_damage.show(r),
     ),
+    col!(
+        "Peak 10s DPS",
+        "Highest DPS within any 10 seconds",
+        |t, a| t.sort_by_option_f64(a, |p| p.peak_dps_10s.value),
+        |t, r| {
+            t.peak_dps_10s.show(r);
+        },
+    ),
+    col!(
+        "Peak 30s DPS",
+        "Highest DPS within any 30 seconds",
+        |t, a| t.sort_by_option_f64(a, |p| p.peak_dps_30s.value),
+        |t, r| {
+            t.peak_dps_30s.show(r);
+        },
+    ),
+    col!(
+        "Peak 60s DPS",
+        "Highest DPS within any 60 seconds",
+        |t, a| t.sort_by_option_f64(a, |p| p.peak_dps_60s.value),
+        |t, r| {
+            t.peak_dps_60s.show(r);
+        },
+    ),
     col!(
         "Damage %",
-        |t| t.sort_by_option_f64_desc(|p| p.damage_percentage.all.value),
+        "Share of the damage of the row above",
+        |t, a| t.sort_by_option_f64(a, |p| p.damage_percentage.all.value),
         |t, r| {
-            t.damage_percentage.show(r);
+            t.damage_percentage.show_percentage(r);
         },
     ),
     col!(
-        "Resistance %",
+        asc "Resistance %",
         "Damage Resistance % excluding any drain damage",
-        |t| t.sort_by_option_f64_asc(|p| p.damage_resistance_percentage.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.damage_resistance_percentage.value),
         |t, r| {
             t.damage_resistance_percentage.show(r);
         },
     ),
     col!(
         "Max One-Hit",
-        |t| t.sort_by_option_f64_desc(|p| p.max_one_hit.damage.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.max_one_hit.damage.value),
         |t, r| t.max_one_hit.show(r),
     ),
     col!(
         "Average Hit",
-        |t| t.sort_by_option_f64_desc(|p| p.average_hit.all.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.average_hit.all.value),
         |t, r| t.average_hit.show(r),
     ),
     col!(
         "Critical %",
-        |t| t.sort_by_option_f64_desc(|p| p.critical_percentage.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.critical_percentage.value),
         |t, r| {
             t.critical_percentage.show(r);
         },
     ),
     col!(
         "Flanking %",
-        |t| t.sort_by_option_f64_desc(|p| p.flanking.value),
+        "Share of the hull hits, which flanked the target.\nFlanking only affects hull damage, so flank flagged shield hits are left out.\nAll flagged hits are listed in the hover of the Hits.",
+        |t, a| t.sort_by_option_f64(a, |p| p.flanking.value),
         |t, r| {
             t.flanking.show(r);
         },
     ),
     col!("Hits",
         "Every damage number that shows up, counts as one hit.\nThis means for an attack, that hits the shields of an enemy, 2 Hits will be counted. One for the shield Hit and one for the hull Hit.",
-        |t| t.sort_by_desc(|p| p.hits.all.count), |t, r| {
+        |t, a| t.sort_by(a, |p| p.hits.counts.all.count), |t, r| {
             t.hits.show(r);
         },
     ),
     col!("Hits / s",
         "Hits Per Second\nCalculated from the first damage of the player to the last damage in the log",
-        |t| t.sort_by_option_f64_desc(|p| p.hits_per_second.all.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.hits_per_second.all.value),
         |t, r| {
             t.hits_per_second.show(r);
         },
     ),
-    col!("Hits %", |t| t.sort_by_option_f64_desc(|p| p.hits_percentage.all.value), |t, r| {
+    col!("Hits %", |t, a| t.sort_by_option_f64(a, |p| p.hits_percentage.all.value), |t, r| {
             t.hits_percentage.show(r);
         },
     ),
-    col!("Misses", |t| t.sort_by_asc(|p| p.misses.count), |t, r| {
+    col!(asc "Misses", |t, a| t.sort_by(a, |p| p.misses.count), |t, r| {
             t.misses.show(r);
         },
     ),
-    col!("Accuracy %", |t| t.sort_by_option_f64_desc(|p| p.accuracy_percentage.value), |t, r| {
+    col!("Accuracy %", |t, a| t.sort_by_option_f64(a, |p| p.accuracy_percentage.value), |t, r| {
             t.accuracy_percentage.show(r);
         },
     ),
-    col!("Kills", |t| t.sort_by_asc(|p| p.kills.total_count), |t, r| {
+    col!(asc "Kills", |t, a| t.sort_by(a, |p| p.kills.total_count), |t, r| {
             t.kills.show(r);
         },
     ),
-    col!("Damage Types", |t| t.sort_by_desc(|p| p.damage_types.clone()), |t, r| {
+    col!(
+        "Uptime %",
+        "Share of the combat, in which the ability was hitting\nHits closer than the uptime gap (see analysis settings) count as continuous uptime",
+        |t, a| t.sort_by_option_f64(a, |p| p.uptime_percentage.value),
+        |t, r| {
+            t.uptime_percentage.show(r);
+        },
+    ),
+    col!("Damage Types", |t, a| t.sort_by(a, |p| p.damage_types.clone()), |t, r| {
             t.damage_types.show(r);
         },
     ),
     col!(
         "Base DPS",
         "Damage Per Second If there were no shields and no damage resistances\nThis excludes any drain damage",
-        |t| t.sort_by_option_f64_desc(|p| p.base_dps.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.base_dps.value),
         |t, r| {
             t.base_dps.show(r);
         },
@@ -100,31 +140,154 @@ static COLUMNS: &[ColumnDescriptor<DamageTablePartData>] = &[
     col!(
         "Base Damage",
         "Damage If there were no shields and no damage resistances\nThis excludes any drain damage",
-        |t| t.sort_by_option_f64_desc(|p| p.base_damage.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.base_damage.value),
         |t, r| {
             t.base_damage.show(r);
         },
     ),
 ];
 
+/// only relevant for incoming damage, e.g. to evaluate drain resistance in PvP
+static DRAIN_TAKEN_COLUMNS: &[ColumnDescriptor<DamageTablePartData>] = &[
+    col!(
+        "Drain Taken",
+        "Shield damage taken from drain abilities (e.g. Tachyon Beam), which is not dealt by classic hits",
+        |t, a| t.sort_by_option_f64(a, |p| p.shield_drain.value),
+        |t, r| {
+            t.shield_drain.show(r);
+        },
+    ),
+    col!(
+        "Drain Taken / s",
+        "Drain Taken Per Second\nCalculated from the first damage of the player to the last damage in the log",
+        |t, a| t.sort_by_option_f64(a, |p| p.shield_drain_per_second.value),
+        |t, r| {
+            t.shield_drain_per_second.show(r);
+        },
+    ),
+];
+
+/// only relevant for incoming damage, to evaluate how many attacks got avoided
+static AVOIDED_COLUMNS: &[ColumnDescriptor<DamageTablePartData>] = &[
+    col!(
+        "Immune Count",
+        "Hits, to which the player was immune",
+        |t, a| t.sort_by(a, |p| p.immunes.count),
+        |t, r| {
+            t.immunes.show(r);
+        },
+    ),
+    col!(
+        "Avoided %",
+        "Share of the hull hits, which were immune, missed or dodged",
+        |t, a| t.sort_by_option_f64(a, |p| p.avoided_percentage.value),
+        |t, r| {
+            t.avoided_percentage.show(r);
+        },
+    ),
+];
+
+/// only relevant for outgoing damage, e.g. to evaluate drain builds
+static DRAIN_OUT_COLUMNS: &[ColumnDescriptor<DamageTablePartData>] = &[
+    col!(
+        "Drain Damage",
+        "Shield damage dealt by drain abilities (e.g. Tachyon Beam), which is not dealt by classic hits",
+        |t, a| t.sort_by_option_f64(a, |p| p.shield_drain.value),
+        |t, r| {
+            t.shield_drain.show(r);
+        },
+    ),
+    col!(
+        "Drain DPS",
+        "Drain Damage Per Second\nCalculated from the first damage of the player to the last damage in the log",
+        |t, a| t.sort_by_option_f64(a, |p| p.shield_drain_per_second.value),
+        |t, r| {
+            t.shield_drain_per_second.show(r);
+        },
+    ),
+];
+
+static COMBAT_PERCENTAGE_COLUMNS: &[ColumnDescriptor<DamageTablePartData>] = &[col!(
+    "Damage % of Combat",
+    "Share of the total damage of the combat",
+    |t, a| t.sort_by_option_f64(a, |p| p.combat_damage_percentage.all.value),
+    |t, r| {
+        t.combat_damage_percentage.show_percentage(r);
+    },
+)];
+
+/// when an ability was hitting, e.g. to spot a trait or console that stopped procing mid-fight
+static ACTIVITY_COLUMNS: &[ColumnDescriptor<DamageTablePartData>] = &[
+    col!(
+        asc "First Hit (s)",
+        "Time of the first hit since the combat start",
+        |t, a| t.sort_by_option_f64(a, |p| p.activity.first_hit.value),
+        |t, r| {
+            t.activity.first_hit.show(r);
+        },
+    ),
+    col!(
+        "Last Hit (s)",
+        "Time of the last hit since the combat start",
+        |t, a| t.sort_by_option_f64(a, |p| p.activity.last_hit.value),
+        |t, r| {
+            t.activity.last_hit.show(r);
+        },
+    ),
+    col!(
+        "Max Gap (s)",
+        "Longest time without a hit between the first and the last hit",
+        |t, a| t.sort_by_option_f64(a, |p| p.activity.max_gap.value),
+        |t, r| t.activity.show_max_gap(r),
+    ),
+];
+
+static HIT_INTERVAL_COLUMNS: &[ColumnDescriptor<DamageTablePartData>] = &[col!(
+    "Median Interval (s)",
+    "Median time between the hits of a weapon, e.g. to verify the firing cycle under haste\nOnly shown for the names of the \"Weapons only\" quick filter",
+    |t, a| t.sort_by_option_f64(a, |p| p.is_weapon().then_some(p.hit_intervals.median.value).flatten()),
+    |t, r| {
+        if t.is_weapon() {
+            t.hit_intervals.show(r);
+        } else {
+            r.cell(|_| {});
+        }
+    },
+)];
+
+/// leaves with fewer hits don't show their hit intervals
+const MIN_INTERVAL_HITS: usize = 5;
+const INTERVAL_HISTOGRAM_BUCKETS: u32 = 15;
+
 pub struct DamageTablePartData {
     total_damage: ShieldAndHullTextValue,
     dps: ShieldAndHullTextValue,
     damage_percentage: ShieldAndHullTextValue,
+    combat_damage_percentage: ShieldAndHullTextValue,
     max_one_hit: MaxOneHit,
     average_hit: ShieldAndHullTextValue,
     critical_percentage: TextValue,
     flanking: TextValue,
     damage_resistance_percentage: TextValue,
+    peak_dps_10s: TextValue,
+    peak_dps_30s: TextValue,
+    peak_dps_60s: TextValue,
     base_damage: TextValue,
     base_dps: TextValue,
-    hits: ShieldAndHullTextCount,
+    shield_drain: TextValue,
+    shield_drain_per_second: TextValue,
+    hits: HitsBreakdown,
     hits_per_second: ShieldAndHullTextValue,
     hits_percentage: ShieldAndHullTextValue,
     misses: TextCount,
     accuracy_percentage: TextValue,
+    immunes: TextCount,
+    avoided_percentage: TextValue,
     kills: Kills,
+    uptime_percentage: TextValue,
     damage_types: DamageTypes,
+    hit_intervals: HitIntervals,
+    activity: Activity,
     pub source_hits: Vec<Hit>,
 }
 
@@ -133,7 +296,34 @@ pub type DamageTablePart = MetricsTablePart<DamageTablePartData>;
 
 struct MaxOneHit {
     damage: TextValue,
-    name: String,
+    details: Vec<(&'static str, String)>,
+}
+
+/// only known for leafs
+#[derive(Default)]
+struct Activity {
+    first_hit: TextValue,
+    last_hit: TextValue,
+    max_gap: TextValue,
+    active_duration: String,
+    average_interval: String,
+}
+
+#[derive(Default)]
+struct HitIntervals {
+    median: TextValue,
+    /// label of the interval range and the amount of intervals in it
+    histogram: Vec<(String, u32)>,
+}
+
+struct HitsBreakdown {
+    counts: ShieldAndHullTextCount,
+    crits: String,
+    flanks: String,
+    flagged_flanks: String,
+    immunes: String,
+    dodges: String,
+    kills: String,
 }
 
 #[derive(PartialEq, PartialOrd, Eq, Ord, Clone)]
@@ -149,12 +339,63 @@ impl DamageTable {
     }
 
     pub fn new(combat: &Combat, damage_group: impl FnMut(&Player) -> &DamageGroup) -> Self {
-        Self::new_base(COLUMNS, combat, damage_group, DamageTablePartData::new)
+        Self::new_base(
+            COLUMNS,
+            combat,
+            damage_group,
+            DamageTablePartData::new,
+            |d| d.damage_percentage.all.value,
+        )
+    }
+
+    pub fn mark_rows_with_increased_damage(&mut self, previous: &Self) {
+        self.mark_changed_rows(previous, |new, old| {
+            new.total_damage.all.value > old.total_damage.all.value
+        });
+    }
+
+    pub fn show_combat_percentage_columns(&mut self, show: bool) {
+        self.set_optional_columns("Damage %", COMBAT_PERCENTAGE_COLUMNS, show);
+    }
+
+    pub fn show_activity_columns(&mut self, show: bool) {
+        self.set_optional_columns("Uptime %", ACTIVITY_COLUMNS, show);
+    }
+
+    pub fn show_hit_interval_columns(&mut self, show: bool) {
+        self.set_optional_columns("Hits / s", HIT_INTERVAL_COLUMNS, show);
+    }
+
+    pub fn show_drain_taken_columns(&mut self) {
+        self.add_columns(DRAIN_TAKEN_COLUMNS);
+    }
+
+    pub fn show_avoided_columns(&mut self) {
+        self.set_optional_columns("Accuracy %", AVOIDED_COLUMNS, true);
+    }
+
+    pub fn show_drain_out_columns(&mut self) {
+        self.add_columns(DRAIN_OUT_COLUMNS);
+    }
+
+    pub fn show_dps_denominator(&mut self, dps_denominator: DpsDenominator) {
+        if let Some(column) = self.column_mut(COLUMNS[0].name) {
+            column.name = dps_denominator.column_name();
+            column.name_info = Some(dps_denominator.description());
+        }
     }
 }
 
 impl DamageTablePartData {
     fn new(source: &DamageGroup, combat: &Combat, number_formatter: &mut NumberFormatter) -> Self {
+        let source_hits: Vec<_> = source
+            .hits
+            .get(&combat.hits_manger)
+            .iter()
+            .filter(|h| combat.is_shown_in_graphs(h.flags))
+            .copied()
+            .collect();
+        let peak_dps = PeakDps::new(source_hits.iter());
         Self {
             total_damage: ShieldAndHullTextValue::new(&source.total_damage, 2, number_formatter),
             dps: ShieldAndHullTextValue::new(&source.dps, 2, number_formatter),
@@ -163,6 +404,11 @@ impl DamageTablePartData {
                 3,
                 number_formatter,
             ),
+            combat_damage_percentage: ShieldAndHullTextValue::option(
+                &source.combat_damage_percentage,
+                3,
+                number_formatter,
+            ),
             average_hit: ShieldAndHullTextValue::option(&source.average_hit, 2, number_formatter),
             critical_percentage: TextValue::option(source.critical_percentage, 3, number_formatter),
             flanking: TextValue::option(source.flanking, 3, number_formatter),
@@ -174,9 +420,16 @@ impl DamageTablePartData {
             ),
             base_damage: TextValue::new(source.total_base_damage, 2, number_formatter),
             base_dps: TextValue::new(source.base_dps, 2, number_formatter),
+            shield_drain: TextValue::new(source.total_shield_drain, 2, number_formatter),
+            shield_drain_per_second: TextValue::new(
+                source.shield_drain_per_second,
+                2,
+                number_formatter,
+            ),
             kills: Kills::new(source, &combat.name_manager),
+            uptime_percentage: TextValue::option(source.uptime_percentage, 3, number_formatter),
             damage_types: DamageTypes::new(source, &combat.name_manager),
-            hits: ShieldAndHullTextCount::new(&source.damage_metrics.hits),
+            hits: HitsBreakdown::new(&source.damage_metrics),
             hits_per_second: ShieldAndHullTextValue::new(
                 &source.hits_per_second,
                 3,
@@ -189,9 +442,123 @@ impl DamageTablePartData {
             ),
             misses: TextCount::new(source.misses),
             accuracy_percentage: TextValue::option(source.accuracy_percentage, 3, number_formatter),
-            source_hits: source.hits.get(&combat.hits_manger).to_vec(),
+            immunes: TextCount::new(source.immunes),
+            avoided_percentage: TextValue::option(source.avoided_percentage, 3, number_formatter),
+            peak_dps_10s: TextValue::new(peak_dps.ten_seconds, 2, number_formatter),
+            peak_dps_30s: TextValue::new(peak_dps.thirty_seconds, 2, number_formatter),
+            peak_dps_60s: TextValue::new(peak_dps.sixty_seconds, 2, number_formatter),
+            source_hits,
+            hit_intervals: if source.sub_groups().is_empty() {
+                HitIntervals::new(source.hits.get(&combat.hits_manger), number_formatter)
+            } else {
+                Default::default()
+            },
+            activity: source
+                .activity_times
+                .map(|a| Activity::new(&a, number_formatter))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl Activity {
+    fn new(activity_times: &ActivityTimes, number_formatter: &mut NumberFormatter) -> Self {
+        let seconds = |millis: f64, number_formatter: &mut NumberFormatter| {
+            TextValue::new(millis / 1e3, 2, number_formatter)
+        };
+        Self {
+            first_hit: seconds(activity_times.first_millis as _, number_formatter),
+            last_hit: seconds(activity_times.last_millis as _, number_formatter),
+            max_gap: seconds(activity_times.max_gap_millis as _, number_formatter),
+            active_duration: format_duration(Duration::milliseconds(
+                activity_times.active_millis as _,
+            )),
+            average_interval: activity_times
+                .average_interval_millis
+                .map(|i| format!("{} s", number_formatter.format(i / 1e3, 2)))
+                .unwrap_or_else(|| "-".to_string()),
         }
     }
+
+    fn show_max_gap(&self, row: &mut TableRow) {
+        let Some(response) = self.max_gap.show(row) else {
+            return;
+        };
+        response.on_hover_text(format!(
+            "Active Duration: {}\nAverage Interval: {}",
+            self.active_duration, self.average_interval
+        ));
+    }
+}
+
+impl HitIntervals {
+    fn new(hits: &[Hit], number_formatter: &mut NumberFormatter) -> Self {
+        let mut intervals = intervals_millis(hits.iter().map(|h| h.time_millis));
+        if intervals.len() + 1 < MIN_INTERVAL_HITS {
+            return Default::default();
+        }
+        intervals.sort_unstable();
+        let median = intervals[intervals.len() / 2] as f64 / 1e3;
+
+        // the buckets cover up to 3 times the median, everything longer ends up in the last one
+        let bucket_millis =
+            (intervals[intervals.len() / 2] * 3 / INTERVAL_HISTOGRAM_BUCKETS).max(10);
+        let mut counts = vec![0; INTERVAL_HISTOGRAM_BUCKETS as usize + 1];
+        for interval in intervals.iter() {
+            let bucket = (interval / bucket_millis).min(INTERVAL_HISTOGRAM_BUCKETS);
+            counts[bucket as usize] += 1;
+        }
+        let histogram = counts
+            .into_iter()
+            .enumerate()
+            .filter(|(_, count)| *count > 0)
+            .map(|(bucket, count)| {
+                let start = (bucket as u32 * bucket_millis) as f64 / 1e3;
+                let label = if bucket as u32 == INTERVAL_HISTOGRAM_BUCKETS {
+                    format!("≥ {} s", number_formatter.format(start, 2))
+                } else {
+                    let end = start + bucket_millis as f64 / 1e3;
+                    format!(
+                        "{} - {} s",
+                        number_formatter.format(start, 2),
+                        number_formatter.format(end, 2)
+                    )
+                };
+                (label, count)
+            })
+            .collect();
+
+        Self {
+            median: TextValue::new(median, 2, number_formatter),
+            histogram,
+        }
+    }
+
+    fn show(&self, row: &mut TableRow) {
+        let Some(response) = self.median.show(row) else {
+            return;
+        };
+        let max_count = self.histogram.iter().map(|(_, c)| *c).max().unwrap_or(1);
+        response.on_hover_ui(|ui| {
+            Table::new(ui).body(ROW_HEIGHT, |t| {
+                for (label, count) in self.histogram.iter() {
+                    t.row(|r| {
+                        r.cell(|ui| {
+                            ui.label(label);
+                        });
+                        r.cell(|ui| {
+                            let width = 100.0 * *count as f32 / max_count as f32;
+                            let (rect, _) =
+                                ui.allocate_exact_size(vec2(width, 10.0), Sense::hover());
+                            ui.painter()
+                                .rect_filled(rect, 0.0, ui.visuals().selection.bg_fill);
+                        });
+                        show_value_text(r, &count.to_string());
+                    });
+                }
+            });
+        });
+    }
 }
 
 impl DamageTablePart {
@@ -210,17 +577,100 @@ impl MaxOneHit {
         number_formatter: &mut NumberFormatter,
         name_manager: &NameManager,
     ) -> Self {
+        let mut details = vec![(
+            "Ability",
+            source.max_one_hit.name.get(name_manager).to_string(),
+        )];
+        if let Some(hit) = &source.max_one_hit.hit {
+            details.push((
+                "Time",
+                format_duration(Duration::milliseconds(hit.time_millis as _)),
+            ));
+            details.push(("Damage", number_formatter.format(hit.damage, 2)));
+            let kind = match hit.specific {
+                SpecificHit::Shield { .. } => "Shield",
+                SpecificHit::ShieldDrain => "Shield Drain",
+                SpecificHit::Hull { base_damage } => {
+                    details.push(("Base Damage", number_formatter.format(base_damage, 2)));
+                    "Hull"
+                }
+            };
+            if let Some(resistance) = hit.damage_resistance_percentage() {
+                details.push(("Resistance %", number_formatter.format(resistance, 3)));
+            }
+            details.push(("Kind", kind.to_string()));
+            details.push(("Critical", yes_no(hit.flags.contains(ValueFlags::CRITICAL))));
+            details.push(("Flanking", yes_no(hit.flags.contains(ValueFlags::FLANK))));
+        }
+
         Self {
             damage: TextValue::new(source.max_one_hit.damage, 2, number_formatter),
-            name: source.max_one_hit.name.get(name_manager).to_string(),
+            details,
         }
     }
 
     fn show(&self, row: &mut TableRow) {
         if let Some(response) = self.damage.show(row) {
-            response.on_hover_text(&self.name);
+            response.on_hover_ui(|ui| {
+                Table::new(ui).body(ROW_HEIGHT, |t| {
+                    for (name, value) in self.details.iter() {
+                        t.row(|r| {
+                            r.cell(|ui| {
+                                ui.label(*name);
+                            });
+                            show_value_text(r, value);
+                        });
+                    }
+                });
+            });
+        }
+    }
+}
+
+fn yes_no(value: bool) -> String {
+    match value {
+        true => "yes".to_string(),
+        false => "no".to_string(),
+    }
+}
+
+impl HitsBreakdown {
+    fn new(metrics: &DamageMetrics) -> Self {
+        Self {
+            counts: ShieldAndHullTextCount::new(&metrics.hits),
+            crits: metrics.crits.to_string(),
+            flanks: metrics.flanks.to_string(),
+            flagged_flanks: metrics.flagged_flanks.to_string(),
+            immunes: metrics.immunes.to_string(),
+            dodges: metrics.dodges.to_string(),
+            kills: metrics.kills.to_string(),
         }
     }
+
+    fn show(&self, row: &mut TableRow) {
+        let response = self.counts.all.show(row);
+        response.on_hover_ui(|ui| {
+            Table::new(ui).body(ROW_HEIGHT, |t| {
+                for (name, value) in [
+                    ("Shield", &self.counts.shield),
+                    ("Hull", &self.counts.hull),
+                    ("Critical", &self.crits),
+                    ("Flanking (hull)", &self.flanks),
+                    ("Flagged Flanking", &self.flagged_flanks),
+                    ("Immune", &self.immunes),
+                    ("Dodge", &self.dodges),
+                    ("Kill", &self.kills),
+                ] {
+                    t.row(|r| {
+                        r.cell(|ui| {
+                            ui.label(name);
+                        });
+                        show_value_text(r, value);
+                    });
+                }
+            });
+        });
+    }
 }
 
 impl DamageTypes {
@@ -8,59 +8,183 @@ static COLUMNS: &[ColumnDescriptor<HealTablePartData>] = &[
     col!(
         "HPS",
         "Heals Per Second\nCalculated from the first action of the player to the last action in the log",
-        |t| t.sort_by_option_f64_desc(|p| p.hps.all.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.hps.all.value),
         |t, r| t.hps.show(r),
     ),
+    col!(
+        "Shield HPS",
+        |t, a| t.sort_by_option_f64(a, |p| p.shield_hps.value),
+        |t, r| {
+            t.shield_hps.show(r);
+        },
+    ),
+    col!(
+        "Hull HPS",
+        |t, a| t.sort_by_option_f64(a, |p| p.hull_hps.value),
+        |t, r| {
+            t.hull_hps.show(r);
+        },
+    ),
     col!(
         "Total Heal",
-        |t| t.sort_by_option_f64_desc(|p| p.total_heal.all.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.total_heal.all.value),
         |t, r| t.total_heal.show(r),
     ),
+    col!(
+        "Shield Heal",
+        |t, a| t.sort_by_option_f64(a, |p| p.shield_heal.value),
+        |t, r| {
+            t.shield_heal.show(r);
+        },
+    ),
+    col!(
+        "Hull Heal",
+        |t, a| t.sort_by_option_f64(a, |p| p.hull_heal.value),
+        |t, r| {
+            t.hull_heal.show(r);
+        },
+    ),
     col!(
         "Heal %",
-        |t| t.sort_by_option_f64_desc(|p| p.heal_percentage.all.value),
+        "Share of the heal of the row above",
+        |t, a| t.sort_by_option_f64(a, |p| p.heal_percentage.all.value),
+        |t, r| {
+            t.heal_percentage.show_percentage(r);
+        },
+    ),
+    col!(
+        "Shield Heal %",
+        "Share of the total shield heal of the combat",
+        |t, a| t.sort_by_option_f64(a, |p| p.shield_heal_percentage.value),
         |t, r| {
-            t.heal_percentage.show(r);
+            t.shield_heal_percentage.show_percentage(r);
+        },
+    ),
+    col!(
+        "Hull Heal %",
+        "Share of the total hull heal of the combat",
+        |t, a| t.sort_by_option_f64(a, |p| p.hull_heal_percentage.value),
+        |t, r| {
+            t.hull_heal_percentage.show_percentage(r);
         },
     ),
     col!(
         "Average Heal",
-        |t| t.sort_by_option_f64_desc(|p| p.average_heal.all.value),
+        "Ticks, which healed nothing, are left out",
+        |t, a| t.sort_by_option_f64(a, |p| p.average_heal.all.value),
         |t, r| t.average_heal.show(r),
     ),
     col!(
         "Critical %",
-        |t| t.sort_by_option_f64_desc(|p| p.critical_percentage.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.critical_percentage.value),
         |t, r| {
             t.critical_percentage.show(r);
         },
     ),
-    col!("Ticks", |t| t.sort_by_desc(|p| p.ticks.all.count), |t, r| {
+    col!(
+        "Average Crit Heal",
+        |t, a| t.sort_by_option_f64(a, |p| p.average_crit_heal.value),
+        |t, r| {
+            t.average_crit_heal.show(r);
+        },
+    ),
+    col!("Ticks", |t, a| t.sort_by(a, |p| p.ticks.all.count), |t, r| {
             t.ticks.show(r);
         },
     ),
     col!("Ticks / s",
         "Ticks Per Second\nCalculated from the first action of the player to the last action in the log",
-        |t| t.sort_by_option_f64_desc(|p| p.ticks_per_second.all.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.ticks_per_second.all.value),
         |t, r| {
             t.ticks_per_second.show(r);
         },
     ),
-    col!("Ticks %", |t| t.sort_by_option_f64_desc(|p| p.ticks_percentage.all.value), |t, r| {
+    col!("Ticks %", |t, a| t.sort_by_option_f64(a, |p| p.ticks_percentage.all.value), |t, r| {
         t.ticks_percentage.show(r);
     },
 ),
+    col!(
+        "Zero Ticks",
+        "Ticks, which healed nothing, e.g. shield heals on full facings\nThey are left out of the average heal\nThe log does not report overheal, so partially wasted ticks are not detected",
+        |t, a| t.sort_by(a, |p| p.zero_ticks.all.count),
+        |t, r| {
+            t.zero_ticks.show(r);
+        },
+    ),
+    col!(
+        "Uptime %",
+        "Share of the combat, in which the ability was ticking\nTicks closer than the uptime gap (see analysis settings) count as continuous uptime",
+        |t, a| t.sort_by_option_f64(a, |p| p.uptime_percentage.value),
+        |t, r| {
+            t.uptime_percentage.show(r);
+        },
+    ),
+];
+
+static COMBAT_PERCENTAGE_COLUMNS: &[ColumnDescriptor<HealTablePartData>] = &[col!(
+    "Heal % of Combat",
+    "Share of the total heal of the combat",
+    |t, a| t.sort_by_option_f64(a, |p| p.combat_heal_percentage.all.value),
+    |t, r| {
+        t.combat_heal_percentage.show_percentage(r);
+    },
+)];
+
+/// only meaningful for outgoing heal
+static TARGET_SPLIT_COLUMNS: &[ColumnDescriptor<HealTablePartData>] = &[
+    col!(
+        "Self Heal",
+        "Heal, which went to the player themselves",
+        |t, a| t.sort_by_option_f64(a, |p| p.self_heal.all.value),
+        |t, r| t.self_heal.show(r),
+    ),
+    col!(
+        "Self Heal %",
+        "Share of the heal, which went to the player themselves",
+        |t, a| t.sort_by_option_f64(a, |p| p.self_heal_percentage.value),
+        |t, r| {
+            t.self_heal_percentage.show(r);
+        },
+    ),
+    col!(
+        "Heal to Others",
+        "Heal, which went to anyone except the player",
+        |t, a| t.sort_by_option_f64(a, |p| p.heal_to_others.all.value),
+        |t, r| t.heal_to_others.show(r),
+    ),
+    col!(
+        "Heal to Others %",
+        "Share of the heal, which went to anyone except the player",
+        |t, a| t.sort_by_option_f64(a, |p| p.heal_to_others_percentage.value),
+        |t, r| {
+            t.heal_to_others_percentage.show(r);
+        },
+    ),
 ];
 
 pub struct HealTablePartData {
     total_heal: ShieldAndHullTextValue,
+    shield_heal: TextValue,
+    hull_heal: TextValue,
     hps: ShieldAndHullTextValue,
+    shield_hps: TextValue,
+    hull_hps: TextValue,
     heal_percentage: ShieldAndHullTextValue,
+    shield_heal_percentage: TextValue,
+    hull_heal_percentage: TextValue,
+    combat_heal_percentage: ShieldAndHullTextValue,
+    self_heal: ShieldAndHullTextValue,
+    self_heal_percentage: TextValue,
+    heal_to_others: ShieldAndHullTextValue,
+    heal_to_others_percentage: TextValue,
     average_heal: ShieldAndHullTextValue,
     critical_percentage: TextValue,
+    average_crit_heal: TextValue,
     ticks: ShieldAndHullTextCount,
+    zero_ticks: ShieldAndHullTextCount,
     ticks_per_second: ShieldAndHullTextValue,
     ticks_percentage: ShieldAndHullTextValue,
+    uptime_percentage: TextValue,
     pub source_ticks: Vec<HealTick>,
 }
 
@@ -73,11 +197,41 @@ impl HealTable {
     }
 
     pub fn new(combat: &Combat, heal_group: impl FnMut(&Player) -> &HealGroup) -> Self {
-        Self::new_base(COLUMNS, combat, heal_group, HealTablePartData::new)
+        Self::new_base(COLUMNS, combat, heal_group, HealTablePartData::new, |d| {
+            d.heal_percentage.all.value
+        })
+    }
+
+    pub fn show_combat_percentage_columns(&mut self, show: bool) {
+        self.set_optional_columns("Heal %", COMBAT_PERCENTAGE_COLUMNS, show);
+    }
+
+    pub fn show_target_split_columns(&mut self, show: bool) {
+        self.set_optional_columns("Heal %", TARGET_SPLIT_COLUMNS, show);
     }
 }
 
 impl HealTablePart {
+    /// the ticks of all leaves below this part, together with the names from this part down to the leaf
+    pub fn ticks_with_source_path(&self) -> Vec<(HealTick, String)> {
+        let mut ticks = Vec::new();
+        self.collect_ticks(&mut Vec::new(), &mut ticks);
+        ticks
+    }
+
+    fn collect_ticks<'a>(&'a self, path: &mut Vec<&'a str>, ticks: &mut Vec<(HealTick, String)>) {
+        path.push(&self.name);
+        if self.sub_parts.is_empty() {
+            let source_path = path.join(" → ");
+            ticks.extend(self.source_ticks.iter().map(|t| (*t, source_path.clone())));
+        } else {
+            self.sub_parts
+                .iter()
+                .for_each(|s| s.collect_ticks(path, ticks));
+        }
+        path.pop();
+    }
+
     pub fn hps(&self) -> f64 {
         self.hps.all.value.unwrap()
     }
@@ -91,15 +245,56 @@ impl HealTablePartData {
     fn new(group: &HealGroup, combat: &Combat, number_formatter: &mut NumberFormatter) -> Self {
         Self {
             total_heal: ShieldAndHullTextValue::new(&group.total_heal, 2, number_formatter),
+            shield_heal: TextValue::new(group.total_heal.shield, 2, number_formatter),
+            hull_heal: TextValue::new(group.total_heal.hull, 2, number_formatter),
             hps: ShieldAndHullTextValue::new(&group.hps, 2, number_formatter),
+            shield_hps: TextValue::new(group.hps.shield, 2, number_formatter),
+            hull_hps: TextValue::new(group.hps.hull, 2, number_formatter),
             heal_percentage: ShieldAndHullTextValue::option(
                 &group.heal_percentage,
                 3,
                 number_formatter,
             ),
+            shield_heal_percentage: TextValue::option(
+                group.combat_heal_percentage.shield,
+                3,
+                number_formatter,
+            ),
+            hull_heal_percentage: TextValue::option(
+                group.combat_heal_percentage.hull,
+                3,
+                number_formatter,
+            ),
+            combat_heal_percentage: ShieldAndHullTextValue::option(
+                &group.combat_heal_percentage,
+                3,
+                number_formatter,
+            ),
+            self_heal: ShieldAndHullTextValue::new(
+                &group.target_split.to_self,
+                2,
+                number_formatter,
+            ),
+            self_heal_percentage: TextValue::option(
+                group.target_split.self_percentage(),
+                3,
+                number_formatter,
+            ),
+            heal_to_others: ShieldAndHullTextValue::new(
+                &group.target_split.to_others,
+                2,
+                number_formatter,
+            ),
+            heal_to_others_percentage: TextValue::option(
+                group.target_split.others_percentage(),
+                3,
+                number_formatter,
+            ),
             average_heal: ShieldAndHullTextValue::option(&group.average_heal, 2, number_formatter),
             critical_percentage: TextValue::option(group.critical_percentage, 3, number_formatter),
+            average_crit_heal: TextValue::option(group.average_crit_heal, 2, number_formatter),
             ticks: ShieldAndHullTextCount::new(&group.heal_metrics.ticks),
+            zero_ticks: ShieldAndHullTextCount::new(&group.heal_metrics.zero_ticks),
             ticks_per_second: ShieldAndHullTextValue::new(
                 &group.ticks_per_second,
                 3,
@@ -110,7 +305,14 @@ impl HealTablePartData {
                 3,
                 number_formatter,
             ),
-            source_ticks: group.ticks.get(&combat.heal_ticks_manger).to_vec(),
+            uptime_percentage: TextValue::option(group.uptime_percentage, 3, number_formatter),
+            source_ticks: group
+                .ticks
+                .get(&combat.heal_ticks_manger)
+                .iter()
+                .filter(|t| combat.is_shown_in_graphs(t.flags))
+                .copied()
+                .collect(),
         }
     }
 }
@@ -1,4 +1,7 @@
-use std::cmp::Reverse;
+use std::{
+    cmp::Reverse,
+    time::{Duration, Instant},
+};
 
 use educe::Educe;
 use eframe::egui::*;
@@ -6,18 +9,45 @@ use rustc_hash::FxHashSet;
 
 use crate::{
     analyzer::*,
-    app::main_tabs::common::*,
+    app::{
+        main_tabs::common::*,
+        settings::{MinorRows, RuleRequest, RuleTarget, TableColumn, VisualsTab},
+    },
     custom_widgets::table::*,
     helpers::{number_formatting::NumberFormatter, F64TotalOrd},
 };
 
+use super::{columns::*, common::show_ambiguous_player_warning, QuickFilter};
+
 #[macro_export]
 macro_rules! col {
+    // columns, which sort ascending on the first click (e.g. lower is better)
+    (asc $name:expr, $sort:expr, $show:expr $(,)?) => {
+        ColumnDescriptor {
+            name: $name,
+            name_info: None,
+            sort: $sort,
+            sorts_ascending_first: true,
+            show: $show,
+        }
+    };
+
+    (asc $name:expr, $name_info:expr, $sort:expr, $show:expr $(,)?) => {
+        ColumnDescriptor {
+            name: $name,
+            name_info: Some($name_info),
+            sort: $sort,
+            sorts_ascending_first: true,
+            show: $show,
+        }
+    };
+
     ($name:expr, $sort:expr, $show:expr $(,)?) => {
         ColumnDescriptor {
             name: $name,
             name_info: None,
             sort: $sort,
+            sorts_ascending_first: false,
             show: $show,
         }
     };
@@ -27,15 +57,22 @@ macro_rules! col {
             name: $name,
             name_info: Some($name_info),
             sort: $sort,
+            sorts_ascending_first: false,
             show: $show,
         }
     };
 }
 
 pub struct MetricsTable<T: 'static> {
-    columns: &'static [ColumnDescriptor<T>],
+    columns: Vec<ColumnDescriptor<T>>,
     players: Vec<MetricsTablePart<T>>,
     selection: SelectionTracker,
+    /// name of the column the rows are sorted by and whether they are sorted ascending
+    sorting: Option<(&'static str, bool)>,
+    /// label of the context menu entry, which requests the details of a row. `None` if there are no details.
+    details_label: Option<&'static str>,
+    details_request: Option<u32>,
+    rule_request: Option<RuleRequest>,
 }
 
 #[derive(Educe)]
@@ -45,26 +82,63 @@ pub struct MetricsTablePart<T> {
     pub data: T,
     pub name: String,
     id: u32,
+    /// share of the parent row in %, used to dim or hide minor rows
+    share: Option<f64>,
 
     pub sub_parts: Vec<Self>,
 
+    is_indirect_source: bool,
+    is_weapon: bool,
+    entity_icon: Option<&'static str>,
+    /// see [`Combat::ambiguous_players`]
+    is_ambiguous_player: bool,
+    filtered_out: bool,
     open: bool,
+    changed_at: Option<Instant>,
+}
+
+const CHANGED_HIGHLIGHT_DURATION: Duration = Duration::from_secs(3);
+const MINOR_ROW_OPACITY: f32 = 0.4;
+const ENTITY_ICONS_LEGEND: &str = "👤 player\n🐾 pet or other indirect source\n🤖 NPC";
+
+struct RowsContext {
+    modifiers: Modifiers,
+    highlight_color: Option<Color32>,
+    is_highlighting: bool,
+    minor_rows: Option<MinorRows>,
+    details_label: Option<&'static str>,
+    details_request: Option<u32>,
+    rule_request: Option<RuleRequest>,
 }
 
-#[derive(Clone, Copy)]
 pub struct ColumnDescriptor<T: 'static> {
     pub name: &'static str,
     pub name_info: Option<&'static str>,
-    pub sort: fn(&mut MetricsTable<T>),
+    /// sorts the rows ascending or descending
+    pub sort: fn(&mut MetricsTable<T>, bool),
+    /// direction of the first click on the column, set with `col!(asc ...)`
+    pub sorts_ascending_first: bool,
     pub show: fn(&mut MetricsTablePart<T>, &mut TableRow),
 }
 
+impl<T: 'static> Clone for ColumnDescriptor<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: 'static> Copy for ColumnDescriptor<T> {}
+
 impl<T: 'static> MetricsTable<T> {
     pub fn empty_base(columns: &'static [ColumnDescriptor<T>]) -> Self {
         Self {
             players: Vec::new(),
             selection: Default::default(),
-            columns,
+            sorting: None,
+            columns: columns.to_vec(),
+            details_label: None,
+            details_request: None,
+            rule_request: None,
         }
     }
 
@@ -73,11 +147,12 @@ impl<T: 'static> MetricsTable<T> {
         combat: &Combat,
         mut group: impl FnMut(&Player) -> &G,
         data_new: fn(&G, &Combat, &mut NumberFormatter) -> T,
+        share: fn(&T) -> Option<f64>,
     ) -> Self {
         let mut number_formatter = NumberFormatter::new();
         let mut id_source = 0;
         let mut table = Self {
-            columns,
+            columns: columns.to_vec(),
             players: combat
                 .players
                 .values()
@@ -88,69 +163,235 @@ impl<T: 'static> MetricsTable<T> {
                         &mut number_formatter,
                         &mut id_source,
                         data_new,
+                        share,
                     )
                 })
                 .collect(),
             selection: Default::default(),
+            sorting: None,
+            details_label: None,
+            details_request: None,
+            rule_request: None,
         };
-        (table.columns[0].sort)(&mut table);
+        table.sort_by_column(columns[0], columns[0].sorts_ascending_first);
 
         table
     }
 
-    pub fn show(&mut self, ui: &mut Ui, mut on_selected: impl FnMut(TableSelectionEvent<T>)) {
-        let modifiers = ui.input(|i| i.modifiers);
+    /// marks the rows, which `has_changed` compared to the row with the same name path in `previous`
+    pub fn mark_changed_rows(
+        &mut self,
+        previous: &Self,
+        has_changed: impl Fn(&T, &T) -> bool + Copy,
+    ) {
+        let now = Instant::now();
+        MetricsTablePart::mark_changed_parts(
+            &mut self.players,
+            &previous.players,
+            now,
+            has_changed,
+        );
+    }
+
+    /// hides the rows below the players, which don't pass the `filter`.
+    /// Branches stay visible as long as one of their sub parts is visible.
+    pub fn apply_quick_filter(&mut self, filter: &QuickFilter) {
+        for player in self.players.iter_mut() {
+            for sub_part in player.sub_parts.iter_mut() {
+                sub_part.apply_quick_filter(filter, false);
+            }
+        }
+    }
+
+    /// marks the leaf rows below the players, whose names match the weapon names of the `filter`
+    pub fn mark_weapons(&mut self, filter: &QuickFilter) {
+        for player in self.players.iter_mut() {
+            for sub_part in player.sub_parts.iter_mut() {
+                sub_part.mark_weapons(filter);
+            }
+        }
+    }
+
+    /// selects the row of the `player` as a group, like clicking it does
+    pub fn select_player(
+        &mut self,
+        player: &str,
+        mut on_selected: impl FnMut(TableSelectionEvent<T>),
+    ) {
+        let Some(part) = self.players.iter().find(|p| p.name == player) else {
+            return;
+        };
+        self.selection = SelectionTracker::Group(part.id);
+        on_selected(TableSelectionEvent::Group(part));
+    }
+
+    /// adds an entry with the `label` to the context menu of the rows, see [`Self::take_details_request`]
+    pub fn enable_details(&mut self, label: &'static str) {
+        self.details_label = Some(label);
+    }
+
+    /// the row, whose details got requested through the context menu since the last call
+    pub fn take_details_request(&mut self) -> Option<&MetricsTablePart<T>> {
+        let id = self.details_request.take()?;
+        self.players.iter().find_map(|p| p.find(id))
+    }
+
+    /// the rule, which got requested through the context menu of a row since the last call
+    pub fn take_rule_request(&mut self) -> Option<RuleRequest> {
+        self.rule_request.take()
+    }
+
+    pub fn add_columns(&mut self, columns: &[ColumnDescriptor<T>]) {
+        self.columns.extend_from_slice(columns);
+    }
+
+    /// shows or hides the `columns`, shown ones are placed right after the column named `after`
+    pub fn set_optional_columns(
+        &mut self,
+        after: &str,
+        columns: &[ColumnDescriptor<T>],
+        show: bool,
+    ) {
+        let is_shown = self.columns.iter().any(|c| c.name == columns[0].name);
+        if is_shown == show {
+            return;
+        }
+
+        if !show {
+            self.columns
+                .retain(|c| !columns.iter().any(|o| o.name == c.name));
+            return;
+        }
+
+        let index = self
+            .columns
+            .iter()
+            .position(|c| c.name == after)
+            .map(|i| i + 1)
+            .unwrap_or(self.columns.len());
+        self.columns.splice(index..index, columns.iter().copied());
+    }
+
+    pub fn column_mut(&mut self, name: &str) -> Option<&mut ColumnDescriptor<T>> {
+        self.columns.iter_mut().find(|c| c.name == name)
+    }
+
+    /// shows the columns in the order of the `columns_config`, returns whether it got changed
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        columns_config: &mut Vec<TableColumn>,
+        mut on_selected: impl FnMut(TableSelectionEvent<T>),
+    ) -> bool {
+        let mut rows_context = RowsContext {
+            modifiers: ui.input(|i| i.modifiers),
+            highlight_color: VisualsTab::changed_rows_highlight(ui.ctx())
+                .then(|| ui.visuals().selection.bg_fill),
+            is_highlighting: false,
+            minor_rows: VisualsTab::minor_rows(ui.ctx()),
+            details_label: self.details_label,
+            details_request: None,
+            rule_request: None,
+        };
+        let row_height = row_height(ui.ctx());
+        let names: Vec<_> = self.columns.iter().map(|c| c.name).collect();
+        let columns: Vec<_> = visible_columns(columns_config, names.iter().copied())
+            .into_iter()
+            .map(|i| self.columns[i])
+            .collect();
+        let mut columns_changed = false;
         ScrollArea::horizontal().show(ui, |ui| {
             Table::new(ui)
                 .cell_spacing(10.0)
-                .header(HEADER_HEIGHT, |mut r| {
-                    r.cell(|ui| {
-                        ui.label("Name");
-                    });
-
-                    for column in self.columns.iter() {
-                        self.show_column_header(&mut r, column);
+                .header(HEADER_HEIGHT, |r| {
+                    let mut show_columns_menu = |response: Response| {
+                        response.context_menu(|ui| {
+                            columns_changed |=
+                                show_columns_menu(columns_config, names.iter().copied(), ui);
+                        });
+                    };
+                    show_columns_menu(
+                        r.cell(|ui| {
+                            ui.label("Name");
+                        })
+                        .on_hover_text(ENTITY_ICONS_LEGEND),
+                    );
+
+                    for column in columns.iter() {
+                        show_columns_menu(self.show_column_header(r, column));
                     }
                 })
-                .body(ROW_HEIGHT, |mut t| {
+                .body(row_height, |mut t| {
                     for player in self.players.iter_mut() {
                         player.show(
-                            &self.columns,
+                            &columns,
                             &mut t,
                             0.0,
                             &mut self.selection,
                             &mut on_selected,
-                            modifiers,
+                            &mut rows_context,
                         );
                     }
                 });
         });
+
+        if rows_context.is_highlighting {
+            ui.ctx().request_repaint();
+        }
+        if rows_context.details_request.is_some() {
+            self.details_request = rows_context.details_request;
+        }
+        if rows_context.rule_request.is_some() {
+            self.rule_request = rows_context.rule_request;
+        }
+        columns_changed
     }
 
-    fn show_column_header(&mut self, row: &mut TableRow, column: &ColumnDescriptor<T>) {
+    fn show_column_header(&mut self, row: &mut TableRow, column: &ColumnDescriptor<T>) -> Response {
+        let sorted_ascending = match self.sorting {
+            Some((name, ascending)) if name == column.name => Some(ascending),
+            _ => None,
+        };
         let response = row.selectable_cell(false, |ui| {
-            ui.label(column.name);
+            ui.label(sort_indicator(column.name, sorted_ascending));
         });
         if response.clicked() {
-            (column.sort)(self);
+            // clicking the column, the rows are sorted by, again reverses the order
+            let ascending = match sorted_ascending {
+                Some(ascending) => !ascending,
+                None => column.sorts_ascending_first,
+            };
+            self.sort_by_column(*column, ascending);
         }
-        if let Some(info) = column.name_info {
-            response.on_hover_text(info);
+        match column.name_info {
+            Some(info) => response.on_hover_text(info),
+            None => response,
         }
     }
 
-    pub fn sort_by_option_f64_desc(
+    fn sort_by_column(&mut self, column: ColumnDescriptor<T>, ascending: bool) {
+        (column.sort)(self, ascending);
+        self.sorting = Some((column.name, ascending));
+    }
+
+    pub fn sort_by_option_f64(
         &mut self,
+        ascending: bool,
         mut key: impl FnMut(&MetricsTablePart<T>) -> Option<f64> + Copy,
     ) {
-        self.sort_by_desc(move |p| key(p).map(|v| F64TotalOrd(v)));
+        self.sort_by(ascending, move |p| key(p).map(F64TotalOrd));
     }
 
-    pub fn sort_by_option_f64_asc(
+    pub fn sort_by<K: Ord>(
         &mut self,
-        mut key: impl FnMut(&MetricsTablePart<T>) -> Option<f64> + Copy,
+        ascending: bool,
+        key: impl FnMut(&MetricsTablePart<T>) -> K + Copy,
     ) {
-        self.sort_by_asc(move |p| key(p).map(|v| F64TotalOrd(v)));
+        if ascending {
+            self.sort_by_asc(key);
+        } else {
+            self.sort_by_desc(key);
+        }
     }
 
     pub fn sort_by_desc<K: Ord>(&mut self, mut key: impl FnMut(&MetricsTablePart<T>) -> K + Copy) {
@@ -173,21 +414,118 @@ impl<T> MetricsTablePart<T> {
         number_formatter: &mut NumberFormatter,
         id_source: &mut u32,
         data_new: fn(&G, &Combat, &mut NumberFormatter) -> T,
+        share: fn(&T) -> Option<f64>,
     ) -> Self {
         let id = *id_source;
         *id_source += 1;
         let sub_parts = source
             .sub_groups()
             .values()
-            .map(|s| MetricsTablePart::new(s, combat, number_formatter, id_source, data_new))
+            .map(|s| MetricsTablePart::new(s, combat, number_formatter, id_source, data_new, share))
             .collect();
+        let data = data_new(source, combat, number_formatter);
+        let flags = combat.name_manager.info(source.name()).flags;
 
         Self {
-            data: data_new(source, combat, number_formatter),
+            share: share(&data),
+            data,
             name: source.name().get(&combat.name_manager).to_string(),
             id,
             sub_parts,
+            is_indirect_source: flags.contains(NameFlags::INDIRECT_SOURCE),
+            is_weapon: false,
+            entity_icon: Self::entity_icon(flags),
+            is_ambiguous_player: combat.ambiguous_players.contains(&source.name()),
+            filtered_out: false,
             open: false,
+            changed_at: None,
+        }
+    }
+
+    fn entity_icon(flags: NameFlags) -> Option<&'static str> {
+        if flags.contains(NameFlags::PLAYER) {
+            Some("👤")
+        } else if flags.contains(NameFlags::INDIRECT_SOURCE) {
+            Some("🐾")
+        } else if flags.contains(NameFlags::VALUE) {
+            None
+        } else if flags.intersects(NameFlags::SOURCE | NameFlags::TARGET) {
+            Some("🤖")
+        } else {
+            None
+        }
+    }
+
+    fn mark_changed_parts(
+        parts: &mut [Self],
+        previous_parts: &[Self],
+        now: Instant,
+        has_changed: impl Fn(&T, &T) -> bool + Copy,
+    ) {
+        for part in parts.iter_mut() {
+            let previous = match previous_parts.iter().find(|p| p.name == part.name) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            part.changed_at = if has_changed(&part.data, &previous.data) {
+                Some(now)
+            } else {
+                previous.changed_at
+            };
+            Self::mark_changed_parts(&mut part.sub_parts, &previous.sub_parts, now, has_changed);
+        }
+    }
+
+    fn find(&self, id: u32) -> Option<&Self> {
+        if self.id == id {
+            return Some(self);
+        }
+        self.sub_parts.iter().find_map(|s| s.find(id))
+    }
+
+    pub fn is_weapon(&self) -> bool {
+        self.is_weapon
+    }
+
+    fn mark_weapons(&mut self, filter: &QuickFilter) {
+        self.is_weapon = self.sub_parts.is_empty() && filter.is_weapon(&self.name);
+        self.sub_parts
+            .iter_mut()
+            .for_each(|s| s.mark_weapons(filter));
+    }
+
+    fn apply_quick_filter(&mut self, filter: &QuickFilter, is_below_pet: bool) -> bool {
+        let visible = if self.is_indirect_source && filter.excludes_pets() {
+            false
+        } else if self.sub_parts.is_empty() {
+            filter.is_leaf_visible(&self.name, is_below_pet || self.is_indirect_source)
+        } else {
+            let is_below_pet = is_below_pet || self.is_indirect_source;
+            self.sub_parts.iter_mut().fold(false, |visible, s| {
+                s.apply_quick_filter(filter, is_below_pet) | visible
+            })
+        };
+        self.filtered_out = !visible;
+        visible
+    }
+
+    fn highlight(&self, rows_context: &mut RowsContext) -> Option<Color32> {
+        let color = rows_context.highlight_color?;
+        let elapsed = self.changed_at?.elapsed();
+        if elapsed >= CHANGED_HIGHLIGHT_DURATION {
+            return None;
+        }
+
+        rows_context.is_highlighting = true;
+        let remaining = 1.0 - elapsed.as_secs_f32() / CHANGED_HIGHLIGHT_DURATION.as_secs_f32();
+        Some(color.gamma_multiply(0.4 * remaining))
+    }
+
+    fn is_minor(&self, rows_context: &RowsContext) -> bool {
+        match (rows_context.minor_rows, self.share) {
+            (Some(minor_rows), Some(share)) => share < minor_rows.threshold_percentage,
+            _ => false,
         }
     }
 
@@ -198,32 +536,44 @@ impl<T> MetricsTablePart<T> {
         indent: f32,
         selection: &mut SelectionTracker,
         on_selected: &mut impl FnMut(TableSelectionEvent<T>),
-        modifiers: Modifiers,
+        rows_context: &mut RowsContext,
     ) {
-        let response = table.selectable_row(selection.is_selected(self.id), |mut r| {
-            r.cell(|ui| {
-                ui.horizontal(|ui| {
-                    ui.add_space(indent * 30.0);
-                    let symbol = if self.open { "⏷" } else { "⏵" };
-                    let can_open = self.sub_parts.len() > 0;
-                    if ui
-                        .add_visible(can_open, SelectableLabel::new(false, symbol))
-                        .clicked()
-                    {
-                        self.open = !self.open;
-                    }
-
-                    ui.label(&self.name);
+        let highlight = self.highlight(rows_context);
+        let is_minor = indent > 0.0 && self.is_minor(rows_context);
+        let response =
+            table.highlighted_selectable_row(selection.is_selected(self.id), highlight, |r| {
+                if is_minor {
+                    r.set_opacity(MINOR_ROW_OPACITY);
+                }
+                r.cell(|ui| {
+                    ui.horizontal(|ui| {
+                        ui.add_space(indent * 30.0);
+                        let symbol = if self.open { "⏷" } else { "⏵" };
+                        let can_open = self.sub_parts.iter().any(|s| !s.filtered_out);
+                        if ui
+                            .add_visible(can_open, SelectableLabel::new(false, symbol))
+                            .clicked()
+                        {
+                            self.open = !self.open;
+                        }
+
+                        if let Some(icon) = self.entity_icon {
+                            ui.label(icon);
+                        }
+                        ui.label(&self.name);
+                        if self.is_ambiguous_player {
+                            show_ambiguous_player_warning(ui);
+                        }
+                    });
                 });
-            });
 
-            for column in columns.iter() {
-                (column.show)(self, &mut r);
-            }
-        });
+                for column in columns.iter() {
+                    (column.show)(self, r);
+                }
+            });
 
         if response.clicked() {
-            if modifiers.contains(Modifiers::CTRL) {
+            if rows_context.modifiers.contains(Modifiers::CTRL) {
                 selection.select_or_unselect_single(self, on_selected);
             } else {
                 selection.select_group(self, on_selected);
@@ -247,22 +597,72 @@ impl<T> MetricsTablePart<T> {
                 selection.select_or_unselect_single(self, on_selected);
                 ui.close_menu();
             }
+
+            if let Some(details_label) = rows_context.details_label {
+                if ui.selectable_label(false, details_label).clicked() {
+                    rows_context.details_request = Some(self.id);
+                    ui.close_menu();
+                }
+            }
+
+            // players and other entities are not matched by the damage or heal name
+            if indent > 0.0 && self.entity_icon.is_none() {
+                ui.separator();
+                for (label, target) in [
+                    ("exclude this (damage out)", RuleTarget::DamageOutExclusion),
+                    ("create grouping rule from this", RuleTarget::CustomGrouping),
+                    ("add to combat name rules", RuleTarget::CombatName),
+                ] {
+                    if ui.selectable_label(false, label).clicked() {
+                        rows_context.rule_request = Some(RuleRequest {
+                            target,
+                            name: self.name.clone(),
+                        });
+                        ui.close_menu();
+                    }
+                }
+            }
         });
 
         if self.open {
-            for sub_part in self.sub_parts.iter_mut() {
+            let hide_minor = rows_context.minor_rows.is_some_and(|m| m.hide);
+            let mut hidden_minor_count = 0;
+            for sub_part in self.sub_parts.iter_mut().filter(|s| !s.filtered_out) {
+                if hide_minor && sub_part.is_minor(rows_context) {
+                    hidden_minor_count += 1;
+                    continue;
+                }
+
                 sub_part.show(
                     columns,
                     table,
                     indent + 1.0,
                     selection,
                     on_selected,
-                    modifiers,
+                    rows_context,
                 );
             }
+
+            if hidden_minor_count > 0 {
+                Self::show_hidden_minor_row(table, indent + 1.0, hidden_minor_count);
+            }
         }
     }
 
+    fn show_hidden_minor_row(table: &mut TableBody, indent: f32, count: usize) {
+        table.row(|r| {
+            r.set_opacity(MINOR_ROW_OPACITY);
+            r.cell(|ui| {
+                ui.horizontal(|ui| {
+                    ui.add_space(indent * 30.0);
+                    ui.add_visible(false, SelectableLabel::new(false, "⏵"));
+                    let sources = if count == 1 { "source" } else { "sources" };
+                    ui.label(format!("…{} minor {}", count, sources));
+                });
+            });
+        });
+    }
+
     pub fn sort_by_desc<K: Ord>(&mut self, mut key: impl FnMut(&Self) -> K + Copy) {
         self.sub_parts.sort_unstable_by_key(|p| Reverse(key(p)));
 
@@ -1,9 +1,13 @@
+mod columns;
 mod common;
+mod damage_sources_table;
 mod damage_table;
 mod heal_table;
 mod metrics_table;
+mod quick_filter;
 mod summary_table;
 
+pub use damage_sources_table::DamageSourcesTable;
 pub use damage_table::DamageTable;
 pub use damage_table::DamageTablePart;
 pub use damage_table::DamageTablePartData;
@@ -11,4 +15,5 @@ pub use heal_table::HealTable;
 pub use heal_table::HealTablePart;
 pub use heal_table::HealTablePartData;
 pub use metrics_table::TableSelectionEvent;
+pub use quick_filter::QuickFilter;
 pub use summary_table::SummaryTable;
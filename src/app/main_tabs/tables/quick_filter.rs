@@ -0,0 +1,73 @@
+use eframe::egui::*;
+
+/// one-click filters for common subsets of the rows below the players
+#[derive(Default, Clone, PartialEq)]
+pub struct QuickFilter {
+    pub pets_only: bool,
+    pub weapons_only: bool,
+    pub exclude_pets: bool,
+    weapon_names: Vec<String>,
+}
+
+impl QuickFilter {
+    pub fn is_active(&self) -> bool {
+        self.pets_only || self.weapons_only || self.exclude_pets
+    }
+
+    /// returns true if a chip was toggled
+    pub fn show(&mut self, ui: &mut Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label("Quick Filters:");
+            changed |= Self::show_chip(ui, &mut self.pets_only, "Pets only");
+            changed |= Self::show_chip(ui, &mut self.weapons_only, "Weapons only");
+            changed |= Self::show_chip(ui, &mut self.exclude_pets, "Exclude pets");
+            if self.is_active() && ui.small_button("clear").clicked() {
+                self.pets_only = false;
+                self.weapons_only = false;
+                self.exclude_pets = false;
+                changed = true;
+            }
+        });
+        changed
+    }
+
+    fn show_chip(ui: &mut Ui, value: &mut bool, text: &str) -> bool {
+        ui.toggle_value(value, RichText::new(text).small())
+            .changed()
+    }
+
+    /// returns true if the weapon names differ from the ones currently used
+    pub fn update_weapon_names(&mut self, weapon_names: &[String]) -> bool {
+        if self.weapon_names == weapon_names {
+            return false;
+        }
+        self.weapon_names = weapon_names.to_vec();
+        true
+    }
+
+    pub(super) fn excludes_pets(&self) -> bool {
+        self.exclude_pets
+    }
+
+    pub(super) fn is_leaf_visible(&self, name: &str, is_below_pet: bool) -> bool {
+        if self.pets_only && !is_below_pet {
+            return false;
+        }
+
+        if self.weapons_only && !self.is_weapon(name) {
+            return false;
+        }
+
+        true
+    }
+
+    pub(super) fn is_weapon(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.weapon_names
+            .iter()
+            .map(|w| w.trim())
+            .filter(|w| !w.is_empty())
+            .any(|w| name.contains(&w.to_lowercase()))
+    }
+}
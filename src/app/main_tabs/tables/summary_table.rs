@@ -1,16 +1,19 @@
-use std::cmp::Reverse;
+use std::{cmp::Reverse, ops::Range};
 
-use chrono::Duration;
+use chrono::{Duration, NaiveDateTime};
 use eframe::egui::*;
 
 use crate::{
     analyzer::{Player as AnalyzedPlayer, *},
-    app::main_tabs::common::*,
+    app::{main_tabs::common::*, settings::TableColumn},
     custom_widgets::table::*,
     helpers::{number_formatting::NumberFormatter, *},
 };
 
-use super::common::Kills;
+use super::{
+    columns::*,
+    common::{show_ambiguous_player_warning, Kills},
+};
 
 macro_rules! col {
     ($name:expr, $sort:expr, $show:expr $(,)?) => {
@@ -25,186 +28,380 @@ macro_rules! col {
 static COLUMNS: &[ColumnDescriptor] = &[
     col!(
         "Outgoing DPS",
-        |t| t.sort_by_option_f64(|p| p.dps_out.all.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.dps_out.all.value),
         |p, r| p.dps_out.show(r),
     ),
     col!(
         "Total Outgoing Damage",
-        |t| t.sort_by_option_f64(|p| p.total_out_damage.all.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.total_out_damage.all.value),
         |p, r| p.total_out_damage.show(r),
     ),
     col!(
         "Outgoing Damage %",
-        |t| t.sort_by_option_f64(|p| p.total_out_damage_percentage.all.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.total_out_damage_percentage.all.value),
         |p, r| p.total_out_damage_percentage.show(r),
     ),
+    col!(
+        "Peak 10s DPS",
+        |t, a| t.sort_by_option_f64(a, |p| p.peak_dps_10s.value),
+        |p, r| {
+            p.peak_dps_10s.show(r);
+        },
+    ),
+    col!(
+        "Peak 30s DPS",
+        |t, a| t.sort_by_option_f64(a, |p| p.peak_dps_30s.value),
+        |p, r| {
+            p.peak_dps_30s.show(r);
+        },
+    ),
+    col!(
+        "Peak 60s DPS",
+        |t, a| t.sort_by_option_f64(a, |p| p.peak_dps_60s.value),
+        |p, r| {
+            p.peak_dps_60s.show(r);
+        },
+    ),
     col!(
         "Total Incoming Damage",
-        |t| t.sort_by_option_f64(|p| p.total_in_damage.all.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.total_in_damage.all.value),
         |p, r| p.total_in_damage.show(r),
     ),
     col!(
         "Incoming Damage %",
-        |t| t.sort_by_option_f64(|p| p.total_in_damage_percentage.all.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.total_in_damage_percentage.all.value),
         |p, r| p.total_in_damage_percentage.show(r),
     ),
+    col!(
+        "Drain Taken",
+        |t, a| t.sort_by_option_f64(a, |p| p.drain_taken.value),
+        |p, r| {
+            p.drain_taken.show(r);
+        },
+    ),
+    col!(
+        "Self Heal",
+        |t, a| t.sort_by_option_f64(a, |p| p.self_heal.all.value),
+        |p, r| p.self_heal.show(r),
+    ),
+    col!(
+        "Self Heal %",
+        |t, a| t.sort_by_option_f64(a, |p| p.self_heal_percentage.value),
+        |p, r| {
+            p.self_heal_percentage.show(r);
+        },
+    ),
+    col!(
+        "Heal to Others",
+        |t, a| t.sort_by_option_f64(a, |p| p.heal_to_others.all.value),
+        |p, r| p.heal_to_others.show(r),
+    ),
+    col!(
+        "Heal to Others %",
+        |t, a| t.sort_by_option_f64(a, |p| p.heal_to_others_percentage.value),
+        |p, r| {
+            p.heal_to_others_percentage.show(r);
+        },
+    ),
     col!(
         "Combat Duration",
-        |t| t.sort_by_key(|p| p.combat_duration.duration),
+        |t, a| t.sort_by_key(a, |p| p.combat_duration.duration),
         |p, r| {
-            p.combat_duration.show(r);
+            let response = p.combat_duration.show(r);
+            if let Some(pre_combat_damage) = &p.pre_combat_damage {
+                response.on_hover_text(pre_combat_damage);
+            }
         },
     ),
     col!(
         "Combat Duration %",
-        |t| t.sort_by_option_f64(|p| p.combat_duration_percentage.value),
+        |t, a| t.sort_by_option_f64(a, |p| p.combat_duration_percentage.value),
         |p, r| {
             p.combat_duration_percentage.show(r);
         },
     ),
+    col!(
+        "Start Delay",
+        |t, a| t.sort_by_option_f64(a, |p| p.start_delay.value),
+        |p, r| {
+            p.start_delay.show(r);
+        },
+    ),
     col!(
         "Active Duration",
-        |t| t.sort_by_key(|p| p.active_duration.duration),
+        |t, a| t.sort_by_key(a, |p| p.active_duration.duration),
         |p, r| {
             p.active_duration.show(r);
         },
     ),
-    col!("Deaths", |t| t.sort_by_key(|p| p.deaths.count), |p, r| {
-        p.deaths.show(r);
-    }),
+    col!(
+        "Deaths",
+        |t, a| t.sort_by_key(a, |p| p.deaths.count),
+        |p, r| {
+            p.deaths.show(r);
+        }
+    ),
     col!(
         "Kills",
-        |t| t.sort_by_key(|p| p.kills.total_count),
+        |t, a| t.sort_by_key(a, |p| p.kills.total_count),
         |p, r| p.kills.show(r),
     ),
     col!(
         "Player Kills",
-        |t| t.sort_by_key(|p| p.player_kills.count),
+        |t, a| t.sort_by_key(a, |p| p.player_kills.count),
         |p, r| {
             p.player_kills.show(r);
         },
     ),
     col!(
         "NPC Kills",
-        |t| t.sort_by_key(|p| p.npc_kills.count),
+        |t, a| t.sort_by_key(a, |p| p.npc_kills.count),
         |p, r| {
             p.npc_kills.show(r);
         },
     ),
 ];
 
+static RATE_COLUMNS: &[ColumnDescriptor] = &[
+    col!(
+        "Kills/min",
+        |t, a| t.sort_by_option_f64(a, |p| p.kills_per_minute.value),
+        |p, r| {
+            p.kills_per_minute.show(r);
+        },
+    ),
+    col!(
+        "Deaths/min",
+        |t, a| t.sort_by_option_f64(a, |p| p.deaths_per_minute.value),
+        |p, r| {
+            p.deaths_per_minute.show(r);
+        },
+    ),
+];
+
+#[derive(Clone, Copy)]
 struct ColumnDescriptor {
     name: &'static str,
-    sort: fn(&mut SummaryTable),
+    /// sorts the players ascending or descending
+    sort: fn(&mut SummaryTable, bool),
     show: fn(&Player, &mut TableRow),
 }
 
 pub struct SummaryTable {
+    columns: Vec<ColumnDescriptor>,
     players: Vec<Player>,
     selected_player: Option<usize>,
+    /// name of the column the players are sorted by and whether they are sorted ascending
+    sorting: Option<(&'static str, bool)>,
 }
 
 struct Player {
     name: String,
+    /// see [`Combat::ambiguous_players`]
+    is_ambiguous: bool,
     total_out_damage: ShieldAndHullTextValue,
     dps_out: ShieldAndHullTextValue,
     total_out_damage_percentage: ShieldAndHullTextValue,
+    peak_dps_10s: TextValue,
+    peak_dps_30s: TextValue,
+    peak_dps_60s: TextValue,
     total_in_damage: ShieldAndHullTextValue,
     total_in_damage_percentage: ShieldAndHullTextValue,
+    drain_taken: TextValue,
+    self_heal: ShieldAndHullTextValue,
+    self_heal_percentage: TextValue,
+    heal_to_others: ShieldAndHullTextValue,
+    heal_to_others_percentage: TextValue,
     combat_duration: TextDuration,
+    /// explains why the combat time starts after the first damage
+    pre_combat_damage: Option<String>,
     combat_duration_percentage: TextValue,
     active_duration: TextDuration,
+    start_delay: TextValue,
     kills: Kills,
     npc_kills: TextCount,
     player_kills: TextCount,
     deaths: TextCount,
+    kills_per_minute: TextValue,
+    deaths_per_minute: TextValue,
 }
 
 impl SummaryTable {
     pub fn empty() -> Self {
         Self {
+            columns: COLUMNS.to_vec(),
             players: Default::default(),
             selected_player: None,
+            sorting: None,
         }
     }
 
     pub fn new(combat: &Combat) -> Self {
         let combat_duration = time_range_to_duration_or_zero(&combat.combat_time);
+        let combat_start = combat
+            .combat_time
+            .as_ref()
+            .map(|t| t.start)
+            .unwrap_or(combat.active_time.start);
         let mut number_formatter = NumberFormatter::new();
         let mut table = Self {
+            columns: COLUMNS.to_vec(),
             players: combat
                 .players
                 .values()
                 .map(|p| {
                     Player::new(
                         combat_duration,
+                        combat_start,
                         p,
-                        &combat.name_manager,
+                        combat,
                         &mut number_formatter,
                     )
                 })
                 .collect(),
             selected_player: None,
+            sorting: None,
         };
-        table.sort_by_option_f64(|p| p.total_out_damage.all.value);
+        table.sort_by_column(COLUMNS[1], false);
         table
     }
 
-    pub fn show(&mut self, ui: &mut Ui) {
+    /// shows the columns in the order of the `columns_config`, returns whether it got changed
+    pub fn show(&mut self, columns_config: &mut Vec<TableColumn>, ui: &mut Ui) -> bool {
+        let row_height = row_height(ui.ctx());
+        let names: Vec<_> = self.columns.iter().map(|c| c.name).collect();
+        let columns = self.visible_columns(columns_config);
+        let mut columns_changed = false;
         ScrollArea::new([true, false]).show(ui, |ui| {
             Table::new(ui)
                 .header(HEADER_HEIGHT, |r| {
-                    r.cell(|ui| {
+                    let mut show_columns_menu = |response: Response| {
+                        response.context_menu(|ui| {
+                            columns_changed |=
+                                show_columns_menu(columns_config, names.iter().copied(), ui);
+                        });
+                    };
+                    show_columns_menu(r.cell(|ui| {
                         ui.horizontal(|ui| {
                             ui.label("Player");
                         });
-                    });
+                    }));
 
-                    for column in COLUMNS.iter() {
-                        Self::show_column_header(r, column.name, || {
-                            (column.sort)(self);
-                        });
+                    for column in columns.iter() {
+                        show_columns_menu(self.show_column_header(r, *column));
                     }
                 })
-                .body(ROW_HEIGHT, |t| {
+                .body(row_height, |t| {
                     for (i, player) in self.players.iter().enumerate() {
                         let player_selected = Some(i) == self.selected_player;
-                        if player.show(t, player_selected).clicked() {
+                        if player.show(&columns, t, player_selected).clicked() {
                             self.selected_player = if player_selected { None } else { Some(i) };
                         }
                     }
                 });
         });
+        columns_changed
+    }
+
+    pub fn players_count(&self) -> usize {
+        self.players.len()
     }
 
-    fn show_column_header(row: &mut TableRow, column_name: &str, sort: impl FnOnce()) {
-        if row
-            .selectable_cell(false, |ui| {
-                ui.label(column_name);
+    /// shows the `rows` without scrolling, selecting or sorting
+    pub fn show_rows(&self, rows: Range<usize>, columns_config: &[TableColumn], ui: &mut Ui) {
+        let row_height = row_height(ui.ctx());
+        let columns = self.visible_columns(columns_config);
+        Table::new(ui)
+            .header(HEADER_HEIGHT, |r| {
+                r.cell(|ui| {
+                    ui.label("Player");
+                });
+
+                for column in columns.iter() {
+                    r.cell(|ui| {
+                        ui.label(column.name);
+                    });
+                }
             })
-            .clicked()
-        {
-            sort();
+            .body(row_height, |t| {
+                for player in self.players[rows].iter() {
+                    player.show(&columns, t, false);
+                }
+            });
+    }
+
+    fn visible_columns(&self, columns_config: &[TableColumn]) -> Vec<ColumnDescriptor> {
+        visible_columns(columns_config, self.columns.iter().map(|c| c.name))
+            .into_iter()
+            .map(|i| self.columns[i])
+            .collect()
+    }
+
+    /// shows or hides the kills and deaths per minute columns
+    pub fn show_rate_columns(&mut self, show: bool) {
+        let is_shown = self.columns.iter().any(|c| c.name == RATE_COLUMNS[0].name);
+        if is_shown == show {
+            return;
+        }
+
+        if show {
+            self.columns.extend_from_slice(RATE_COLUMNS);
+        } else {
+            self.columns
+                .retain(|c| !RATE_COLUMNS.iter().any(|r| r.name == c.name));
         }
     }
 
-    fn sort_by_option_f64(&mut self, mut value: impl FnMut(&Player) -> Option<f64>) {
-        self.players
-            .sort_unstable_by_key(|p| Reverse(value(p).map(|v| F64TotalOrd(v))))
+    pub fn select_player(&mut self, name: &str) {
+        self.selected_player = self.players.iter().position(|p| p.name == name);
     }
 
-    fn sort_by_key<K: Ord>(&mut self, mut key: impl FnMut(&Player) -> K) {
-        self.players.sort_unstable_by_key(|p| Reverse(key(p)));
+    fn show_column_header(&mut self, row: &mut TableRow, column: ColumnDescriptor) -> Response {
+        let sorted_ascending = match self.sorting {
+            Some((name, ascending)) if name == column.name => Some(ascending),
+            _ => None,
+        };
+        let response = row.selectable_cell(false, |ui| {
+            ui.label(sort_indicator(column.name, sorted_ascending));
+        });
+        if response.clicked() {
+            // clicking the column, the players are sorted by, again reverses the order
+            self.sort_by_column(column, sorted_ascending == Some(false));
+        }
+        response
+    }
+
+    fn sort_by_column(&mut self, column: ColumnDescriptor, ascending: bool) {
+        (column.sort)(self, ascending);
+        self.sorting = Some((column.name, ascending));
+    }
+
+    fn sort_by_option_f64(
+        &mut self,
+        ascending: bool,
+        mut value: impl FnMut(&Player) -> Option<f64>,
+    ) {
+        self.sort_by_key(ascending, |p| value(p).map(F64TotalOrd));
+    }
+
+    fn sort_by_key<K: Ord>(&mut self, ascending: bool, mut key: impl FnMut(&Player) -> K) {
+        if ascending {
+            self.players.sort_unstable_by_key(key);
+        } else {
+            self.players.sort_unstable_by_key(|p| Reverse(key(p)));
+        }
     }
 }
 
 impl Player {
     fn new(
         combat_duration: Duration,
+        combat_start: NaiveDateTime,
         player: &AnalyzedPlayer,
-        name_manager: &NameManager,
+        combat: &Combat,
         number_formatter: &mut NumberFormatter,
     ) -> Self {
+        let name_manager = &combat.name_manager;
         let player_combat_duration = time_range_to_duration_or_zero(&player.combat_time);
         let player_combat_duration_percentage = if combat_duration.num_milliseconds() == 0 {
             0.0
@@ -214,6 +411,9 @@ impl Player {
                 * 100.0
         };
         let player_active_duration = time_range_to_duration_or_zero(&player.active_time);
+        let start_delay = player
+            .start_delay(combat_start)
+            .map(|d| d.num_milliseconds() as f64 / 1e3);
         let npc_kills: u32 = player
             .damage_out
             .kills
@@ -240,6 +440,9 @@ impl Player {
             .sum();
         Self {
             name: player.damage_out.name().get(name_manager).to_string(),
+            is_ambiguous: combat
+                .ambiguous_players
+                .contains(&player.damage_out.name()),
             total_out_damage: ShieldAndHullTextValue::new(
                 &player.damage_out.total_damage,
                 2,
@@ -251,6 +454,9 @@ impl Player {
                 number_formatter,
             ),
             dps_out: ShieldAndHullTextValue::new(&player.damage_out.dps, 2, number_formatter),
+            peak_dps_10s: TextValue::new(player.peak_dps.ten_seconds, 2, number_formatter),
+            peak_dps_30s: TextValue::new(player.peak_dps.thirty_seconds, 2, number_formatter),
+            peak_dps_60s: TextValue::new(player.peak_dps.sixty_seconds, 2, number_formatter),
             total_in_damage: ShieldAndHullTextValue::new(
                 &player.damage_in.total_damage,
                 2,
@@ -261,27 +467,80 @@ impl Player {
                 3,
                 number_formatter,
             ),
+            drain_taken: TextValue::new(player.damage_in.total_shield_drain, 2, number_formatter),
+            self_heal: ShieldAndHullTextValue::new(
+                &player.heal_out.target_split.to_self,
+                2,
+                number_formatter,
+            ),
+            self_heal_percentage: TextValue::option(
+                player.heal_out.target_split.self_percentage(),
+                3,
+                number_formatter,
+            ),
+            heal_to_others: ShieldAndHullTextValue::new(
+                &player.heal_out.target_split.to_others,
+                2,
+                number_formatter,
+            ),
+            heal_to_others_percentage: TextValue::option(
+                player.heal_out.target_split.others_percentage(),
+                3,
+                number_formatter,
+            ),
             combat_duration: TextDuration::new(player_combat_duration),
+            pre_combat_damage: player
+                .pre_combat_damage_start
+                .zip(player.combat_time.as_ref())
+                .map(|(first_damage, combat_time)| {
+                    format!(
+                        "The combat time starts {} s after the first damage,\nas the damage before was below the combat start threshold",
+                        number_formatter.format(
+                            combat_time
+                                .start
+                                .signed_duration_since(first_damage)
+                                .num_milliseconds() as f64
+                                / 1e3,
+                            1
+                        )
+                    )
+                }),
             combat_duration_percentage: TextValue::new(
                 player_combat_duration_percentage,
                 3,
                 number_formatter,
             ),
             active_duration: TextDuration::new(player_active_duration),
+            start_delay: TextValue {
+                text: start_delay.map(|d| format!("{} s", number_formatter.format(d, 2))),
+                value: start_delay,
+            },
             kills: Kills::new(&player.damage_out, name_manager),
             deaths: TextCount::new(player.damage_in.kills.values().copied().sum::<u32>() as _),
             npc_kills: TextCount::new(npc_kills as _),
             player_kills: TextCount::new(player_kills as _),
+            kills_per_minute: TextValue::option(player.kills_per_minute(), 2, number_formatter),
+            deaths_per_minute: TextValue::option(player.deaths_per_minute(), 2, number_formatter),
         }
     }
 
-    pub fn show(&self, table: &mut TableBody, selected: bool) -> Response {
+    pub fn show(
+        &self,
+        columns: &[ColumnDescriptor],
+        table: &mut TableBody,
+        selected: bool,
+    ) -> Response {
         table.selectable_row(selected, |r| {
             r.cell(|ui| {
-                ui.label(&self.name);
+                ui.horizontal(|ui| {
+                    ui.label(&self.name);
+                    if self.is_ambiguous {
+                        show_ambiguous_player_warning(ui);
+                    }
+                });
             });
 
-            for column in COLUMNS.iter() {
+            for column in columns.iter() {
                 (column.show)(self, r);
             }
         })
@@ -1,62 +1,124 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
 use eframe::egui::*;
 use rfd::FileDialog;
 
 use crate::{
     analyzer::Combat,
-    upload::{Records, Upload},
+    upload::{Records, Upload, UploadQueue},
 };
 
 use self::{
-    analysis_handling::AnalysisInfo, main_tabs::*, overlay::Overlay, settings::*, state::AppState,
-    status::*, summary_copy::SummaryCopy,
+    analysis_handling::AnalysisInfo,
+    combat_list::{CombatList, CombatListEntries},
+    combat_rename::CombatRename,
+    exports::ExportResultsWindow,
+    live_summary::LiveSummary,
+    main_tabs::*,
+    overlay::Overlay,
+    record_query::RecordQueryWindow,
+    settings::*,
+    single_instance::SingleInstance,
+    source_selection::show_source_selection,
+    state::AppState,
+    status::*,
+    summary_copy::SummaryCopy,
+    time_slice::TimeSliceSelection,
 };
 
-mod analysis_handling;
+pub mod analysis_handling;
+mod combat_list;
+mod combat_notes;
+mod combat_rename;
+mod exports;
+mod live_summary;
 pub mod logging;
 mod main_tabs;
 mod overlay;
-mod settings;
+mod record_query;
+pub mod settings;
+pub mod single_instance;
+mod source_selection;
 mod state;
 mod status;
 mod summary_copy;
+mod time_slice;
 
 pub struct App {
     settings_window: SettingsWindow,
-    combats: Vec<String>,
+    combats: CombatListEntries,
+    combat_list: CombatList,
     selected_combat_index: Option<usize>,
     selected_combat: Option<Arc<Combat>>,
+    /// combats of the combat list, which are shown merged into one, see [`AnalysisHandler::get_merged_combat`]
+    merged_combat_indices: Vec<usize>,
     status_indicator: StatusIndicator,
+    live_summary: LiveSummary,
     main_tabs: MainTabs,
     summary_copy: SummaryCopy,
+    export_results: ExportResultsWindow,
+    record_query: RecordQueryWindow,
+    time_slice: TimeSliceSelection,
+    combat_rename: CombatRename,
     overlay: Overlay,
     upload: Upload,
+    upload_queue: UploadQueue,
     records: Records,
+    auto_refresh_paused: bool,
+    single_instance: SingleInstance,
+    /// combatlog file handed over by another start of the app, waiting for the user to open it
+    handed_over_file: Option<PathBuf>,
     state: AppState,
 }
 
 impl App {
-    pub fn new(cc: &eframe::CreationContext) -> Self {
+    /// `combatlog_file` is the one the app was started with, if any
+    pub fn new(
+        cc: &eframe::CreationContext,
+        single_instance: SingleInstance,
+        combatlog_file: Option<PathBuf>,
+    ) -> Self {
         let mut style = Style::clone(&cc.egui_ctx.style());
         style.override_font_id = Some(FontId::monospace(12.0));
         cc.egui_ctx.set_style(Arc::new(style));
         cc.egui_ctx
             .memory_mut(|m| m.options.repaint_on_widget_change = false);
-        let state = AppState::new(&cc.egui_ctx);
-        let settings_window =
-            SettingsWindow::new(&cc.egui_ctx, cc.egui_ctx.native_pixels_per_point());
+        let mut state = AppState::new(&cc.egui_ctx);
+        let mut settings_window = SettingsWindow::new(
+            &cc.egui_ctx,
+            cc.egui_ctx.native_pixels_per_point(),
+            cc.integration_info.system_theme,
+        );
+        if let Some(file) = combatlog_file {
+            settings_window.open_combatlog_file(&mut state, &file);
+        }
+        let mut main_tabs = MainTabs::empty();
+        main_tabs.select_tab(state.settings.startup.default_tab);
+        if state.settings.startup.refresh {
+            state.analysis_handler.refresh();
+        }
         Self {
             settings_window,
             combats: Default::default(),
+            combat_list: Default::default(),
             selected_combat_index: None,
             selected_combat: None,
+            merged_combat_indices: Vec::new(),
             status_indicator: StatusIndicator::new(),
-            main_tabs: MainTabs::empty(),
+            live_summary: Default::default(),
+            main_tabs,
             summary_copy: Default::default(),
-            overlay: Overlay::new(&state.analysis_handler),
+            export_results: Default::default(),
+            record_query: Default::default(),
+            time_slice: Default::default(),
+            combat_rename: Default::default(),
+            overlay: Overlay::new(&state.analysis_handler, &state.settings.overlay),
             upload: Default::default(),
+            upload_queue: Default::default(),
             records: Default::default(),
+            auto_refresh_paused: false,
+            single_instance,
+            handed_over_file: None,
             state,
         }
     }
@@ -64,7 +126,11 @@ impl App {
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &Context, frame: &mut eframe::Frame) {
-        self.handle_analysis_infos();
+        self.handle_analysis_infos(ctx);
+        self.handle_handed_over_files(ctx);
+        self.pause_auto_refresh_when_minimized(ctx);
+        self.settings_window
+            .update_system_theme(ctx, frame.info().system_theme);
 
         CentralPanel::default().show(ctx, |ui| {
             ui.vertical(|ui| {
@@ -80,33 +146,74 @@ impl eframe::App for App {
                 });
 
                 ui.horizontal_wrapped(|ui| {
-                    self.status_indicator
-                        .show(self.state.analysis_handler.is_busy(), ui);
-
-                    ComboBox::new("combat list", "Combats")
-                        .width(400.0)
-                        .selected_text(self.main_tabs.identifier.as_str())
-                        .show_ui(ui, |ui| {
-                            for (i, combat) in self.combats.iter().enumerate().rev() {
-                                if ui
-                                    .selectable_value(
-                                        &mut self.selected_combat_index,
-                                        Some(i),
-                                        combat.as_str(),
-                                    )
-                                    .changed()
-                                {
-                                    if let Some(combat_index) = self.selected_combat_index {
-                                        self.state.analysis_handler.get_combat(combat_index);
-                                    }
-                                }
+                    show_source_selection(ui, &mut self.settings_window, &mut self.state);
+                    if self.status_indicator.show(
+                        self.state.analysis_handler.busy_state(),
+                        self.state.settings.auto_refresh.enable,
+                        ui,
+                    ) {
+                        self.state.analysis_handler.cancel();
+                    }
+
+                    if self.state.settings.auto_refresh.enable && self.live_summary.show(ui) {
+                        self.main_tabs.select_tab(MainTab::Summary);
+                    }
+
+                    self.combat_list.show_sort(ui);
+
+                    let date_time_format = self.state.settings.visuals.date_time_format;
+                    let click = self.combat_list.show(
+                        ui,
+                        &self.combats,
+                        &self.state.combat_notes,
+                        &date_time_format,
+                        self.main_tabs.identifier(&date_time_format),
+                        |index| {
+                            if self.merged_combat_indices.is_empty() {
+                                self.selected_combat_index == Some(index)
+                            } else {
+                                self.merged_combat_indices.contains(&index)
                             }
-                        });
+                        },
+                    );
+                    if let Some(click) = click {
+                        if click.merge {
+                            self.toggle_merged_combat(click.combat_index);
+                        } else if !click.was_selected || !self.merged_combat_indices.is_empty() {
+                            self.merged_combat_indices.clear();
+                            self.selected_combat_index = Some(click.combat_index);
+                            self.state.analysis_handler.get_combat(click.combat_index);
+                        }
+                    }
+
+                    let shows_partial_combat = self.shows_partial_combat();
+                    self.combat_rename.show(
+                        ui,
+                        self.selected_combat.as_deref(),
+                        self.selected_combat_index
+                            .filter(|_| !shows_partial_combat),
+                        &mut self.state,
+                    );
 
                     if ui.button("Refresh Now ⟲").clicked() {
                         self.state.analysis_handler.refresh();
                     }
 
+                    if ui
+                        .button("Import Logs…")
+                        .on_hover_text("adds the combats of saved logs to the combat list")
+                        .clicked()
+                    {
+                        if let Some(files) = FileDialog::new()
+                            .set_title("Import Logs")
+                            .add_filter("log", &["log", "gz"])
+                            .set_parent(frame)
+                            .pick_files()
+                        {
+                            self.state.analysis_handler.import_files(files);
+                        }
+                    }
+
                     self.settings_window
                         .show_clear_log_dialog(&self.state.analysis_handler, ui);
 
@@ -123,18 +230,43 @@ impl eframe::App for App {
                         self.state.settings.save();
                     }
 
-                    if ui
-                        .add_enabled(
-                            self.selected_combat.is_some(),
-                            Button::new("Save Combat 💾"),
-                        )
-                        .clicked()
+                    let combat_indices = match self.merged_combat_indices.as_slice() {
+                        [] => self.selected_combat_index.as_slice(),
+                        indices => indices,
+                    };
+                    self.time_slice.show(
+                        ui,
+                        self.selected_combat.as_deref(),
+                        combat_indices,
+                        &self.state.analysis_handler,
+                    );
+
+                    let is_merged = self
+                        .selected_combat
+                        .as_ref()
+                        .is_some_and(|c| !c.merged_combats.is_empty());
+                    let shows_whole_combat = !self.shows_partial_combat();
+                    let log_pos = self.selected_combat.as_ref().map(|c| c.log_pos.is_some());
+                    let mut save_combat =
+                        ui.add_enabled(log_pos == Some(true), Button::new("Save Combat 💾"));
+                    if is_merged {
+                        save_combat =
+                            save_combat.on_disabled_hover_text("merged combats can not be saved");
+                    } else if log_pos == Some(false) {
+                        save_combat = save_combat.on_disabled_hover_text(
+                            "combats of compressed logs can not be saved, as they have no position in the log",
+                        );
+                    }
+                    if save_combat.clicked()
                     {
                         if let Some(file) = FileDialog::new()
                             .set_title("Save Combat")
                             .add_filter("log", &["log"])
                             .set_file_name(
-                                &self.selected_combat.as_ref().unwrap().file_identifier(),
+                                self.selected_combat
+                                    .as_ref()
+                                    .unwrap()
+                                    .formatted_file_identifier(&date_time_format),
                             )
                             .set_parent(frame)
                             .save_file()
@@ -147,26 +279,171 @@ impl eframe::App for App {
 
                     self.upload.show(
                         ui,
-                        self.selected_combat.as_deref(),
-                        &self.state.settings.analysis,
+                        self.selected_combat.as_deref().filter(|_| shows_whole_combat),
+                        self.selected_combat_index,
+                        &self.state.analysis_handler,
                         &self.state.settings.upload.oscr_url,
                     );
+                    self.upload_queue.show_button(ui);
 
                     ui.separator();
-                    self.summary_copy.show(self.selected_combat.as_deref(), ui);
+                    if self.summary_copy.show(
+                        self.selected_combat.as_deref(),
+                        &self.state.combat_notes,
+                        &mut self.state.settings.summary_copy,
+                        &self.state.settings.summary,
+                        ui,
+                    ) {
+                        self.state.settings.save();
+                        self.state
+                            .analysis_handler
+                            .set_export_settings(&self.state.settings);
+                    }
+                    let exports = &self.state.settings.exports.presets;
+                    if ui
+                        .add_enabled(
+                            self.selected_combat.is_some()
+                                && shows_whole_combat
+                                && !exports.is_empty(),
+                            Button::new("Run Exports"),
+                        )
+                        .on_hover_text("runs the export presets (see settings) for this combat")
+                        .clicked()
+                    {
+                        self.state
+                            .analysis_handler
+                            .run_exports(self.selected_combat_index.unwrap(), exports.clone());
+                    }
+                    self.record_query.show_button(ui);
                     ui.separator();
-                    self.overlay.show(ui);
+                    self.overlay.show(&mut self.state.settings, ui);
                 });
 
-                self.main_tabs.show(ui);
+                if let Some(request) = self.main_tabs.show(&mut self.state, ui) {
+                    self.settings_window.add_rule(&self.state, request);
+                }
             });
         });
+
+        self.export_results.show(ctx);
+        let query_combat_index = self
+            .selected_combat_index
+            .filter(|_| !self.shows_partial_combat());
+        self.record_query
+            .show(ctx, &self.state.analysis_handler, query_combat_index);
+        self.upload_queue.show(
+            ctx,
+            &self.combats,
+            &self.state.settings.visuals.date_time_format,
+            &self.state.analysis_handler,
+            &self.state.settings.upload.oscr_url,
+        );
     }
 }
 
 impl App {
-    fn handle_analysis_infos(&mut self) {
-        let combatlog_file = &self.state.settings.analysis.combatlog_file;
+    /// combatlog files of other starts of the app, see [`SingleInstance`], opened once the user confirmed it
+    fn handle_handed_over_files(&mut self, ctx: &Context) {
+        let files: Vec<_> = self.single_instance.handed_over_files(ctx).collect();
+        if let Some(file) = files.into_iter().flatten().last() {
+            self.handed_over_file = Some(file);
+        }
+        let Some(file) = &self.handed_over_file else {
+            return;
+        };
+
+        // the analyzed log is only switched, if the user wants to
+        let open = Window::new("Open Combatlog")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label("The app was started again with the combatlog:");
+                ui.label(file.to_string_lossy());
+                ui.add_space(10.0);
+                ui.horizontal(|ui| {
+                    if ui.button("Open").clicked() {
+                        return Some(true);
+                    }
+                    if ui.button("Keep Current").clicked() {
+                        return Some(false);
+                    }
+                    None
+                })
+                .inner
+            })
+            .and_then(|r| r.inner)
+            .flatten();
+        match open {
+            Some(true) => {
+                let file = self.handed_over_file.take().unwrap();
+                self.settings_window
+                    .open_combatlog_file(&mut self.state, &file);
+            }
+            Some(false) => self.handed_over_file = None,
+            None => (),
+        }
+    }
+
+    fn pause_auto_refresh_when_minimized(&mut self, ctx: &Context) {
+        let auto_refresh = &self.state.settings.auto_refresh;
+        let minimized = ctx.input(|i| i.viewport().minimized.unwrap_or(false));
+        let pause = auto_refresh.enable
+            && auto_refresh.pause_when_minimized
+            && minimized
+            && !self.overlay.is_shown();
+        if pause == self.auto_refresh_paused {
+            return;
+        }
+
+        self.auto_refresh_paused = pause;
+        self.state.analysis_handler.enable_auto_refresh(!pause);
+        if !pause {
+            // catch up with what happened while paused
+            self.state.analysis_handler.refresh();
+        }
+    }
+
+    /// true while merged combats or a time slice of a combat are shown,
+    /// rather than the whole combat at [`App::selected_combat_index`]
+    fn shows_partial_combat(&self) -> bool {
+        !self.merged_combat_indices.is_empty()
+            || self
+                .selected_combat
+                .as_ref()
+                .is_some_and(|c| c.time_slice.is_some())
+    }
+
+    /// adds the combat to the merged ones or removes it, starting with the currently selected combat
+    fn toggle_merged_combat(&mut self, combat_index: usize) {
+        if self.merged_combat_indices.is_empty() {
+            self.merged_combat_indices
+                .extend(self.selected_combat_index);
+        }
+        match self
+            .merged_combat_indices
+            .iter()
+            .position(|i| *i == combat_index)
+        {
+            Some(position) => _ = self.merged_combat_indices.remove(position),
+            None => self.merged_combat_indices.push(combat_index),
+        }
+
+        match self.merged_combat_indices.as_slice() {
+            [] => (),
+            [single] => {
+                self.selected_combat_index = Some(*single);
+                self.state.analysis_handler.get_combat(*single);
+                self.merged_combat_indices.clear();
+            }
+            indices => self
+                .state
+                .analysis_handler
+                .get_merged_combat(indices.to_vec()),
+        }
+    }
+
+    fn handle_analysis_infos(&mut self, ctx: &Context) {
         for info in self.state.analysis_handler.check_for_info() {
             match info {
                 AnalysisInfo::Combat(combat) => {
@@ -177,19 +454,109 @@ impl App {
                     latest_combat,
                     combats,
                     file_size,
+                    invalid_records,
                 } => {
-                    self.main_tabs.update(&latest_combat);
-                    self.combats = combats;
-                    self.selected_combat_index = Some(self.combats.len() - 1);
-                    self.selected_combat = Some(latest_combat);
+                    self.live_summary
+                        .update(&latest_combat, &self.state.settings.my_player);
+                    self.combats.replace(combats);
+                    // nothing is selected yet only before the first refresh completed,
+                    // merged or sliced combats are kept until another combat gets selected
+                    if (self.selected_combat.is_some()
+                        || self.state.settings.startup.select_newest_combat)
+                        && !self.shows_partial_combat()
+                    {
+                        self.main_tabs.update(&latest_combat);
+                        self.selected_combat_index = Some(self.combats.len() - 1);
+                        self.selected_combat = Some(latest_combat);
+                    }
                     self.status_indicator.status = Status::Loaded {
-                        combatlog_file: combatlog_file.clone(),
+                        combatlog_file: self.state.settings.analysis.combatlog_file.clone(),
                         file_size,
+                        invalid_records,
                     };
                 }
-                AnalysisInfo::RefreshError => {
+                AnalysisInfo::CombatListRenamed(combats) => {
+                    self.combats.replace(combats);
+                }
+                AnalysisInfo::LogRestarted { combats, replaced } => {
+                    self.combats.replace(combats);
+                    self.merged_combat_indices.clear();
+                    self.selected_combat_index = None;
+                    self.status_indicator.show_note(if replaced {
+                        "log file was replaced and is analyzed from the start"
+                    } else {
+                        "log file got truncated and is analyzed from the start"
+                    });
+                }
+                AnalysisInfo::CombatlogFileSwitched(file) => {
+                    let analysis = &mut self.state.settings.analysis;
+                    let previous_file = std::mem::replace(&mut analysis.combatlog_file, file);
+                    analysis.add_recent_combatlog_file(&previous_file);
+                    let file = analysis.combatlog_file.clone();
+                    analysis.add_recent_combatlog_file(&file);
+                    self.state.settings.save();
+                    self.merged_combat_indices.clear();
+                    self.status_indicator.show_note(format!(
+                        "switched to the newest combatlog file: {}",
+                        self.state.settings.analysis.combatlog_file
+                    ));
+                }
+                AnalysisInfo::Imported {
+                    combats,
+                    failed,
+                    reanalyzed,
+                } => {
+                    // the imported combats are listed first, which shifts the indices
+                    let previous_combats = self.combats.replace(combats);
+                    let new_index = |index: usize| {
+                        let previous = previous_combats.get(index)?;
+                        self.combats
+                            .iter()
+                            .find(|c| {
+                                c.identity == previous.identity && c.origin == previous.origin
+                            })
+                            .map(|c| c.index)
+                    };
+                    self.selected_combat_index = self.selected_combat_index.and_then(new_index);
+                    self.merged_combat_indices = self
+                        .merged_combat_indices
+                        .iter()
+                        .filter_map(|i| new_index(*i))
+                        .collect();
+                    if failed.is_empty() && reanalyzed {
+                        self.status_indicator
+                            .show_note("imported logs analyzed with the new settings");
+                    } else if failed.is_empty() {
+                        self.status_indicator.show_note("logs imported");
+                    } else {
+                        self.status_indicator
+                            .show_error_note(format!("failed to import: {}", failed.join(", ")));
+                    }
+                }
+                AnalysisInfo::Cancelled(combats) => {
+                    self.combats.replace(combats);
+                    self.merged_combat_indices.clear();
+                    self.selected_combat_index = None;
+                    self.status_indicator.show_note("analysis cancelled");
+                }
+                AnalysisInfo::ExportsDone(results) => {
+                    self.export_results.set_results(results, ctx);
+                }
+                AnalysisInfo::RecordQueryDone(result) => self.record_query.set_result(result),
+                AnalysisInfo::CombatsReadForUpload(combats) => self.upload_queue.enqueue(combats),
+                AnalysisInfo::CombatReadForUpload(combat) => self.upload.set_combat(combat),
+                AnalysisInfo::SliceError(error) => {
+                    self.status_indicator
+                        .show_error_note(format!("failed to analyze the time slice: {}", error));
+                }
+                AnalysisInfo::ClearLogError(error) => {
+                    self.status_indicator
+                        .show_error_note(format!("failed to clear the log: {}", error));
+                }
+                AnalysisInfo::RefreshError(error) => {
                     self.status_indicator.status = Status::LoadError {
-                        combatlog_file: combatlog_file.clone(),
+                        combatlog_file: self.state.settings.analysis.combatlog_file.clone(),
+                        error,
                     };
                 }
             }
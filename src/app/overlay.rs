@@ -1,26 +1,50 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use eframe::{egui::*, epaint::mutex::Mutex};
 
 use crate::{
     analyzer::{Combat, Player},
-    custom_widgets::{popup_button::PopupButton, table::Table},
+    custom_widgets::{number_edit::NumberEdit, popup_button::PopupButton, table::Table},
     helpers::number_formatting::NumberFormatter,
 };
 
-use super::analysis_handling::{AnalysisHandler, AnalysisInfo};
+use super::{
+    analysis_handling::{AnalysisHandler, AnalysisInfo},
+    live_summary::is_my_player,
+    settings::{OverlaySettings, Settings},
+};
+
+/// top left corner of the primary monitor, with some distance to the edges
+const DEFAULT_POSITION: Pos2 = pos2(50.0, 50.0);
+/// how much of the overlay has to be on a monitor at least, so it can still be moved
+const VISIBLE_MARGIN: f32 = 20.0;
+/// if the overlay did not render within this time, its window is considered as failed to be created
+const VIEWPORT_CREATION_TIMEOUT: Duration = Duration::from_secs(3);
 
 pub struct Overlay(Arc<Mutex<OverlayInner>>);
 
 struct OverlayInner {
     position: Option<Pos2>,
+    /// the position is checked, when the overlay gets shown
+    check_position: bool,
     current_size: Vec2,
     data: DisplayData,
     show: bool,
     move_around: bool,
     columns: Vec<ColumnDescriptor>,
+    number_format: OverlaySettings,
+    my_player: String,
     analysis_handler: AnalysisHandler,
     state: State,
+    /// when the overlay got shown, until it rendered for the first time
+    viewport_pending_since: Option<Instant>,
+    /// shows the overlay as a normal window, after the always on top passthrough window could not be created
+    fallback_window: bool,
+    /// shown in a popup, once the overlay window could not be created
+    viewport_error: Option<String>,
 }
 
 #[derive(Default)]
@@ -39,6 +63,9 @@ struct DisplayData {
 
 struct DisplayPlayer {
     name: String,
+    is_my_player: bool,
+    /// see [`Combat::ambiguous_players`]
+    is_ambiguous: bool,
     columns: Vec<ColumnValue>,
 }
 
@@ -58,7 +85,13 @@ fn val(value: f64, value_string: String) -> ColumnValue {
 struct ColumnDescriptor {
     name: &'static str,
     enabled: bool,
-    select: fn(&Player, &mut NumberFormatter) -> ColumnValue,
+    select: fn(&Player, &mut OverlayFormatter) -> ColumnValue,
+}
+
+/// formats the overlay numbers independent of the tables, to save space
+struct OverlayFormatter<'a> {
+    formatter: NumberFormatter,
+    number_format: &'a OverlaySettings,
 }
 
 macro_rules! col {
@@ -69,22 +102,30 @@ macro_rules! col {
             select: $select,
         }
     };
-    ($name:expr, $enabled:expr, $select:expr $(,)?) => {
-        ColumnDescriptor {
-            name: $name,
-            enabled: $enabled,
-            select: $select,
-        }
-    };
 }
 
 static COLUMNS: &[ColumnDescriptor] = &[
-    col!("DPS", true, |p, f| {
+    col!("DPS", |p, f| {
         val(
             p.damage_out.damage_metrics.dps.all,
             f.format(p.damage_out.damage_metrics.dps.all, 2),
         )
     }),
+    col!("Peak 10s DPS", |p, f| {
+        val(p.peak_dps.ten_seconds, f.format(p.peak_dps.ten_seconds, 2))
+    }),
+    col!("Peak 30s DPS", |p, f| {
+        val(
+            p.peak_dps.thirty_seconds,
+            f.format(p.peak_dps.thirty_seconds, 2),
+        )
+    }),
+    col!("Peak 60s DPS", |p, f| {
+        val(
+            p.peak_dps.sixty_seconds,
+            f.format(p.peak_dps.sixty_seconds, 2),
+        )
+    }),
     col!("Dmg Out", |p, f| {
         val(
             p.damage_out.damage_metrics.total_damage.all,
@@ -107,6 +148,12 @@ static COLUMNS: &[ColumnDescriptor] = &[
             f.format(p.damage_out.max_one_hit.damage, 2),
         )
     }),
+    col!("Drain Out", |p, f| {
+        val(
+            p.damage_out.damage_metrics.total_shield_drain,
+            f.format(p.damage_out.damage_metrics.total_shield_drain, 2),
+        )
+    }),
     col!("Dmg In", |p, f| {
         val(
             p.damage_in.damage_metrics.total_damage.all,
@@ -155,6 +202,46 @@ static COLUMNS: &[ColumnDescriptor] = &[
                 .unwrap_or(String::new()),
         )
     }),
+    col!("HPS", |p, f| {
+        val(
+            p.heal_out.heal_metrics.hps.all,
+            f.format(p.heal_out.heal_metrics.hps.all, 2),
+        )
+    }),
+    col!("Heal Out", |p, f| {
+        val(
+            p.heal_out.heal_metrics.total_heal.all,
+            f.format(p.heal_out.heal_metrics.total_heal.all, 2),
+        )
+    }),
+    col!("Heal Out %", |p, f| {
+        val(
+            p.heal_out.heal_percentage.all.unwrap_or(0.0),
+            p.heal_out
+                .heal_percentage
+                .all
+                .map(|p| f.format(p, 3))
+                .unwrap_or(String::new()),
+        )
+    }),
+    col!("Heal to Others", |p, f| {
+        val(
+            p.heal_out.target_split.to_others.all,
+            f.format(p.heal_out.target_split.to_others.all, 2),
+        )
+    }),
+    col!("Heal In", |p, f| {
+        val(
+            p.heal_in.heal_metrics.total_heal.all,
+            f.format(p.heal_in.heal_metrics.total_heal.all, 2),
+        )
+    }),
+    col!("HPS In", |p, f| {
+        val(
+            p.heal_in.heal_metrics.hps.all,
+            f.format(p.heal_in.heal_metrics.hps.all, 2),
+        )
+    }),
     col!("Kills", |p, _| {
         let count: u32 = p.damage_out.kills.values().copied().sum();
         val(count as _, count.to_string())
@@ -163,23 +250,53 @@ static COLUMNS: &[ColumnDescriptor] = &[
         let count: u32 = p.damage_in.kills.values().copied().sum();
         val(count as _, count.to_string())
     }),
+    col!("Kills/min", |p, f| {
+        let rate = p.kills_per_minute();
+        val(
+            rate.unwrap_or(0.0),
+            rate.map(|r| f.format(r, 2)).unwrap_or(String::new()),
+        )
+    }),
+    col!("Deaths/min", |p, f| {
+        let rate = p.deaths_per_minute();
+        val(
+            rate.unwrap_or(0.0),
+            rate.map(|r| f.format(r, 2)).unwrap_or(String::new()),
+        )
+    }),
 ];
 
 impl Overlay {
-    pub fn new(root_handler: &AnalysisHandler) -> Self {
+    pub fn new(root_handler: &AnalysisHandler, settings: &OverlaySettings) -> Self {
         Self(Arc::new(Mutex::new(OverlayInner {
             move_around: true,
-            columns: COLUMNS.iter().cloned().collect(),
+            columns: COLUMNS
+                .iter()
+                .map(|c| ColumnDescriptor {
+                    enabled: settings.columns.iter().any(|n| n == c.name),
+                    ..c.clone()
+                })
+                .collect(),
+            number_format: settings.clone(),
+            my_player: String::new(),
             current_size: Vec2::ZERO,
             data: Default::default(),
             position: None,
+            check_position: false,
             show: false,
             analysis_handler: root_handler.get_handler(true, Self::viewport_id()),
             state: State::Empty,
+            viewport_pending_since: None,
+            fallback_window: false,
+            viewport_error: None,
         })))
     }
 
-    pub fn show(self: &Self, ui: &mut Ui) {
+    pub fn is_shown(&self) -> bool {
+        self.0.lock().show
+    }
+
+    pub fn show(&self, settings: &mut Settings, ui: &mut Ui) {
         let mut inner = self.0.lock();
 
         if Button::new("Overlay")
@@ -194,17 +311,108 @@ impl Overlay {
         PopupButton::new("⛭").show(ui, |ui| {
             ui.label("Configure what columns are displayed in the Overlay");
             let mut config_changed = false;
+            ui.horizontal(|ui| {
+                for (label, enabled) in [("Select All", true), ("Clear", false)] {
+                    if ui.button(label).clicked() {
+                        inner.columns.iter_mut().for_each(|c| c.enabled = enabled);
+                        config_changed = true;
+                    }
+                }
+            });
             for column in inner.columns.iter_mut() {
                 if ui.checkbox(&mut column.enabled, column.name).clicked() {
                     config_changed = true;
                 }
             }
+
+            ui.separator();
+            let overlay_settings = &mut settings.overlay;
+            let sort_column = overlay_settings
+                .sort_column
+                .as_deref()
+                .unwrap_or("first shown column")
+                .to_string();
+            ComboBox::from_label("Sort by")
+                .selected_text(sort_column)
+                .show_ui(ui, |ui| {
+                    config_changed |= ui
+                        .selectable_value(
+                            &mut overlay_settings.sort_column,
+                            None,
+                            "first shown column",
+                        )
+                        .changed();
+                    for column in inner.columns.iter().filter(|c| c.enabled) {
+                        config_changed |= ui
+                            .selectable_value(
+                                &mut overlay_settings.sort_column,
+                                Some(column.name.to_string()),
+                                column.name,
+                            )
+                            .changed();
+                    }
+                });
+            config_changed |= ui
+                .checkbox(
+                    &mut overlay_settings.pin_my_player,
+                    "Always show My Player at the top",
+                )
+                .on_hover_text("My Player can be set in the File tab of the settings")
+                .changed();
+            ui.horizontal(|ui| {
+                if ui
+                    .checkbox(
+                        &mut overlay_settings.abbreviate_numbers,
+                        "Abbreviate numbers (e.g. 312k) with",
+                    )
+                    .changed()
+                {
+                    config_changed = true;
+                }
+                ui.add_enabled_ui(overlay_settings.abbreviate_numbers, |ui| {
+                    if NumberEdit::new(
+                        &mut overlay_settings.abbreviated_decimals,
+                        "overlay abbreviated decimals",
+                    )
+                    .clamp_max(3)
+                    .desired_text_edit_width(20.0)
+                    .show(ui)
+                    .changed()
+                    {
+                        config_changed = true;
+                    }
+                });
+                ui.label("decimals");
+            });
+
+            ui.separator();
+            if ui
+                .button("Reset overlay position")
+                .on_hover_text(
+                    "Moves the Overlay back into the top left corner of the primary monitor",
+                )
+                .clicked()
+            {
+                inner.position = Some(DEFAULT_POSITION);
+            }
+
             if config_changed {
+                overlay_settings.columns = inner
+                    .columns
+                    .iter()
+                    .filter(|c| c.enabled)
+                    .map(|c| c.name.to_string())
+                    .collect();
+                if inner.number_format != *overlay_settings {
+                    inner.number_format = overlay_settings.clone();
+                    settings.save();
+                }
                 inner.force_update(ui.ctx());
             }
         });
 
-        ui.add_enabled_ui(inner.show, |ui: &mut Ui| {
+        inner.show_viewport_error(ui.ctx());
+        ui.add_enabled_ui(inner.show && !inner.fallback_window, |ui: &mut Ui| {
             if Button::new("✋")
                 .selected(inner.move_around)
                 .ui(ui)
@@ -215,23 +423,34 @@ impl Overlay {
             }
         });
 
+        if inner.my_player != settings.my_player {
+            inner.my_player.clone_from(&settings.my_player);
+            inner.force_update(ui.ctx());
+        }
         inner.poll_update(ui.ctx());
         if !inner.show {
             return;
         }
+        inner.validate_position(ui.ctx());
+        if !inner.check_viewport_creation(ui.ctx()) {
+            return;
+        }
 
         let mut builder = ViewportBuilder::default()
             .with_title("CLA Overlay")
-            .with_decorations(inner.move_around)
             .with_minimize_button(false)
             .with_maximize_button(false)
             .with_close_button(true)
             .with_resizable(false)
             .with_min_inner_size(vec2(240.0, 80.0))
-            .with_inner_size(inner.current_size)
-            .with_always_on_top()
-            .with_taskbar(false)
-            .with_mouse_passthrough(!inner.move_around);
+            .with_inner_size(inner.current_size);
+        if !inner.fallback_window {
+            builder = builder
+                .with_decorations(inner.move_around)
+                .with_always_on_top()
+                .with_taskbar(false)
+                .with_mouse_passthrough(!inner.move_around);
+        }
         builder.position = inner.position;
         drop(inner);
         let inner = self.0.clone();
@@ -252,6 +471,7 @@ impl Overlay {
 
 impl OverlayInner {
     fn show_overlay(&mut self, ctx: &Context) {
+        self.viewport_pending_since = None;
         self.check_update(ctx);
         CentralPanel::default().show(ctx, |ui| {
             if ctx.input_for(Overlay::viewport_id(), |i| i.viewport().close_requested()) {
@@ -260,6 +480,7 @@ impl OverlayInner {
             self.position = ctx.input_for(Overlay::viewport_id(), |i| {
                 i.viewport().outer_rect.map(|r| r.left_top())
             });
+            let my_player_color = ui.visuals().selection.stroke.color;
             let required_size = Table::new(ui)
                 .min_scroll_height(f32::MAX)
                 .header(15.0, |h| {
@@ -275,14 +496,27 @@ impl OverlayInner {
                 })
                 .body(25.0, |t| {
                     for player in self.data.players.iter() {
+                        let text = |text: &str| {
+                            let text = RichText::new(text);
+                            if player.is_my_player {
+                                text.color(my_player_color)
+                            } else {
+                                text
+                            }
+                        };
                         t.row(|r| {
                             r.cell(|ui| {
-                                ui.label(player.name.as_str());
+                                if player.is_ambiguous {
+                                    ui.label(text(&format!("⚠ {}", player.name)))
+                                        .on_hover_text("same character name as another player");
+                                } else {
+                                    ui.label(text(&player.name));
+                                }
                             });
 
                             for column in player.columns.iter() {
                                 r.cell(|ui| {
-                                    ui.label(column.value_string.as_str());
+                                    ui.label(text(&column.value_string));
                                 });
                             }
                         });
@@ -306,9 +540,108 @@ impl OverlayInner {
 
     fn toggle_show(&mut self) {
         self.show = !self.show;
+        self.check_position = self.show;
+        self.viewport_pending_since = self.show.then(Instant::now);
         self.analysis_handler.enable_auto_refresh(self.show);
     }
 
+    /// Eframe only logs failures to create a window, so a window that never rendered is considered as failed.
+    /// Falls back to a normal window first, before the overlay gets disabled.
+    /// Returns false, if the overlay should not be shown this frame.
+    fn check_viewport_creation(&mut self, ctx: &Context) -> bool {
+        let Some(pending_since) = self.viewport_pending_since else {
+            return true;
+        };
+        let elapsed = pending_since.elapsed();
+        if elapsed < VIEWPORT_CREATION_TIMEOUT {
+            ctx.request_repaint_after(VIEWPORT_CREATION_TIMEOUT - elapsed);
+            return true;
+        }
+
+        let reason = format!(
+            "the overlay window did not render within {} seconds after it was shown",
+            VIEWPORT_CREATION_TIMEOUT.as_secs()
+        );
+        if self.fallback_window {
+            log::error!("failed to create the overlay as normal window: {}", reason);
+            self.viewport_error = Some(format!(
+                "The overlay could not be shown, neither as overlay nor as normal window.\n\n{}",
+                reason
+            ));
+            self.toggle_show();
+            return false;
+        }
+
+        log::error!(
+            "failed to create the always on top passthrough overlay window, falling back to a normal window: {}",
+            reason
+        );
+        self.viewport_error = Some(format!(
+            "The overlay could not be shown in front of other windows, so it is shown as a normal window instead.\n\n{}",
+            reason
+        ));
+        self.fallback_window = true;
+        self.move_around = true;
+        // skip a frame, so the viewport gets dropped and is created again as normal window
+        self.viewport_pending_since = Some(Instant::now());
+        ctx.request_repaint();
+        false
+    }
+
+    fn show_viewport_error(&mut self, ctx: &Context) {
+        let Some(error) = &self.viewport_error else {
+            return;
+        };
+        let mut close = false;
+        Window::new("Overlay Error")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(error);
+                ui.label("The details are in the log file of the app.");
+                close = ui.button("OK").clicked();
+            });
+        if close {
+            self.viewport_error = None;
+        }
+    }
+
+    /// Resets the position, if the overlay would end up outside of the displays (e.g. on a disconnected monitor).
+    /// eframe does not expose the monitor layout, so the monitors are assumed to be of the same size and lined up,
+    /// starting with the primary monitor at the origin.
+    fn validate_position(&mut self, ctx: &Context) {
+        if !std::mem::take(&mut self.check_position) {
+            return;
+        }
+        let Some(position) = self.position else {
+            return;
+        };
+        let (Some(monitor_size), Some(main_window)) =
+            ctx.input(|i| (i.viewport().monitor_size, i.viewport().outer_rect))
+        else {
+            return;
+        };
+
+        let overlay = Rect::from_min_size(position, self.current_size.max(vec2(1.0, 1.0)));
+        let primary_monitor = Rect::from_min_size(Pos2::ZERO, monitor_size);
+        // the monitor of the main window is the one its center is on
+        let monitor_index = (main_window.center().to_vec2() / monitor_size).floor();
+        let main_window_monitor =
+            Rect::from_min_size((monitor_index * monitor_size).to_pos2(), monitor_size);
+        let is_visible_on = |monitor: Rect| overlay.intersects(monitor.shrink(VISIBLE_MARGIN));
+        if is_visible_on(primary_monitor) || is_visible_on(main_window_monitor) {
+            return;
+        }
+
+        log::warn!(
+            "overlay position {:?} is outside of the displays, resetting it to {:?}",
+            position,
+            DEFAULT_POSITION
+        );
+        self.position = Some(DEFAULT_POSITION);
+    }
+
     fn check_update(&mut self, ctx: &Context) {
         self.poll_update(ctx);
         let combat = match &self.state {
@@ -320,13 +653,16 @@ impl OverlayInner {
     }
 
     fn poll_update(&mut self, ctx: &Context) {
-        let combat = match self.analysis_handler.check_for_info().last() {
-            Some(AnalysisInfo::Refreshed {
-                latest_combat,
-                combats: _,
-                file_size: _,
-            }) => latest_combat,
-            _ => return,
+        let combat = self
+            .analysis_handler
+            .check_for_info()
+            .filter_map(|i| match i {
+                AnalysisInfo::Refreshed { latest_combat, .. } => Some(latest_combat),
+                _ => None,
+            })
+            .last();
+        let Some(combat) = combat else {
+            return;
         };
         self.state = State::Update(combat);
         if self.show {
@@ -348,14 +684,16 @@ impl OverlayInner {
 
         let mut display_data = DisplayData::default();
         display_data.columns = self.columns.iter().filter(|c| c.enabled).cloned().collect();
-        let mut formatter = NumberFormatter::new();
+        let mut formatter = OverlayFormatter {
+            formatter: NumberFormatter::new(),
+            number_format: &self.number_format,
+        };
         for (&player_name, player) in combat.players.iter() {
+            let name = combat.name_manager.get_name(player_name).unwrap();
             let mut display_player = DisplayPlayer {
-                name: combat
-                    .name_manager
-                    .get_name(player_name)
-                    .unwrap()
-                    .to_string(),
+                name: name.to_string(),
+                is_my_player: is_my_player(name, &self.my_player),
+                is_ambiguous: combat.ambiguous_players.contains(&player_name),
                 columns: Vec::new(),
             };
             for column in display_data.columns.iter() {
@@ -367,16 +705,41 @@ impl OverlayInner {
         }
 
         if display_data.columns.len() > 0 {
-            display_data
-                .players
-                .sort_by(|p1, p2| p1.sort_value().total_cmp(&p2.sort_value()).reverse());
+            let sort_column = self
+                .number_format
+                .sort_column
+                .as_ref()
+                .and_then(|s| display_data.columns.iter().position(|c| c.name == s))
+                .unwrap_or(0);
+            display_data.players.sort_by(|p1, p2| {
+                p1.sort_value(sort_column)
+                    .total_cmp(&p2.sort_value(sort_column))
+                    .reverse()
+            });
+        }
+        if self.number_format.pin_my_player {
+            display_data.players.sort_by_key(|p| !p.is_my_player);
         }
         self.data = display_data;
     }
 }
 
+impl OverlayFormatter<'_> {
+    fn format(&mut self, number: f64, precision: usize) -> String {
+        if self.number_format.abbreviate_numbers {
+            return self.formatter.format_abbreviated(
+                number,
+                self.number_format.abbreviated_decimals,
+                precision,
+            );
+        }
+
+        self.formatter.format(number, precision)
+    }
+}
+
 impl DisplayPlayer {
-    fn sort_value(&self) -> f64 {
-        self.columns.first().map(|c| c.value).unwrap_or(0.0)
+    fn sort_value(&self, column: usize) -> f64 {
+        self.columns.get(column).map(|c| c.value).unwrap_or(0.0)
     }
 }
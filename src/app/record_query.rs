@@ -0,0 +1,219 @@
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+use eframe::egui::*;
+use itertools::Itertools;
+
+use crate::{
+    analyzer::{settings::*, *},
+    custom_widgets::table::Table,
+    helpers::number_formatting::NumberFormatter,
+};
+
+use super::{analysis_handling::AnalysisHandler, settings::RulesTable};
+
+/// records listed by a query, any further matches are only counted
+const LISTED_MATCHES: usize = 100;
+
+#[derive(Clone, Debug)]
+pub struct RecordQueryResult {
+    pub count: usize,
+    /// the first [`LISTED_MATCHES`] matching records
+    pub matches: Vec<RecordMatch>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordMatch {
+    time: NaiveDateTime,
+    source: String,
+    target: String,
+    indirect_source: String,
+    value_name: String,
+    value_type: String,
+    flags: String,
+    amount: f64,
+}
+
+/// counts the records of the selected combat, which match all rules of the query
+#[derive(Default)]
+pub struct RecordQueryWindow {
+    is_open: bool,
+    rules: Vec<MatchRule>,
+    selected_rule: Option<usize>,
+    is_running: bool,
+    result: Option<Result<RecordQueryResult, String>>,
+}
+
+/// Reads the records of the `combat` again and collects the ones matching all enabled `rules`.
+pub fn query_records(
+    rules: &[MatchRule],
+    combat: &Combat,
+    combatlog_file: &Path,
+) -> Result<RecordQueryResult, String> {
+    let mut result = RecordQueryResult {
+        count: 0,
+        matches: Vec::new(),
+    };
+    let read = combat.read_log_records(combatlog_file, |record| {
+        if rules
+            .iter()
+            .filter(|r| r.enabled)
+            .all(|r| r.matches_record(record))
+        {
+            result.count += 1;
+            if result.matches.len() < LISTED_MATCHES {
+                result.matches.push(RecordMatch::new(record));
+            }
+        }
+        true
+    });
+    if !read {
+        return Err("failed to read the combat from the log".to_string());
+    }
+    Ok(result)
+}
+
+impl RecordMatch {
+    fn new(record: &Record) -> Self {
+        let name = |entity: &Entity| entity.name().unwrap_or_default().to_string();
+        Self {
+            time: record.time,
+            source: name(&record.source),
+            target: name(&record.target),
+            indirect_source: name(&record.indirect_source),
+            value_name: record.value_name.to_string(),
+            value_type: record.value_type.to_string(),
+            flags: record.value_flags.iter_names().map(|(n, _)| n).join(" | "),
+            amount: record.value.amount(),
+        }
+    }
+}
+
+impl RecordQueryWindow {
+    pub fn show_button(&mut self, ui: &mut Ui) {
+        if ui
+            .button("Query Records…")
+            .on_hover_text("counts and lists the records of the combat matching rules")
+            .clicked()
+        {
+            self.is_open = true;
+        }
+    }
+
+    pub fn set_result(&mut self, result: Result<RecordQueryResult, String>) {
+        self.is_running = false;
+        self.result = Some(result);
+    }
+
+    /// `combat_index` is the one of the selected combat, `None` if its records can not be read
+    pub fn show(
+        &mut self,
+        ctx: &Context,
+        analysis_handler: &AnalysisHandler,
+        combat_index: Option<usize>,
+    ) {
+        let mut is_open = self.is_open;
+        Window::new("Record Query")
+            .open(&mut is_open)
+            .default_size([900.0, 500.0])
+            .show(ctx, |ui| {
+                RulesTable::new(
+                    &mut self.rules,
+                    "Records matching all rules",
+                    &[
+                        MatchAspect::DamageOrHealName,
+                        MatchAspect::SourceOrTargetName,
+                        MatchAspect::SourceOrTargetUniqueName,
+                        MatchAspect::IndirectSourceName,
+                        MatchAspect::IndirectUniqueSourceName,
+                    ],
+                    &mut self.selected_rule,
+                )
+                .show(ui);
+
+                ui.horizontal(|ui| {
+                    let run = ui
+                        .add_enabled(
+                            combat_index.is_some() && !self.is_running,
+                            Button::new("Run Query"),
+                        )
+                        .on_disabled_hover_text("the records of merged combats can not be queried");
+                    if run.clicked() {
+                        self.is_running = true;
+                        analysis_handler.query_records(combat_index.unwrap(), self.rules.clone());
+                    }
+                    match &self.result {
+                        _ if self.is_running => _ = ui.spinner(),
+                        Some(Ok(result)) => _ = ui.label(Self::result_summary(result)),
+                        Some(Err(error)) => {
+                            _ = ui.colored_label(ui.visuals().error_fg_color, error)
+                        }
+                        None => (),
+                    }
+                });
+                ui.separator();
+
+                if let Some(Ok(result)) = &self.result {
+                    Self::show_matches(&result.matches, ui);
+                }
+            });
+        self.is_open = is_open;
+    }
+
+    fn result_summary(result: &RecordQueryResult) -> String {
+        if result.count > result.matches.len() {
+            format!(
+                "{} matching records, the first {} are listed",
+                result.count,
+                result.matches.len()
+            )
+        } else {
+            format!("{} matching records", result.count)
+        }
+    }
+
+    fn show_matches(matches: &[RecordMatch], ui: &mut Ui) {
+        let mut formatter = NumberFormatter::new();
+        Table::new(ui)
+            .id("record query table")
+            .min_scroll_height(200.0)
+            .max_scroll_height(f32::MAX)
+            .cell_spacing(10.0)
+            .header(15.0, |r| {
+                for header in [
+                    "Time",
+                    "Source",
+                    "Target",
+                    "Indirect Source",
+                    "Value Name",
+                    "Type",
+                    "Flags",
+                    "Value",
+                ] {
+                    r.cell(|ui| {
+                        ui.label(header);
+                    });
+                }
+            })
+            .body(20.0, |t| {
+                for record in matches.iter() {
+                    t.row(|r| {
+                        for text in [
+                            &record.time.format("%H:%M:%S%.1f").to_string(),
+                            &record.source,
+                            &record.target,
+                            &record.indirect_source,
+                            &record.value_name,
+                            &record.value_type,
+                            &record.flags,
+                            &formatter.format(record.amount, 2),
+                        ] {
+                            r.cell(|ui| {
+                                ui.label(text);
+                            });
+                        }
+                    });
+                }
+            });
+    }
+}
@@ -1,10 +1,11 @@
 use std::borrow::BorrowMut;
 
 use eframe::egui::*;
+use rfd::FileDialog;
 
 use super::Settings;
 use crate::analyzer::Combat;
-use crate::custom_widgets::table::Table;
+use crate::custom_widgets::{slider_text_edit::SliderTextEdit, table::Table};
 use crate::unwrap_or_return;
 use crate::{analyzer::settings::*, custom_widgets::popup_button::PopupButton};
 
@@ -19,6 +20,39 @@ pub struct AnalysisTab {
     custom_grouping_rules: CustomGroupingRules,
     damage_out_exclusion_rules: DamageOutExclusionRules,
     combat_names_rules: CombatNameRules,
+    player_merges: PlayerMerges,
+    rules_sharing: RulesSharing,
+    /// the rules the view scrolls to, after a rule got added by [`AnalysisTab::add_rule`]
+    scroll_to: Option<RuleTarget>,
+}
+
+/// rules, which can be created from a row of the tables
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RuleTarget {
+    DamageOutExclusion,
+    CustomGrouping,
+    CombatName,
+}
+
+/// a rule to be created from the `name` of a table row, for the user to confirm in the settings
+#[derive(Clone, Debug)]
+pub struct RuleRequest {
+    pub target: RuleTarget,
+    pub name: String,
+}
+
+/// exports the rules to a file or imports them from one, to share them e.g. within a fleet
+#[derive(Default)]
+struct RulesSharing {
+    /// imported rules, until the user chose to replace or merge them
+    pending_import: Option<AnalysisRules>,
+    /// result of the last export or import
+    status: Option<Result<String, String>>,
+}
+
+#[derive(Default)]
+struct PlayerMerges {
+    selected: Option<usize>,
 }
 
 #[derive(Default)]
@@ -39,6 +73,8 @@ struct DamageOutExclusionRules {
 
 #[derive(Default)]
 struct CombatNameRules {
+    /// opens the collapsed rules once
+    open: bool,
     selected_group: Option<usize>,
     selected_rule: Option<usize>,
     selected_additional_info_group: Option<usize>,
@@ -53,7 +89,7 @@ struct GroupRulesTable<'a, T: BorrowMut<RulesGroup> + Default> {
     popup_extra_space: f32,
 }
 
-struct RulesTable<'a> {
+pub struct RulesTable<'a> {
     rules: &'a mut Vec<MatchRule>,
     title: &'a str,
     match_aspect_set: &'a [MatchAspect],
@@ -61,6 +97,46 @@ struct RulesTable<'a> {
 }
 
 impl AnalysisTab {
+    /// adds a rule matching the damage or heal name of the `request` and selects it
+    pub fn add_rule(&mut self, modified_settings: &mut AnalysisSettings, request: RuleRequest) {
+        let mut rule = MatchRule::default();
+        rule.aspect = MatchAspect::DamageOrHealName;
+        rule.method = MatchMethod::Equals;
+        rule.expression.clone_from(&request.name);
+        let group = RulesGroup {
+            name: request.name,
+            rules: vec![rule.clone()],
+            ..Default::default()
+        };
+        match request.target {
+            RuleTarget::DamageOutExclusion => {
+                let rules = &mut modified_settings.damage_out_exclusion_rules;
+                rules.push(rule);
+                self.damage_out_exclusion_rules.selected = Some(rules.len() - 1);
+            }
+            RuleTarget::CustomGrouping => {
+                let groups = &mut modified_settings.custom_group_rules;
+                groups.push(group);
+                self.custom_grouping_rules.selected_group = Some(groups.len() - 1);
+                self.custom_grouping_rules.selected_rule = Some(0);
+            }
+            RuleTarget::CombatName => {
+                let rules = &mut modified_settings.combat_name_rules;
+                rules.push(CombatNameRule {
+                    name_rule: group,
+                    ..Default::default()
+                });
+                self.combat_names_rules = CombatNameRules {
+                    open: true,
+                    selected_group: Some(rules.len() - 1),
+                    selected_rule: Some(0),
+                    ..Default::default()
+                };
+            }
+        }
+        self.scroll_to = Some(request.target);
+    }
+
     pub fn show(
         &mut self,
         modified_settings: &mut Settings,
@@ -76,7 +152,109 @@ impl AnalysisTab {
         {
             self.list_selected_combat_occurred_names = true;
         }
+        if let Some(combat) = selected_combat {
+            Self::show_applied_rules(combat, ui);
+        }
+        if self.rules_sharing.show(&mut modified_settings.analysis, ui) {
+            // the indices of the rules changed
+            self.indirect_source_reversal_rules = Default::default();
+            self.custom_grouping_rules = Default::default();
+            self.damage_out_exclusion_rules = Default::default();
+            self.combat_names_rules = Default::default();
+        }
 
+        ui.add_space(10.0);
+        let dps_denominator = &mut modified_settings.analysis.dps_denominator;
+        ComboBox::from_label("DPS Denominator")
+            .selected_text(dps_denominator.display())
+            .width(200.0)
+            .show_ui(ui, |ui| {
+                [
+                    DpsDenominator::PlayerCombatTime,
+                    DpsDenominator::PlayerActiveTime,
+                    DpsDenominator::CombatDuration,
+                ]
+                .into_iter()
+                .for_each(|d| {
+                    ui.selectable_value(dps_denominator, d, d.display())
+                        .on_hover_text(d.description());
+                });
+            });
+
+        ui.add_space(10.0);
+        ui.label(
+            "Uptime Gap in seconds (hits or ticks closer than this count as continuous uptime)",
+        );
+        SliderTextEdit::new(
+            &mut modified_settings.analysis.uptime_gap_seconds,
+            0.5..=10.0,
+            "uptime gap slider",
+        )
+        .clamp_to_range(false)
+        .step_by(0.5)
+        .desired_text_edit_width(40.0)
+        .clamp_min(0.0)
+        .show(ui);
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.add(
+                DragValue::new(&mut modified_settings.analysis.combat_start_damage_threshold)
+                    .speed(1_000.0)
+                    .clamp_range(0.0..=f64::MAX),
+            );
+            ui.label("Combat Start Damage Threshold within");
+            ui.add(
+                DragValue::new(&mut modified_settings.analysis.combat_start_window_seconds)
+                    .speed(0.5)
+                    .clamp_range(0.5..=60.0)
+                    .suffix(" s"),
+            );
+        })
+        .response
+        .on_hover_text(
+            "A player's combat time only starts, once they dealt this much damage within the time span,\n\
+            or with their first damage after another player did so.\n\
+            This keeps single shots while buffing before the pull out of the combat time.\n\
+            0 starts the combat time with the first damage.",
+        );
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.add(
+                DragValue::new(&mut modified_settings.analysis.anomaly_threshold)
+                    .speed(1_000_000.0)
+                    .clamp_range(0.0..=f64::MAX),
+            );
+            ui.label(
+                "Anomaly Threshold (damage or heal values above this are flagged as implausible)",
+            );
+        });
+        ui.checkbox(
+            &mut modified_settings.analysis.exclude_anomalies,
+            "Exclude anomalies from the Max One-Hit and the graphs",
+        )
+        .on_hover_text("Anomalies are still counted towards all other values. They are listed in the Debug settings.");
+        ui.add_space(20.0);
+
+        ui.checkbox(
+            &mut modified_settings.analysis.separate_pet_instances,
+            "Separate Pet Instances (e.g. \"To'Duj Fighter #3\")",
+        )
+        .on_hover_text("Every instance of a pet gets its own row instead of being merged by name. This can make the tables very large.");
+        ui.checkbox(
+            &mut modified_settings.analysis.detect_ally_shield_heals,
+            "Detect shield heals on allies, which are logged like shield damage",
+        )
+        .on_hover_text("Shield values logged with a positive first and a non-zero second value are counted as heals instead of damage. Actual shield damage is always logged with negative values.");
+        ui.checkbox(
+            &mut modified_settings.analysis.drop_duplicate_records,
+            "Drop blocks of records duplicated by the game",
+        )
+        .on_hover_text("The game occasionally duplicates parts of the log, which doubles the values of the combat. Lines repeating a recent block of lines are dropped. Identical lines right after each other (e.g. ticks of several DoT stacks) are kept. The amount is shown on the Summary tab.");
+        ui.add_space(20.0);
+
+        ui.separator();
         self.indirect_source_reversal_rules
             .show(&mut modified_settings.analysis, ui);
         ui.add_space(20.0);
@@ -86,20 +264,105 @@ impl AnalysisTab {
             self.custom_grouping_rules
                 .show(&mut modified_settings.analysis, ui);
         });
+        self.scroll_to_added_rule(RuleTarget::CustomGrouping, ui);
         ui.add_space(20.0);
 
         ui.separator();
         self.damage_out_exclusion_rules
             .show(&mut modified_settings.analysis, ui);
+        self.scroll_to_added_rule(RuleTarget::DamageOutExclusion, ui);
+        ui.checkbox(
+            &mut modified_settings.analysis.exclude_from_incoming,
+            "Also exclude from incoming damage and healing",
+        )
+        .on_hover_text(
+            "Excluded records never count towards the outgoing damage and healing, the Total Outgoing Damage and the outgoing percentages.\n\
+            With this enabled they are also left out of the incoming damage and healing of the targets,\n\
+            the Total Incoming Damage and the incoming percentages, so that the numbers of all tabs add up.",
+        );
         ui.add_space(20.0);
 
         ui.separator();
         self.combat_names_rules
             .show(&mut modified_settings.analysis, ui);
+        self.scroll_to_added_rule(RuleTarget::CombatName, ui);
+        ui.add_space(20.0);
+
+        ui.separator();
+        self.player_merges.show(&mut modified_settings.analysis, ui);
 
         self.show_occurred_names_window(selected_combat, ui);
     }
 
+    fn scroll_to_added_rule(&mut self, target: RuleTarget, ui: &mut Ui) {
+        if self.scroll_to == Some(target) {
+            self.scroll_to = None;
+            ui.scroll_to_cursor(Some(Align::Center));
+        }
+    }
+
+    /// lists the rules, which affected the `combat`, as they were when it got analyzed
+    fn show_applied_rules(combat: &Combat, ui: &mut Ui) {
+        let applied = &combat.applied_rules;
+        CollapsingHeader::new("Applied rules")
+            .id_source("applied rules")
+            .show(ui, |ui| {
+                let rule_description = |r: &MatchRule| {
+                    format!(
+                        "{} {} \"{}\"",
+                        r.aspect.display(),
+                        r.method.display().to_lowercase(),
+                        r.expression
+                    )
+                };
+                Self::show_applied_rules_section(
+                    "Indirect Source Grouping Reversal Rules",
+                    applied
+                        .reversal_rules
+                        .iter()
+                        .map(|(i, r)| (*i, rule_description(r))),
+                    ui,
+                );
+                Self::show_applied_rules_section(
+                    "Custom Grouping Rules",
+                    applied.grouping_rules.iter().map(|(i, n)| (*i, n.clone())),
+                    ui,
+                );
+                Self::show_applied_rules_section(
+                    "Damage Out Exclusion Rules",
+                    applied
+                        .exclusion_rules
+                        .iter()
+                        .map(|(i, r)| (*i, rule_description(r))),
+                    ui,
+                );
+                Self::show_applied_rules_section(
+                    "Combat Name Detection Rules",
+                    applied
+                        .combat_name_rules
+                        .iter()
+                        .map(|(i, n)| (*i, n.clone())),
+                    ui,
+                );
+            });
+    }
+
+    /// the `rules` by their index and description
+    fn show_applied_rules_section(
+        title: &str,
+        rules: impl ExactSizeIterator<Item = (usize, String)>,
+        ui: &mut Ui,
+    ) {
+        ui.strong(title);
+        if rules.len() == 0 {
+            ui.weak("none");
+        }
+        for (rule, description) in rules {
+            ui.label(format!("#{} {}", rule + 1, description));
+        }
+        ui.add_space(5.0);
+    }
+
     fn show_occurred_names_window(&mut self, selected_combat: Option<&Combat>, ui: &mut Ui) {
         let combat = unwrap_or_return!(selected_combat);
         if !self.list_selected_combat_occurred_names {
@@ -203,6 +466,124 @@ impl AnalysisTab {
     }
 }
 
+impl RulesSharing {
+    /// returns true, if the rules got replaced
+    fn show(&mut self, modified_settings: &mut AnalysisSettings, ui: &mut Ui) -> bool {
+        ui.horizontal(|ui| {
+            if ui
+                .button("Export rules…")
+                .on_hover_text("saves the grouping, exclusion and combat name rules to a file")
+                .clicked()
+            {
+                self.export(modified_settings);
+            }
+            if ui
+                .button("Import rules…")
+                .on_hover_text("loads rules exported by someone else")
+                .clicked()
+            {
+                self.import();
+            }
+
+            match &self.status {
+                Some(Ok(status)) => _ = ui.label(status),
+                Some(Err(error)) => _ = ui.colored_label(ui.visuals().error_fg_color, error),
+                None => (),
+            }
+        });
+
+        let Some(rules) = &self.pending_import else {
+            return false;
+        };
+        let mut finished = None;
+        let mut replaced = false;
+        ui.horizontal(|ui| {
+            ui.label(format!(
+                "Import {} reversal, {} grouping, {} exclusion and {} combat name rules by",
+                rules.indirect_source_grouping_revers_rules.len(),
+                rules.custom_group_rules.len(),
+                rules.damage_out_exclusion_rules.len(),
+                rules.combat_name_rules.len()
+            ));
+            if ui
+                .button("Replace")
+                .on_hover_text("the current rules are removed")
+                .clicked()
+            {
+                modified_settings.replace_rules(rules.clone());
+                replaced = true;
+                finished = Some(Some("rules replaced"));
+            }
+            if ui
+                .button("Merge")
+                .on_hover_text("only rules and groups (by name), which do not exist yet, are added")
+                .clicked()
+            {
+                modified_settings.merge_rules(rules);
+                finished = Some(Some("rules merged"));
+            }
+            if ui.button("Cancel").clicked() {
+                finished = Some(None);
+            }
+        });
+
+        if let Some(status) = finished {
+            self.pending_import = None;
+            self.status = status.map(|s| Ok(format!("{}, press Ok to apply them", s)));
+        }
+        replaced
+    }
+
+    fn export(&mut self, modified_settings: &AnalysisSettings) {
+        let Some(file) = FileDialog::new()
+            .set_title("Export Rules")
+            .add_filter("json", &["json"])
+            .set_file_name("analysis_rules.json")
+            .save_file()
+        else {
+            return;
+        };
+
+        let result = serde_json::to_string_pretty(&modified_settings.rules())
+            .map_err(|e| e.to_string())
+            .and_then(|data| std::fs::write(&file, data).map_err(|e| e.to_string()));
+        self.status = Some(match result {
+            Ok(()) => Ok("rules exported".to_string()),
+            Err(err) => {
+                log::error!("failed to export the rules to {:?}: {}", file, err);
+                Err(format!("failed to export the rules: {}", err))
+            }
+        });
+    }
+
+    fn import(&mut self) {
+        let Some(file) = FileDialog::new()
+            .set_title("Import Rules")
+            .add_filter("json", &["json"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        let rules = std::fs::read_to_string(&file)
+            .map_err(|e| e.to_string())
+            .and_then(|data| {
+                serde_json::from_str::<AnalysisRules>(&data)
+                    .map_err(|e| format!("not a valid rules file: {}", e))
+            });
+        match rules {
+            Ok(rules) => {
+                self.pending_import = Some(rules);
+                self.status = None;
+            }
+            Err(err) => {
+                self.pending_import = None;
+                self.status = Some(Err(format!("failed to import the rules: {}", err)));
+            }
+        }
+    }
+}
+
 impl IndirectSourceReversalRules {
     fn show(&mut self, modified_settings: &mut AnalysisSettings, ui: &mut Ui) {
         RulesTable::new(
@@ -237,6 +618,66 @@ impl DamageOutExclusionRules {
     }
 }
 
+impl PlayerMerges {
+    fn show(&mut self, modified_settings: &mut AnalysisSettings, ui: &mut Ui) {
+        let merges = &mut modified_settings.player_merges;
+        ui.horizontal(|ui| {
+            ui.label("Player Merges\n(e.g. renamed characters)");
+            if ui.button("Add ✚").clicked() {
+                merges.push(Default::default());
+            }
+
+            show_move_up_down(&mut self.selected, merges, ui);
+        });
+        ui.push_id("player merges", |ui| {
+            Table::new(ui)
+                .min_scroll_height(100.0)
+                .max_scroll_height(200.0)
+                .cell_spacing(10.0)
+                .header(HEADER_HEIGHT, |r| {
+                    r.cell(|ui| {
+                        ui.label("Old Name (name@handle)");
+                    });
+                    r.cell(|ui| {
+                        ui.label("New Name (name@handle)");
+                    });
+                })
+                .body(ROW_HEIGHT, |t| {
+                    let mut to_remove = Vec::new();
+                    for (id, merge) in merges.iter_mut().enumerate() {
+                        let row_response = t.selectable_row(self.selected == Some(id), |r| {
+                            r.cell(|ui| {
+                                TextEdit::singleline(&mut merge.old_name)
+                                    .min_size(vec2(250.0, 0.0))
+                                    .show(ui);
+                            });
+
+                            r.cell(|ui| {
+                                TextEdit::singleline(&mut merge.new_name)
+                                    .min_size(vec2(250.0, 0.0))
+                                    .show(ui);
+                            });
+
+                            r.cell(|ui| {
+                                if ui.selectable_label(false, "🗑").clicked() {
+                                    to_remove.push(id);
+                                }
+                            });
+                        });
+
+                        if row_response.clicked() {
+                            self.selected = Some(id);
+                        }
+                    }
+
+                    to_remove.into_iter().rev().for_each(|i| {
+                        merges.remove(i);
+                    });
+                });
+        });
+    }
+}
+
 impl CustomGroupingRules {
     fn show(&mut self, modified_settings: &mut AnalysisSettings, ui: &mut Ui) {
         GroupRulesTable::new(
@@ -264,55 +705,80 @@ impl CustomGroupingRules {
 
 impl CombatNameRules {
     fn show(&mut self, modified_settings: &mut AnalysisSettings, ui: &mut Ui) {
-        CollapsingHeader::new("Combat Name Detection Rules").show_unindented(ui, |ui| {
-            GroupRulesTable::new(
-                &mut modified_settings.combat_name_rules,
-                "",
-                "Combat Name",
-                &mut self.selected_group,
-                200.0,
-            )
-            .show(ui, |r, ui| {
-                RulesTable::new(
-                    &mut r.name_rule.rules,
-                    "combat name",
-                    &[
-                        MatchAspect::DamageOrHealName,
-                        MatchAspect::IndirectSourceName,
-                        MatchAspect::IndirectUniqueSourceName,
-                        MatchAspect::SourceOrTargetName,
-                        MatchAspect::SourceOrTargetUniqueName,
-                    ],
-                    &mut self.selected_rule,
+        CollapsingHeader::new("Combat Name Detection Rules")
+            .open(std::mem::take(&mut self.open).then_some(true))
+            .show_unindented(ui, |ui| {
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Add Defaults")
+                        .on_hover_text("adds the rules shipped with the app, which are missing")
+                        .clicked()
+                    {
+                        modified_settings
+                            .add_combat_name_rules(&Settings::default_combat_name_rules());
+                    }
+                    if ui
+                        .button("Reset Defaults")
+                        .on_hover_text(
+                            "restores the rules shipped with the app, your own rules are kept",
+                        )
+                        .clicked()
+                    {
+                        modified_settings
+                            .reset_combat_name_rules(&Settings::default_combat_name_rules());
+                        // the indices of the rules changed
+                        *self = Self::default();
+                    }
+                });
+
+                GroupRulesTable::new(
+                    &mut modified_settings.combat_name_rules,
+                    "",
+                    "Combat Name",
+                    &mut self.selected_group,
+                    200.0,
                 )
-                .show(ui);
-
-                ui.push_id("additional info rules", |ui| {
-                    GroupRulesTable::new(
-                        &mut r.additional_info_rules,
-                        "additional infos rules (e.g. difficulty)",
-                        "Info",
-                        &mut self.selected_additional_info_group,
-                        200.0,
+                .show(ui, |r, ui| {
+                    RulesTable::new(
+                        &mut r.name_rule.rules,
+                        "combat name",
+                        &[
+                            MatchAspect::DamageOrHealName,
+                            MatchAspect::IndirectSourceName,
+                            MatchAspect::IndirectUniqueSourceName,
+                            MatchAspect::SourceOrTargetName,
+                            MatchAspect::SourceOrTargetUniqueName,
+                        ],
+                        &mut self.selected_rule,
                     )
-                    .show(ui, |r, ui| {
-                        RulesTable::new(
-                            &mut r.rules,
-                            &r.name,
-                            &[
-                                MatchAspect::DamageOrHealName,
-                                MatchAspect::IndirectSourceName,
-                                MatchAspect::IndirectUniqueSourceName,
-                                MatchAspect::SourceOrTargetName,
-                                MatchAspect::SourceOrTargetUniqueName,
-                            ],
-                            &mut self.selected_additional_info_rule,
+                    .show(ui);
+
+                    ui.push_id("additional info rules", |ui| {
+                        GroupRulesTable::new(
+                            &mut r.additional_info_rules,
+                            "additional infos rules (e.g. difficulty)",
+                            "Info",
+                            &mut self.selected_additional_info_group,
+                            200.0,
                         )
-                        .show(ui);
+                        .show(ui, |r, ui| {
+                            RulesTable::new(
+                                &mut r.rules,
+                                &r.name,
+                                &[
+                                    MatchAspect::DamageOrHealName,
+                                    MatchAspect::IndirectSourceName,
+                                    MatchAspect::IndirectUniqueSourceName,
+                                    MatchAspect::SourceOrTargetName,
+                                    MatchAspect::SourceOrTargetUniqueName,
+                                ],
+                                &mut self.selected_additional_info_rule,
+                            )
+                            .show(ui);
+                        });
                     });
                 });
             });
-        });
     }
 }
 
@@ -399,7 +865,7 @@ impl<'a, T: BorrowMut<RulesGroup> + Default> GroupRulesTable<'a, T> {
 }
 
 impl<'a> RulesTable<'a> {
-    fn new(
+    pub fn new(
         rules: &'a mut Vec<MatchRule>,
         title: &'a str,
         match_aspect_set: &'a [MatchAspect],
@@ -413,7 +879,7 @@ impl<'a> RulesTable<'a> {
         }
     }
 
-    fn show(&mut self, ui: &mut Ui) {
+    pub fn show(&mut self, ui: &mut Ui) {
         ui.horizontal(|ui| {
             ui.label(self.title);
             if ui.button("Add ✚").clicked() {
@@ -440,6 +906,9 @@ impl<'a> RulesTable<'a> {
                     r.cell(|ui| {
                         ui.label("Text to match");
                     });
+                    r.cell(|ui| {
+                        ui.label("Ignore Case");
+                    });
                 })
                 .body(ROW_HEIGHT, |t| {
                     let mut to_remove = Vec::new();
@@ -484,6 +953,10 @@ impl<'a> RulesTable<'a> {
                                     .show(ui);
                             });
 
+                            r.cell(|ui| {
+                                ui.checkbox(&mut rule.ignore_case, "");
+                            });
+
                             r.cell(|ui| {
                                 if ui.selectable_label(false, "🗑").clicked() {
                                     to_remove.push(id);
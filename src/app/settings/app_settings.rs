@@ -1,8 +1,14 @@
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-use crate::analyzer::settings::AnalysisSettings;
+use crate::{
+    analyzer::{
+        settings::{AnalysisSettings, CombatNameRule},
+        DateTimeFormat,
+    },
+    app::main_tabs::MainTab,
+};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Settings {
@@ -12,18 +18,69 @@ pub struct Settings {
     pub debug: DebugSettings,
     #[serde(default)]
     pub upload: UploadSettings,
+    #[serde(default)]
+    pub overlay: OverlaySettings,
+    #[serde(default)]
+    pub my_player: String,
+    #[serde(default)]
+    pub quick_filters: QuickFilterSettings,
+    #[serde(default)]
+    pub exports: ExportSettings,
+    #[serde(default)]
+    pub startup: StartupSettings,
+    #[serde(default)]
+    pub tables: TableSettings,
+    #[serde(default)]
+    pub summary_copy: SummaryCopySettings,
+    #[serde(default)]
+    pub summary: SummarySettings,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct AutoRefresh {
     pub enable: bool,
     pub interval_seconds: f64,
+    #[serde(default = "AutoRefresh::default_debounce_seconds")]
+    pub debounce_seconds: f64,
+    /// pauses auto refresh while the main window is minimized and the overlay is hidden
+    #[serde(default = "AutoRefresh::default_pause_when_minimized")]
+    pub pause_when_minimized: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Visuals {
     pub ui_scale: f64,
     pub theme: Theme,
+    #[serde(default)]
+    pub percentage_bars: bool,
+    #[serde(default = "Visuals::default_changed_rows_highlight")]
+    pub changed_rows_highlight: bool,
+    #[serde(default)]
+    pub date_time_format: DateTimeFormat,
+    #[serde(default)]
+    pub stacked_shield_hull_values: bool,
+    #[serde(default)]
+    pub combat_percentage_columns: bool,
+    #[serde(default)]
+    pub minor_rows: MinorRows,
+    #[serde(default)]
+    pub rate_columns: bool,
+    #[serde(default)]
+    pub hit_interval_column: bool,
+    #[serde(default)]
+    pub activity_columns: bool,
+    #[serde(default)]
+    pub absolute_time_axis: bool,
+}
+
+/// rows below the players, whose share of their parent row is below `threshold_percentage`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MinorRows {
+    pub dim: bool,
+    pub threshold_percentage: f64,
+    /// collapses the rows into a single summary row per parent, instead of dimming them
+    pub hide: bool,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Default)]
@@ -32,6 +89,8 @@ pub enum Theme {
     #[default]
     LightDark,
     Light,
+    /// follows the dark or light mode of the OS
+    System,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
@@ -45,6 +104,118 @@ pub struct UploadSettings {
     pub oscr_url: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OverlaySettings {
+    pub abbreviate_numbers: bool,
+    pub abbreviated_decimals: usize,
+    /// names of the shown columns
+    #[serde(default = "OverlaySettings::default_columns")]
+    pub columns: Vec<String>,
+    /// name of the column the players are sorted by, the first shown column if `None` or hidden
+    #[serde(default)]
+    pub sort_column: Option<String>,
+    /// shows the row of [`Settings::my_player`] always at the top
+    #[serde(default)]
+    pub pin_my_player: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QuickFilterSettings {
+    /// parts of the names, which are shown by the "Weapons only" filter
+    pub weapon_names: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct TableSettings {
+    /// shown columns in their order, keyed by the table. Columns, which are not listed, are shown.
+    pub columns: BTreeMap<String, Vec<TableColumn>>,
+}
+
+impl TableSettings {
+    pub fn columns_mut(&mut self, table: &str) -> &mut Vec<TableColumn> {
+        self.columns.entry(table.to_string()).or_default()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TableColumn {
+    pub name: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct StartupSettings {
+    pub default_tab: MainTab,
+    pub refresh: bool,
+    /// selects the newest combat, once the first refresh completed
+    pub select_newest_combat: bool,
+    /// a second start of the app hands its combatlog file over to the running one
+    pub single_instance: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct ExportSettings {
+    pub presets: Vec<ExportPreset>,
+    /// runs the presets for every combat, as soon as the next combat started
+    pub run_when_combat_finished: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ExportPreset {
+    pub name: String,
+    pub format: ExportFormat,
+    pub destination: ExportDestination,
+    /// path of the exported file, can contain the placeholders `{combat_name}`, `{date}` and `{time}`.
+    /// Relative paths are placed in the directory of the log.
+    pub file_pattern: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum ExportFormat {
+    #[default]
+    SummaryCsv,
+    SummaryJson,
+    SummaryText,
+    CombatLog,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
+#[serde(default)]
+pub struct SummaryCopySettings {
+    pub preset: SummaryCopyPreset,
+    /// used by [`SummaryCopyPreset::Custom`]
+    pub custom_template: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SummarySettings {
+    /// players with a lower share of the damage out in percent are counted in the summary
+    pub damage_share_threshold: f64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum SummaryCopyPreset {
+    /// the configurable elements of the summary copy, joined to a single line
+    #[default]
+    Default,
+    Plain,
+    Discord,
+    Csv,
+    Custom,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum ExportDestination {
+    #[default]
+    File,
+    Clipboard,
+}
+
 static DEFAULT_SETTINGS: &str = include_str!("STO_CombatLogAnalyzer_Settings.json");
 
 impl Settings {
@@ -55,6 +226,11 @@ impl Settings {
         Some(path)
     }
 
+    /// the combat name rules shipped with the app
+    pub fn default_combat_name_rules() -> Vec<CombatNameRule> {
+        Self::default().analysis.combat_name_rules
+    }
+
     pub fn load_or_default() -> Self {
         Self::file_path()
             .and_then(|f| std::fs::read_to_string(&f).ok())
@@ -87,21 +263,160 @@ impl Default for Settings {
     }
 }
 
+impl Default for SummarySettings {
+    fn default() -> Self {
+        Self {
+            damage_share_threshold: 5.0,
+        }
+    }
+}
+
+impl Default for StartupSettings {
+    fn default() -> Self {
+        Self {
+            default_tab: MainTab::Summary,
+            refresh: false,
+            select_newest_combat: true,
+            single_instance: true,
+        }
+    }
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self {
+            abbreviate_numbers: false,
+            abbreviated_decimals: 1,
+            columns: Self::default_columns(),
+            sort_column: None,
+            pin_my_player: false,
+        }
+    }
+}
+
+impl OverlaySettings {
+    fn default_columns() -> Vec<String> {
+        vec!["DPS".to_string()]
+    }
+}
+
+impl Default for QuickFilterSettings {
+    fn default() -> Self {
+        Self {
+            weapon_names: ["Beam", "Cannon", "Turret", "Torpedo", "Mine"]
+                .map(String::from)
+                .to_vec(),
+        }
+    }
+}
+
 impl Default for AutoRefresh {
     fn default() -> Self {
         Self {
             enable: false,
             interval_seconds: 1.0,
+            debounce_seconds: Self::default_debounce_seconds(),
+            pause_when_minimized: Self::default_pause_when_minimized(),
+        }
+    }
+}
+
+impl AutoRefresh {
+    fn default_debounce_seconds() -> f64 {
+        0.5
+    }
+
+    fn default_pause_when_minimized() -> bool {
+        true
+    }
+}
+
+impl Default for ExportPreset {
+    fn default() -> Self {
+        Self {
+            name: "New Preset".to_string(),
+            format: Default::default(),
+            destination: Default::default(),
+            file_pattern: "{date}_{time}_{combat_name}.csv".to_string(),
+        }
+    }
+}
+
+impl ExportFormat {
+    pub const ALL: [Self; 4] = [
+        Self::SummaryCsv,
+        Self::SummaryJson,
+        Self::SummaryText,
+        Self::CombatLog,
+    ];
+
+    pub const fn display(&self) -> &'static str {
+        match self {
+            Self::SummaryCsv => "Summary (CSV)",
+            Self::SummaryJson => "Summary (JSON)",
+            Self::SummaryText => "Summary (Text)",
+            Self::CombatLog => "Combat Log",
+        }
+    }
+}
+
+impl SummaryCopySettings {
+    /// `None` for [`SummaryCopyPreset::Default`]
+    pub fn template(&self) -> Option<&str> {
+        match self.preset {
+            SummaryCopyPreset::Default => None,
+            SummaryCopyPreset::Plain => Some(
+                "{combat_name} ({combat_time}, settings {settings_digest})\n{players}{rank}. {player}: {dps} DPS, {total_damage} damage\n{/players}",
+            ),
+            SummaryCopyPreset::Discord => Some(
+                "```\n{combat_name} ({combat_time}, settings {settings_digest})\n{players}{rank}. {player} | {dps} | {damage_percentage}%\n{/players}```",
+            ),
+            SummaryCopyPreset::Csv => Some(
+                "{players}{combat_name},{combat_time},{player},{dps},{total_damage},{settings_digest}\n{/players}",
+            ),
+            SummaryCopyPreset::Custom => Some(&self.custom_template),
+        }
+    }
+}
+
+impl SummaryCopyPreset {
+    pub const ALL: [Self; 5] = [
+        Self::Default,
+        Self::Plain,
+        Self::Discord,
+        Self::Csv,
+        Self::Custom,
+    ];
+
+    pub const fn display(&self) -> &'static str {
+        match self {
+            Self::Default => "Default",
+            Self::Plain => "Plain",
+            Self::Discord => "Discord",
+            Self::Csv => "CSV",
+            Self::Custom => "Custom",
+        }
+    }
+}
+
+impl ExportDestination {
+    pub const fn display(&self) -> &'static str {
+        match self {
+            Self::File => "File",
+            Self::Clipboard => "Clipboard",
         }
     }
 }
 
 impl Theme {
+    pub const ALL: [Self; 4] = [Self::Dark, Self::LightDark, Self::Light, Self::System];
+
     pub const fn display(&self) -> &'static str {
         match self {
             Theme::Dark => "Dark",
             Theme::LightDark => "Light Dark",
             Theme::Light => "Light",
+            Theme::System => "System",
         }
     }
 }
@@ -111,10 +426,36 @@ impl Default for Visuals {
         Self {
             ui_scale: 1.0,
             theme: Default::default(),
+            percentage_bars: false,
+            changed_rows_highlight: Self::default_changed_rows_highlight(),
+            date_time_format: Default::default(),
+            stacked_shield_hull_values: false,
+            combat_percentage_columns: false,
+            minor_rows: Default::default(),
+            rate_columns: false,
+            hit_interval_column: false,
+            activity_columns: false,
+            absolute_time_axis: false,
+        }
+    }
+}
+
+impl Default for MinorRows {
+    fn default() -> Self {
+        Self {
+            dim: false,
+            threshold_percentage: 1.0,
+            hide: false,
         }
     }
 }
 
+impl Visuals {
+    fn default_changed_rows_highlight() -> bool {
+        true
+    }
+}
+
 impl Default for DebugSettings {
     fn default() -> Self {
         Self {
@@ -1,4 +1,6 @@
-use eframe::egui::{ComboBox, Ui};
+use eframe::egui::{CollapsingHeader, ComboBox, Grid, Ui};
+
+use crate::analyzer::Combat;
 
 use super::Settings;
 
@@ -6,7 +8,12 @@ use super::Settings;
 pub struct DebugTab {}
 
 impl DebugTab {
-    pub fn show(&mut self, modified_settings: &mut Settings, ui: &mut Ui) {
+    pub fn show(
+        &mut self,
+        modified_settings: &mut Settings,
+        selected_combat: Option<&Combat>,
+        ui: &mut Ui,
+    ) {
         ui.label("App Log Settings");
         ui.label(
             "Any change to these settings requires a restart of the application to take affect.",
@@ -41,5 +48,58 @@ impl DebugTab {
                     log::LevelFilter::Trace.as_str(),
                 );
             });
+
+        ui.add_space(10.0);
+        ui.separator();
+        Self::show_statistics(selected_combat, ui);
+    }
+
+    fn show_statistics(selected_combat: Option<&Combat>, ui: &mut Ui) {
+        ui.label("Statistics of the selected Combat");
+        let Some(combat) = selected_combat else {
+            ui.label("no combat selected");
+            return;
+        };
+
+        Grid::new("debug statistics").show(ui, |ui| {
+            ui.label("Out of order records")
+                .on_hover_text("records with a time before the one of the previous record");
+            ui.label(combat.out_of_order_records.to_string());
+            ui.end_row();
+
+            ui.label("Anomalies");
+            ui.label(combat.anomalies.len().to_string());
+            ui.end_row();
+
+            ui.label("Dropped duplicate records").on_hover_text(
+                "records identical to too many recent ones, if enabled in the analysis settings",
+            );
+            ui.label(combat.dropped_duplicate_records.to_string());
+            ui.end_row();
+        });
+
+        Self::show_anomalies(combat, ui);
+    }
+
+    fn show_anomalies(combat: &Combat, ui: &mut Ui) {
+        if combat.anomalies.is_empty() {
+            return;
+        }
+
+        ui.add_space(10.0);
+        CollapsingHeader::new(format!("Anomalies ({})", combat.anomalies.len()))
+            .id_source("debug anomalies")
+            .show(ui, |ui| {
+                ui.label("records with implausible values, likely from corrupted lines");
+                Grid::new("debug anomalies grid").show(ui, |ui| {
+                    for anomaly in combat.anomalies.iter() {
+                        ui.label(anomaly.time.format("%T%.3f").to_string());
+                        ui.label(&anomaly.value_name);
+                        ui.label(format!("{:.2}", anomaly.amount))
+                            .on_hover_text(&anomaly.raw);
+                        ui.end_row();
+                    }
+                });
+            });
     }
 }
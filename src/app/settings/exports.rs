@@ -0,0 +1,85 @@
+use eframe::egui::*;
+
+use super::{ExportDestination, ExportFormat, ExportPreset, Settings};
+
+#[derive(Default)]
+pub struct ExportsTab {}
+
+impl ExportsTab {
+    pub fn show(&mut self, modified_settings: &mut Settings, ui: &mut Ui) {
+        let exports = &mut modified_settings.exports;
+        ui.label("Export presets are run together with the \"Run Exports\" button.");
+        ui.label("File patterns can contain the placeholders {combat_name}, {date} and {time}.");
+        ui.label("Without {time}, the start time of the combat is added to the file name. Relative paths are placed next to the combatlog.");
+        ui.label("Text summaries are built like the summary copy.");
+        ui.checkbox(
+            &mut exports.run_when_combat_finished,
+            "Run the exports automatically, once a combat finished (i.e. the next combat started)",
+        );
+        ui.add_space(10.0);
+
+        let mut remove = None;
+        Grid::new("export presets")
+            .num_columns(5)
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Name");
+                ui.label("Export");
+                ui.label("Destination");
+                ui.label("File Pattern");
+                ui.end_row();
+
+                for (i, preset) in exports.presets.iter_mut().enumerate() {
+                    ui.push_id(i, |ui| {
+                        TextEdit::singleline(&mut preset.name)
+                            .desired_width(120.0)
+                            .show(ui);
+                    });
+                    Self::show_format(preset, i, ui);
+                    Self::show_destination(preset, i, ui);
+                    ui.push_id(("file pattern", i), |ui| {
+                        ui.add_enabled(
+                            preset.destination == ExportDestination::File,
+                            TextEdit::singleline(&mut preset.file_pattern).desired_width(300.0),
+                        );
+                    });
+                    if ui.button("❌").on_hover_text("remove preset").clicked() {
+                        remove = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        if let Some(remove) = remove {
+            exports.presets.remove(remove);
+        }
+
+        if ui.button("Add Preset").clicked() {
+            exports.presets.push(ExportPreset::default());
+        }
+    }
+
+    fn show_format(preset: &mut ExportPreset, index: usize, ui: &mut Ui) {
+        ComboBox::from_id_source(("export format", index))
+            .selected_text(preset.format.display())
+            .show_ui(ui, |ui| {
+                for format in ExportFormat::ALL {
+                    ui.selectable_value(&mut preset.format, format, format.display());
+                }
+            });
+    }
+
+    fn show_destination(preset: &mut ExportPreset, index: usize, ui: &mut Ui) {
+        ComboBox::from_id_source(("export destination", index))
+            .selected_text(preset.destination.display())
+            .show_ui(ui, |ui| {
+                for destination in [ExportDestination::File, ExportDestination::Clipboard] {
+                    ui.selectable_value(
+                        &mut preset.destination,
+                        destination,
+                        destination.display(),
+                    );
+                }
+            });
+    }
+}
@@ -3,7 +3,8 @@ use eframe::Frame;
 use rfd::FileDialog;
 
 use crate::{
-    app::analysis_handling::AnalysisHandler, custom_widgets::slider_text_edit::SliderTextEdit,
+    app::{analysis_handling::AnalysisHandler, main_tabs::MainTab},
+    custom_widgets::slider_text_edit::SliderTextEdit,
 };
 
 use super::Settings;
@@ -31,7 +32,7 @@ impl FileTab {
             if ui.button("Browse").clicked() {
                 if let Some(new_combatlog_file) = FileDialog::new()
                     .set_title("Choose combatlog File")
-                    .add_filter("combatlog", &["log"])
+                    .add_filter("combatlog", &["log", "gz"])
                     .set_parent(frame)
                     .pick_file()
                 {
@@ -40,11 +41,54 @@ impl FileTab {
                 }
             }
 
+            Self::show_recent_combatlog_files(modified_settings, ui);
             self.clear_log_dialog.show(analysis_handler, ui);
         });
         TextEdit::singleline(&mut modified_settings.analysis.combatlog_file)
             .desired_width(f32::MAX)
             .show(ui);
+        ui.checkbox(
+            &mut modified_settings.analysis.follow_newest_combatlog_file,
+            "Switch to newer combatlog Files of the Directory",
+        )
+        .on_hover_text(
+            "while auto refresh is enabled, newer combatlog*.log files are loaded,\n\
+            once the current file did not change for the combat separation time",
+        );
+
+        ui.separator();
+
+        ui.horizontal(|ui| {
+            ui.label("My Player");
+            TextEdit::singleline(&mut modified_settings.my_player)
+                .hint_text("name@handle or @handle")
+                .desired_width(200.0)
+                .show(ui);
+        })
+        .response
+        .on_hover_text(
+            "used for the combat summary in the toolbar, if empty the top player is shown",
+        );
+
+        ui.separator();
+
+        let startup = &mut modified_settings.startup;
+        ComboBox::from_label("Tab shown on Startup")
+            .selected_text(startup.default_tab.display())
+            .show_ui(ui, |ui| {
+                for tab in MainTab::ALL {
+                    ui.selectable_value(&mut startup.default_tab, tab, tab.display());
+                }
+            });
+        ui.checkbox(&mut startup.refresh, "Refresh on Startup");
+        ui.checkbox(
+            &mut startup.select_newest_combat,
+            "Select the newest Combat, once the first Refresh completed",
+        );
+        ui.checkbox(&mut startup.single_instance, "Single Instance")
+            .on_hover_text(
+                "starting the app again brings the running one to the foreground and opens the combatlog file it was started with\ntakes effect on the next start",
+            );
 
         ui.separator();
 
@@ -78,6 +122,45 @@ impl FileTab {
         .desired_text_edit_width(40.0)
         .clamp_min(0.1)
         .show(ui);
+
+        ui.label(
+            "Auto Refresh Delay in seconds (minimum time between a log change and the refresh)",
+        );
+        SliderTextEdit::new(
+            &mut modified_settings.auto_refresh.debounce_seconds,
+            0.0..=2.0,
+            "auto refresh debounce slider",
+        )
+        .clamp_to_range(false)
+        .step_by(0.1)
+        .display_precision(2)
+        .desired_text_edit_width(40.0)
+        .clamp_min(0.0)
+        .show(ui);
+
+        ui.checkbox(
+            &mut modified_settings.auto_refresh.pause_when_minimized,
+            "Pause Auto Refresh while minimized (unless the Overlay is shown)",
+        )
+        .on_hover_text(
+            "Saves battery. Disable this if you rely on the log being analyzed in the background.",
+        );
+    }
+
+    fn show_recent_combatlog_files(modified_settings: &mut Settings, ui: &mut Ui) {
+        let analysis = &mut modified_settings.analysis;
+        ui.add_enabled_ui(!analysis.recent_combatlog_files.is_empty(), |ui| {
+            ComboBox::from_id_source("recent combatlog files")
+                .selected_text("Recent")
+                .width(80.0)
+                .show_ui(ui, |ui| {
+                    for file in analysis.recent_combatlog_files.iter() {
+                        ui.selectable_value(&mut analysis.combatlog_file, file.clone(), file);
+                    }
+                })
+                .response
+                .on_hover_text("switch to a recently used combatlog file, applied with \"Ok\"");
+        });
     }
 
     pub fn show_clear_log_dialog(&mut self, analysis_handler: &AnalysisHandler, ui: &mut Ui) {
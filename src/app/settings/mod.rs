@@ -1,12 +1,17 @@
-use std::ffi::OsStr;
+use std::{ffi::OsStr, path::Path};
 
-pub use app_settings::Settings;
+pub use analysis::{RuleRequest, RuleTarget, RulesTable};
+pub use app_settings::{
+    ExportDestination, ExportFormat, ExportPreset, ExportSettings, MinorRows, OverlaySettings,
+    Settings, SummaryCopyPreset, SummaryCopySettings, SummarySettings, TableColumn,
+};
 use eframe::{egui::*, Frame};
+pub use visuals::VisualsTab;
 
 use crate::analyzer::Combat;
 
 use self::{
-    analysis::AnalysisTab, debug::DebugTab, file::FileTab, upload::UploadTab, visuals::VisualsTab,
+    analysis::AnalysisTab, debug::DebugTab, exports::ExportsTab, file::FileTab, upload::UploadTab,
 };
 
 use super::{analysis_handling::AnalysisHandler, state::AppState};
@@ -14,6 +19,7 @@ use super::{analysis_handling::AnalysisHandler, state::AppState};
 mod analysis;
 mod app_settings;
 mod debug;
+mod exports;
 mod file;
 mod upload;
 mod visuals;
@@ -26,6 +32,7 @@ pub struct SettingsWindow {
     analysis_tab: AnalysisTab,
     visuals_tab: VisualsTab,
     upload_tab: UploadTab,
+    exports_tab: ExportsTab,
     debug_tab: DebugTab,
 }
 
@@ -37,12 +44,18 @@ enum SettingsTab {
     Visuals,
     Debug,
     Upload,
+    Exports,
 }
 
 impl SettingsWindow {
-    pub fn new(ctx: &Context, native_pixels_per_point: Option<f32>) -> Self {
+    pub fn new(
+        ctx: &Context,
+        native_pixels_per_point: Option<f32>,
+        system_theme: Option<eframe::Theme>,
+    ) -> Self {
         let mut visuals_tab = VisualsTab::default();
         let settings = Settings::load_or_default();
+        VisualsTab::set_system_theme(ctx, system_theme);
         visuals_tab.update_visuals(ctx, native_pixels_per_point, &settings);
         Self {
             is_open: false,
@@ -52,6 +65,7 @@ impl SettingsWindow {
             analysis_tab: Default::default(),
             debug_tab: Default::default(),
             upload_tab: Default::default(),
+            exports_tab: Default::default(),
             visuals_tab,
         }
     }
@@ -82,6 +96,7 @@ impl SettingsWindow {
                     ui.selectable_value(&mut self.selected_tab, SettingsTab::Analysis, "Analysis");
                     ui.selectable_value(&mut self.selected_tab, SettingsTab::Visuals, "Visuals");
                     ui.selectable_value(&mut self.selected_tab, SettingsTab::Upload, "Upload");
+                    ui.selectable_value(&mut self.selected_tab, SettingsTab::Exports, "Exports");
                     ui.selectable_value(&mut self.selected_tab, SettingsTab::Debug, "Debug");
                 });
 
@@ -99,7 +114,11 @@ impl SettingsWindow {
                     }
                     SettingsTab::Visuals => self.visuals_tab.show(&mut self.modified_settings, ui),
                     SettingsTab::Upload => self.upload_tab.show(&mut self.modified_settings, ui),
-                    SettingsTab::Debug => self.debug_tab.show(&mut self.modified_settings, ui),
+                    SettingsTab::Exports => self.exports_tab.show(&mut self.modified_settings, ui),
+                    SettingsTab::Debug => {
+                        self.debug_tab
+                            .show(&mut self.modified_settings, selected_combat, ui)
+                    }
                 });
 
                 ui.separator();
@@ -116,29 +135,41 @@ impl SettingsWindow {
             });
     }
 
+    /// opens the analysis settings with the requested rule added, for the user to confirm it
+    pub fn add_rule(&mut self, state: &AppState, request: RuleRequest) {
+        if !self.is_open {
+            self.initialize(state);
+        }
+        self.selected_tab = SettingsTab::Analysis;
+        self.analysis_tab
+            .add_rule(&mut self.modified_settings.analysis, request);
+    }
+
     pub fn show_clear_log_dialog(&mut self, analysis_handler: &AnalysisHandler, ui: &mut Ui) {
         self.file_tab.show_clear_log_dialog(analysis_handler, ui);
     }
 
     fn handle_dropped_file(&mut self, ui: &mut Ui, state: &mut AppState) {
-        ui.ctx().input(|i| {
-            let file = i
-                .raw
-                .dropped_files
-                .last()
-                .map(|f| f.path.as_ref())
-                .flatten();
-            if let Some(file) = file {
-                if file.extension() != Some(OsStr::new("log")) {
-                    return;
-                }
-                if !self.is_open {
-                    self.initialize(state);
-                }
-                self.modified_settings.analysis.combatlog_file = file.to_string_lossy().into();
-                self.apply_setting_changes(state);
-            }
-        });
+        let file = ui
+            .ctx()
+            .input(|i| i.raw.dropped_files.last().and_then(|f| f.path.clone()));
+        if let Some(file) = file {
+            self.open_combatlog_file(state, &file);
+        }
+    }
+
+    /// switches to the combatlog `file`, unless it is not a log
+    pub fn open_combatlog_file(&mut self, state: &mut AppState, file: &Path) {
+        // compressed logs end with .gz
+        if file.extension() != Some(OsStr::new("log")) && file.extension() != Some(OsStr::new("gz"))
+        {
+            return;
+        }
+        if !self.is_open {
+            self.initialize(state);
+        }
+        self.modified_settings.analysis.combatlog_file = file.to_string_lossy().into();
+        self.apply_setting_changes(state);
     }
 
     fn initialize(&mut self, state: &AppState) {
@@ -149,6 +180,13 @@ impl SettingsWindow {
 
     fn apply_setting_changes(&mut self, state: &mut AppState) {
         self.is_open = false;
+        let analysis = &mut self.modified_settings.analysis;
+        if analysis.combatlog_file != state.settings.analysis.combatlog_file {
+            analysis.add_recent_combatlog_file(&state.settings.analysis.combatlog_file);
+            let combatlog_file = analysis.combatlog_file.clone();
+            analysis.add_recent_combatlog_file(&combatlog_file);
+        }
+
         if self.modified_settings.analysis != state.settings.analysis {
             state
                 .analysis_handler
@@ -160,15 +198,38 @@ impl SettingsWindow {
             state
                 .analysis_handler
                 .set_auto_refresh_interval(self.modified_settings.auto_refresh.interval_seconds);
+            state
+                .analysis_handler
+                .set_auto_refresh_debounce(self.modified_settings.auto_refresh.debounce_seconds);
             state
                 .analysis_handler
                 .enable_auto_refresh(self.modified_settings.auto_refresh.enable);
         }
 
+        if self.modified_settings.exports != state.settings.exports
+            || self.modified_settings.summary != state.settings.summary
+        {
+            state
+                .analysis_handler
+                .set_export_settings(&self.modified_settings);
+        }
+
         state.settings = self.modified_settings.clone();
         self.modified_settings.save();
     }
 
+    /// reapplies the visuals, when the dark or light mode of the OS changed.
+    /// This also restores a manually chosen theme, which eframe replaces on such a change.
+    pub fn update_system_theme(&mut self, ctx: &Context, system_theme: Option<eframe::Theme>) {
+        if VisualsTab::set_system_theme(ctx, system_theme) {
+            self.visuals_tab.update_visuals(
+                ctx,
+                ctx.native_pixels_per_point(),
+                &self.modified_settings,
+            );
+        }
+    }
+
     fn discard_setting_changes(&mut self, ui: &Ui, state: &AppState) {
         self.is_open = false;
         if self.modified_settings.visuals != state.settings.visuals {
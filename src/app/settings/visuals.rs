@@ -1,11 +1,31 @@
 use eframe::{
-    egui::{style::Selection, ComboBox, Context, Ui, Visuals},
+    egui::{style::Selection, ComboBox, Context, DragValue, Id, TextEdit, Ui, Visuals},
     epaint::{Rgba, Shadow},
+    Theme as SystemTheme,
 };
 
-use crate::{app::overlay::Overlay, custom_widgets::slider_text_edit::SliderTextEdit};
+use crate::{
+    analyzer::{ClockFormat, DateFormat, DateTimeFormat},
+    app::overlay::Overlay,
+    custom_widgets::slider_text_edit::SliderTextEdit,
+};
+
+use super::{
+    app_settings::{MinorRows, Theme},
+    Settings,
+};
 
-use super::{app_settings::Theme, Settings};
+const PERCENTAGE_BARS_ID: &str = "percentage bars";
+const CHANGED_ROWS_HIGHLIGHT_ID: &str = "changed rows highlight";
+const DATE_TIME_FORMAT_ID: &str = "date time format";
+const STACKED_SHIELD_HULL_VALUES_ID: &str = "stacked shield hull values";
+const COMBAT_PERCENTAGE_COLUMNS_ID: &str = "combat percentage columns";
+const MINOR_ROWS_ID: &str = "minor rows";
+const RATE_COLUMNS_ID: &str = "rate columns";
+const HIT_INTERVAL_COLUMN_ID: &str = "hit interval column";
+const ACTIVITY_COLUMNS_ID: &str = "activity columns";
+const ABSOLUTE_TIME_AXIS_ID: &str = "absolute time axis";
+const SYSTEM_THEME_ID: &str = "system theme";
 
 #[derive(Default)]
 pub struct VisualsTab {}
@@ -17,27 +37,13 @@ impl VisualsTab {
         ComboBox::from_id_source("theme combo box")
             .selected_text(visuals.theme.display())
             .show_ui(ui, |ui| {
-                if ui
-                    .selectable_value(&mut visuals.theme, Theme::Dark, Theme::Dark.display())
-                    .changed()
-                {
-                    Self::set_theme(ui.ctx(), visuals.theme);
-                }
-                if ui
-                    .selectable_value(
-                        &mut visuals.theme,
-                        Theme::LightDark,
-                        Theme::LightDark.display(),
-                    )
-                    .changed()
-                {
-                    Self::set_theme(ui.ctx(), visuals.theme);
-                }
-                if ui
-                    .selectable_value(&mut visuals.theme, Theme::Light, Theme::Light.display())
-                    .changed()
-                {
-                    Self::set_theme(ui.ctx(), visuals.theme);
+                for theme in Theme::ALL {
+                    if ui
+                        .selectable_value(&mut visuals.theme, theme, theme.display())
+                        .changed()
+                    {
+                        Self::set_theme(ui.ctx(), visuals.theme);
+                    }
                 }
             });
 
@@ -60,6 +66,215 @@ impl VisualsTab {
                 visuals.ui_scale,
             );
         }
+
+        ui.add_space(10.0);
+        ui.separator();
+
+        if ui
+            .checkbox(
+                &mut visuals.percentage_bars,
+                "Show bars behind the Damage % and Heal % values",
+            )
+            .changed()
+        {
+            Self::set_percentage_bars(ui.ctx(), visuals.percentage_bars);
+        }
+
+        if ui
+            .checkbox(
+                &mut visuals.changed_rows_highlight,
+                "Highlight table rows, whose damage increased with the last refresh",
+            )
+            .changed()
+        {
+            Self::set_changed_rows_highlight(ui.ctx(), visuals.changed_rows_highlight);
+        }
+
+        if ui
+            .checkbox(
+                &mut visuals.stacked_shield_hull_values,
+                "Show the shield/hull split below the values instead of only on hover",
+            )
+            .changed()
+        {
+            Self::set_stacked_shield_hull_values(ui.ctx(), visuals.stacked_shield_hull_values);
+        }
+
+        if ui
+            .checkbox(
+                &mut visuals.combat_percentage_columns,
+                "Show \"% of Combat\" columns with the share of the total of the combat",
+            )
+            .changed()
+        {
+            Self::set_combat_percentage_columns(ui.ctx(), visuals.combat_percentage_columns);
+        }
+
+        if ui
+            .checkbox(
+                &mut visuals.rate_columns,
+                "Show kills and deaths per minute columns in the summary table",
+            )
+            .changed()
+        {
+            Self::set_rate_columns(ui.ctx(), visuals.rate_columns);
+        }
+
+        if ui
+            .checkbox(
+                &mut visuals.hit_interval_column,
+                "Show the median interval between the hits of weapons (see the \"Weapons only\" names below)",
+            )
+            .changed()
+        {
+            Self::set_hit_interval_column(ui.ctx(), visuals.hit_interval_column);
+        }
+
+        if ui
+            .checkbox(
+                &mut visuals.activity_columns,
+                "Show the first hit, last hit and longest gap between the hits of the abilities",
+            )
+            .changed()
+        {
+            Self::set_activity_columns(ui.ctx(), visuals.activity_columns);
+        }
+
+        let minor_rows = &mut visuals.minor_rows;
+        let mut minor_rows_changed = false;
+        ui.horizontal(|ui| {
+            minor_rows_changed |= ui
+                .checkbox(&mut minor_rows.dim, "Dim table rows under")
+                .changed();
+            minor_rows_changed |= ui
+                .add_enabled(
+                    minor_rows.dim,
+                    DragValue::new(&mut minor_rows.threshold_percentage)
+                        .speed(0.1)
+                        .clamp_range(0.0..=100.0)
+                        .suffix("%"),
+                )
+                .changed();
+            ui.label("of the parent row");
+        });
+        ui.add_enabled_ui(minor_rows.dim, |ui| {
+            minor_rows_changed |= ui
+                .checkbox(
+                    &mut minor_rows.hide,
+                    "Hide instead of dim (summarized in one row per parent)",
+                )
+                .changed();
+        });
+        if minor_rows_changed {
+            Self::set_minor_rows(ui.ctx(), *minor_rows);
+        }
+
+        ui.add_space(10.0);
+        ui.separator();
+
+        let date_time_format = &mut visuals.date_time_format;
+        let mut format_changed = false;
+        ComboBox::from_label("Time Format")
+            .selected_text(date_time_format.clock.display())
+            .show_ui(ui, |ui| {
+                for clock in [ClockFormat::Hours24, ClockFormat::Hours12] {
+                    format_changed |= ui
+                        .selectable_value(&mut date_time_format.clock, clock, clock.display())
+                        .changed();
+                }
+            });
+        ComboBox::from_label("Date Format")
+            .selected_text(date_time_format.date.display())
+            .show_ui(ui, |ui| {
+                for date in [
+                    DateFormat::Iso,
+                    DateFormat::MonthDayYear,
+                    DateFormat::DayMonthYear,
+                ] {
+                    format_changed |= ui
+                        .selectable_value(&mut date_time_format.date, date, date.display())
+                        .changed();
+                }
+            });
+        if format_changed {
+            Self::set_date_time_format(ui.ctx(), *date_time_format);
+        }
+
+        if ui
+            .checkbox(
+                &mut visuals.absolute_time_axis,
+                "Label the time axis of the diagrams with the time of day instead of the seconds since the combat start",
+            )
+            .changed()
+        {
+            Self::set_absolute_time_axis(ui.ctx(), visuals.absolute_time_axis);
+        }
+
+        ui.add_space(10.0);
+        ui.separator();
+
+        ui.label("Names for the \"Weapons only\" quick filter (one per line, matching any part of the name)");
+        let weapon_names = &mut modified_settings.quick_filters.weapon_names;
+        let mut weapon_names_text = weapon_names.join("\n");
+        if TextEdit::multiline(&mut weapon_names_text)
+            .desired_rows(5)
+            .show(ui)
+            .response
+            .changed()
+        {
+            *weapon_names = weapon_names_text.split('\n').map(String::from).collect();
+        }
+    }
+
+    pub fn percentage_bars(ctx: &Context) -> bool {
+        ctx.data(|d| d.get_temp(Id::new(PERCENTAGE_BARS_ID)))
+            .unwrap_or_default()
+    }
+
+    pub fn changed_rows_highlight(ctx: &Context) -> bool {
+        ctx.data(|d| d.get_temp(Id::new(CHANGED_ROWS_HIGHLIGHT_ID)))
+            .unwrap_or_default()
+    }
+
+    pub fn stacked_shield_hull_values(ctx: &Context) -> bool {
+        ctx.data(|d| d.get_temp(Id::new(STACKED_SHIELD_HULL_VALUES_ID)))
+            .unwrap_or_default()
+    }
+
+    pub fn combat_percentage_columns(ctx: &Context) -> bool {
+        ctx.data(|d| d.get_temp(Id::new(COMBAT_PERCENTAGE_COLUMNS_ID)))
+            .unwrap_or_default()
+    }
+
+    pub fn rate_columns(ctx: &Context) -> bool {
+        ctx.data(|d| d.get_temp(Id::new(RATE_COLUMNS_ID)))
+            .unwrap_or_default()
+    }
+
+    pub fn hit_interval_column(ctx: &Context) -> bool {
+        ctx.data(|d| d.get_temp(Id::new(HIT_INTERVAL_COLUMN_ID)))
+            .unwrap_or_default()
+    }
+
+    pub fn activity_columns(ctx: &Context) -> bool {
+        ctx.data(|d| d.get_temp(Id::new(ACTIVITY_COLUMNS_ID)))
+            .unwrap_or_default()
+    }
+
+    pub fn absolute_time_axis(ctx: &Context) -> bool {
+        ctx.data(|d| d.get_temp(Id::new(ABSOLUTE_TIME_AXIS_ID)))
+            .unwrap_or_default()
+    }
+
+    /// the minor rows settings, or [`None`] if they are neither dimmed nor hidden
+    pub fn minor_rows(ctx: &Context) -> Option<MinorRows> {
+        ctx.data(|d| d.get_temp::<MinorRows>(Id::new(MINOR_ROWS_ID)))
+            .filter(|m| m.dim)
+    }
+
+    pub fn date_time_format(ctx: &Context) -> DateTimeFormat {
+        ctx.data(|d| d.get_temp(Id::new(DATE_TIME_FORMAT_ID)))
+            .unwrap_or_default()
     }
 
     pub fn update_visuals(
@@ -71,6 +286,27 @@ impl VisualsTab {
         let visuals = &settings.visuals;
         Self::set_theme(ctx, visuals.theme);
         Self::set_ui_scale(ctx, native_pixels_per_point, visuals.ui_scale);
+        Self::set_percentage_bars(ctx, visuals.percentage_bars);
+        Self::set_changed_rows_highlight(ctx, visuals.changed_rows_highlight);
+        Self::set_date_time_format(ctx, visuals.date_time_format);
+        Self::set_stacked_shield_hull_values(ctx, visuals.stacked_shield_hull_values);
+        Self::set_combat_percentage_columns(ctx, visuals.combat_percentage_columns);
+        Self::set_minor_rows(ctx, visuals.minor_rows);
+        Self::set_rate_columns(ctx, visuals.rate_columns);
+        Self::set_hit_interval_column(ctx, visuals.hit_interval_column);
+        Self::set_activity_columns(ctx, visuals.activity_columns);
+        Self::set_absolute_time_axis(ctx, visuals.absolute_time_axis);
+    }
+
+    /// remembers the dark or light mode of the OS, returns whether it changed
+    pub fn set_system_theme(ctx: &Context, system_theme: Option<SystemTheme>) -> bool {
+        let changed = Self::system_theme(ctx) != system_theme;
+        ctx.data_mut(|d| d.insert_temp(Id::new(SYSTEM_THEME_ID), system_theme));
+        changed
+    }
+
+    fn system_theme(ctx: &Context) -> Option<SystemTheme> {
+        ctx.data(|d| d.get_temp(Id::new(SYSTEM_THEME_ID))).flatten()
     }
 
     fn set_theme(ctx: &Context, theme: Theme) {
@@ -78,6 +314,11 @@ impl VisualsTab {
             Theme::Dark => Visuals::dark(),
             Theme::LightDark => Self::light_dark(),
             Theme::Light => Visuals::light(),
+            // the default theme is used, when the OS does not tell
+            Theme::System => match Self::system_theme(ctx) {
+                Some(SystemTheme::Light) => Visuals::light(),
+                Some(SystemTheme::Dark) | None => Self::light_dark(),
+            },
         };
         ctx.set_visuals(visuals);
         ctx.style_mut(|s| {
@@ -87,6 +328,56 @@ impl VisualsTab {
         Overlay::request_repaint(ctx);
     }
 
+    fn set_percentage_bars(ctx: &Context, percentage_bars: bool) {
+        ctx.data_mut(|d| d.insert_temp(Id::new(PERCENTAGE_BARS_ID), percentage_bars));
+    }
+
+    fn set_changed_rows_highlight(ctx: &Context, changed_rows_highlight: bool) {
+        ctx.data_mut(|d| d.insert_temp(Id::new(CHANGED_ROWS_HIGHLIGHT_ID), changed_rows_highlight));
+    }
+
+    fn set_stacked_shield_hull_values(ctx: &Context, stacked_shield_hull_values: bool) {
+        ctx.data_mut(|d| {
+            d.insert_temp(
+                Id::new(STACKED_SHIELD_HULL_VALUES_ID),
+                stacked_shield_hull_values,
+            )
+        });
+    }
+
+    fn set_combat_percentage_columns(ctx: &Context, combat_percentage_columns: bool) {
+        ctx.data_mut(|d| {
+            d.insert_temp(
+                Id::new(COMBAT_PERCENTAGE_COLUMNS_ID),
+                combat_percentage_columns,
+            )
+        });
+    }
+
+    fn set_rate_columns(ctx: &Context, rate_columns: bool) {
+        ctx.data_mut(|d| d.insert_temp(Id::new(RATE_COLUMNS_ID), rate_columns));
+    }
+
+    fn set_hit_interval_column(ctx: &Context, hit_interval_column: bool) {
+        ctx.data_mut(|d| d.insert_temp(Id::new(HIT_INTERVAL_COLUMN_ID), hit_interval_column));
+    }
+
+    fn set_activity_columns(ctx: &Context, activity_columns: bool) {
+        ctx.data_mut(|d| d.insert_temp(Id::new(ACTIVITY_COLUMNS_ID), activity_columns));
+    }
+
+    fn set_absolute_time_axis(ctx: &Context, absolute_time_axis: bool) {
+        ctx.data_mut(|d| d.insert_temp(Id::new(ABSOLUTE_TIME_AXIS_ID), absolute_time_axis));
+    }
+
+    fn set_minor_rows(ctx: &Context, minor_rows: MinorRows) {
+        ctx.data_mut(|d| d.insert_temp(Id::new(MINOR_ROWS_ID), minor_rows));
+    }
+
+    fn set_date_time_format(ctx: &Context, date_time_format: DateTimeFormat) {
+        ctx.data_mut(|d| d.insert_temp(Id::new(DATE_TIME_FORMAT_ID), date_time_format));
+    }
+
     fn set_ui_scale(ctx: &Context, native_pixels_per_point: Option<f32>, ui_scale: f64) {
         ctx.set_pixels_per_point(native_pixels_per_point.unwrap_or(1.0) * ui_scale as f32);
     }
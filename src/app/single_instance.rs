@@ -0,0 +1,400 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+    sync::{Arc, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crossbeam_channel::{bounded, unbounded, Receiver, Sender};
+use eframe::egui::{Context, ViewportCommand};
+use log::{info, warn};
+
+/// answer of the running instance, to tell it apart from anything else listening
+const GREETING: &str = "STO_CombatLogAnalyzer";
+const CONNECT_TIMEOUT: Duration = Duration::from_millis(500);
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(50);
+/// waited for the greeting of the running instance after connecting
+const ANSWER_TIMEOUT: Duration = Duration::from_millis(500);
+/// waited after failing to accept a connection, doubled with each failure in a row
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+/// the listening stops after this many failures in a row, as the listener is most likely broken
+const ACCEPT_ATTEMPTS: u32 = 8;
+
+/// Keeps only one instance of the app running.
+/// The running instance holds a lock (a named mutex on Windows, a lock file elsewhere) and listens on
+/// a named pipe (Windows) or a unix socket, which only the same user can connect to.
+/// A second start hands its combatlog file over to the running instance and exits.
+pub struct SingleInstance {
+    files: Receiver<Option<PathBuf>>,
+    ctx: Arc<OnceLock<Context>>,
+    /// held while the app runs, `None` if others are not kept from starting
+    _lock: Option<platform::Lock>,
+}
+
+impl SingleInstance {
+    /// `None` if another instance is running, which got the `combatlog_file` and was brought to the foreground
+    pub fn acquire(combatlog_file: Option<&PathBuf>) -> Option<Self> {
+        let (lock, listener) = match platform::lock() {
+            Ok(Some(l)) => l,
+            Ok(None) => {
+                if Self::notify_running_instance(combatlog_file) {
+                    info!("handed over to the running instance");
+                    return None;
+                }
+                warn!("the running instance did not answer");
+                return Some(Self::unguarded());
+            }
+            Err(err) => {
+                warn!("failed to listen for other instances: {}", err);
+                return Some(Self::unguarded());
+            }
+        };
+
+        let (tx, files) = unbounded();
+        let ctx = Arc::new(OnceLock::new());
+        let listener_ctx = ctx.clone();
+        std::thread::spawn(move || Self::listen(listener, tx, listener_ctx));
+        Some(Self {
+            files,
+            ctx,
+            _lock: Some(lock),
+        })
+    }
+
+    /// an instance, which does not keep others from starting
+    pub fn unguarded() -> Self {
+        Self {
+            files: unbounded().1,
+            ctx: Default::default(),
+            _lock: None,
+        }
+    }
+
+    /// combatlog files handed over by other starts of the app, `None` if it was started without one
+    pub fn handed_over_files(&self, ctx: &Context) -> impl Iterator<Item = Option<PathBuf>> + '_ {
+        _ = self.ctx.set(ctx.clone());
+        self.files.try_iter()
+    }
+
+    fn notify_running_instance(combatlog_file: Option<&PathBuf>) -> bool {
+        let file = combatlog_file
+            .map(|f| f.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // the named pipe has no read timeout, so a running instance, which does not answer, must not keep this one waiting
+        let (tx, answered) = bounded(1);
+        std::thread::spawn(move || _ = tx.send(Self::hand_over(&file)));
+        answered
+            .recv_timeout(CONNECT_TIMEOUT + ANSWER_TIMEOUT)
+            .unwrap_or(false)
+    }
+
+    fn hand_over(file: &str) -> bool {
+        // the running instance might still be about to listen
+        let start = Instant::now();
+        let mut stream = loop {
+            match platform::connect() {
+                Ok(s) => break s,
+                Err(_) if start.elapsed() < CONNECT_TIMEOUT => {
+                    std::thread::sleep(CONNECT_RETRY_DELAY)
+                }
+                Err(_) => return false,
+            }
+        };
+        if writeln!(stream, "{}", file).is_err() {
+            return false;
+        }
+
+        let mut greeting = String::new();
+        _ = BufReader::new(stream).read_line(&mut greeting);
+        greeting.trim_end() == GREETING
+    }
+
+    fn listen(
+        mut listener: platform::Listener,
+        tx: Sender<Option<PathBuf>>,
+        ctx: Arc<OnceLock<Context>>,
+    ) {
+        let mut failed_accepts = 0;
+        loop {
+            let stream = match listener.accept() {
+                Ok(s) => s,
+                Err(err) => {
+                    failed_accepts += 1;
+                    warn!("failed to accept another instance: {}", err);
+                    if failed_accepts >= ACCEPT_ATTEMPTS {
+                        warn!("stopped listening for other instances");
+                        return;
+                    }
+                    std::thread::sleep(ACCEPT_RETRY_DELAY * (1 << (failed_accepts - 1)));
+                    continue;
+                }
+            };
+            failed_accepts = 0;
+            // read on its own thread, so a connection, which sends nothing, does not block the next ones
+            let tx = tx.clone();
+            let ctx = ctx.clone();
+            std::thread::spawn(move || Self::receive(stream, &tx, &ctx));
+        }
+    }
+
+    fn receive(
+        mut stream: platform::Stream,
+        tx: &Sender<Option<PathBuf>>,
+        ctx: &OnceLock<Context>,
+    ) {
+        let mut file = String::new();
+        if BufReader::new(&mut stream).read_line(&mut file).is_err() {
+            return;
+        }
+        _ = writeln!(stream, "{}", GREETING);
+
+        let file = file.trim_end();
+        let file = (!file.is_empty()).then(|| PathBuf::from(file));
+        // only brought to the foreground, the app has nothing to open
+        let file = file.filter(|f| {
+            let exists = f.is_file();
+            if !exists {
+                warn!("handed over file does not exist: {}", f.display());
+            }
+            exists
+        });
+        if tx.send(file).is_err() {
+            return;
+        }
+        if let Some(ctx) = ctx.get() {
+            ctx.send_viewport_cmd(ViewportCommand::Minimized(false));
+            ctx.send_viewport_cmd(ViewportCommand::Focus);
+            ctx.request_repaint();
+        }
+    }
+}
+
+#[cfg(unix)]
+mod platform {
+    use std::{
+        fs::{self, DirBuilder, File, OpenOptions, Permissions, TryLockError},
+        io,
+        os::unix::{
+            fs::{DirBuilderExt, PermissionsExt},
+            net::{UnixListener, UnixStream},
+        },
+        path::PathBuf,
+    };
+
+    use super::CONNECT_TIMEOUT;
+
+    /// the lock file stays locked as long as the instance runs
+    pub struct Lock {
+        _file: File,
+        socket: PathBuf,
+    }
+
+    pub struct Listener(UnixListener);
+
+    pub type Stream = UnixStream;
+
+    /// `None` if another instance holds the lock
+    pub fn lock() -> io::Result<Option<(Lock, Listener)>> {
+        let dir = instance_dir()?;
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join("lock"))?;
+        match file.try_lock() {
+            Ok(()) => (),
+            Err(TryLockError::WouldBlock) => return Ok(None),
+            Err(TryLockError::Error(err)) => return Err(err),
+        }
+
+        let socket = dir.join("socket");
+        // left behind by an instance, which did not exit normally
+        _ = fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket)?;
+        Ok(Some((
+            Lock {
+                _file: file,
+                socket,
+            },
+            Listener(listener),
+        )))
+    }
+
+    pub fn connect() -> io::Result<UnixStream> {
+        let stream = UnixStream::connect(instance_dir()?.join("socket"))?;
+        stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+        Ok(stream)
+    }
+
+    impl Listener {
+        pub fn accept(&mut self) -> io::Result<UnixStream> {
+            let (stream, _) = self.0.accept()?;
+            stream.set_read_timeout(Some(CONNECT_TIMEOUT))?;
+            Ok(stream)
+        }
+    }
+
+    impl Drop for Lock {
+        fn drop(&mut self) {
+            // removed before the lock is released, so it can not be the socket of the next instance
+            _ = fs::remove_file(&self.socket);
+        }
+    }
+
+    /// next to the settings, only accessible by the user, so others can not connect to the socket
+    fn instance_dir() -> io::Result<PathBuf> {
+        let mut path = std::env::current_exe()?;
+        path.pop();
+        path.push("STO_CombatLogAnalyzer_Instance.d");
+        DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(&path)?;
+        // fails if it was created by another user
+        fs::set_permissions(&path, Permissions::from_mode(0o700))?;
+        Ok(path)
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::{
+        ffi::OsStr,
+        fs::{File, OpenOptions},
+        io,
+        os::windows::{
+            ffi::OsStrExt,
+            io::{AsRawHandle, FromRawHandle},
+        },
+        ptr,
+    };
+
+    use windows_sys::Win32::{
+        Foundation::{
+            CloseHandle, GetLastError, ERROR_ALREADY_EXISTS, ERROR_PIPE_CONNECTED, HANDLE,
+            INVALID_HANDLE_VALUE,
+        },
+        Storage::FileSystem::{FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX},
+        System::{
+            Pipes::{
+                ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+            },
+            Threading::CreateMutexW,
+        },
+    };
+
+    /// per session, so other users keep their own instance
+    const MUTEX_NAME: &str = "Local\\STO_CombatLogAnalyzer";
+    const PIPE_BUFFER_SIZE: u32 = 4096;
+
+    /// the named mutex exists as long as the instance runs
+    pub struct Lock(HANDLE);
+
+    /// the pipe instance waiting for the next connection, a new one is created after every connection
+    pub struct Listener(Option<File>);
+
+    pub type Stream = File;
+
+    /// `None` if another instance holds the lock
+    pub fn lock() -> io::Result<Option<(Lock, Listener)>> {
+        let name = wide(MUTEX_NAME);
+        let mutex = unsafe { CreateMutexW(ptr::null(), 0, name.as_ptr()) };
+        if mutex == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let lock = Lock(mutex);
+        if unsafe { GetLastError() } == ERROR_ALREADY_EXISTS {
+            return Ok(None);
+        }
+
+        // the first instance of the pipe, so no other process can have created it before
+        let pipe = create_pipe(true)?;
+        Ok(Some((lock, Listener(Some(pipe)))))
+    }
+
+    pub fn connect() -> io::Result<File> {
+        OpenOptions::new().read(true).write(true).open(pipe_name())
+    }
+
+    impl Listener {
+        pub fn accept(&mut self) -> io::Result<File> {
+            let pipe = match self.0.take() {
+                Some(p) => p,
+                None => create_pipe(false)?,
+            };
+            let connected =
+                unsafe { ConnectNamedPipe(pipe.as_raw_handle() as HANDLE, ptr::null_mut()) };
+            if connected == 0 {
+                // the client connected between creating the pipe and waiting for it
+                let err = io::Error::last_os_error();
+                if err.raw_os_error() != Some(ERROR_PIPE_CONNECTED as i32) {
+                    return Err(err);
+                }
+            }
+            Ok(pipe)
+        }
+    }
+
+    impl Drop for Lock {
+        fn drop(&mut self) {
+            unsafe { CloseHandle(self.0) };
+        }
+    }
+
+    /// the default security of the pipe only lets the same user and administrators write to it
+    fn create_pipe(first: bool) -> io::Result<File> {
+        let mut open_mode = PIPE_ACCESS_DUPLEX;
+        if first {
+            open_mode |= FILE_FLAG_FIRST_PIPE_INSTANCE;
+        }
+        let name = wide(&pipe_name());
+        let pipe = unsafe {
+            CreateNamedPipeW(
+                name.as_ptr(),
+                open_mode,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                PIPE_BUFFER_SIZE,
+                PIPE_BUFFER_SIZE,
+                0,
+                ptr::null(),
+            )
+        };
+        if pipe == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(unsafe { File::from_raw_handle(pipe as _) })
+    }
+
+    /// pipes are shared by all sessions, so the name contains the user
+    fn pipe_name() -> String {
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        format!("\\\\.\\pipe\\STO_CombatLogAnalyzer_{}", user)
+    }
+
+    fn wide(value: &str) -> Vec<u16> {
+        OsStr::new(value).encode_wide().chain([0]).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn second_start_hands_over_to_the_running_instance() {
+        let running = SingleInstance::acquire(None).unwrap();
+        let existing = std::env::current_exe().unwrap();
+        let missing = existing.with_extension("missing");
+        // connected, but never sends anything
+        let _silent = platform::connect().unwrap();
+
+        assert!(SingleInstance::acquire(Some(&existing)).is_none());
+        assert!(SingleInstance::acquire(Some(&missing)).is_none());
+        assert!(SingleInstance::acquire(None).is_none());
+
+        let files: Vec<_> = running.files.iter().take(3).collect();
+        assert_eq!(files, [Some(existing), None, None]);
+    }
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use eframe::egui::*;
+use itertools::Itertools;
+
+use super::{analysis_handling::is_combatlog_file_name, settings::SettingsWindow, state::AppState};
+
+/// Shows the analyzed file and switches between the live log and the recently opened saved logs,
+/// the same way as dropping the file onto the app.
+pub fn show_source_selection(
+    ui: &mut Ui,
+    settings_window: &mut SettingsWindow,
+    state: &mut AppState,
+) {
+    let analysis = &state.settings.analysis;
+    let current = analysis.combatlog_file.clone();
+    let files = || std::iter::once(&current).chain(analysis.recent_combatlog_files.iter());
+    let live_log = files().find(|f| is_live_log(f)).cloned();
+    let saved_logs: Vec<_> = files()
+        .filter(|f| !f.is_empty() && !is_live_log(f))
+        .unique()
+        .cloned()
+        .collect();
+
+    let mut selected = None;
+    ComboBox::from_id_source("analysis source")
+        .width(180.0)
+        .selected_text(file_name(&current))
+        .show_ui(ui, |ui| {
+            match &live_log {
+                Some(live_log) => {
+                    if ui
+                        .selectable_label(
+                            *live_log == current,
+                            format!("Live Log: {}", file_name(live_log)),
+                        )
+                        .on_hover_text(live_log)
+                        .clicked()
+                    {
+                        selected = Some(live_log.clone());
+                    }
+                }
+                None => _ = ui.weak("no live log opened yet"),
+            }
+            if !saved_logs.is_empty() {
+                ui.separator();
+            }
+            for file in saved_logs.iter() {
+                if ui
+                    .selectable_label(*file == current, format!("Saved: {}", file_name(file)))
+                    .on_hover_text(file)
+                    .clicked()
+                {
+                    selected = Some(file.clone());
+                }
+            }
+        })
+        .response
+        .on_hover_text(format!("analyzed file:\n{}", current));
+
+    if let Some(file) = selected {
+        settings_window.open_combatlog_file(state, Path::new(&file));
+    }
+}
+
+/// the log the game writes to, as opposed to saved logs
+pub fn is_live_log(file: &str) -> bool {
+    Path::new(file)
+        .file_name()
+        .is_some_and(|n| is_combatlog_file_name(&n.to_string_lossy()))
+}
+
+fn file_name(file: &str) -> String {
+    match Path::new(file).file_name() {
+        Some(name) => name.to_string_lossy().into_owned(),
+        None => "<no file>".to_string(),
+    }
+}
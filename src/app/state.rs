@@ -1,10 +1,11 @@
 use eframe::egui::Context;
 
-use super::{analysis_handling::AnalysisHandler, settings::Settings};
+use super::{analysis_handling::AnalysisHandler, combat_notes::CombatNotes, settings::Settings};
 
 pub struct AppState {
     pub settings: Settings,
     pub analysis_handler: AnalysisHandler,
+    pub combat_notes: CombatNotes,
 }
 
 impl AppState {
@@ -14,12 +15,17 @@ impl AppState {
             settings.analysis.clone(),
             ctx.clone(),
             settings.auto_refresh.interval_seconds,
+            settings.auto_refresh.debounce_seconds,
             settings.auto_refresh.enable,
         );
+        analysis_handler.set_export_settings(&settings);
+        let combat_notes = CombatNotes::load_or_default();
+        analysis_handler.set_combat_name_overrides(combat_notes.name_overrides());
 
         Self {
             settings,
             analysis_handler,
+            combat_notes,
         }
     }
 }
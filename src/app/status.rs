@@ -1,10 +1,30 @@
+use std::{
+    borrow::Cow,
+    time::{Duration, Instant},
+};
+
 use eframe::egui::*;
 
-use crate::helpers::number_formatting::NumberFormatter;
+use crate::{analyzer::InvalidRecords, helpers::number_formatting::NumberFormatter};
+
+use super::{
+    analysis_handling::{BusyPhase, BusyState, RefreshError},
+    source_selection::is_live_log,
+};
+
+const INLINE_BUSY_PHASE_DELAY: Duration = Duration::from_secs(1);
+const NOTE_DURATION: Duration = Duration::from_secs(10);
 
 pub struct StatusIndicator {
     pub status: Status,
     pub is_busy: bool,
+    note: Option<(Note, Instant)>,
+    invalid_records_open: bool,
+}
+
+enum Note {
+    Info(Cow<'static, str>),
+    Error(String),
 }
 
 pub enum Status {
@@ -12,10 +32,12 @@ pub enum Status {
     Busy,
     LoadError {
         combatlog_file: String,
+        error: RefreshError,
     },
     Loaded {
         combatlog_file: String,
         file_size: Option<u64>,
+        invalid_records: InvalidRecords,
     },
 }
 
@@ -24,10 +46,25 @@ impl StatusIndicator {
         Self {
             status: Status::NothingLoaded,
             is_busy: false,
+            note: None,
+            invalid_records_open: false,
         }
     }
 
-    pub fn show(&mut self, is_analysis_busy: bool, ui: &mut Ui) {
+    /// shows a note next to the indicator for a few seconds
+    pub fn show_note(&mut self, note: impl Into<Cow<'static, str>>) {
+        self.note = Some((Note::Info(note.into()), Instant::now()));
+    }
+
+    /// like [`Self::show_note`], but highlighted as error
+    pub fn show_error_note(&mut self, note: String) {
+        self.note = Some((Note::Error(note), Instant::now()));
+    }
+
+    /// returns true if the user requested to cancel the running analysis
+    pub fn show(&mut self, busy_state: BusyState, auto_refresh: bool, ui: &mut Ui) -> bool {
+        let mut cancel = false;
+        let is_analysis_busy = busy_state.phase != BusyPhase::Idle;
         let status = if is_analysis_busy {
             &Status::Busy
         } else {
@@ -39,21 +76,48 @@ impl StatusIndicator {
                     .on_hover_text("nothing loaded yet");
             }
             Status::Busy => {
+                let phase = if is_analysis_busy {
+                    busy_state.phase.display()
+                } else {
+                    "Working..".to_string()
+                };
                 ui.label(WidgetText::from("⏳").color(Color32::YELLOW))
-                    .on_hover_text("Working..");
+                    .on_hover_text(&phase);
+                if is_analysis_busy {
+                    if busy_state.since.elapsed() >= INLINE_BUSY_PHASE_DELAY {
+                        ui.label(phase);
+                        if busy_state.phase.is_cancelable() {
+                            cancel = ui
+                                .small_button("cancel")
+                                .on_hover_text(
+                                    "the log gets analyzed from scratch on the next refresh",
+                                )
+                                .clicked();
+                        }
+                    }
+                    ui.ctx().request_repaint_after(Duration::from_millis(200));
+                }
             }
             Status::LoadError {
                 combatlog_file: path,
-            } => {
-                ui.label(WidgetText::from("✖").color(Color32::RED))
-                    .on_hover_ui(|ui| {
-                        ui.label("failed to load log from:");
-                        ui.label(path);
-                    });
-            }
+                error: RefreshError::EmptyLog,
+            } => Self::show_waiting(path, "log is empty, waiting for combat…", ui),
+            Status::LoadError {
+                combatlog_file: path,
+                error: RefreshError::NoCombatsFound,
+            } => Self::show_waiting(path, "no combats in the log yet, waiting for combat…", ui),
+            Status::LoadError {
+                combatlog_file: path,
+                error: RefreshError::FileNotFound(details),
+            } => Self::show_error(path, "log file not found:", details, ui),
+            Status::LoadError {
+                combatlog_file: path,
+                error: RefreshError::ReadError(details),
+            } => Self::show_error(path, "failed to read log from:", details, ui),
             Status::Loaded {
                 combatlog_file,
                 file_size,
+                invalid_records,
             } => {
                 ui.label(WidgetText::from("✔").color(Color32::GREEN))
                     .on_hover_ui(|ui| {
@@ -71,7 +135,99 @@ impl StatusIndicator {
                             ui.label(size_text);
                         }
                     });
+                let source = match (is_live_log(combatlog_file), auto_refresh) {
+                    (true, true) => "live log (auto refresh)",
+                    (true, false) => "live log",
+                    (false, _) => "static file",
+                };
+                ui.weak(source);
+                if invalid_records.count > 0
+                    && ui
+                        .small_button(
+                            WidgetText::from(format!("{} lines skipped", invalid_records.count))
+                                .color(Color32::YELLOW),
+                        )
+                        .on_hover_text("lines of the log, which could not be parsed")
+                        .clicked()
+                {
+                    self.invalid_records_open = true;
+                }
             }
         }
+        self.show_invalid_records(ui.ctx());
+
+        if let Some((note, since)) = &self.note {
+            let elapsed = since.elapsed();
+            if elapsed < NOTE_DURATION {
+                match note {
+                    Note::Info(note) => {
+                        ui.label(WidgetText::from(note.as_ref()).color(Color32::YELLOW))
+                    }
+                    Note::Error(note) => ui.label(WidgetText::from(note).color(Color32::RED)),
+                };
+                ui.ctx().request_repaint_after(NOTE_DURATION - elapsed);
+            } else {
+                self.note = None;
+            }
+        }
+
+        cancel
+    }
+
+    fn show_invalid_records(&mut self, ctx: &Context) {
+        let Status::Loaded {
+            invalid_records, ..
+        } = &self.status
+        else {
+            self.invalid_records_open = false;
+            return;
+        };
+        if invalid_records.count == 0 {
+            self.invalid_records_open = false;
+            return;
+        }
+        Window::new("Skipped Lines")
+            .open(&mut self.invalid_records_open)
+            .default_size([800.0, 400.0])
+            .show(ctx, |ui| {
+                if invalid_records.count > invalid_records.lines.len() as u64 {
+                    ui.label(format!(
+                        "{} lines of the log could not be parsed, the first {} are listed",
+                        invalid_records.count,
+                        invalid_records.lines.len()
+                    ));
+                } else {
+                    ui.label(format!(
+                        "{} lines of the log could not be parsed",
+                        invalid_records.count
+                    ));
+                }
+                ui.separator();
+                ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+                    for line in invalid_records.lines.iter() {
+                        ui.monospace(line);
+                    }
+                });
+            });
+    }
+
+    fn show_waiting(combatlog_file: &str, waiting_text: &str, ui: &mut Ui) {
+        let color = ui.visuals().weak_text_color();
+        ui.label(WidgetText::from("…").color(color))
+            .on_hover_ui(|ui| {
+                ui.label("log loaded from:");
+                ui.label(combatlog_file);
+            });
+        ui.label(WidgetText::from(waiting_text).color(color));
+    }
+
+    fn show_error(combatlog_file: &str, description: &str, details: &str, ui: &mut Ui) {
+        ui.label(WidgetText::from("✖").color(Color32::RED))
+            .on_hover_ui(|ui| {
+                ui.label(description);
+                ui.label(combatlog_file);
+                ui.add_space(20.0);
+                ui.label(details);
+            });
     }
 }
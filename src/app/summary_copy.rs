@@ -1,9 +1,13 @@
 use eframe::egui::*;
 use itertools::Itertools;
 
+use super::{
+    combat_notes::{CombatNote, CombatNotes},
+    settings::{SummaryCopyPreset, SummaryCopySettings, SummarySettings},
+};
 use crate::{
     analyzer::*,
-    custom_widgets::popup_button::PopupButton,
+    custom_widgets::{number_edit::NumberEdit, popup_button::PopupButton},
     helpers::{
         format_duration, number_formatting::NumberFormatter, time_range_to_duration_or_zero,
     },
@@ -11,8 +15,19 @@ use crate::{
 
 pub struct SummaryCopy {
     aspects: Vec<Aspect>,
+    limit_players: bool,
+    player_limit: usize,
+    align_columns: bool,
+    include_players_under_threshold: bool,
+    /// see [`SummarySettings::damage_share_threshold`]
+    damage_share_threshold: f64,
 }
 
+/// repeated for every player in a template
+const PLAYERS_START: &str = "{players}";
+const PLAYERS_END: &str = "{/players}";
+const TEMPLATE_HELP: &str = "Placeholders: {combat_name}, {combat_time}, {date}, {time}, {settings_digest}, {players_under_threshold}\nThe part between {players} and {/players} is repeated for every player, sorted by DPS.\nPlayer placeholders: {rank}, {player}, {dps}, {total_damage}, {damage_percentage}, {damage_in}, {deaths}";
+
 struct Aspect {
     name: &'static str,
     header: &'static str,
@@ -23,14 +38,44 @@ struct Aspect {
 }
 
 impl SummaryCopy {
-    pub fn show(&mut self, combat: Option<&Combat>, ui: &mut Ui) {
+    pub(super) fn new(summary_settings: &SummarySettings) -> Self {
+        Self {
+            damage_share_threshold: summary_settings.damage_share_threshold,
+            ..Default::default()
+        }
+    }
+
+    /// returns whether the `settings` changed
+    pub fn show(
+        &mut self,
+        combat: Option<&Combat>,
+        combat_notes: &CombatNotes,
+        settings: &mut SummaryCopySettings,
+        summary_settings: &SummarySettings,
+        ui: &mut Ui,
+    ) -> bool {
+        self.damage_share_threshold = summary_settings.damage_share_threshold;
         if ui
             .add_enabled(combat.is_some(), Button::new("Copy Combat Summary"))
             .clicked()
         {
-            ui.output_mut(|o| o.copied_text = self.build_summary(combat.unwrap()));
+            let combat = combat.unwrap();
+            let note = combat_notes.get(&combat.identity());
+            ui.output_mut(|o| o.copied_text = self.build(combat, note, settings));
         }
 
+        let mut changed = false;
+        ComboBox::from_id_source("summary copy preset")
+            .width(80.0)
+            .selected_text(settings.preset.display())
+            .show_ui(ui, |ui| {
+                for preset in SummaryCopyPreset::ALL {
+                    changed |= ui
+                        .selectable_value(&mut settings.preset, preset, preset.display())
+                        .changed();
+                }
+            });
+
         ui.add_enabled(combat.is_some(), |ui: &mut Ui| {
             PopupButton::new("⛭")
                 .show(ui, |ui| {
@@ -40,16 +85,108 @@ impl SummaryCopy {
                     }
 
                     ui.label("Limit the number of elements,\nif you wish to paste the summary into the game chat.\nSo that it will not be truncated by the game.");
+
+                    ui.add_space(10.0);
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut self.limit_players, "Only copy the top");
+                        ui.add_enabled_ui(self.limit_players, |ui| {
+                            NumberEdit::new(&mut self.player_limit, "summary copy player limit")
+                                .clamp_min(1)
+                                .desired_text_edit_width(30.0)
+                                .show(ui);
+                        });
+                        ui.label("players");
+                    });
+                    ui.checkbox(&mut self.align_columns, "Right align the number columns");
+                    ui.checkbox(
+                        &mut self.include_players_under_threshold,
+                        format!(
+                            "Players under {}% of the damage",
+                            self.damage_share_threshold
+                        ),
+                    );
+
+                    ui.separator();
+                    ui.label("Custom template");
+                    changed |= TextEdit::multiline(&mut settings.custom_template)
+                        .code_editor()
+                        .desired_width(400.0)
+                        .show(ui)
+                        .response
+                        .changed();
+                    ui.label(RichText::new(TEMPLATE_HELP).small());
                 })
                 .response
         });
+        changed
+    }
+
+    /// builds the summary from the template of the `settings`.
+    /// Falls back to the default summary, if the template is malformed.
+    pub(super) fn build(
+        &self,
+        combat: &Combat,
+        note: Option<&CombatNote>,
+        settings: &SummaryCopySettings,
+    ) -> String {
+        let Some(template) = settings.template() else {
+            return self.build_summary(combat, note);
+        };
+
+        match self.build_from_template(template, combat) {
+            Ok(summary) => summary,
+            Err(err) => {
+                log::warn!("malformed summary copy template: {}", err);
+                self.build_summary(combat, note)
+            }
+        }
     }
 
-    fn build_summary(&self, combat: &Combat) -> String {
+    fn build_from_template(&self, template: &str, combat: &Combat) -> Result<String, String> {
+        let (before, players_template, after) = match template.split_once(PLAYERS_START) {
+            Some((before, rest)) => {
+                let (players_template, after) = rest
+                    .split_once(PLAYERS_END)
+                    .ok_or_else(|| format!("{} is not closed by {}", PLAYERS_START, PLAYERS_END))?;
+                (before, players_template, after)
+            }
+            None => (template, "", ""),
+        };
+
+        let mut number_formatter = NumberFormatter::new();
+        let threshold = self.damage_share_threshold;
+        let mut summary = fill_placeholders(before, |n| combat_value(combat, threshold, n))?;
+        let player_limit = if self.limit_players {
+            self.player_limit
+        } else {
+            usize::MAX
+        };
+        for (rank, player) in combat
+            .players
+            .values()
+            .sorted_by(|p1, p2| p2.damage_out.dps.all.total_cmp(&p1.damage_out.dps.all))
+            .take(player_limit)
+            .enumerate()
+        {
+            summary += &fill_placeholders(players_template, |n| {
+                player_value(combat, player, rank + 1, &mut number_formatter, n)
+                    .or_else(|| combat_value(combat, threshold, n))
+            })?;
+        }
+        summary += &fill_placeholders(after, |n| combat_value(combat, threshold, n))?;
+        Ok(summary)
+    }
+
+    fn build_summary(&self, combat: &Combat, note: Option<&CombatNote>) -> String {
         let mut number_formatter = NumberFormatter::new();
         let aspects = self.aspects.iter().filter(|a| a.include);
         let first_aspect = aspects.clone().nth(0).unwrap_or(&self.aspects[0]);
-        let players = combat
+        let player_limit = if self.limit_players {
+            self.player_limit
+        } else {
+            usize::MAX
+        };
+        let players: Vec<_> = combat
             .players
             .values()
             .sorted_by(|p1, p2| {
@@ -59,40 +196,82 @@ impl SummaryCopy {
                 }
                 cmp
             })
+            .take(player_limit)
             .map(|p| {
-                let aspects = aspects
+                let name = player_handle(combat, p);
+                let values: Vec<_> = aspects
                     .clone()
-                    .map(|a| {
-                        let value = (a.get)(p);
-                        (a.format)(value, &mut number_formatter)
-                    })
-                    .join("|");
-
-                format!(
-                    "{} {}",
-                    String::from_iter(
-                        p.damage_in
-                            .name()
-                            .get(&combat.name_manager)
-                            .chars()
-                            .skip_while(|c| *c != '@')
-                    ),
-                    aspects
-                )
-            });
+                    .map(|a| (a.format)((a.get)(p), &mut number_formatter))
+                    .collect();
+                (name, values)
+            })
+            .collect();
+
+        let widths: Vec<_> = aspects
+            .clone()
+            .enumerate()
+            .map(|(i, a)| {
+                if !self.align_columns {
+                    return 0;
+                }
+                players
+                    .iter()
+                    .map(|(_, v)| v[i].chars().count())
+                    .chain(std::iter::once(a.header.chars().count()))
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+
+        let players = players.iter().map(|(name, values)| {
+            let values = values
+                .iter()
+                .zip(widths.iter())
+                .map(|(v, w)| format!("{:>1$}", v, w))
+                .join("|");
+            format!("{} {}", name, values)
+        });
 
-        let aspects = aspects.clone().map(|a| a.header).join("|");
+        let aspects = aspects
+            .clone()
+            .zip(widths.iter())
+            .map(|(a, w)| format!("{:>1$}", a.header, w))
+            .join("|");
         let aspects_header = format!("Name {}", aspects);
 
         let header_and_players = std::iter::once(aspects_header).chain(players).join(" / ");
 
         let duration = format_duration(time_range_to_duration_or_zero(&combat.combat_time));
 
+        let tags = match note {
+            Some(n) if !n.tags.is_empty() => format!(" [{}]", n.tags.join(", ")),
+            _ => String::new(),
+        };
+        let under_threshold = if self.include_players_under_threshold {
+            format!(
+                " / Under {}%: {}",
+                self.damage_share_threshold,
+                players_under_threshold(combat, self.damage_share_threshold)
+            )
+        } else {
+            String::new()
+        };
+        let notes = match note {
+            Some(n) if !n.notes.trim().is_empty() => {
+                format!(" / Notes: {}", n.notes.split_whitespace().join(" "))
+            }
+            _ => String::new(),
+        };
+
         format!(
-            "CLA - {} ({}): {}",
+            "CLA - {} ({}){}: {}{}{} / Settings: {}",
             combat.name(),
             duration,
-            header_and_players
+            tags,
+            header_and_players,
+            under_threshold,
+            notes,
+            combat.settings_digest
         )
     }
 }
@@ -165,9 +344,115 @@ impl Default for SummaryCopy {
                     |v, f| f.format(v, 1),
                     true,
                 ),
+                aspect(
+                    "Deaths",
+                    "Deaths",
+                    false,
+                    |p| p.damage_in.kills.values().copied().sum::<u32>() as _,
+                    |v, f| f.format(v, 0),
+                    false,
+                ),
             ],
+            limit_players: false,
+            player_limit: 5,
+            align_columns: false,
+            include_players_under_threshold: false,
+            damage_share_threshold: SummarySettings::default().damage_share_threshold,
+        }
+    }
+}
+
+/// replaces the `{placeholder}`s of the `template` with their `value`
+fn fill_placeholders(
+    template: &str,
+    mut value: impl FnMut(&str) -> Option<String>,
+) -> Result<String, String> {
+    let mut filled = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        filled.push_str(&rest[..start]);
+        if rest[start..].starts_with('}') {
+            return Err("'}' without '{'".to_string());
         }
+        let end = start
+            + rest[start..]
+                .find('}')
+                .ok_or_else(|| "'{' without '}'".to_string())?;
+        let name = &rest[start + 1..end];
+        filled += &value(name).ok_or_else(|| format!("unknown placeholder {{{}}}", name))?;
+        rest = &rest[end + 1..];
     }
+    filled.push_str(rest);
+    Ok(filled)
+}
+
+/// players with a lower share of the damage out than the `threshold` in percent
+pub fn players_under_threshold(combat: &Combat, threshold: f64) -> usize {
+    combat
+        .players
+        .values()
+        .filter(|p| p.damage_out.damage_percentage.all.unwrap_or(0.0) < threshold)
+        .count()
+}
+
+fn combat_value(combat: &Combat, damage_share_threshold: f64, name: &str) -> Option<String> {
+    let start = combat.active_time.start;
+    let value = match name {
+        "combat_name" => combat.name(),
+        "combat_time" => format_duration(time_range_to_duration_or_zero(&combat.combat_time)),
+        "date" => start.format("%Y-%m-%d").to_string(),
+        "time" => start.format("%H:%M:%S").to_string(),
+        "settings_digest" => combat.settings_digest.clone(),
+        "players_under_threshold" => {
+            players_under_threshold(combat, damage_share_threshold).to_string()
+        }
+        _ => return None,
+    };
+    Some(value)
+}
+
+fn player_value(
+    combat: &Combat,
+    player: &Player,
+    rank: usize,
+    number_formatter: &mut NumberFormatter,
+    name: &str,
+) -> Option<String> {
+    let value = match name {
+        "rank" => rank.to_string(),
+        "player" => player_handle(combat, player),
+        "dps" => number_formatter.format_with_automated_suffixes(player.damage_out.dps.all),
+        "total_damage" => {
+            number_formatter.format_with_automated_suffixes(player.damage_out.total_damage.all)
+        }
+        "damage_percentage" => {
+            number_formatter.format(player.damage_out.damage_percentage.all.unwrap_or(0.0), 1)
+        }
+        "damage_in" => {
+            number_formatter.format_with_automated_suffixes(player.damage_in.total_damage.all)
+        }
+        "deaths" => player
+            .damage_in
+            .kills
+            .values()
+            .copied()
+            .sum::<u32>()
+            .to_string(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// the account name of the player, starting with the '@'
+fn player_handle(combat: &Combat, player: &Player) -> String {
+    String::from_iter(
+        player
+            .damage_in
+            .name()
+            .get(&combat.name_manager)
+            .chars()
+            .skip_while(|c| *c != '@'),
+    )
 }
 
 fn aspect(
@@ -0,0 +1,85 @@
+use eframe::egui::*;
+
+use crate::{analyzer::Combat, helpers::time_range_to_duration};
+
+use super::analysis_handling::AnalysisHandler;
+
+/// limits the analysis of the selected combat to a time range, e.g. a single phase of a long mission
+#[derive(Default)]
+pub struct TimeSliceSelection {
+    start_seconds: f64,
+    end_seconds: f64,
+    /// identity of the combat the range was last reset for
+    combat: Option<String>,
+}
+
+impl TimeSliceSelection {
+    /// `combat_indices` are the ones of the combat list the selected `combat` consists of
+    pub fn show(
+        &mut self,
+        ui: &mut Ui,
+        combat: Option<&Combat>,
+        combat_indices: &[usize],
+        analysis_handler: &AnalysisHandler,
+    ) {
+        let Some(combat) = combat.filter(|_| !combat_indices.is_empty()) else {
+            ui.add_enabled(false, Button::new("Time Slice ⏱"));
+            return;
+        };
+        let duration_seconds =
+            time_range_to_duration(&combat.active_time).num_milliseconds() as f64 / 1e3;
+        if combat.time_slice.is_none() && self.combat.as_deref() != Some(&combat.identity()) {
+            self.combat = Some(combat.identity());
+            self.start_seconds = 0.0;
+            self.end_seconds = duration_seconds;
+        }
+
+        let text = match &combat.time_slice {
+            Some(slice) => format!(
+                "Time Slice ⏱ {:.0} s - {:.0} s",
+                slice.start as f64 / 1e3,
+                slice.end as f64 / 1e3
+            ),
+            None => "Time Slice ⏱".to_string(),
+        };
+        ui.menu_button(text, |ui| {
+            ui.label("analyzes only the part of the combat within the range");
+            ui.horizontal(|ui| {
+                ui.add(
+                    DragValue::new(&mut self.start_seconds)
+                        .clamp_range(0.0..=self.end_seconds)
+                        .suffix(" s"),
+                );
+                ui.label("to");
+                ui.add(
+                    DragValue::new(&mut self.end_seconds)
+                        .clamp_range(self.start_seconds..=f64::MAX)
+                        .suffix(" s"),
+                );
+            });
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(self.start_seconds < self.end_seconds, Button::new("Apply"))
+                    .clicked()
+                {
+                    let slice = (self.start_seconds * 1e3) as u32..(self.end_seconds * 1e3) as u32;
+                    analysis_handler.get_combat_slice(combat_indices.to_vec(), slice);
+                    ui.close_menu();
+                }
+                if ui
+                    .add_enabled(
+                        combat.time_slice.is_some(),
+                        Button::new("Reset to Full Combat"),
+                    )
+                    .clicked()
+                {
+                    match combat_indices {
+                        [combat_index] => analysis_handler.get_combat(*combat_index),
+                        _ => analysis_handler.get_merged_combat(combat_indices.to_vec()),
+                    }
+                    ui.close_menu();
+                }
+            });
+        });
+    }
+}
@@ -35,6 +35,7 @@ pub struct TableRow<'a> {
     left_offset: f32,
     row_height: f32,
     cell_spacing: f32,
+    opacity: f32,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -106,6 +107,7 @@ impl<'a> Table<'a> {
             add_header,
             false,
             None,
+            None,
         );
         let header_rect = Rect::from_min_size(left_top, vec2(state.last_size.x, header_height));
         self.ui.allocate_rect(header_rect, Sense::hover());
@@ -194,6 +196,7 @@ impl<'a> TableBody<'a> {
             add_cells,
             self.striped && (self.current_row % 2) == 0,
             None,
+            None,
         );
 
         self.current_row += 1;
@@ -205,6 +208,16 @@ impl<'a> TableBody<'a> {
         &mut self,
         checked: bool,
         add_cells: impl FnOnce(&mut TableRow),
+    ) -> Response {
+        self.highlighted_selectable_row(checked, None, add_cells)
+    }
+
+    /// like [`Self::selectable_row`], but additionally fills the row background with `highlight`
+    pub fn highlighted_selectable_row(
+        &mut self,
+        checked: bool,
+        highlight: Option<Color32>,
+        add_cells: impl FnOnce(&mut TableRow),
     ) -> Response {
         let response = TableRow::show(
             self.ui,
@@ -216,6 +229,7 @@ impl<'a> TableBody<'a> {
             add_cells,
             self.striped && (self.current_row % 2) == 0,
             Some(checked),
+            highlight,
         );
 
         self.current_row += 1;
@@ -235,6 +249,7 @@ impl<'a> TableRow<'a> {
         add_cells: impl FnOnce(&mut TableRow),
         is_stripe: bool,
         checked: Option<bool>,
+        highlight: Option<Color32>,
     ) -> Response {
         let left_top = pos2(
             table_left_top.x,
@@ -249,6 +264,9 @@ impl<'a> TableRow<'a> {
         let response = ui.interact(rect, ui.id().with(row_index), sense);
 
         draw_visuals(ui, is_stripe, checked, &response);
+        if let Some(highlight) = highlight {
+            ui.painter().rect_filled(response.rect, 0.0, highlight);
+        }
 
         let mut row = TableRow {
             current_column: 0,
@@ -258,6 +276,7 @@ impl<'a> TableRow<'a> {
             left_offset: 0.0,
             row_height: row_height,
             cell_spacing,
+            opacity: 1.0,
         };
         add_cells(&mut row);
         state.update_height(row_index + 1, row_height);
@@ -265,6 +284,11 @@ impl<'a> TableRow<'a> {
         response
     }
 
+    /// makes the content of the following cells semi-transparent
+    pub fn set_opacity(&mut self, opacity: f32) {
+        self.opacity = opacity;
+    }
+
     pub fn cell(&mut self, add_column: impl FnOnce(&mut Ui)) -> Response {
         self.cell_with_layout(Layout::left_to_right(Align::Center), add_column)
     }
@@ -315,6 +339,7 @@ impl<'a> TableRow<'a> {
             .interact(interact_rect, self.ui.next_auto_id(), sense);
         draw_visuals(self.ui, false, checked, &response);
         let mut ui = self.ui.child_ui(rect, layout);
+        ui.set_opacity(self.opacity);
 
         add_column(&mut ui);
 
@@ -51,6 +51,21 @@ impl NumberFormatter {
     }
 
     pub fn format_with_automated_suffixes(&mut self, number: f64) -> String {
+        self.format_with_suffixes(number, None)
+    }
+
+    /// formats numbers of at least 1000 as e.g. `312k` or `1.5M` with the given decimals,
+    /// smaller numbers are formatted with `precision`
+    pub fn format_abbreviated(&mut self, number: f64, decimals: usize, precision: usize) -> String {
+        if number.abs() < 1.0e3 {
+            return self.format(number, precision);
+        }
+
+        self.format_with_suffixes(number, Some(decimals))
+    }
+
+    /// without a `precision`, the precision depends on the size of the number
+    fn format_with_suffixes(&mut self, number: f64, precision: Option<usize>) -> String {
         if number.abs() == 0.0 {
             return "0.0".to_string();
         }
@@ -75,11 +90,13 @@ impl NumberFormatter {
         for (threshold, suffix) in THRESHOLD_AND_SUFFIX.iter().copied() {
             if number < threshold {
                 let normalized_number = number / (threshold / 1e3);
-                let precision = PRECISION_THRESHOLD
-                    .iter()
-                    .copied()
-                    .find_map(|(t, p)| if normalized_number < t { Some(p) } else { None })
-                    .unwrap_or(0);
+                let precision = precision.unwrap_or_else(|| {
+                    PRECISION_THRESHOLD
+                        .iter()
+                        .copied()
+                        .find_map(|(t, p)| if normalized_number < t { Some(p) } else { None })
+                        .unwrap_or(0)
+                });
                 return Self::add_sign(
                     format!("{}{}", self.format(normalized_number, precision), suffix),
                     is_negative,
@@ -147,4 +164,15 @@ mod tests {
         assert_eq!(formatter.format_with_automated_suffixes(0.0), "0.0");
         assert_eq!(formatter.format_with_automated_suffixes(-0.0), "0.0");
     }
+
+    #[test]
+    fn format_abbreviated() {
+        let mut formatter = NumberFormatter::new();
+
+        assert_eq!(formatter.format_abbreviated(312456.7, 0, 2), "312k");
+        assert_eq!(formatter.format_abbreviated(312456.7, 1, 2), "312.5k");
+        assert_eq!(formatter.format_abbreviated(1534567.0, 2, 2), "1.53M");
+        assert_eq!(formatter.format_abbreviated(-45678.0, 1, 2), "-45.7k");
+        assert_eq!(formatter.format_abbreviated(999.123, 0, 2), "999.12");
+    }
 }
@@ -1,15 +1,16 @@
 #![allow(non_snake_case)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-use std::backtrace::Backtrace;
+use std::{backtrace::Backtrace, path::PathBuf};
 
-use app::logging;
+use app::{logging, settings::Settings, single_instance::SingleInstance};
 use eframe::{
     egui::{IconData, ViewportBuilder},
     epaint::vec2,
 };
 
-mod analyzer;
+use sto_combatlog_analyzer as analyzer;
+
 mod app;
 mod custom_widgets;
 mod helpers;
@@ -26,18 +27,31 @@ fn main() {
     }));
 
     logging::initialize();
+    // e.g. a combatlog file dropped onto the executable
+    let combatlog_file = std::env::args_os().nth(1).map(PathBuf::from);
+    let single_instance = if Settings::load_or_default().startup.single_instance {
+        match SingleInstance::acquire(combatlog_file.as_ref()) {
+            Some(i) => i,
+            None => return,
+        }
+    } else {
+        SingleInstance::unguarded()
+    };
+
     let native_options = eframe::NativeOptions {
         viewport: ViewportBuilder::default()
             .with_inner_size(vec2(1280.0, 720.0))
             .with_min_inner_size(vec2(480.0, 270.0))
             .with_icon(icon_data()),
+        // only enabled on Windows and macOS by default
+        follow_system_theme: true,
         ..Default::default()
     };
 
     let res = eframe::run_native(
         &format!("STO_CombatLogAnalyzer V{}", env!("CARGO_PKG_VERSION")),
         native_options,
-        Box::new(|cc| Box::new(app::App::new(cc))),
+        Box::new(|cc| Box::new(app::App::new(cc, single_instance, combatlog_file))),
     );
 
     if let Err(err) = res {
@@ -75,6 +75,12 @@ impl From<Error> for RequestError {
     }
 }
 
+impl From<(StatusCode, Option<String>)> for RequestError {
+    fn from((status, error): (StatusCode, Option<String>)) -> Self {
+        Self::new(RequestErrorKind::Status(status, error))
+    }
+}
+
 impl From<Response> for RequestError {
     fn from(value: Response) -> Self {
         Self::new(RequestErrorKind::Status(value.status(), value.text().ok()))
@@ -1,6 +1,8 @@
 mod common;
 mod records;
 mod upload;
+mod upload_queue;
 
 pub use records::Records;
 pub use upload::Upload;
+pub use upload_queue::{UploadCombat, UploadQueue};
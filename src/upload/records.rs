@@ -10,6 +10,8 @@ use serde::Deserialize;
 use serde_json::Value;
 
 use crate::{
+    analyzer::DateTimeFormat,
+    app::settings::VisualsTab,
     custom_widgets::{
         number_edit::NumberEdit,
         table::{Table, TableRow},
@@ -340,6 +342,7 @@ impl Entries {
                 entries,
                 search_player,
                 show_full_data,
+                &VisualsTab::date_time_format(&ctx),
             )),
             Err(err) => Entries::LoadError(format!(
                 "{}",
@@ -400,9 +403,11 @@ impl LoadedEntries {
         model: LadderEntriesModel,
         search_player: String,
         show_full_data: bool,
+        date_time_format: &DateTimeFormat,
     ) -> Self {
         let mut formatter = NumberFormatter::new();
-        let (reduced_columns_count, entries) = TableColumn::build_table(&model, &mut formatter);
+        let (reduced_columns_count, entries) =
+            TableColumn::build_table(&model, &mut formatter, date_time_format);
         let combat_log_ids = model.results.iter().map(|e| e.combatlog).collect();
         Self {
             page_count: model.count / PAGE_SIZE + if model.count % PAGE_SIZE > 0 { 1 } else { 0 },
@@ -743,6 +748,7 @@ impl TableColumn {
     fn build_table(
         entries: &LadderEntriesModel,
         formatter: &mut NumberFormatter,
+        date_time_format: &DateTimeFormat,
     ) -> (usize, Vec<Self>) {
         let mut ranks = Vec::new();
         let mut players = Vec::new();
@@ -753,7 +759,7 @@ impl TableColumn {
             ranks.push(DataValue::number(entry.rank.to_string()));
             players.push(DataValue::non_number(entry.player.clone()));
             let date_time = DateTime::parse_from_str(&entry.date, "%+")
-                .map(|d| format!("{}", d.format("%v %T")))
+                .map(|d| date_time_format.format_date_time(d.naive_local()))
                 .unwrap_or_else(|_| entry.date.clone());
             dates.push(DataValue::non_number(date_time));
 
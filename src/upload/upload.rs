@@ -6,44 +6,84 @@ use reqwest::{
         multipart::{Form, Part},
         ClientBuilder,
     },
-    Url,
+    StatusCode, Url,
 };
 use serde::Deserialize;
 
 use crate::{
-    analyzer::{settings::AnalysisSettings, Combat},
+    analyzer::{Combat, DateTimeFormat},
+    app::{analysis_handling::AnalysisHandler, settings::VisualsTab},
     custom_widgets::table::Table,
-    helpers::number_formatting::NumberFormatter,
+    helpers::{format_duration, number_formatting::NumberFormatter, time_range_to_duration},
 };
 
-use super::common::{spawn_request, RequestError};
+use super::{
+    common::{spawn_request, RequestError},
+    UploadCombat,
+};
 
 #[derive(Default)]
 pub struct Upload {
     state: UploadState,
 }
 
+const DIFFICULTIES: [&str; 3] = ["Normal", "Advanced", "Elite"];
+
 const UPLOAD_TOOLTIP: &str = "Uploads the current combat to the records (powered by OSCR). Note that the uploaded values may vary compared to the values displayed here, since the calculations may be done differently.";
 
 impl Upload {
+    /// `combat_index` is the one of the selected `combat`, which is read from its log through the `analysis_handler`
     pub fn show(
         &mut self,
         ui: &mut Ui,
         combat: Option<&Combat>,
-        settings: &AnalysisSettings,
+        combat_index: Option<usize>,
+        analysis_handler: &AnalysisHandler,
         url: &str,
     ) {
+        let combat = combat.zip(combat_index);
         ui.add_enabled_ui(self.state.is_idle() && combat.is_some(), |ui| {
             if ui
                 .button("Upload 🌎")
                 .on_hover_text(UPLOAD_TOOLTIP)
                 .clicked()
             {
-                self.state = self.begin_upload(ui.ctx().clone(), combat.unwrap(), settings, url);
+                let (combat, combat_index) = combat.unwrap();
+                self.state = Self::prepare_upload(
+                    combat,
+                    combat_index,
+                    analysis_handler,
+                    url,
+                    &VisualsTab::date_time_format(ui.ctx()),
+                );
             };
         });
         match &mut self.state {
             UploadState::Idle => (),
+            UploadState::Reading(_) => {
+                Self::window(ui, true, |ui| {
+                    ui.with_layout(Layout::top_down(Align::Center), |ui| {
+                        ui.add_space(20.0);
+                        ui.label("reading the combat from the log...");
+                        ui.add_space(20.0);
+                        ui.spinner();
+                        ui.add_space(20.0);
+                    });
+                });
+            }
+            UploadState::Confirming(preview) => {
+                match Self::window(ui, false, |ui| preview.show(ui)) {
+                    Some(Some(true)) => {
+                        let UploadState::Confirming(preview) = std::mem::take(&mut self.state)
+                        else {
+                            unreachable!()
+                        };
+                        self.state = Self::begin_upload(ui.ctx().clone(), *preview);
+                    }
+                    Some(Some(false)) => self.state = UploadState::Idle,
+                    _ => (),
+                }
+            }
             UploadState::Uploading(join_handle) => {
                 if join_handle.as_ref().unwrap().is_finished() {
                     self.state = join_handle.take().unwrap().join().unwrap();
@@ -62,47 +102,7 @@ impl Upload {
             }
             UploadState::UploadComplete(result) => {
                 if let Some(true) = Self::window(ui, false, |ui| {
-                    Table::new(ui)
-                        .header(15.0, |r| {
-                            r.cell(|ui| {
-                                ui.label("Name");
-                            });
-                            r.cell(|ui| {
-                                ui.label("Updated");
-                            });
-                            r.cell(|ui| {
-                                ui.label("Details");
-                            });
-                            r.cell(|ui| {
-                                ui.label("Value");
-                            });
-                        })
-                        .body(25.0, |b| {
-                            for result in result.iter() {
-                                b.row(|r| {
-                                    r.cell(|ui| {
-                                        ui.label(&result.name);
-                                    });
-                                    r.cell_with_layout(
-                                        Layout::top_down(Align::Center)
-                                            .with_cross_align(Align::Center),
-                                        |ui| {
-                                            let text = match result.updated {
-                                                true => WidgetText::from("✔").color(Color32::GREEN),
-                                                false => WidgetText::from("✖").color(Color32::RED),
-                                            };
-                                            ui.label(text);
-                                        },
-                                    );
-                                    r.cell(|ui| {
-                                        ui.label(&result.detail);
-                                    });
-                                    r.cell(|ui| {
-                                        ui.label(&result.value);
-                                    });
-                                });
-                            }
-                        });
+                    UploadResponse::show_table(result, ui);
                     ui.add_space(40.0);
                     if ui.button("Close").clicked() {
                         true
@@ -145,31 +145,60 @@ impl Upload {
             .flatten()
     }
 
-    fn begin_upload(
-        &self,
-        ctx: Context,
+    /// requests the records of the combat from the log it came from,
+    /// so the upload can be reviewed before it is sent, see [`Upload::set_combat`]
+    fn prepare_upload(
         combat: &Combat,
-        settings: &AnalysisSettings,
+        combat_index: usize,
+        analysis_handler: &AnalysisHandler,
         url: &str,
+        date_time_format: &DateTimeFormat,
     ) -> UploadState {
-        let combat_data = combat.read_log_combat_data(settings.combatlog_file());
-        let combat_data = match combat_data {
-            Some(d) => d,
-            None => return UploadState::Idle,
-        };
         let url = match Url::parse(url) {
             Ok(u) => u,
             Err(_) => {
                 return UploadState::UploadError("the provided upload URL is invalid".into());
             }
         };
-        let combat_name = combat.name();
-        let join_handle = spawn_request(move || Self::upload(ctx, url, combat_data, combat_name));
+        analysis_handler.read_combat_for_upload(combat_index);
+        UploadState::Reading(Box::new(UploadPreview::new(combat, url, date_time_format)))
+    }
+
+    /// the combat read with [`AnalysisHandler::read_combat_for_upload`]
+    pub fn set_combat(&mut self, combat: Result<UploadCombat, String>) {
+        let UploadState::Reading(mut preview) = std::mem::take(&mut self.state) else {
+            return;
+        };
+        self.state = match combat {
+            Ok(combat) => {
+                preview.set_combat(combat);
+                UploadState::Confirming(preview)
+            }
+            Err(error) => UploadState::UploadError(error),
+        };
+    }
+
+    fn begin_upload(ctx: Context, preview: UploadPreview) -> UploadState {
+        let join_handle = spawn_request(move || {
+            Self::upload(
+                ctx,
+                preview.url,
+                preview.data,
+                preview.combat_name,
+                preview.metadata,
+            )
+        });
         UploadState::Uploading(Some(join_handle))
     }
 
-    fn upload(ctx: Context, url: Url, combat_data: Vec<u8>, combat_name: String) -> UploadState {
-        let state = match Self::do_upload(url, combat_data, combat_name) {
+    fn upload(
+        ctx: Context,
+        url: Url,
+        combat_data: Vec<u8>,
+        combat_name: String,
+        metadata: Option<UploadMetadata>,
+    ) -> UploadState {
+        let state = match Self::do_upload(url, combat_data, combat_name, metadata) {
             Ok(r) => UploadState::UploadComplete(r),
             Err(e) => UploadState::UploadError(format!(
                 "{}",
@@ -180,19 +209,40 @@ impl Upload {
         state
     }
 
-    fn do_upload(
+    /// posts the gzip compressed `combat_data`
+    pub(super) fn do_upload(
         url: Url,
         combat_data: Vec<u8>,
         combat_name: String,
+        metadata: Option<UploadMetadata>,
     ) -> Result<Vec<UploadResponse>, RequestError> {
-        let mut data = Vec::new();
-        let mut encoder = flate2::GzBuilder::new().write(&mut data, flate2::Compression::best());
-        encoder.write_all(combat_data.as_slice()).unwrap();
-        encoder.finish().unwrap();
         let client = ClientBuilder::new().build().unwrap();
         let url = url.join("/combatlog/upload/").unwrap();
-        let form = Form::new().part("file", Part::bytes(data).file_name(combat_name));
-        let response = client.post(url).multipart(form).send()?;
+        let form = |metadata: Option<&UploadMetadata>| {
+            let form = Form::new().part(
+                "file",
+                Part::bytes(combat_data.clone()).file_name(combat_name.clone()),
+            );
+            match metadata {
+                Some(metadata) => metadata.add_to_form(form),
+                None => form,
+            }
+        };
+
+        let mut response = client
+            .post(url.clone())
+            .multipart(form(metadata.as_ref()))
+            .send()?;
+        if metadata.is_some() && response.status().is_client_error() {
+            // servers that don't know about the metadata fields reject them,
+            // in that case the upload is retried without them
+            let status = response.status();
+            let error = response.text().ok();
+            if !UploadMetadata::is_rejected(status, &error) {
+                return Err(RequestError::from((status, error)));
+            }
+            response = client.post(url).multipart(form(None)).send()?;
+        }
         if !response.status().is_success() {
             return Err(RequestError::from(response));
         }
@@ -206,10 +256,179 @@ impl Upload {
     }
 }
 
+/// what gets uploaded, shown for confirmation before anything is sent
+struct UploadPreview {
+    url: Url,
+    combat_name: String,
+    metadata: Option<UploadMetadata>,
+    time_range: String,
+    duration: String,
+    /// names and DPS, highest first
+    players: Vec<(String, String)>,
+    /// gzip compressed combat data
+    data: Vec<u8>,
+}
+
+impl UploadPreview {
+    /// the data is added with [`UploadPreview::set_combat`], once it was read from the log
+    fn new(combat: &Combat, url: Url, date_time_format: &DateTimeFormat) -> Self {
+        let mut players: Vec<_> = combat
+            .players
+            .iter()
+            .map(|(&name, player)| {
+                let name = combat.name_manager.get_name(name).unwrap_or_default();
+                (name.to_string(), player.damage_out.dps.all)
+            })
+            .collect();
+        players.sort_by(|(_, dps1), (_, dps2)| dps2.total_cmp(dps1));
+        let mut formatter = NumberFormatter::new();
+        Self {
+            url,
+            combat_name: combat.name(),
+            metadata: UploadMetadata::new(combat),
+            time_range: format!(
+                "{} - {}",
+                date_time_format.format_date_time(combat.active_time.start),
+                date_time_format.format_time(combat.active_time.end.time())
+            ),
+            duration: format_duration(time_range_to_duration(&combat.active_time)),
+            players: players
+                .into_iter()
+                .map(|(name, dps)| (name, formatter.format(dps, 2)))
+                .collect(),
+            data: Vec::new(),
+        }
+    }
+
+    fn set_combat(&mut self, combat: UploadCombat) {
+        self.combat_name = combat.name;
+        self.metadata = combat.metadata;
+        self.data = combat.data;
+    }
+
+    /// `Some(true)` to upload, `Some(false)` to cancel
+    fn show(&self, ui: &mut Ui) -> Option<bool> {
+        let not_detected = "not detected";
+        Grid::new("upload preview").show(ui, |ui| {
+            ui.label("Combat");
+            ui.label(&self.combat_name);
+            ui.end_row();
+            ui.label("Map");
+            ui.label(self.metadata.as_ref().map_or(not_detected, |m| &m.map));
+            ui.end_row();
+            ui.label("Difficulty");
+            ui.label(
+                self.metadata
+                    .as_ref()
+                    .and_then(|m| m.difficulty.as_deref())
+                    .unwrap_or(not_detected),
+            );
+            ui.end_row();
+            ui.label("Time");
+            ui.label(&self.time_range);
+            ui.end_row();
+            ui.label("Duration");
+            ui.label(&self.duration);
+            ui.end_row();
+            ui.label("Upload Size");
+            ui.label(format!(
+                "{}B",
+                NumberFormatter::new().format_with_automated_suffixes(self.data.len() as _)
+            ));
+            ui.end_row();
+        });
+        ui.add_space(10.0);
+
+        Table::new(ui)
+            .id("upload preview players")
+            .max_scroll_height(300.0)
+            .header(15.0, |r| {
+                r.cell(|ui| {
+                    ui.label("Player");
+                });
+                r.cell(|ui| {
+                    ui.label("DPS");
+                });
+            })
+            .body(20.0, |b| {
+                for (name, dps) in self.players.iter() {
+                    b.row(|r| {
+                        r.cell(|ui| {
+                            ui.label(name);
+                        });
+                        r.cell(|ui| {
+                            ui.label(dps);
+                        });
+                    });
+                }
+            });
+        ui.add_space(20.0);
+
+        ui.horizontal(|ui| {
+            if ui.button("Upload").clicked() {
+                return Some(true);
+            }
+            if ui.button("Cancel").clicked() {
+                return Some(false);
+            }
+            None
+        })
+        .inner
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct UploadMetadata {
+    map: String,
+    difficulty: Option<String>,
+}
+
+impl UploadMetadata {
+    pub(super) fn new(combat: &Combat) -> Option<Self> {
+        if combat.combat_names.len() != 1 {
+            return None;
+        }
+
+        let combat_name = combat.combat_names.values().next()?;
+        let difficulty = combat_name
+            .additional_infos
+            .iter()
+            .find(|i| DIFFICULTIES.iter().any(|d| d.eq_ignore_ascii_case(i)))
+            .cloned();
+        Some(Self {
+            map: combat_name.name.clone(),
+            difficulty,
+        })
+    }
+
+    fn add_to_form(&self, form: Form) -> Form {
+        let form = form.text("map", self.map.clone());
+        match &self.difficulty {
+            Some(difficulty) => form.text("difficulty", difficulty.clone()),
+            None => form,
+        }
+    }
+
+    fn is_rejected(status: StatusCode, error: &Option<String>) -> bool {
+        if status != StatusCode::BAD_REQUEST && status != StatusCode::UNPROCESSABLE_ENTITY {
+            return false;
+        }
+
+        error
+            .as_ref()
+            .map(|e| e.contains("\"map\"") || e.contains("\"difficulty\""))
+            .unwrap_or(false)
+    }
+}
+
 #[derive(Default)]
 enum UploadState {
     #[default]
     Idle,
+    /// waits for the records of the combat to be read from its log
+    Reading(Box<UploadPreview>),
+    /// waits for the user to confirm the upload
+    Confirming(Box<UploadPreview>),
     Uploading(Option<JoinHandle<Self>>),
     UploadComplete(Vec<UploadResponse>),
     UploadError(String),
@@ -232,13 +451,66 @@ struct UploadResponseModel {
     value: f64,
 }
 
+pub(super) fn compress(combat_data: &[u8]) -> Vec<u8> {
+    let mut data = Vec::new();
+    let mut encoder = flate2::GzBuilder::new().write(&mut data, flate2::Compression::best());
+    encoder.write_all(combat_data).unwrap();
+    encoder.finish().unwrap();
+    data
+}
+
 #[derive(Deserialize)]
-struct UploadResponse {
+pub(super) struct UploadResponse {
     name: String,
-    updated: bool,
+    pub(super) updated: bool,
     detail: String,
     value: String,
 }
+impl UploadResponse {
+    pub(super) fn show_table(responses: &[Self], ui: &mut Ui) {
+        Table::new(ui)
+            .header(15.0, |r| {
+                r.cell(|ui| {
+                    ui.label("Name");
+                });
+                r.cell(|ui| {
+                    ui.label("Updated");
+                });
+                r.cell(|ui| {
+                    ui.label("Details");
+                });
+                r.cell(|ui| {
+                    ui.label("Value");
+                });
+            })
+            .body(25.0, |b| {
+                for result in responses.iter() {
+                    b.row(|r| {
+                        r.cell(|ui| {
+                            ui.label(&result.name);
+                        });
+                        r.cell_with_layout(
+                            Layout::top_down(Align::Center).with_cross_align(Align::Center),
+                            |ui| {
+                                let text = match result.updated {
+                                    true => WidgetText::from("✔").color(Color32::GREEN),
+                                    false => WidgetText::from("✖").color(Color32::RED),
+                                };
+                                ui.label(text);
+                            },
+                        );
+                        r.cell(|ui| {
+                            ui.label(&result.detail);
+                        });
+                        r.cell(|ui| {
+                            ui.label(&result.value);
+                        });
+                    });
+                }
+            });
+    }
+}
+
 impl From<UploadResponseModel> for UploadResponse {
     fn from(value: UploadResponseModel) -> Self {
         let mut formatter = NumberFormatter::new();
@@ -0,0 +1,323 @@
+use std::{collections::BTreeSet, ops::Range, path::Path, thread::JoinHandle, time::Duration};
+
+use chrono::NaiveDateTime;
+use eframe::egui::*;
+use reqwest::Url;
+
+use crate::{
+    analyzer::{Combat, DateTimeFormat},
+    app::analysis_handling::{AnalysisHandler, CombatListEntry},
+    custom_widgets::table::Table,
+};
+
+use super::{
+    common::spawn_request,
+    upload::{compress, Upload, UploadMetadata, UploadResponse},
+};
+
+/// a combat read from its log, ready to be uploaded
+#[derive(Clone)]
+pub struct UploadCombat {
+    pub(super) name: String,
+    active_time: Range<NaiveDateTime>,
+    pub(super) metadata: Option<UploadMetadata>,
+    /// the gzip compressed records of the combat
+    pub(super) data: Vec<u8>,
+}
+
+impl UploadCombat {
+    /// `None` if the records of the combat can not be read from the `combatlog_file`.
+    /// Compresses the records, so this is done by the analysis instead of the UI.
+    pub fn read(combat: &Combat, combatlog_file: &Path) -> Option<Self> {
+        Some(Self {
+            name: combat.name(),
+            active_time: combat.active_time.clone(),
+            metadata: UploadMetadata::new(combat),
+            data: compress(&combat.read_log_combat_data(combatlog_file)?),
+        })
+    }
+}
+
+/// uploads several combats one after another in the background
+#[derive(Default)]
+pub struct UploadQueue {
+    is_open: bool,
+    /// [`CombatListEntry::identity`]s of the selected combats
+    selected: BTreeSet<String>,
+    items: Vec<QueueItem>,
+    next_item_id: u64,
+    /// id of the item being uploaded
+    running: Option<(u64, JoinHandle<UploadResult>)>,
+    /// combats, which could not be added to the queue
+    read_errors: Vec<String>,
+    is_reading: bool,
+}
+
+type UploadResult = Result<Vec<UploadResponse>, String>;
+
+struct QueueItem {
+    id: u64,
+    combat: UploadCombat,
+    state: QueueItemState,
+}
+
+enum QueueItemState {
+    Pending,
+    Uploading,
+    Uploaded(Vec<UploadResponse>),
+    Failed(String),
+}
+
+impl UploadQueue {
+    pub fn show_button(&mut self, ui: &mut Ui) {
+        let text = match self.pending_count() {
+            0 => "Upload Queue…".to_string(),
+            pending => format!("Upload Queue ({})…", pending),
+        };
+        if ui
+            .button(text)
+            .on_hover_text("uploads several combats one after another")
+            .clicked()
+        {
+            self.is_open = true;
+        }
+    }
+
+    /// adds the combats read with [`AnalysisHandler::read_combats_for_upload`]
+    pub fn enqueue(&mut self, combats: Vec<Result<UploadCombat, String>>) {
+        self.is_reading = false;
+        for combat in combats {
+            match combat {
+                Ok(combat) => {
+                    self.items.push(QueueItem {
+                        id: self.next_item_id,
+                        combat,
+                        state: QueueItemState::Pending,
+                    });
+                    self.next_item_id += 1;
+                }
+                Err(error) => self.read_errors.push(error),
+            }
+        }
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &Context,
+        combats: &[CombatListEntry],
+        date_time_format: &DateTimeFormat,
+        analysis_handler: &AnalysisHandler,
+        url: &str,
+    ) {
+        // the queue keeps going while the window is closed
+        self.process(ctx, url);
+
+        let mut is_open = self.is_open;
+        Window::new("Upload Queue")
+            .open(&mut is_open)
+            .default_size([700.0, 500.0])
+            .show(ctx, |ui| {
+                self.show_combat_selection(ui, combats, date_time_format, analysis_handler);
+                ui.separator();
+                self.show_items(ui, date_time_format);
+            });
+        self.is_open = is_open;
+    }
+
+    fn show_combat_selection(
+        &mut self,
+        ui: &mut Ui,
+        combats: &[CombatListEntry],
+        date_time_format: &DateTimeFormat,
+        analysis_handler: &AnalysisHandler,
+    ) {
+        self.selected
+            .retain(|identity| combats.iter().any(|c| &c.identity == identity));
+        ui.label("Combats");
+        ScrollArea::vertical()
+            .id_source("upload queue combats")
+            .max_height(200.0)
+            .auto_shrink([false, true])
+            .show(ui, |ui| {
+                for combat in combats.iter().rev() {
+                    let mut selected = self.selected.contains(&combat.identity);
+                    if ui
+                        .checkbox(&mut selected, combat.identifier(date_time_format))
+                        .changed()
+                    {
+                        if selected {
+                            self.selected.insert(combat.identity.clone());
+                        } else {
+                            self.selected.remove(&combat.identity);
+                        }
+                    }
+                }
+            });
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    !self.selected.is_empty() && !self.is_reading,
+                    Button::new("Add to Queue"),
+                )
+                .clicked()
+            {
+                self.is_reading = true;
+                analysis_handler.read_combats_for_upload(
+                    std::mem::take(&mut self.selected).into_iter().collect(),
+                );
+            }
+            if self.is_reading {
+                ui.spinner();
+            }
+        });
+        for error in self.read_errors.iter() {
+            ui.colored_label(ui.visuals().error_fg_color, error);
+        }
+    }
+
+    fn show_items(&mut self, ui: &mut Ui, date_time_format: &DateTimeFormat) {
+        ui.horizontal(|ui| {
+            ui.label("Queue");
+            if ui
+                .add_enabled(
+                    self.items.iter().any(|i| i.is_finished()) || !self.read_errors.is_empty(),
+                    Button::new("Clear Finished"),
+                )
+                .clicked()
+            {
+                self.items.retain(|i| !i.is_finished());
+                self.read_errors.clear();
+            }
+        });
+
+        let mut retry = None;
+        let mut remove = None;
+        Table::new(ui)
+            .id("upload queue table")
+            .min_scroll_height(100.0)
+            .max_scroll_height(f32::MAX)
+            .cell_spacing(10.0)
+            .header(15.0, |r| {
+                for header in ["Combat", "Status", ""] {
+                    r.cell(|ui| {
+                        ui.label(header);
+                    });
+                }
+            })
+            .body(20.0, |t| {
+                for (index, item) in self.items.iter().enumerate() {
+                    t.row(|r| {
+                        r.cell(|ui| {
+                            ui.label(
+                                date_time_format
+                                    .combat_identifier(&item.combat.name, &item.combat.active_time),
+                            );
+                        });
+                        r.cell(|ui| item.show_state(ui));
+                        r.cell(|ui| {
+                            if let QueueItemState::Failed(_) = item.state {
+                                if ui.small_button("Retry").clicked() {
+                                    retry = Some(index);
+                                }
+                            }
+                            if !matches!(item.state, QueueItemState::Uploading)
+                                && ui.small_button("Remove").clicked()
+                            {
+                                remove = Some(index);
+                            }
+                        });
+                    });
+                }
+            });
+
+        if let Some(retry) = retry {
+            self.items[retry].state = QueueItemState::Pending;
+        }
+        if let Some(remove) = remove {
+            self.items.remove(remove);
+        }
+    }
+
+    /// collects the finished upload and starts the next one
+    fn process(&mut self, ctx: &Context, url: &str) {
+        if let Some((_, join_handle)) = &self.running {
+            if !join_handle.is_finished() {
+                return;
+            }
+            let (id, join_handle) = self.running.take().unwrap();
+            let result = join_handle.join().unwrap();
+            // items being uploaded can not be removed
+            let item = self.items.iter_mut().find(|i| i.id == id).unwrap();
+            item.state = match result {
+                Ok(responses) => QueueItemState::Uploaded(responses),
+                Err(error) => QueueItemState::Failed(error),
+            };
+        }
+
+        let Some(index) = self
+            .items
+            .iter()
+            .position(|i| matches!(i.state, QueueItemState::Pending))
+        else {
+            return;
+        };
+        let url = match Url::parse(url) {
+            Ok(u) => u,
+            Err(_) => {
+                self.items[index].state =
+                    QueueItemState::Failed("the provided upload URL is invalid".to_string());
+                return;
+            }
+        };
+        let item = &mut self.items[index];
+        item.state = QueueItemState::Uploading;
+        let combat = item.combat.clone();
+        let ctx = ctx.clone();
+        let join_handle = spawn_request(move || {
+            let result = Upload::do_upload(url, combat.data, combat.name, combat.metadata)
+                .map_err(|e| e.action_error("Failed to upload combat log.").to_string());
+            ctx.request_repaint_after_for(Duration::from_millis(10), ViewportId::ROOT);
+            result
+        });
+        self.running = Some((item.id, join_handle));
+    }
+
+    fn pending_count(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|i| matches!(i.state, QueueItemState::Pending | QueueItemState::Uploading))
+            .count()
+    }
+}
+
+impl QueueItem {
+    fn is_finished(&self) -> bool {
+        matches!(self.state, QueueItemState::Uploaded(_))
+    }
+
+    fn show_state(&self, ui: &mut Ui) {
+        match &self.state {
+            QueueItemState::Pending => {
+                ui.label("pending");
+            }
+            QueueItemState::Uploading => {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("uploading");
+                });
+            }
+            QueueItemState::Uploaded(responses) => {
+                let updated = responses.iter().filter(|r| r.updated).count();
+                ui.label(
+                    WidgetText::from(format!("✔ uploaded, {} records updated", updated))
+                        .color(Color32::GREEN),
+                )
+                .on_hover_ui(|ui| UploadResponse::show_table(responses, ui));
+            }
+            QueueItemState::Failed(error) => {
+                ui.label(WidgetText::from("✖ failed").color(Color32::RED))
+                    .on_hover_text(error);
+            }
+        }
+    }
+}